@@ -0,0 +1,28 @@
+//! Бенчмарки движка: `cargo bench --features benchmarks`.
+//!
+//! Случаи общие с `cryptor.run_benchmarks` (см. `cryptor::bench`).
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use cryptor::bench;
+
+/// Размер данных одного прохода.
+const SIZE: usize = 64 * 1024;
+
+fn engine(c: &mut Criterion) {
+    let data = vec![0x5a; SIZE];
+    let cases = bench::cases();
+    let mut groups: Vec<&str> = cases.iter().map(|case| case.group).collect();
+    groups.dedup();
+
+    for name in groups {
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(SIZE as u64));
+        for case in cases.iter().filter(|case| case.group == name) {
+            group.bench_function(&case.name, |b| b.iter(|| case.run(black_box(&data))));
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, engine);
+criterion_main!(benches);
@@ -2,8 +2,14 @@
 Основные элементы обеспечения шифрации и дешифрации.
 """
 import warnings
+from pathlib import Path
+from typing import Optional
 
-from cryptor import do_encrypt, do_decrypt  # noqa
+from cryptor import do_encrypt, do_decrypt, do_mac, mac_file  # noqa
+from cryptor import do_seal, do_open  # noqa
+from cryptor import generate_data_key, unwrap_data_key  # noqa
+from cryptor import do_reencrypt  # noqa
+from cryptor import ciphertext_len, streebog256  # noqa
 
 from .tools import EncryptMode
 
@@ -11,27 +17,178 @@ from .tools import EncryptMode
 def encrypting_rust(plaintext: bytes,
                     *,
                     code: bytes,
-                    mode: EncryptMode) -> bytes:
+                    mode: EncryptMode,
+                    iv: Optional[bytes] = None,
+                    cipher: str = 'kuznyechik',
+                    random_iv: bool = False,
+                    aad: Optional[bytes] = None,
+                    timeout: Optional[float] = None,
+                    padding: str = 'PKCS7',
+                    nonce: Optional[bytes] = None,
+                    counter_start: int = 0,
+                    threads: Optional[int] = None) -> bytes:
     """ Мост с Rust для шифрования открытого текста.
 
+    :param iv: Вектор инициализации (16 байт, для ``magma`` — 8); по
+               умолчанию встроенный.
+    :param cipher: Шифр: ``kuznyechik``, ``aes256`` или ``magma``.
+    :param random_iv: Случайный вектор в заголовке шифротекста (кроме ECB).
+    :param aad: Дополнительные данные под имитовставкой (только MGM).
+    :param timeout: Ограничение времени в секундах (кроме MGM).
+    :param padding: Выравнивание ECB и CBC: ``PKCS7``, ``X923``,
+                    ``ISO7816`` или ``ZERO``.
+    :param nonce: Nonce CTR (8 байт, для ``magma`` — 4) вместо ``iv``.
+    :param counter_start: Начальный счётчик CTR (только с ``nonce``).
+    :param threads: Число потоков для больших сообщений ECB и CTR.
     :returns:
         Возвращает зашифрованный текст без метаданных.
+    :raises OperationTimeoutError: Если время истекло.
     """
 
-    return do_encrypt(plaintext, code, mode.value)
+    return do_encrypt(plaintext, code, mode.value, cipher=cipher, iv=iv,
+                      random_iv=random_iv, aad=aad, timeout=timeout,
+                      padding=padding, nonce=nonce,
+                      counter_start=counter_start, threads=threads)
 
 
 def decrypting_rust(ciphertext: bytes,
                     *,
                     code: bytes,
-                    mode: EncryptMode) -> bytes:
+                    mode: EncryptMode,
+                    iv: Optional[bytes] = None,
+                    cipher: str = 'kuznyechik',
+                    random_iv: bool = False,
+                    aad: Optional[bytes] = None,
+                    timeout: Optional[float] = None,
+                    padding: str = 'PKCS7',
+                    nonce: Optional[bytes] = None,
+                    counter_start: int = 0,
+                    threads: Optional[int] = None) -> bytes:
     """ Мост с Rust для дешифрования предоставленного текста.
 
+    :param iv: Вектор инициализации (16 байт, для ``magma`` — 8); по
+               умолчанию встроенный.
+    :param cipher: Шифр: ``kuznyechik``, ``aes256`` или ``magma``.
+    :param random_iv: Вектор из заголовка шифротекста, зашифрованного с
+                      ``random_iv=True``.
+    :param aad: Дополнительные данные, переданные при шифровании (MGM).
+    :param timeout: Ограничение времени в секундах (кроме MGM).
+    :param padding: Выравнивание, использованное при шифровании.
+    :param nonce: Nonce CTR, использованный при шифровании.
+    :param counter_start: Счётчик CTR первого блока ``ciphertext``.
+    :param threads: Число потоков для больших сообщений ECB и CTR.
     :returns:
         Возвращает дешифрованный текст.
+    :raises OperationTimeoutError: Если время истекло.
+    :raises InvalidPaddingError: Если выравнивание не совпало.
     """
 
-    return do_decrypt(ciphertext, code, mode.value)
+    return do_decrypt(ciphertext, code, mode.value, cipher=cipher, iv=iv,
+                      random_iv=random_iv, aad=aad, timeout=timeout,
+                      padding=padding, nonce=nonce,
+                      counter_start=counter_start, threads=threads)
+
+
+def reencrypting_rust(ciphertext: bytes,
+                      *,
+                      code: bytes,
+                      mode: EncryptMode,
+                      new_code: bytes,
+                      new_mode: EncryptMode,
+                      cipher: str = 'kuznyechik',
+                      aad: Optional[bytes] = None,
+                      new_aad: Optional[bytes] = None) -> bytes:
+    """ Мост с Rust для перешифрования без возврата открытого текста.
+
+    :param aad: Дополнительные данные шифротекста (MGM).
+    :param new_aad: Дополнительные данные результата (MGM).
+    :returns:
+        Шифротекст под ``new_code`` в режиме ``new_mode``.
+    """
+
+    return do_reencrypt(ciphertext, code, mode.value, new_code,
+                        new_mode.value, cipher=cipher, aad=aad,
+                        new_aad=new_aad)
+
+
+def seal_rust(plaintext: bytes,
+              *,
+              code: bytes,
+              cipher: str,
+              aad: Optional[bytes] = None,
+              nonce: Optional[bytes] = None) -> bytes:
+    """ Мост с Rust для шифрования с аутентификацией.
+
+    :param aad: Дополнительные данные, защищаемые имитовставкой.
+    :param nonce: Одноразовый номер (12 байт); по умолчанию случайный.
+    :returns:
+        Одноразовый номер, шифротекст и имитовставка.
+    """
+
+    return do_seal(plaintext, code, cipher=cipher, aad=aad, nonce=nonce)
+
+
+def open_rust(ciphertext: bytes,
+              *,
+              code: bytes,
+              cipher: str,
+              aad: Optional[bytes] = None) -> bytes:
+    """ Мост с Rust для проверки и дешифровки результата ``seal_rust``.
+
+    :raises AuthenticationError: Если данные повреждены или ключ неверный.
+    """
+
+    return do_open(ciphertext, code, cipher=cipher, aad=aad)
+
+
+def generate_data_key_rust(master_key: bytes) -> tuple[bytes, bytes]:
+    """ Мост с Rust для создания ключа данных.
+
+    :returns:
+        Ключ данных и он же, обёрнутый мастер-ключом.
+    """
+
+    return generate_data_key(master_key)
+
+
+def unwrap_data_key_rust(master_key: bytes, wrapped_key: bytes) -> bytes:
+    """ Мост с Rust для развёртывания ключа данных.
+
+    :raises AuthenticationError: Если ключ повреждён или мастер-ключ неверный.
+    """
+
+    return unwrap_data_key(master_key, wrapped_key)
+
+
+def mac_rust(data: bytes, *, key: bytes) -> bytes:
+    """ Мост с Rust для расчёта HMAC-Стрибог-256 данных. """
+
+    return do_mac(data, key)
+
+
+def mac_file_rust(path: Path, *, key: bytes) -> bytes:
+    """ Мост с Rust для потокового расчёта HMAC-Стрибог-256 файла. """
+
+    return mac_file(path, key)
+
+
+def hash_rust(data: bytes) -> bytes:
+    """ Мост с Rust для хеша Стрибог-256. """
+
+    return streebog256(data)
+
+
+def ciphertext_len_rust(plaintext_len: int,
+                        *,
+                        mode: EncryptMode,
+                        cipher: str = 'kuznyechik') -> int:
+    """ Мост с Rust для расчёта размера шифротекста (без метаданных).
+
+    :returns:
+        Точная длина шифротекста в байтах.
+    """
+
+    return ciphertext_len(plaintext_len, mode.value, cipher=cipher)
 
 
 def _mode_warning(mode: EncryptMode):
@@ -0,0 +1,81 @@
+"""
+Асинхронное потоковое шифрование для ASGI/aiohttp.
+
+Поток разбивается на записи: 4 байта длины (big-endian) и результат
+``interfaces.encrypt`` для фрагмента. Генераторы читают следующий
+фрагмент только тогда, когда потребитель запросил очередную запись, что
+естественным образом передаёт противодавление источнику.
+"""
+from typing import AsyncIterable, AsyncIterator, Protocol
+
+from .exceptions import MetaStringError
+from .interfaces import decrypt, encrypt
+from .tools import EncryptMode, MAX_RECORD, RECORD_HEADER
+
+
+class AsyncReader(Protocol):
+    async def read(self, size: int = -1) -> bytes: ...
+
+
+async def encrypt_aiter(reader: AsyncReader | AsyncIterable[bytes],
+                        *,
+                        code: str,
+                        mode: EncryptMode = EncryptMode.ECB,
+                        chunk_size: int = 64 * 1024) -> AsyncIterator[bytes]:
+    """Шифровать асинхронный поток по мере чтения.
+
+    :param reader: Объект с ``async read(size)`` (``asyncio.StreamReader``,
+                   ``aiohttp`` content) или асинхронный итератор bytes.
+    :param code: Код шифрования.
+    :param mode: Режим шифрования.
+    :param chunk_size: Размер фрагмента при чтении через ``read()``.
+    :returns:
+        Асинхронный итератор зашифрованных записей.
+    """
+    async for chunk in _chunks(reader, chunk_size):
+        record = encrypt(chunk, code=code, mode=mode)
+        yield RECORD_HEADER.pack(len(record)) + record
+
+
+async def decrypt_aiter(reader: AsyncReader | AsyncIterable[bytes],
+                        *,
+                        code: str,
+                        chunk_size: int = 64 * 1024) -> AsyncIterator[bytes]:
+    """Расшифровать асинхронный поток записей ``encrypt_aiter``.
+
+    :param reader: Источник записей (как в ``encrypt_aiter``).
+    :param code: Код шифрования.
+    :param chunk_size: Размер фрагмента при чтении через ``read()``.
+    :returns:
+        Асинхронный итератор расшифрованных фрагментов.
+    :raises MetaStringError: При повреждённом потоке или неверном коде.
+    """
+    buffer = bytearray()
+    async for chunk in _chunks(reader, chunk_size):
+        buffer += chunk
+        while len(buffer) >= RECORD_HEADER.size:
+            length = RECORD_HEADER.unpack_from(buffer)[0]
+            if not 0 < length <= MAX_RECORD:
+                raise MetaStringError(f'invalid record length: {length}')
+            end = RECORD_HEADER.size + length
+            if len(buffer) < end:
+                break
+            record = bytes(buffer[RECORD_HEADER.size:end])
+            del buffer[:end]
+            data = decrypt(record, code=code)
+            yield data.encode('utf-8') if isinstance(data, str) else data
+
+    if buffer:
+        raise MetaStringError('stream ended inside a record')
+
+
+async def _chunks(reader: AsyncReader | AsyncIterable[bytes],
+                  chunk_size: int) -> AsyncIterator[bytes]:
+    """Единый асинхронный итератор фрагментов источника."""
+    if hasattr(reader, 'read'):
+        while chunk := await reader.read(chunk_size):
+            yield chunk
+    else:
+        async for chunk in reader:
+            if chunk:
+                yield chunk
@@ -0,0 +1,89 @@
+"""
+Преобразование "всё или ничего" (AONT) с разбиением на фрагменты.
+
+Схема (package transform Ривеста над "Кузнечиком"):
+
+- данные шифруются случайным ключом ``K`` в режиме CTR: ``C``;
+- к шифротексту добавляется ``K xor Стрибог-256(C)``;
+- результат делится на ``N`` фрагментов.
+
+Без любого из фрагментов хеш ``C`` не вычислить, а значит не восстановить
+ключ, поэтому для расшифровки нужны все фрагменты.
+"""
+import os
+import struct
+
+from ._engine import decrypting_rust, encrypting_rust, hash_rust
+from .tools import EncryptMode
+
+_MAGIC = b'GAO'
+# Заголовок фрагмента: маркер, идентификатор пакета (8), номер, количество.
+_HEADER = struct.Struct('>3s8sBB')
+_KEY_LEN = 32
+
+
+def aont_split(data: bytes, fragments: int) -> list[bytes]:
+    """Преобразовать данные и разделить на фрагменты.
+
+    :param data: Данные.
+    :param fragments: Количество фрагментов (от 2 до 255).
+    :returns:
+        Список фрагментов, все они нужны для восстановления.
+    :raises ValueError: При предоставлении неверных аргументов.
+    """
+
+    if not isinstance(data, bytes) or not data:
+        raise ValueError('data must be bytes and cannot be empty')
+    if not isinstance(fragments, int) or not 2 <= fragments <= 255:
+        raise ValueError('fragments must be int from 2 to 255')
+
+    key = os.urandom(_KEY_LEN)
+    ciphertext = encrypting_rust(data, code=key, mode=EncryptMode.CTR)
+    package = ciphertext + _xor(key, hash_rust(ciphertext))
+
+    package_id = os.urandom(8)
+    size = -(-len(package) // fragments)
+    return [
+        _HEADER.pack(_MAGIC, package_id, index, fragments)
+        + package[index * size:(index + 1) * size]
+        for index in range(fragments)
+    ]
+
+
+def aont_join(fragments: list[bytes]) -> bytes:
+    """Собрать фрагменты и восстановить данные.
+
+    :param fragments: Все фрагменты пакета в любом порядке.
+    :returns:
+        Исходные данные.
+    :raises ValueError: При неполном или повреждённом наборе фрагментов.
+    """
+
+    parts: dict[int, bytes] = {}
+    package_ids, counts = set(), set()
+    for fragment in fragments:
+        if not isinstance(fragment, bytes) or len(fragment) < _HEADER.size:
+            raise ValueError('fragment is corrupted')
+        magic, package_id, index, count = _HEADER.unpack_from(fragment)
+        if magic != _MAGIC:
+            raise ValueError('fragment is corrupted')
+        package_ids.add(package_id)
+        counts.add(count)
+        parts[index] = fragment[_HEADER.size:]
+
+    if len(package_ids) != 1 or len(counts) != 1:
+        raise ValueError('fragments belong to different packages')
+    if sorted(parts) != list(range(counts.pop())):
+        raise ValueError('all fragments are required')
+
+    package = b''.join(parts[index] for index in sorted(parts))
+    if len(package) <= _KEY_LEN:
+        raise ValueError('package is too short')
+    ciphertext, tail = package[:-_KEY_LEN], package[-_KEY_LEN:]
+    key = _xor(tail, hash_rust(ciphertext))
+
+    return decrypting_rust(ciphertext, code=key, mode=EncryptMode.CTR)
+
+
+def _xor(left: bytes, right: bytes) -> bytes:
+    return bytes(a ^ b for a, b in zip(left, right))
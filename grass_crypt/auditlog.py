@@ -0,0 +1,189 @@
+"""
+Зашифрованный журнал только для дописывания (аудит).
+
+Каждая запись — 4 байта длины (big-endian) и результат
+``interfaces.encrypt`` в режиме MGM: записи шифруются и проверяются по
+отдельности, поэтому повреждение одной не мешает читать остальные.
+Запись, прерванная сбоем, остаётся неполной в конце файла: ``read_log``
+её обнаруживает, а ``EncryptedLogWriter`` отрезает при открытии.
+
+С подключами периодов (``shredding.EpochKeys``) вместо кода записи
+шифруются ключом своего периода, и уничтожение подключа стирает записи
+периода без перезаписи журнала.
+"""
+import os
+import threading
+from pathlib import Path
+from typing import Iterator, Optional
+
+from .exceptions import KeyShreddedError, MetaStringError, TruncatedLogError
+from .interfaces import decrypt, encrypt
+from .shredding import EpochKeys
+from .tools import EncryptMode, MAX_RECORD, RECORD_HEADER
+
+
+class EncryptedLogWriter:
+    """Дописывание зашифрованных записей в журнал.
+
+    Безопасен для нескольких потоков: каждая запись добавляется одним
+    вызовом ``write`` файла, открытого на дописывание (``O_APPEND``).
+    Число байт неполной записи, отрезанных при открытии, — ``recovered``.
+
+    :param path: Файл журнала (создаётся при отсутствии).
+    :param code: Код шифрования.
+    :param keys: Подключи периодов вместо ``code``.
+    :param fsync: Сбрасывать каждую запись на диск (``os.fsync``); без
+                  этого — только в ``flush`` и ``close``.
+    :raises ValueError: Если не задан ровно один из ``code`` и ``keys``.
+    :raises MetaStringError: Если файл не является журналом.
+    """
+
+    def __init__(self,
+                 path: str | Path,
+                 *,
+                 code: Optional[str] = None,
+                 keys: Optional[EpochKeys] = None,
+                 fsync: bool = True):
+        _check_code(code, keys)
+        self.path = Path(path)
+        self._code = code
+        self._keys = keys
+        self._fsync = fsync
+        self._lock = threading.Lock()
+        flags = (os.O_RDWR | os.O_CREAT | os.O_APPEND
+                 | getattr(os, 'O_BINARY', 0))
+        self._fd = os.open(self.path, flags, 0o600)
+        try:
+            self.recovered = self._recover()
+        except BaseException:
+            os.close(self._fd)
+            raise
+        self.closed = False
+
+    def write(self, entry: str | bytes) -> None:
+        """Зашифровать и дописать запись."""
+        if self._keys is not None:
+            record = self._keys.seal(entry)
+        else:
+            record = encrypt(entry, code=self._code, mode=EncryptMode.MGM)
+        data = RECORD_HEADER.pack(len(record)) + record
+        with self._lock:
+            self._check_open()
+            os.write(self._fd, data)
+            if self._fsync:
+                os.fsync(self._fd)
+
+    def flush(self) -> None:
+        """Сбросить записанные данные на диск."""
+        with self._lock:
+            self._check_open()
+            os.fsync(self._fd)
+
+    def close(self) -> None:
+        with self._lock:
+            if self.closed:
+                return
+            os.fsync(self._fd)
+            os.close(self._fd)
+            self.closed = True
+
+    def __enter__(self) -> 'EncryptedLogWriter':
+        return self
+
+    def __exit__(self, *exc_info) -> None:
+        self.close()
+
+    def _recover(self) -> int:
+        """Отрезать неполную запись в конце журнала.
+
+        :returns:
+            Число отрезанных байт.
+        """
+        size = os.fstat(self._fd).st_size
+        end = _complete_end(self._fd, size)
+        if end < size:
+            os.ftruncate(self._fd, end)
+            os.fsync(self._fd)
+
+        return size - end
+
+    def _check_open(self) -> None:
+        if self.closed:
+            raise ValueError('I/O operation on closed log')
+
+
+def read_log(path: str | Path,
+             *,
+             code: Optional[str] = None,
+             keys: Optional[EpochKeys] = None,
+             truncated: str = 'error',
+             shredded: str = 'error') -> Iterator[str | bytes]:
+    """Прочитать записи журнала по порядку.
+
+    :param path: Файл журнала.
+    :param code: Код шифрования.
+    :param keys: Подключи периодов вместо ``code``.
+    :param truncated: Неполная запись в конце: ``error`` —
+                      ``TruncatedLogError`` после полных записей,
+                      ``ignore`` — пропустить.
+    :param shredded: Запись уничтоженного периода: ``error`` —
+                     ``KeyShreddedError``, ``ignore`` — пропустить.
+    :returns:
+        Итератор расшифрованных записей.
+    :raises MetaStringError: При повреждённой записи или неверном коде.
+    :raises TruncatedLogError: Если журнал заканчивается неполной записью.
+    :raises KeyShreddedError: Если подключ периода записи уничтожен.
+    """
+
+    _check_code(code, keys)
+    if truncated not in ('error', 'ignore'):
+        raise ValueError("truncated must be 'error' or 'ignore'")
+    if shredded not in ('error', 'ignore'):
+        raise ValueError("shredded must be 'error' or 'ignore'")
+
+    with open(path, 'rb') as source:
+        while header := source.read(RECORD_HEADER.size):
+            record = b''
+            if len(header) == RECORD_HEADER.size:
+                length = RECORD_HEADER.unpack(header)[0]
+                if not 0 < length <= MAX_RECORD:
+                    raise MetaStringError(f'invalid record length: {length}')
+                record = source.read(length)
+                if len(record) == length:
+                    if keys is None:
+                        yield decrypt(record, code=code)
+                        continue
+                    try:
+                        entry = keys.open(record)
+                    except KeyShreddedError:
+                        if shredded == 'error':
+                            raise
+                        continue
+                    yield entry
+                    continue
+            if truncated == 'error':
+                raise TruncatedLogError()
+            return
+
+
+def _check_code(code: Optional[str], keys: Optional[EpochKeys]) -> None:
+    if (code is None) == (keys is None):
+        raise ValueError('exactly one of code and keys must be given')
+    if keys is None and (not isinstance(code, str) or not code):
+        raise ValueError('code must be str and cannot be empty')
+
+
+def _complete_end(fd: int, size: int) -> int:
+    """Конец последней полной записи (по заголовкам длины)."""
+    offset = 0
+    while offset + RECORD_HEADER.size <= size:
+        os.lseek(fd, offset, os.SEEK_SET)
+        header = os.read(fd, RECORD_HEADER.size)
+        length = RECORD_HEADER.unpack(header)[0]
+        if not 0 < length <= MAX_RECORD:
+            raise MetaStringError(f'invalid record length: {length}')
+        if offset + RECORD_HEADER.size + length > size:
+            break
+        offset += RECORD_HEADER.size + length
+
+    return offset
@@ -0,0 +1,496 @@
+"""
+Фрагментированный контейнер с имитовставками и расшифровка по обращению.
+
+Формат:
+
+- заголовок: маркер ``GCK`` (3), версия (1), размер фрагмента (4), длина
+  открытых данных (8), соль (16), функция выработки ключа (1) и её
+  параметры (3: log2 n, r, p для scrypt) и имитовставка заголовка (32);
+- фрагменты: шифротекст (CTR, ключ свой для каждого фрагмента) и
+  имитовставка HMAC-Стрибог (32) над заголовком, номером и шифротекстом.
+
+Имитовставка фрагмента включает заголовок и номер, поэтому фрагменты нельзя
+переставить, перенести в другой контейнер или обрезать незаметно.
+
+Параметры выработки ключа защищены имитовставкой заголовка, поэтому видно,
+чем получен ключ контейнера, а ``upgrade_kdf`` перешифровывает контейнер с
+более стойкими параметрами. Контейнеры версии 1 (без параметров) используют
+blake2b.
+
+Имитовставки фрагментов независимы, поэтому ``EncryptedMmap.verify`` и
+``decrypt_chunked_file`` проверяют и расшифровывают фрагменты в пуле
+потоков: шифры и HMAC работают без GIL.
+"""
+import hmac
+import mmap
+import os
+import struct
+import tempfile
+from collections import deque
+from concurrent.futures import ThreadPoolExecutor
+from pathlib import Path
+from typing import Any, Callable, Iterable, Iterator, Optional, TypeVar
+
+from ._engine import decrypting_rust, encrypting_rust, mac_rust
+from .exceptions import AuthenticationError, MetaStringError
+from .interfaces import _TAG_LEN, _split_key
+from .tools import (EncryptMode, KDFS, SCRYPT_DEFAULTS, derive_key,
+                    get_salt)
+
+_MAGIC = b'GCK'
+_VERSION = 2
+_HEADER_V1 = struct.Struct('>3sBIQ16s')
+_HEADER = struct.Struct('>3sBIQ16sBBBB')
+_INDEX = struct.Struct('>Q')
+# Ограничение стоимости scrypt: защита от заголовка, требующего гигабайты.
+_SCRYPT_MAX_N = 2 ** 22
+DEFAULT_CHUNK_SIZE = 64 * 1024
+
+_T = TypeVar('_T')
+
+
+def encrypt_chunked_file(input_path: str | Path,
+                         output_path: str | Path,
+                         *,
+                         code: str,
+                         chunk_size: int = DEFAULT_CHUNK_SIZE,
+                         overwrite_output: bool = False,
+                         kdf: str = 'blake2b',
+                         kdf_params: Optional[dict[str, int]] = None
+                         ) -> Path:
+    """Зашифровать файл во фрагментированный контейнер.
+
+    Файл читается по фрагментам и целиком в память не загружается.
+
+    :param input_path: Исходный файл.
+    :param output_path: Файл контейнера.
+    :param code: Код шифрования.
+    :param chunk_size: Размер фрагмента открытых данных.
+    :param overwrite_output: Разрешить перезапись ``output_path``.
+    :param kdf: Функция выработки ключа из ``tools.KDFS``.
+    :param kdf_params: Параметры scrypt ``n`` (степень двойки), ``r``, ``p``.
+    :returns:
+        Путь к контейнеру.
+    :raises ValueError: При предоставлении неверных аргументов.
+    :raises FileExistsError: Если контейнер существует, а перезапись
+                             запрещена.
+    """
+
+    input_path, output_path = Path(input_path), Path(output_path)
+    if not isinstance(code, str) or not code:
+        raise ValueError('code must be str and cannot be empty')
+    if not 0 < chunk_size <= 0xFFFFFFFF:
+        raise ValueError('chunk_size must be a positive 32-bit int')
+    if not input_path.is_file():
+        raise FileNotFoundError('input_path does not exist or is not a file')
+    if not overwrite_output and output_path.exists():
+        raise FileExistsError('output path already exists')
+
+    kdf_fields = _kdf_fields(kdf, kdf_params)
+    with input_path.open('rb') as source, output_path.open('wb') as sink:
+        _write_container(iter(lambda: source.read(chunk_size), b''), sink,
+                         code=code, chunk_size=chunk_size,
+                         size=input_path.stat().st_size,
+                         kdf_fields=kdf_fields)
+
+    return output_path
+
+
+def decrypt_chunked_file(input_path: str | Path,
+                         output_path: str | Path,
+                         *,
+                         code: str,
+                         workers: Optional[int] = None,
+                         overwrite_output: bool = False) -> Path:
+    """Расшифровать фрагментированный контейнер в файл.
+
+    Сначала параллельно проверяются имитовставки всех фрагментов, поэтому
+    повреждённый контейнер отвергается до расшифровки и файл открытых данных
+    не создаётся. Затем фрагменты параллельно расшифровываются и
+    записываются по порядку; каждый фрагмент копируется из файла один раз и
+    перед расшифровкой проверяется повторно, поэтому изменение файла между
+    проходами обнаруживается, а неполный файл открытых данных удаляется.
+
+    :param input_path: Файл контейнера.
+    :param output_path: Файл открытых данных.
+    :param code: Код шифрования.
+    :param workers: Число потоков, по умолчанию ``os.cpu_count()``.
+    :param overwrite_output: Разрешить перезапись ``output_path``.
+    :returns:
+        Путь к файлу открытых данных.
+    :raises AuthenticationError: Если контейнер повреждён или код неверный.
+    :raises FileExistsError: Если файл существует, а перезапись запрещена.
+    """
+
+    output_path = Path(output_path)
+    if not overwrite_output and output_path.exists():
+        raise FileExistsError('output path already exists')
+
+    with EncryptedMmap(input_path, code=code) as view:
+        view.verify(workers=workers)
+        try:
+            with output_path.open('wb') as sink:
+                for data in _parallel(view._open_chunk, range(view.chunks),
+                                      workers):
+                    sink.write(data)
+        except BaseException:
+            output_path.unlink(missing_ok=True)
+            raise
+
+    return output_path
+
+
+def upgrade_kdf(path: str | Path,
+                *,
+                code: str,
+                kdf: str = 'scrypt',
+                kdf_params: Optional[dict[str, int]] = None) -> dict[str, Any]:
+    """Перешифровать контейнер с новыми параметрами выработки ключа.
+
+    Контейнер расшифровывается с проверкой имитовставок и записывается
+    во временный файл рядом, который затем атомарно заменяет исходный.
+
+    :param path: Файл контейнера.
+    :param code: Код шифрования.
+    :param kdf: Новая функция выработки ключа.
+    :param kdf_params: Её параметры.
+    :returns:
+        Новые параметры (как ``EncryptedMmap.kdf``).
+    :raises AuthenticationError: Если контейнер повреждён или код неверный.
+    """
+
+    path = Path(path)
+    kdf_fields = _kdf_fields(kdf, kdf_params)
+    with EncryptedMmap(path, code=code) as view:
+        chunks = (view.chunk(index) for index in range(view.chunks))
+        fd, temp_name = tempfile.mkstemp(dir=path.parent,
+                                         prefix=f'.{path.name}.')
+        try:
+            with os.fdopen(fd, 'wb') as sink:
+                _write_container(chunks, sink, code=code,
+                                 chunk_size=view.chunk_size, size=len(view),
+                                 kdf_fields=kdf_fields)
+            os.replace(temp_name, path)
+        except BaseException:
+            os.unlink(temp_name)
+            raise
+
+    with EncryptedMmap(path, code=code) as view:
+        return view.kdf
+
+
+class EncryptedMmap:
+    """Отображение контейнера в память с расшифровкой по обращению.
+
+    Поддерживает ``len()``, индексы и срезы, как ``bytes``. Расшифровываются
+    и проверяются только фрагменты, затронутые обращением; последний
+    фрагмент кешируется для последовательного чтения.
+
+    Атрибут ``kdf`` описывает выработку ключа: функцию, соль и параметры.
+    """
+
+    def __init__(self, path: str | Path, *, code: str):
+        self._file = open(path, 'rb')
+        try:
+            self._map = mmap.mmap(self._file.fileno(), 0,
+                                  access=mmap.ACCESS_READ)
+        except ValueError:
+            self._file.close()
+            raise MetaStringError('container is empty') from None
+        self._cached: tuple[int, bytes] = (-1, b'')
+        self.closed = False
+        try:
+            self._open(code)
+        except BaseException:
+            self.close()
+            raise
+
+    def _open(self, code: str) -> None:
+        """Проверить заголовок и подготовить ключи."""
+        self._header, info = _unpack_header(self._map)
+        chunk_size, size, self.kdf = (info['chunk_size'], info['size'],
+                                      info['kdf'])
+
+        key = derive_key(code, salt=self.kdf['salt'], kdf=self.kdf['kdf'],
+                         params=self.kdf.get('params'))
+        self._enc_key, self._mac_key = _split_key(key)
+        self._data_offset = len(self._header) + _TAG_LEN
+        tag = bytes(self._map[len(self._header):self._data_offset])
+        if not hmac.compare_digest(mac_rust(self._header, key=self._mac_key),
+                                   tag):
+            raise AuthenticationError('the authentication tag is invalid')
+
+        self._chunk_size, self._size = chunk_size, size
+        if len(self._map) != (self._data_offset + size
+                              + self.chunks * _TAG_LEN):
+            raise MetaStringError('container is truncated or extended')
+
+    def __len__(self) -> int:
+        return self._size
+
+    @property
+    def chunk_size(self) -> int:
+        return self._chunk_size
+
+    @property
+    def chunks(self) -> int:
+        """Количество фрагментов."""
+        return -(-self._size // self._chunk_size)
+
+    def __getitem__(self, index: int | slice) -> int | bytes:
+        self._check_open()
+        if isinstance(index, slice):
+            indices = range(*index.indices(self._size))
+            if not indices:
+                return b''
+            if indices.step == 1:
+                return self._read(indices.start, indices.stop)
+            low = min(indices[0], indices[-1])
+            data = self._read(low, max(indices[0], indices[-1]) + 1)
+            return bytes(data[i - low] for i in indices)
+        if index < 0:
+            index += self._size
+        if not 0 <= index < self._size:
+            raise IndexError('index out of range')
+
+        return self._read(index, index + 1)[0]
+
+    def chunk(self, index: int) -> bytes:
+        """Проверить и расшифровать фрагмент.
+
+        :raises IndexError: При отсутствии фрагмента.
+        :raises AuthenticationError: Если фрагмент повреждён.
+        """
+        self._check_open()
+        if self._cached[0] == index:
+            return self._cached[1]
+
+        data = self._open_chunk(index)
+        self._cached = (index, data)
+        return data
+
+    def verify(self, *, workers: Optional[int] = None) -> None:
+        """Проверить имитовставки всех фрагментов без расшифровки.
+
+        :param workers: Число потоков, по умолчанию ``os.cpu_count()``.
+        :raises AuthenticationError: Если фрагмент повреждён.
+        """
+        self._check_open()
+        for _ in _parallel(self._verify_chunk, range(self.chunks), workers):
+            pass
+
+    def close(self) -> None:
+        """Закрыть отображение и файл."""
+        if self.closed:
+            return
+        self.closed = True
+        self._cached = (-1, b'')
+        self._map.close()
+        self._file.close()
+
+    def __enter__(self) -> 'EncryptedMmap':
+        return self
+
+    def __exit__(self, *exc_info) -> None:
+        self.close()
+
+    def _chunk_data(self, index: int) -> tuple[bytes, bytes]:
+        """Шифротекст и имитовставка фрагмента."""
+        start = index * self._chunk_size
+        if not 0 <= start < self._size:
+            raise IndexError('chunk index out of range')
+
+        length = min(self._chunk_size, self._size - start)
+        offset = self._data_offset + index * (self._chunk_size + _TAG_LEN)
+        return (bytes(self._map[offset:offset + length]),
+                bytes(self._map[offset + length:offset + length + _TAG_LEN]))
+
+    def _verify_chunk(self, index: int) -> None:
+        """Проверить имитовставку фрагмента."""
+        self._check_tag(index, *self._chunk_data(index))
+
+    def _check_tag(self, index: int, ciphertext: bytes, tag: bytes) -> None:
+        expected = _chunk_tag(self._mac_key, self._header, index, ciphertext)
+        if not hmac.compare_digest(expected, tag):
+            raise AuthenticationError(f'chunk {index} authentication failed')
+
+    def _open_chunk(self, index: int) -> bytes:
+        """Проверить и расшифровать одну копию фрагмента.
+
+        Отображение может измениться вместе с файлом, поэтому проверяется и
+        расшифровывается одна и та же копия данных.
+        """
+        ciphertext, tag = self._chunk_data(index)
+        self._check_tag(index, ciphertext, tag)
+        return decrypting_rust(ciphertext,
+                               code=_chunk_key(self._enc_key, index),
+                               mode=EncryptMode.CTR)
+
+    def _read(self, start: int, stop: int) -> bytes:
+        """Открытые данные непустого диапазона ``start:stop``."""
+        parts = []
+        first, last = start // self._chunk_size, (stop - 1) // self._chunk_size
+        for index in range(first, last + 1):
+            base = index * self._chunk_size
+            data = self.chunk(index)
+            parts.append(data[max(start - base, 0):stop - base])
+
+        return b''.join(parts)
+
+    def _check_open(self) -> None:
+        if self.closed:
+            raise ValueError('I/O operation on closed container')
+
+
+def read_header(data: bytes) -> dict[str, Any]:
+    """Параметры контейнера из его начала без кода шифрования.
+
+    Имитовставка заголовка требует ключа и здесь не проверяется.
+
+    :param data: Начало контейнера, не короче заголовка с имитовставкой.
+    :returns:
+        Словарь: ``version`` — версия, ``chunk_size`` — размер фрагмента,
+        ``size`` — длина открытых данных, ``chunks`` — число фрагментов,
+        ``kdf`` — функция выработки ключа, соль и параметры (как
+        ``EncryptedMmap.kdf``).
+    :raises MetaStringError: Если данные — не контейнер или заголовок
+                             повреждён.
+    """
+    return _unpack_header(data)[1]
+
+
+def is_chunked(data: bytes) -> bool:
+    """Данные начинаются с маркера фрагментированного контейнера."""
+    return data[:len(_MAGIC)] == _MAGIC
+
+
+def _unpack_header(data) -> tuple[bytes, dict[str, Any]]:
+    """Заголовок контейнера (без имитовставки) и его поля.
+
+    :param data: Начало контейнера: bytes или ``mmap``.
+    """
+    if (len(data) < _HEADER_V1.size + _TAG_LEN
+            or data[:len(_MAGIC)] != _MAGIC):
+        raise MetaStringError('not a chunked container')
+    version = data[len(_MAGIC)]
+    if version == 1:
+        header_struct, kdf_fields = _HEADER_V1, (KDFS['blake2b'], 0, 0, 0)
+    elif version == _VERSION:
+        header_struct, kdf_fields = _HEADER, None
+    else:
+        raise MetaStringError(f'unsupported version: {version}')
+    if len(data) < header_struct.size + _TAG_LEN:
+        raise MetaStringError('container header is truncated')
+
+    header = bytes(data[:header_struct.size])
+    fields = header_struct.unpack(header)
+    chunk_size, size, salt = fields[2:5]
+    if chunk_size == 0:
+        raise MetaStringError('not a chunked container')
+
+    return header, {
+        'version': version,
+        'chunk_size': chunk_size,
+        'size': size,
+        'chunks': -(-size // chunk_size),
+        'kdf': _kdf_info(kdf_fields or fields[5:], salt),
+    }
+
+
+def _write_container(chunks, sink, *, code: str, chunk_size: int, size: int,
+                     kdf_fields: tuple[int, int, int, int]) -> None:
+    """Записать заголовок и фрагменты контейнера."""
+    salt = get_salt()
+    header = _HEADER.pack(_MAGIC, _VERSION, chunk_size, size, salt,
+                          *kdf_fields)
+    info = _kdf_info(kdf_fields, salt)
+    key = derive_key(code, salt=salt, kdf=info['kdf'],
+                     params=info.get('params'))
+    enc_key, mac_key = _split_key(key)
+
+    sink.write(header + mac_rust(header, key=mac_key))
+    for index, chunk in enumerate(chunks):
+        chunk_key = _chunk_key(enc_key, index)
+        ciphertext = encrypting_rust(chunk, code=chunk_key,
+                                     mode=EncryptMode.CTR)
+        sink.write(ciphertext + _chunk_tag(mac_key, header, index, ciphertext))
+
+
+def _parallel(func: Callable[[int], _T],
+              indices: Iterable[int],
+              workers: Optional[int]) -> Iterator[_T]:
+    """Результаты ``func`` для ``indices`` по порядку из пула потоков.
+
+    Заданий в очереди не больше удвоенного числа потоков, поэтому память не
+    зависит от числа фрагментов, а после первой ошибки оставшиеся задания
+    отменяются.
+
+    :raises ValueError: Если ``workers`` меньше 1.
+    """
+    if workers is None:
+        workers = os.cpu_count() or 1
+    if workers < 1:
+        raise ValueError('workers must be a positive int')
+
+    pool = ThreadPoolExecutor(max_workers=workers)
+    try:
+        pending = deque()
+        for index in indices:
+            pending.append(pool.submit(func, index))
+            if len(pending) >= 2 * workers:
+                yield pending.popleft().result()
+        while pending:
+            yield pending.popleft().result()
+    finally:
+        pool.shutdown(cancel_futures=True)
+
+
+def _kdf_fields(kdf: str,
+                params: Optional[dict[str, int]]) -> tuple[int, int, int, int]:
+    """Поля заголовка для функции выработки ключа.
+
+    :raises ValueError: При неизвестной функции или неверных параметрах.
+    """
+    if kdf not in KDFS:
+        raise ValueError(f'kdf must be one of {", ".join(KDFS)}')
+    if kdf == 'blake2b':
+        if params:
+            raise ValueError('blake2b has no cost parameters')
+        return KDFS[kdf], 0, 0, 0
+
+    scrypt = {**SCRYPT_DEFAULTS, **(params or {})}
+    n, r, p = scrypt['n'], scrypt['r'], scrypt['p']
+    if (not 2 <= n <= _SCRYPT_MAX_N or n & (n - 1)
+            or not 0 < r <= 255 or not 0 < p <= 255):
+        raise ValueError(f'scrypt needs n = 2**k from 2 to {_SCRYPT_MAX_N} '
+                         f'and r, p from 1 to 255')
+    return KDFS[kdf], n.bit_length() - 1, r, p
+
+
+def _kdf_info(fields: tuple[int, int, int, int],
+              salt: bytes) -> dict[str, Any]:
+    """Описание функции выработки ключа из полей заголовка."""
+    kdf_id, log_n, r, p = fields
+    names = {v: k for k, v in KDFS.items()}
+    if kdf_id not in names:
+        raise MetaStringError(f'unknown kdf: {kdf_id}')
+    info: dict[str, Any] = {'kdf': names[kdf_id], 'salt': salt}
+    if names[kdf_id] == 'scrypt':
+        if not 0 < log_n <= _SCRYPT_MAX_N.bit_length() - 1 or not r or not p:
+            raise MetaStringError('scrypt parameters are incorrect')
+        info['params'] = {'n': 2 ** log_n, 'r': r, 'p': p}
+
+    return info
+
+
+def _chunk_key(enc_key: bytes, index: int) -> bytes:
+    """Ключ шифрования фрагмента."""
+    return mac_rust(b'grass-chunk' + _INDEX.pack(index), key=enc_key)
+
+
+def _chunk_tag(mac_key: bytes,
+               header: bytes,
+               index: int,
+               ciphertext: bytes) -> bytes:
+    """Имитовставка фрагмента."""
+    return mac_rust(header + _INDEX.pack(index) + ciphertext, key=mac_key)
@@ -0,0 +1,160 @@
+"""
+Компактные кодировки для QR-кодов и ручной передачи небольших шифровок.
+
+- ``base45`` (RFC 9285) — алфавит QR-кода в режиме alphanumeric;
+- ``base32`` (RFC 4648, без ``=``) — удобна для диктовки и ввода вручную,
+  выводится группами по 4 символа через дефис.
+
+К данным добавляется контрольная сумма CRC32, а длинные строки можно
+разделить на пронумерованные части (``chunk``/``unchunk``).
+"""
+import base64
+import struct
+import zlib
+
+BASE45_ALPHABET = '0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:'
+_BASE45_INDEX = {c: i for i, c in enumerate(BASE45_ALPHABET)}
+_CRC = struct.Struct('>I')
+_GROUP = 4
+
+
+def to_base45(data: bytes) -> str:
+    """Кодирование base45 (RFC 9285)."""
+    out = []
+    for i in range(0, len(data), 2):
+        pair = data[i:i + 2]
+        if len(pair) == 2:
+            value = pair[0] * 256 + pair[1]
+            digits = 3
+        else:
+            value = pair[0]
+            digits = 2
+        for _ in range(digits):
+            value, rest = divmod(value, 45)
+            out.append(BASE45_ALPHABET[rest])
+
+    return ''.join(out)
+
+
+def from_base45(text: str) -> bytes:
+    """Декодирование base45 (RFC 9285).
+
+    :raises ValueError: При недопустимых символах или длине.
+    """
+    try:
+        values = [_BASE45_INDEX[c] for c in text]
+    except KeyError as err:
+        raise ValueError(f'invalid base45 character: {err}') from None
+    if len(values) % 3 == 1:
+        raise ValueError('invalid base45 length')
+
+    out = bytearray()
+    for i in range(0, len(values), 3):
+        group = values[i:i + 3]
+        value = sum(v * 45 ** n for n, v in enumerate(group))
+        if len(group) == 3:
+            if value > 0xFFFF:
+                raise ValueError('invalid base45 group')
+            out += value.to_bytes(2, 'big')
+        else:
+            if value > 0xFF:
+                raise ValueError('invalid base45 group')
+            out.append(value)
+
+    return bytes(out)
+
+
+def encode_compact(data: bytes,
+                   *,
+                   encoding: str = 'base45',
+                   checksum: bool = True) -> str:
+    """Закодировать данные компактной кодировкой.
+
+    :param data: Данные (например, результат ``encrypt`` или ключ).
+    :param encoding: ``base45`` или ``base32``.
+    :param checksum: Добавить контрольную сумму CRC32.
+    :returns:
+        Строка для QR-кода или ручной передачи.
+    :raises ValueError: При неизвестной кодировке.
+    """
+    if not isinstance(data, bytes) or not data:
+        raise ValueError('data must be bytes and cannot be empty')
+    if checksum:
+        data += _CRC.pack(zlib.crc32(data))
+
+    if encoding == 'base45':
+        return to_base45(data)
+    if encoding == 'base32':
+        text = base64.b32encode(data).decode('ascii').rstrip('=')
+        return '-'.join(text[i:i + _GROUP]
+                        for i in range(0, len(text), _GROUP))
+
+    raise ValueError("encoding must be 'base45' or 'base32'")
+
+
+def decode_compact(text: str,
+                   *,
+                   encoding: str = 'base45',
+                   checksum: bool = True) -> bytes:
+    """Декодировать строку ``encode_compact``.
+
+    Для base32 допускаются строчные буквы, пробелы, дефисы, а также цифры
+    ``0`` и ``1`` вместо похожих букв ``O`` и ``I``.
+
+    :raises ValueError: При ошибках формата или несовпадении контрольной
+                        суммы.
+    """
+    if encoding == 'base45':
+        data = from_base45(text.strip())
+    elif encoding == 'base32':
+        text = ''.join(text.split()).replace('-', '').upper()
+        text = text.replace('0', 'O').replace('1', 'I')
+        data = base64.b32decode(text + '=' * (-len(text) % 8))
+    else:
+        raise ValueError("encoding must be 'base45' or 'base32'")
+
+    if not checksum:
+        return data
+    if len(data) <= _CRC.size:
+        raise ValueError('data is too short for the checksum')
+    data, crc = data[:-_CRC.size], _CRC.unpack(data[-_CRC.size:])[0]
+    if zlib.crc32(data) != crc:
+        raise ValueError('checksum mismatch')
+
+    return data
+
+
+def chunk(text: str, size: int) -> list[str]:
+    """Разделить строку на части с префиксом ``номер/всего:``.
+
+    :param text: Строка ``encode_compact``.
+    :param size: Максимальная длина полезной части.
+    :returns:
+        Части для отдельных QR-кодов или сообщений.
+    """
+    if size < 1:
+        raise ValueError('size must be positive')
+    parts = [text[i:i + size] for i in range(0, len(text), size)] or ['']
+
+    return [f'{n}/{len(parts)}:{part}' for n, part in enumerate(parts, 1)]
+
+
+def unchunk(parts: list[str]) -> str:
+    """Собрать строку из частей ``chunk`` в любом порядке.
+
+    :raises ValueError: При неполном или повреждённом наборе частей.
+    """
+    found: dict[int, str] = {}
+    totals = set()
+    for part in parts:
+        prefix, sep, body = part.partition(':')
+        number, slash, total = prefix.partition('/')
+        if not sep or not slash or not number.isdigit() or not total.isdigit():
+            raise ValueError(f'invalid chunk: {part[:16]!r}')
+        found[int(number)] = body
+        totals.add(int(total))
+
+    if len(totals) != 1 or sorted(found) != list(range(1, totals.pop() + 1)):
+        raise ValueError('chunks are missing or inconsistent')
+
+    return ''.join(found[n] for n in sorted(found))
@@ -0,0 +1,135 @@
+"""
+Выборочное шифрование значений JSON-документов.
+
+Селекторы — упрощённый JSONPath: ``$.user.password``, ``cards[*].number``,
+``items[0].secret``, ``tokens.*``. Префикс ``$.`` необязателен.
+"""
+import copy
+import json
+import re
+from typing import Any, Iterable
+
+from .interfaces import decrypt, encrypt
+from .tools import EncryptMode
+
+# Ключ маркера зашифрованного значения.
+ENCRYPTED_MARKER = '$grass'
+
+_TOKEN_RE = re.compile(r'\[(\*|\d+)]|\.?([^.\[\]]+)')
+_WILDCARD = object()
+
+
+def encrypt_json(doc: Any,
+                 paths: Iterable[str],
+                 *,
+                 code: str,
+                 mode: EncryptMode = EncryptMode.ECB) -> Any:
+    """Зашифровать значения документа, выбранные селекторами.
+
+    Каждое найденное значение сериализуется в JSON, шифруется и заменяется
+    объектом ``{"$grass": "<шифровка>"}``. Исходный документ не изменяется.
+
+    :param doc: Документ (результат ``json.loads``).
+    :param paths: Селекторы значений для шифрования.
+    :param code: Код шифрования.
+    :param mode: Режим шифрования.
+    :returns:
+        Копия документа с зашифрованными значениями.
+    :raises ValueError: При неверном синтаксисе селектора.
+    """
+
+    doc = copy.deepcopy(doc)
+
+    def seal(value: Any) -> dict[str, str]:
+        if _is_encrypted(value):
+            return value
+        plaintext = json.dumps(value, ensure_ascii=False)
+        ciphertext = encrypt(plaintext, code=code, mode=mode)
+        return {ENCRYPTED_MARKER: ciphertext.decode('ascii')}
+
+    for path in paths:
+        tokens = _parse_path(path)
+        if not tokens:
+            doc = seal(doc)
+            continue
+        _apply(doc, tokens, seal)
+
+    return doc
+
+
+def decrypt_json(doc: Any, *, code: str) -> Any:
+    """Расшифровать все значения, зашифрованные ``encrypt_json``.
+
+    :param doc: Документ с зашифрованными значениями.
+    :param code: Код шифрования.
+    :returns:
+        Копия документа с исходными значениями.
+    :raises MetaStringError: Если предоставлен неверный код шифрования.
+    """
+
+    if _is_encrypted(doc):
+        return json.loads(
+            decrypt(doc[ENCRYPTED_MARKER].encode('ascii'), code=code))
+    if isinstance(doc, dict):
+        return {k: decrypt_json(v, code=code) for k, v in doc.items()}
+    if isinstance(doc, list):
+        return [decrypt_json(v, code=code) for v in doc]
+
+    return doc
+
+
+def _is_encrypted(value: Any) -> bool:
+    """Значение является маркером шифровки."""
+    return (isinstance(value, dict) and len(value) == 1
+            and isinstance(value.get(ENCRYPTED_MARKER), str))
+
+
+def _parse_path(path: str) -> list[Any]:
+    """Разобрать селектор на ключи, индексы и подстановки ``*``."""
+    if not isinstance(path, str):
+        raise ValueError('path must be str')
+
+    rest = path.strip()
+    if rest.startswith('$'):
+        rest = rest[1:]
+
+    tokens: list[Any] = []
+    position = 0
+    while position < len(rest):
+        match = _TOKEN_RE.match(rest, position)
+        if match is None:
+            raise ValueError(f'invalid path: {path!r}')
+        index, key = match.groups()
+        if index is not None:
+            tokens.append(_WILDCARD if index == '*' else int(index))
+        else:
+            tokens.append(_WILDCARD if key == '*' else key)
+        position = match.end()
+
+    return tokens
+
+
+def _apply(node: Any, tokens: list[Any], func) -> None:
+    """Заменить значения, найденные по ``tokens``, результатом ``func``."""
+    token, rest = tokens[0], tokens[1:]
+
+    if token is _WILDCARD:
+        if isinstance(node, dict):
+            keys = list(node)
+        elif isinstance(node, list):
+            keys = range(len(node))
+        else:
+            return
+    elif isinstance(node, dict) and isinstance(token, str) and token in node:
+        keys = [token]
+    elif (isinstance(node, list) and isinstance(token, int)
+          and token < len(node)):
+        keys = [token]
+    else:
+        return
+
+    for key in keys:
+        if rest:
+            _apply(node[key], rest, func)
+        else:
+            node[key] = func(node[key])
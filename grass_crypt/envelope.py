@@ -0,0 +1,126 @@
+"""
+Конвертное шифрование с ключами данных (схема KMS).
+
+Данные шифруются случайным ключом данных, а сам ключ хранится в шифровке
+обёрнутым мастер-ключом. Для расшифровки достаточно мастер-ключа, а смена
+мастер-ключа требует перешифровать только обёрнутые ключи.
+
+Формат: base64 от ``маркер GEV (3) + длина обёрнутого ключа (1) + обёрнутый
+ключ + шифровка encrypt`` (без base64).
+"""
+import base64
+from typing import Optional
+
+from ._engine import generate_data_key_rust, unwrap_data_key_rust
+from .exceptions import MetaStringError
+from .interfaces import _b64decode, decrypt, encrypt
+from .tools import DEFAULT_CIPHER, EncryptMode
+
+_MAGIC = b'GEV'
+DATA_KEY_LEN = 32
+
+
+def generate_data_key(master_key: bytes) -> tuple[bytes, bytes]:
+    """Создать ключ данных.
+
+    Ключ данных можно использовать для нескольких шифровок, а после
+    шифрования — удалить из памяти, сохранив только обёрнутый ключ.
+
+    :param master_key: Мастер-ключ.
+    :returns:
+        Кортеж: ключ данных (32 байта) и он же, обёрнутый мастер-ключом.
+    :raises ValueError: При пустом мастер-ключе.
+    """
+
+    _check_master_key(master_key)
+    return generate_data_key_rust(master_key)
+
+
+def unwrap_data_key(master_key: bytes, wrapped_key: bytes) -> bytes:
+    """Развернуть ключ данных мастер-ключом.
+
+    :raises AuthenticationError: Если ключ повреждён или мастер-ключ неверный.
+    """
+
+    _check_master_key(master_key)
+    return unwrap_data_key_rust(master_key, wrapped_key)
+
+
+def encrypt_with_data_key(plaintext: str | bytes,
+                          *,
+                          data_key: bytes,
+                          wrapped_key: bytes,
+                          mode: EncryptMode = EncryptMode.ECB,
+                          key_id: Optional[str | bytes] = None,
+                          cipher: str = DEFAULT_CIPHER) -> bytes:
+    """Зашифровать данные ключом данных и сохранить обёрнутый ключ.
+
+    :param plaintext: Данные для шифрования.
+    :param data_key: Ключ данных из ``generate_data_key``.
+    :param wrapped_key: Обёрнутый ключ данных из ``generate_data_key``.
+    :param mode: Режим шифрования.
+    :param key_id: Идентификатор (например, мастер-ключа) для метаданных.
+    :param cipher: Шифр.
+    :returns:
+        Зашифрованный текст bytes-строкой в формате ASCII.
+    :raises ValueError: При предоставлении неверных аргументов.
+    """
+
+    if not isinstance(data_key, bytes) or len(data_key) != DATA_KEY_LEN:
+        raise ValueError(f'data_key must be {DATA_KEY_LEN} bytes')
+    if not isinstance(wrapped_key, bytes) or not 1 <= len(wrapped_key) <= 255:
+        raise ValueError('wrapped_key must be bytes of 1 to 255 length')
+
+    inner = encrypt(plaintext, code=data_key.hex(), mode=mode, key_id=key_id,
+                    cipher=cipher)
+
+    return base64.b64encode(_MAGIC + bytes([len(wrapped_key)]) + wrapped_key
+                            + base64.b64decode(inner))
+
+
+def decrypt_with_master_key(ciphertext: bytes,
+                            *,
+                            master_key: bytes) -> str | bytes:
+    """Расшифровать данные ``encrypt_with_data_key`` мастер-ключом.
+
+    :param ciphertext: Результат ``encrypt_with_data_key``.
+    :param master_key: Мастер-ключ.
+    :returns:
+        Расшифрованные данные.
+    :raises MetaStringError: При повреждённой шифровке.
+    :raises AuthenticationError: Если мастер-ключ неверный.
+    """
+
+    if not isinstance(ciphertext, bytes) or not ciphertext:
+        raise ValueError('ciphertext must be bytes and cannot be empty')
+
+    wrapped_key, inner = split_envelope(ciphertext)
+    data_key = unwrap_data_key(master_key, wrapped_key)
+
+    return decrypt(base64.b64encode(inner), code=data_key.hex())
+
+
+def split_envelope(ciphertext: bytes) -> tuple[bytes, bytes]:
+    """Разделить шифровку на обёрнутый ключ и шифровку данных (без base64).
+
+    Позволяет перешифровать обёрнутый ключ новым мастер-ключом, не
+    расшифровывая данные.
+
+    :raises MetaStringError: При повреждённой шифровке.
+    """
+
+    raw = _b64decode(ciphertext)
+    if not raw.startswith(_MAGIC) or len(raw) <= len(_MAGIC) + 1:
+        raise MetaStringError('not an envelope ciphertext')
+    key_len = raw[len(_MAGIC)]
+    start = len(_MAGIC) + 1
+    wrapped_key, inner = raw[start:start + key_len], raw[start + key_len:]
+    if len(wrapped_key) != key_len or not inner:
+        raise MetaStringError('envelope ciphertext is truncated')
+
+    return wrapped_key, inner
+
+
+def _check_master_key(master_key: bytes) -> None:
+    if not isinstance(master_key, bytes) or not master_key:
+        raise ValueError('master_key must be bytes and cannot be empty')
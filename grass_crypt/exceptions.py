@@ -7,12 +7,19 @@ from typing import Optional
 class GrassCryptException(Exception):
     """
     Базовое исключение.
+
+    Исключения сериализуются pickle вместе с атрибутами, поэтому переживают
+    передачу из процессов ``multiprocessing`` и ``concurrent.futures``.
     """
+    message = 'An error occurred during encryption'
 
     def __init__(self, message: Optional[str] = None):
-        self.message = message or 'An error occurred during encryption'
+        self.message = message or type(self).message
         super().__init__(self.message)
 
+    def __reduce__(self):
+        return type(self), (self.message,), self.__dict__.copy()
+
 
 class MetaStringError(GrassCryptException):
     """
@@ -42,21 +49,32 @@ class BlockSizeError(GrassCryptException):
     message = 'The block size is invalid'
 
 
-class DataTooShortError(GrassCryptException):
+class DecryptionError(GrassCryptException):
+    """
+    Данные не дешифруются: повреждены, ключ или параметры неверные.
+
+    Причина (выравнивание, длина, имитовставка) намеренно не различается,
+    чтобы ошибка не служила оракулом (padding oracle). После
+    ``cryptor.set_debug_errors(True)`` возникают подклассы с причиной.
+    """
+    message = 'The data cannot be decrypted'
+
+
+class DataTooShortError(DecryptionError):
     """
     Данные слишком короткие для обработки.
     """
     message = 'The data is too short to process'
 
 
-class DataNotAlignedError(GrassCryptException):
+class DataNotAlignedError(DecryptionError):
     """
     Данные не выровнены по размеру блока.
     """
     message = 'The data is not aligned to block size'
 
 
-class InvalidPaddingError(GrassCryptException):
+class InvalidPaddingError(DecryptionError):
     """
     Неправильное выравнивание данных.
     """
@@ -75,3 +93,59 @@ class InvalidModeError(GrassCryptException):
     Неправильный режим шифрования/дешифрования.
     """
     message = 'The mode is invalid'
+
+
+class AuthenticationError(DecryptionError):
+    """
+    Имитовставка не совпала: данные повреждены или ключ неверный.
+    """
+    message = 'The authentication tag is invalid'
+
+
+class SelfTestError(GrassCryptException):
+    """
+    Контрольные примеры ГОСТ не прошли (``cryptor.run_self_test``): сборка
+    или платформа неисправны, шифрование использовать нельзя.
+    """
+    message = 'The self-test has failed'
+
+
+class AlreadyFinalized(GrassCryptException):
+    """
+    Контекст шифрования уже завершён.
+    """
+    message = 'Context was already finalized'
+
+
+class OperationTimeoutError(GrassCryptException):
+    """
+    Операция не завершилась за отведённое время (``timeout``).
+    """
+    message = 'The operation deadline has expired'
+
+
+class TruncatedLogError(GrassCryptException):
+    """
+    Журнал заканчивается неполной записью (прерванная запись).
+    """
+    message = 'The log ends with an incomplete record'
+
+
+class KeyShreddedError(GrassCryptException):
+    """
+    Подключ периода уничтожен: запись невосстановима.
+    """
+    message = 'The key of the record has been shredded'
+
+
+class PolicyError(GrassCryptException):
+    """
+    Ситуация запрещена политикой (``grass_crypt.policy``).
+    """
+    message = 'The operation is forbidden by the policy'
+
+
+class PolicyWarning(UserWarning):
+    """
+    Предупреждение политики (``grass_crypt.policy``).
+    """
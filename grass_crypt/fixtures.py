@@ -0,0 +1,133 @@
+"""
+Эталонные наборы данных для проверки других реализаций.
+
+``generate_fixtures`` записывает в каталог наборы (открытый текст, ключ,
+вектор инициализации, шифротекст) для ``cryptor.do_encrypt``. Данные
+выводятся из зерна, поэтому одно зерно всегда даёт одинаковые наборы::
+
+    python -m grass_crypt.fixtures fixtures/ --seed 00ff
+
+Формат каталога (версия 1), все байтовые значения — hex-строки:
+
+- ``index.json`` — ``{"format": 1, "seed": ..., "fixtures": [имена]}``;
+- ``<cipher>-<mode>-<size>.json`` — ``{"cipher", "mode", "plaintext",
+  "key", "iv", "ciphertext"}``. В ECB ``iv`` равен ``null``. В MGM ``iv`` —
+  nonce, ``ciphertext`` заканчивается имитовставкой (``tag_len`` байт), а
+  ``aad`` — дополнительные данные.
+"""
+import argparse
+import json
+from pathlib import Path
+from typing import Iterable
+
+from ._engine import encrypting_rust, mac_rust
+from .tools import BLOCK_LEN, DEFAULT_CIPHER, MGM_TAG_LEN, EncryptMode
+
+FORMAT_VERSION = 1
+DEFAULT_SIZES = (1, 15, 16, 17, 64, 1000)
+DEFAULT_SEED = b'grass-fixtures'
+
+
+def generate_fixtures(output_dir: str | Path,
+                      modes: Iterable[EncryptMode] = tuple(EncryptMode),
+                      sizes: Iterable[int] = DEFAULT_SIZES,
+                      seed: bytes = DEFAULT_SEED,
+                      *,
+                      cipher: str = DEFAULT_CIPHER) -> list[Path]:
+    """Записать эталонные наборы для всех сочетаний режимов и размеров.
+
+    :param output_dir: Каталог (создаётся при отсутствии); существующие
+                       наборы перезаписываются.
+    :param modes: Режимы шифрования.
+    :param sizes: Размеры открытого текста в байтах (от 1).
+    :param seed: Зерно, из которого выводятся все данные.
+    :param cipher: Шифр: ``kuznyechik``, ``aes256`` или ``magma`` (без
+                   MGM).
+    :returns:
+        Пути записанных наборов (без ``index.json``).
+    :raises ValueError: При пустом зерне или неверном размере.
+    """
+
+    modes, sizes = list(modes), list(sizes)
+    if not isinstance(seed, bytes) or not seed:
+        raise ValueError('seed must be bytes and cannot be empty')
+    if any(not isinstance(size, int) or size < 1 for size in sizes):
+        raise ValueError('sizes must be positive ints')
+
+    output_dir = Path(output_dir)
+    output_dir.mkdir(parents=True, exist_ok=True)
+    paths = []
+    for mode in modes:
+        for size in sizes:
+            fixture = _fixture(seed, cipher, mode, size)
+            path = output_dir / f'{cipher}-{mode.value}-{size}.json'
+            _write_json(path, fixture)
+            paths.append(path)
+
+    _write_json(output_dir / 'index.json', {
+        'format': FORMAT_VERSION,
+        'seed': seed.hex(),
+        'fixtures': [path.name for path in paths],
+    })
+
+    return paths
+
+
+def _fixture(seed: bytes, cipher: str, mode: EncryptMode, size: int) -> dict:
+    """Набор данных, выведенный из зерна и параметров."""
+    label = f'{cipher}/{mode.value}/{size}/'.encode('utf-8')
+    plaintext = _derive(seed, label + b'plaintext', size)
+    key = _derive(seed, label + b'key', 32)
+    iv = None
+    if mode is not EncryptMode.ECB:
+        iv = _derive(seed, label + b'iv', BLOCK_LEN[cipher])
+    fixture = {'cipher': cipher, 'mode': mode.value}
+
+    if mode is EncryptMode.MGM:
+        # Старший бит nonce MGM равен нулю.
+        iv = bytes([iv[0] & 0x7F]) + iv[1:]
+        aad = _derive(seed, label + b'aad', 16)
+        ciphertext = encrypting_rust(plaintext, code=key, mode=mode, iv=iv,
+                                     cipher=cipher, aad=aad)
+        fixture.update(tag_len=MGM_TAG_LEN, aad=aad.hex())
+    else:
+        ciphertext = encrypting_rust(plaintext, code=key, mode=mode, iv=iv,
+                                     cipher=cipher)
+
+    fixture.update(plaintext=plaintext.hex(), key=key.hex(),
+                   iv=iv.hex() if iv is not None else None,
+                   ciphertext=ciphertext.hex())
+    return fixture
+
+
+def _derive(seed: bytes, label: bytes, size: int) -> bytes:
+    """Детерминированные байты: HMAC-Стрибог в режиме счётчика."""
+    blocks = (size + 31) // 32
+    return b''.join(mac_rust(label + counter.to_bytes(4, 'big'), key=seed)
+                    for counter in range(blocks))[:size]
+
+
+def _write_json(path: Path, data: dict) -> None:
+    path.write_text(json.dumps(data, indent=2) + '\n', encoding='utf-8')
+
+
+if __name__ == '__main__':
+    parser = argparse.ArgumentParser(
+        description='Generate reference fixtures for cryptor.do_encrypt.')
+    parser.add_argument('output_dir')
+    parser.add_argument('--seed', default=DEFAULT_SEED.hex(),
+                        help='seed as a hex string')
+    parser.add_argument('--mode', action='append', dest='modes',
+                        choices=[mode.value for mode in EncryptMode])
+    parser.add_argument('--size', action='append', dest='sizes', type=int)
+    parser.add_argument('--cipher', default=DEFAULT_CIPHER)
+    args = parser.parse_args()
+
+    written = generate_fixtures(
+        args.output_dir,
+        modes=([EncryptMode(mode) for mode in args.modes] if args.modes
+               else tuple(EncryptMode)),
+        sizes=args.sizes or DEFAULT_SIZES,
+        seed=bytes.fromhex(args.seed),
+        cipher=args.cipher)
+    print(f'{len(written)} fixtures written to {args.output_dir}')
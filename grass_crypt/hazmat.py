@@ -0,0 +1,269 @@
+"""
+Адаптер в стиле ``cryptography.hazmat.primitives.ciphers``.
+
+Позволяет заменить, например, AES на "Кузнечик" с минимальными правками::
+
+    from grass_crypt.hazmat import Cipher, algorithms, modes
+
+    cipher = Cipher(algorithms.Kuznyechik(key), modes.ECB())
+    encryptor = cipher.encryptor()
+    ct = encryptor.update(data) + encryptor.finalize()
+
+Отличия от ``cryptography``:
+
+- данные накапливаются в контексте и обрабатываются в ``finalize()``,
+  поэтому ``update()`` возвращает пустую строку;
+- в режимах ECB и CBC движок сам добавляет выравнивание PKCS7.
+
+Незавершённый контекст можно сохранить ``export_state()`` и продолжить
+позже, в том числе в другом процессе, через ``Cipher.resume(state)``.
+Состояние зашифровано ключом алгоритма и привязано к направлению, режиму и
+вектору инициализации шифра.
+
+Без вектора режимы CBC, CFB, OFB и CTR используют встроенный вектор
+движка. Политика ``iv_policy='strict'`` запрещает это при шифровании, а при
+дешифровке допускает только с явным ``decryptor(legacy_iv=True)``, чтобы
+переходить на свои векторы, не теряя доступа к старым данным.
+"""
+import struct
+from types import SimpleNamespace
+from typing import Optional
+
+from . import policy
+from ._engine import decrypting_rust, encrypting_rust, mac_rust, open_rust, \
+    seal_rust
+from .exceptions import AlreadyFinalized, MetaStringError
+from .tools import EncryptMode, IV_POLICIES
+
+_STATE_MAGIC = b'GCS'
+_STATE_VERSION = 1
+_STATE_HEADER = struct.Struct('>3sB?')
+_STATE_CIPHER = 'chacha20poly1305'
+
+
+class Kuznyechik:
+    """Алгоритм "Кузнечик" (ГОСТ Р 34.12-2015)."""
+    name = 'kuznyechik'
+    block_size = 128
+    key_sizes = frozenset([256])
+
+    def __init__(self, key: bytes):
+        if not isinstance(key, bytes) or len(key) * 8 not in self.key_sizes:
+            raise ValueError('Invalid key size for Kuznyechik (must be 256)')
+        self.key = key
+
+    @property
+    def key_size(self) -> int:
+        return len(self.key) * 8
+
+
+class Mode:
+    """Базовый режим шифрования."""
+    name: str
+    encrypt_mode: EncryptMode
+
+    def __init__(self, initialization_vector: Optional[bytes] = None):
+        if initialization_vector is not None and (
+                not isinstance(initialization_vector, bytes)
+                or len(initialization_vector) * 8 != Kuznyechik.block_size):
+            raise ValueError(f'Invalid IV size for {self.name}')
+        self._iv = initialization_vector
+
+    @property
+    def initialization_vector(self) -> Optional[bytes]:
+        return self._iv
+
+
+class ECB(Mode):
+    name = 'ECB'
+    encrypt_mode = EncryptMode.ECB
+
+    def __init__(self):
+        super().__init__()
+
+
+class CBC(Mode):
+    name = 'CBC'
+    encrypt_mode = EncryptMode.CBC
+
+    def __init__(self, initialization_vector: Optional[bytes] = None):
+        super().__init__(initialization_vector)
+
+
+class CFB(Mode):
+    name = 'CFB'
+    encrypt_mode = EncryptMode.CFB
+
+    def __init__(self, initialization_vector: Optional[bytes] = None):
+        super().__init__(initialization_vector)
+
+
+class OFB(Mode):
+    name = 'OFB'
+    encrypt_mode = EncryptMode.OFB
+
+    def __init__(self, initialization_vector: Optional[bytes] = None):
+        super().__init__(initialization_vector)
+
+
+class CTR(Mode):
+    name = 'CTR'
+    encrypt_mode = EncryptMode.CTR
+
+    def __init__(self, nonce: Optional[bytes] = None):
+        super().__init__(nonce)
+
+    @property
+    def nonce(self) -> Optional[bytes]:
+        return self._iv
+
+
+class CipherContext:
+    """Контекст шифрования или дешифрования."""
+
+    def __init__(self, cipher: 'Cipher', encrypting: bool):
+        self._cipher = cipher
+        self._encrypting = encrypting
+        self._buffer: Optional[bytearray] = bytearray()
+
+    def update(self, data: bytes) -> bytes:
+        """Добавить данные в контекст.
+
+        :returns:
+            Пустая строка: результат возвращает ``finalize()``.
+        :raises AlreadyFinalized: Если контекст уже завершён.
+        """
+        if self._buffer is None:
+            raise AlreadyFinalized()
+        self._buffer += data
+
+        return b''
+
+    def finalize(self) -> bytes:
+        """Завершить контекст и получить результат.
+
+        :raises AlreadyFinalized: Если контекст уже завершён.
+        """
+        if self._buffer is None:
+            raise AlreadyFinalized()
+        data, self._buffer = bytes(self._buffer), None
+        if not data:
+            return b''
+
+        algorithm, mode = self._cipher.algorithm, self._cipher.mode
+        bridge = encrypting_rust if self._encrypting else decrypting_rust
+
+        return bridge(data, code=algorithm.key, mode=mode.encrypt_mode,
+                      iv=mode.initialization_vector)
+
+    def export_state(self) -> bytes:
+        """Сохранить состояние незавершённого контекста.
+
+        Контекст остаётся рабочим; состояние восстанавливает
+        ``Cipher.resume`` с тем же ключом, режимом и вектором.
+
+        :returns:
+            Непрозрачная строка состояния.
+        :raises AlreadyFinalized: Если контекст уже завершён.
+        """
+        if self._buffer is None:
+            raise AlreadyFinalized()
+        header = _STATE_HEADER.pack(_STATE_MAGIC, _STATE_VERSION,
+                                    self._encrypting)
+
+        return header + seal_rust(bytes(self._buffer),
+                                  code=self._cipher._state_key(),
+                                  cipher=_STATE_CIPHER,
+                                  aad=self._cipher._state_aad(header))
+
+
+class Cipher:
+    """Шифр: сочетание алгоритма и режима."""
+
+    def __init__(self,
+                 algorithm: Kuznyechik,
+                 mode: Mode,
+                 backend=None,
+                 *,
+                 iv_policy: str = 'legacy'):
+        if not isinstance(algorithm, Kuznyechik):
+            raise TypeError('algorithm must be Kuznyechik')
+        if not isinstance(mode, Mode):
+            raise TypeError('mode must be a grass_crypt.hazmat mode')
+        if iv_policy not in IV_POLICIES:
+            raise ValueError(
+                f'iv_policy must be one of {", ".join(IV_POLICIES)}')
+        self.algorithm = algorithm
+        self.mode = mode
+        self.iv_policy = iv_policy
+
+    def encryptor(self) -> CipherContext:
+        """Контекст шифрования.
+
+        :raises ValueError: Если политика ``strict`` требует вектор.
+        :raises PolicyError: Если ECB или встроенный вектор запрещает
+                             ``grass_crypt.policy``.
+        """
+        if self._uses_builtin_iv() and self.iv_policy == 'strict':
+            raise ValueError(
+                f'{self.mode.name} requires a caller-supplied IV '
+                f'under the strict IV policy')
+        if self.mode.encrypt_mode is EncryptMode.ECB:
+            policy.check('ecb', 'ECB mode reveals repeated blocks')
+        elif self._uses_builtin_iv():
+            policy.check('legacy_iv', f'{self.mode.name} uses the built-in IV')
+        return CipherContext(self, encrypting=True)
+
+    def decryptor(self, *, legacy_iv: bool = False) -> CipherContext:
+        """Контекст дешифрования.
+
+        :param legacy_iv: Разрешить встроенный вектор при политике
+                          ``strict`` для данных, зашифрованных до перехода.
+        :raises ValueError: Если политика ``strict`` требует вектор.
+        """
+        if (self._uses_builtin_iv() and self.iv_policy == 'strict'
+                and not legacy_iv):
+            raise ValueError(
+                f'{self.mode.name} requires a caller-supplied IV under the '
+                f'strict IV policy; pass legacy_iv=True for old data')
+        return CipherContext(self, encrypting=False)
+
+    def resume(self, state: bytes) -> CipherContext:
+        """Восстановить контекст из ``CipherContext.export_state``.
+
+        :param state: Строка состояния.
+        :returns:
+            Контекст того же направления с накопленными данными.
+        :raises MetaStringError: Если строка не является состоянием.
+        :raises AuthenticationError: Если состояние повреждено или сохранено
+                                     для другого ключа, режима или вектора.
+        """
+        if not isinstance(state, bytes) or len(state) < _STATE_HEADER.size:
+            raise MetaStringError('not a cipher context state')
+        header = state[:_STATE_HEADER.size]
+        magic, version, encrypting = _STATE_HEADER.unpack(header)
+        if magic != _STATE_MAGIC or version != _STATE_VERSION:
+            raise MetaStringError('not a cipher context state')
+
+        context = CipherContext(self, encrypting=encrypting)
+        context._buffer += open_rust(state[_STATE_HEADER.size:],
+                                     code=self._state_key(),
+                                     cipher=_STATE_CIPHER,
+                                     aad=self._state_aad(header))
+        return context
+
+    def _uses_builtin_iv(self) -> bool:
+        return (self.mode.encrypt_mode != EncryptMode.ECB
+                and self.mode.initialization_vector is None)
+
+    def _state_key(self) -> bytes:
+        return mac_rust(b'grass-context', key=self.algorithm.key)
+
+    def _state_aad(self, header: bytes) -> bytes:
+        return (header + self.mode.name.encode('ascii')
+                + (self.mode.initialization_vector or b''))
+
+
+# Пространства имён, повторяющие ``cryptography``.
+algorithms = SimpleNamespace(Kuznyechik=Kuznyechik)
+modes = SimpleNamespace(ECB=ECB, CBC=CBC, CFB=CFB, OFB=OFB, CTR=CTR)
@@ -2,30 +2,74 @@
 Интерфейс взаимодействия с инфраструктурой шифрования cryptor.
 """
 import base64
+import hmac
+import math
 import os
+from hashlib import blake2b
 from pathlib import Path
-from typing import Optional
+from typing import Optional, Any
 
-from ._engine import encrypting_rust, decrypting_rust
-from .exceptions import MetaStringError
-from .tools import EncryptMode, get_hash_blake2b, make_meta, read_meta
+from . import policy
+from ._engine import (encrypting_rust, decrypting_rust, ciphertext_len_rust,
+                      mac_rust, mac_file_rust, open_rust, reencrypting_rust,
+                      seal_rust)
+from .exceptions import (AuthenticationError, MetaStringError,
+                         OperationTimeoutError)
+from .tools import (AEAD_CIPHERS, AEAD_NONCE_LEN, AEAD_TAG_LEN, BLOCK_LEN,
+                    CIPHERS, DEFAULT_CIPHER, EncryptMode, KEY_ID_LEN,
+                    MAX_RECORD, META_VERSION, MGM_NONCE_LEN, MGM_TAG_LEN,
+                    RECORD_HEADER,
+                    TRAILING_POLICIES, armor_lines, dearmor_lines,
+                    get_hash_blake2b, get_key_id, header_tag, is_armored,
+                    make_meta, meta_len, read_meta, split_message)
+
+
+# Сколько байт начала шифровки достаточно для чтения метаданных: base64
+# от максимальных метаданных (299 байт) с запасом на "броню".
+_HEADER_PROBE = 1024
 
 
 def encrypt(plaintext: str | bytes,
             *,
             code: str,
             mode: EncryptMode = EncryptMode.ECB,
+            key_id: Optional[str | bytes] = None,
+            cipher: str = DEFAULT_CIPHER,
+            seed: Optional[bytes] = None,
+            timeout: Optional[float] = None,
             ) -> bytes:
     """Зашифровать предоставленные данные.
 
     :param plaintext: Данные для шифрования, текст или bytes-объект.
     :param code: Код шифрования.
     :param mode: Режим шифрования.
+    :param key_id: Идентификатор ключа для метаданных (до 255 байт). Если
+                   не предоставлен, вычисляется отпечаток ключа шифровки:
+                   он разный для каждой шифровки. Метаданные, в том числе
+                   идентификатор, защищены имитовставкой во всех режимах.
+    :param cipher: Шифр: ``kuznyechik``, ``aes256`` (для сравнения и
+                   совместимости), ``magma`` (блок 8 байт, без MGM) или
+                   ``chacha20poly1305`` (с аутентификацией, для систем без
+                   ГОСТ). Сохраняется в метаданных. Для шифров с
+                   аутентификацией ``mode`` не используется, а метаданные
+                   защищены имитовставкой.
+    :param seed: Секретное зерно для воспроизводимого результата (сборка
+                 подписываемых артефактов): соль и одноразовый номер
+                 выводятся из зерна и данных, поэтому одинаковые данные,
+                 код и параметры дают побайтно одинаковую шифровку. Метки
+                 времени контейнер не содержит. Одинаковые шифровки
+                 раскрывают совпадение данных, поэтому без необходимости
+                 зерно не используется.
+    :param timeout: Ограничение времени шифрования в секундах (для шифров с
+                    аутентификацией и MGM не поддерживается).
     :returns:
         Зашифрованный текст bytes-строкой в формате ASCII.
     :raises UnicodeEncodeErrors: При ошибках декодирования строковых значений
                                  в байт-строки.
     :raises ValueError: При предоставлении неверных аргументов.
+    :raises OperationTimeoutError: Если время истекло.
+    :raises PolicyError: Если ситуацию запрещает ``grass_crypt.policy``
+                         (слабый код, ECB, встроенный вектор).
     """
 
     def validate_inputs_data() -> None:
@@ -39,51 +83,475 @@ def encrypt(plaintext: str | bytes,
             raise ValueError('code must be str and cannot be empty')
         if not isinstance(mode, EncryptMode):
             raise ValueError('mode must be an instance of EncryptMode')
+        if key_id is not None and (
+                not isinstance(key_id, (str, bytes)) or not key_id):
+            raise ValueError('key_id must be str, bytes and cannot be empty')
+        if cipher not in CIPHERS:
+            raise ValueError(f'cipher must be one of {", ".join(CIPHERS)}')
+        if seed is not None and (not isinstance(seed, bytes) or not seed):
+            raise ValueError('seed must be bytes and cannot be empty')
+        if timeout is not None:
+            _check_timeout(cipher, mode)
 
     validate_inputs_data()
+    policy.check_code(code)
+    if cipher not in AEAD_CIPHERS:
+        if mode is EncryptMode.ECB:
+            policy.check('ecb', 'ECB mode reveals repeated blocks')
+        elif mode is not EncryptMode.MGM:
+            policy.check('legacy_iv', f'{mode.value} uses the built-in IV')
 
-    hash_code, salt = get_hash_blake2b(code)
     plaintext_type = type(plaintext)
     if isinstance(plaintext, str):
         plaintext = plaintext.encode('utf-8')
-    encoded_data = encrypting_rust(plaintext, code=hash_code, mode=mode)
     # make metadata
-    meta = make_meta(plaintext_type=plaintext_type, salt=salt, mode=mode)
+    if isinstance(key_id, str):
+        key_id = key_id.encode('utf-8')
+    salt = nonce = None
+    if seed is not None:
+        # Зерно охватывает и параметры: иначе одинаковые данные в разных
+        # режимах дали бы один ключ и одноразовый номер.
+        salt, nonce = _seeded(seed, make_meta(
+            plaintext_type=plaintext_type, salt=bytes(16), mode=mode,
+            key_id=key_id or bytes(KEY_ID_LEN), cipher=cipher) + plaintext)
+    hash_code, salt = get_hash_blake2b(code, salt=salt)
+    meta = make_meta(plaintext_type=plaintext_type, salt=salt, mode=mode,
+                     key_id=key_id or get_key_id(hash_code), cipher=cipher)
+    meta += header_tag(meta, hash_code)
+    if cipher in AEAD_CIPHERS:
+        encoded_data = seal_rust(plaintext, code=hash_code, cipher=cipher,
+                                 aad=meta, nonce=nonce)
+    elif mode is EncryptMode.MGM:
+        # Метаданные защищены имитовставкой, как у шифров с аутентификацией.
+        # Nonce из зерна записывается там же, где и случайный.
+        mgm_nonce = None
+        if seed is not None:
+            mgm_nonce = bytes([nonce[0] & 0x7F]) + nonce[1:].ljust(
+                MGM_NONCE_LEN - 1, b'\0')
+        encoded_data = (mgm_nonce or b'') + encrypting_rust(
+            plaintext, code=hash_code, mode=mode, iv=mgm_nonce,
+            cipher=cipher, aad=meta)
+    else:
+        encoded_data = encrypting_rust(plaintext, code=hash_code, mode=mode,
+                                       cipher=cipher, timeout=timeout)
 
     return base64.b64encode(meta + encoded_data)
 
 
-def decrypt(ciphertext: bytes, *, code: str) -> str | bytes:
+def decrypt(ciphertext: bytes,
+            *,
+            code: str,
+            trailing: str = 'strict',
+            timeout: Optional[float] = None,
+            ) -> str | bytes | tuple[str | bytes, bytes]:
     """Расшифровать предоставленный байт-массив.
 
-    :param ciphertext: Данные для расшифровки.
+    :param ciphertext: Данные для расшифровки (в том числе в "броне").
     :param code: Код шифрования.
+    :param trailing: Данные после сообщения: ``strict`` — ошибка,
+                     ``lenient`` — возвращаются вызывающей стороне (для
+                     разбора потока сообщений, разделённых пробелами).
+    :param timeout: Ограничение времени дешифровки в секундах (см.
+                    ``encrypt``).
     :returns:
-        Строковое или байтовое представление расшифрованного текста.
+        Строковое или байтовое представление расшифрованного текста, для
+        ``lenient`` — кортеж из него и остатка данных.
     :raises ValueError: При предоставлении неверных аргументов.
-    :raises MetaStringError: Если предоставлен неверный код шифрования.
+    :raises MetaStringError: Если предоставлен неверный код шифрования,
+                             метаданные изменены или после сообщения есть
+                             данные (``strict``).
+    :raises OperationTimeoutError: Если время истекло.
     """
 
     if not isinstance(ciphertext, bytes) or not ciphertext:
         raise ValueError('ciphertext must be bytes and cannot be empty')
+    _check_trailing(trailing)
+
+    ciphertext, rest = split_message(ciphertext)
+    if rest and trailing == 'strict':
+        raise MetaStringError('unexpected data after the message')
 
-    ciphertext = base64.b64decode(ciphertext)
-    error, ciphertext, meta_data = read_meta(ciphertext=ciphertext)
+    raw = _b64decode(ciphertext)
+    error, ciphertext, meta_data = read_meta(ciphertext=raw)
     if error is not None:
         raise MetaStringError(str(error)) from error
+    cipher = meta_data['cipher']
+    if timeout is not None:
+        _check_timeout(cipher, meta_data['mode'])
     hash_code = get_hash_blake2b(code, salt=meta_data['salt'])[0]
+    _check_header(meta_data, hash_code)
 
+    err_msg, timed_out = None, False
     try:
-        decoded = decrypting_rust(
-            ciphertext, code=hash_code, mode=meta_data['mode'])
+        if cipher in AEAD_CIPHERS:
+            meta = raw[:len(raw) - len(ciphertext)]
+            decoded = open_rust(ciphertext, code=hash_code, cipher=cipher,
+                                aad=meta)
+        else:
+            aad = None
+            if meta_data['mode'] is EncryptMode.MGM:
+                aad = raw[:len(raw) - len(ciphertext)]
+            decoded = decrypting_rust(ciphertext, code=hash_code,
+                                      mode=meta_data['mode'], cipher=cipher,
+                                      aad=aad, timeout=timeout)
+    except OperationTimeoutError:
+        timed_out = True
     except Exception as err:
-        err_msg = f'decryption failed: {err}'
-        raise MetaStringError(err_msg) from err
+        err_msg = f'decryption failed: {type(err).__name__}: {err}'
+    # Исключение поднимается вне блока except: трассировка исходной ошибки
+    # содержит кадры с ключом и не должна попасть в __context__.
+    del hash_code
+    if timed_out:
+        raise OperationTimeoutError()
+    if err_msg is not None:
+        raise MetaStringError(err_msg)
+
+    if meta_data['source_type'] != bytes:
+        decoded = decoded.decode('utf-8')
+
+    return (decoded, rest) if trailing == 'lenient' else decoded
+
+
+def upgrade_container(blob: bytes,
+                      *,
+                      code: str,
+                      target_version: int = META_VERSION) -> bytes:
+    """Перевести шифровку старого формата в текущий с аутентификацией.
+
+    Шифровки без аутентификации (в том числе со встроенным вектором
+    инициализации) и шифровки MGM версий до 4 перешифровываются в режиме
+    MGM с новой солью, метаданные защищаются имитовставкой. Дешифровка и
+    шифрование выполняются на стороне Rust, открытый текст в Python не
+    попадает. Тип данных и явный идентификатор ключа сохраняются, а
+    отпечаток кодовой фразы версий 2–3 (и отсутствующий идентификатор
+    версии 1) заменяется отпечатком нового ключа. Шифровки MGM текущей
+    версии и шифровки с аутентификацией версий 3–4 возвращаются без
+    изменений.
+
+    :param blob: Результат ``encrypt`` (в том числе в "броне").
+    :param code: Код шифрования.
+    :param target_version: Версия метаданных результата; поддерживается
+                           только текущая (``META_VERSION``).
+    :returns:
+        Шифровка в формате ``encrypt`` с режимом MGM.
+    :raises ValueError: При неверных аргументах или шифре ``magma`` (MGM
+                        требует блок 16 байт).
+    :raises MetaStringError: Если метаданные повреждены или код неверен.
+    """
+
+    if not isinstance(blob, bytes) or not blob:
+        raise ValueError('blob must be bytes and cannot be empty')
+    if not isinstance(code, str) or not code:
+        raise ValueError('code must be str and cannot be empty')
+    if target_version != META_VERSION:
+        raise ValueError(f'target_version must be {META_VERSION}')
+
+    message, rest = split_message(blob)
+    if rest:
+        raise MetaStringError('unexpected data after the message')
+    raw = _b64decode(message)
+    error, ciphertext, meta_data = read_meta(ciphertext=raw)
+    if error is not None:
+        raise MetaStringError(str(error)) from error
+    cipher, mode = meta_data['cipher'], meta_data['mode']
+    version = meta_data['version']
+    if (version == META_VERSION and mode is EncryptMode.MGM) or (
+            version >= 3 and cipher in AEAD_CIPHERS):
+        return blob
+    if cipher not in ('kuznyechik', 'aes256'):
+        raise ValueError(f'{cipher} containers cannot be upgraded to MGM')
+
+    hash_code = get_hash_blake2b(code, salt=meta_data['salt'])[0]
+    _check_header(meta_data, hash_code)
+    new_hash_code, salt = get_hash_blake2b(code)
+    key_id = meta_data['key_id']
+    if key_id is None or key_id == _legacy_key_id(code):
+        key_id = get_key_id(new_hash_code)
+    meta = make_meta(plaintext_type=meta_data['source_type'], salt=salt,
+                     mode=EncryptMode.MGM, key_id=key_id, cipher=cipher)
+    meta += header_tag(meta, new_hash_code)
+    aad = None
+    if mode is EncryptMode.MGM:
+        aad = raw[:len(raw) - len(ciphertext)]
+
+    err_msg = None
+    try:
+        encoded_data = reencrypting_rust(
+            ciphertext, code=hash_code, mode=mode, new_code=new_hash_code,
+            new_mode=EncryptMode.MGM, cipher=cipher, aad=aad, new_aad=meta)
+    except Exception as err:
+        err_msg = f'decryption failed: {type(err).__name__}: {err}'
+    # Как в decrypt: трассировка с ключами не попадает в __context__.
+    del hash_code, new_hash_code
+    if err_msg is not None:
+        raise MetaStringError(err_msg)
+
+    return base64.b64encode(meta + encoded_data)
+
+
+def encrypt_record(plaintext: str | bytes,
+                   *,
+                   code: str,
+                   mode: EncryptMode = EncryptMode.ECB) -> bytes:
+    """Зашифровать данные записью потокового протокола.
+
+    :returns:
+        4 байта длины (big-endian) и результат ``encrypt``.
+    """
+
+    record = encrypt(plaintext, code=code, mode=mode)
+    return RECORD_HEADER.pack(len(record)) + record
+
+
+def decrypt_record(data: bytes,
+                   *,
+                   code: str,
+                   trailing: str = 'strict'
+                   ) -> str | bytes | tuple[str | bytes, bytes]:
+    """Расшифровать запись ``encrypt_record``.
+
+    :param data: Данные, начинающиеся с записи.
+    :param code: Код шифрования.
+    :param trailing: Данные после записи: ``strict`` — ошибка,
+                     ``lenient`` — возвращаются вызывающей стороне.
+    :returns:
+        Расшифрованные данные, для ``lenient`` — кортеж из них и остатка.
+    :raises MetaStringError: При неполной или повреждённой записи, неверном
+                             коде или данных после записи (``strict``).
+    """
+
+    if not isinstance(data, bytes):
+        raise ValueError('data must be bytes')
+    _check_trailing(trailing)
+    if len(data) < RECORD_HEADER.size:
+        raise MetaStringError('record is truncated')
+
+    length = RECORD_HEADER.unpack_from(data)[0]
+    end = RECORD_HEADER.size + length
+    if not 0 < length <= MAX_RECORD:
+        raise MetaStringError(f'invalid record length: {length}')
+    if len(data) < end:
+        raise MetaStringError('record is truncated')
+    if trailing == 'strict' and len(data) > end:
+        raise MetaStringError('unexpected data after the record')
+
+    decoded = decrypt(data[RECORD_HEADER.size:end], code=code)
+    return (decoded, data[end:]) if trailing == 'lenient' else decoded
+
+
+def peek_header(blob_or_path: bytes | str | Path) -> dict[str, Any]:
+    """Прочитать метаданные шифровки без кода шифрования.
+
+    Для файлов читается только начало, достаточное для метаданных.
+    Имитовставка метаданных без ключа не проверяется: ``key_id`` годится для
+    выбора ключа, подлинность подтверждает ``decrypt``. Фрагментированные
+    контейнеры (``chunked``) распознаются по маркеру ``GCK``.
+
+    :param blob_or_path: Результат ``encrypt`` (bytes, в том числе в
+                         "броне"), контейнер ``chunked`` или путь к
+                         зашифрованному файлу.
+    :returns:
+        Словарь: ``version`` — версия метаданных, ``cipher`` — шифр,
+        ``mode`` — режим шифрования, ``source_type`` — тип исходных данных,
+        ``key_id`` — идентификатор ключа (``None`` для шифровок версии 1 и
+        контейнеров ``chunked``), ``kdf`` — параметры получения ключа из
+        кодовой фразы (алгоритм ``name``, размер ключа ``digest_size``,
+        соль ``salt``, для scrypt — ``params``), ``chunks`` — разбиение на
+        фрагменты (``None``: сообщение шифруется целиком; иначе размер
+        фрагмента ``size``, их число ``count`` и длина открытых данных
+        ``length``), ``armored`` — признак "брони".
+    :raises ValueError: При предоставлении неверных аргументов.
+    :raises FileNotFoundError: При отсутствии файла.
+    :raises MetaStringError: При повреждённых метаданных.
+    """
+
+    if isinstance(blob_or_path, (str, Path)):
+        path = Path(blob_or_path)
+        if not path.is_file():
+            raise FileNotFoundError('path does not exist or is not a file')
+        with path.open('rb') as file:
+            blob = file.read(_HEADER_PROBE)
+    elif isinstance(blob_or_path, bytes) and blob_or_path:
+        blob = blob_or_path[:_HEADER_PROBE]
+    else:
+        raise ValueError('blob_or_path must be non-empty bytes, str or Path')
+
+    # Импорт здесь: chunked использует ключи из этого модуля.
+    from .chunked import is_chunked, read_header
+    if is_chunked(blob):
+        info = read_header(blob)
+        kdf = {'name': info['kdf']['kdf'], 'digest_size': 32,
+               'salt': info['kdf']['salt']}
+        if 'params' in info['kdf']:
+            kdf['params'] = info['kdf']['params']
+        return {
+            'version': info['version'],
+            'cipher': DEFAULT_CIPHER,
+            'mode': EncryptMode.CTR,
+            'source_type': bytes,
+            'key_id': None,
+            'kdf': kdf,
+            'chunks': {
+                'size': info['chunk_size'],
+                'count': info['chunks'],
+                'length': info['size'],
+            },
+            'armored': False,
+        }
+
+    armored = is_armored(blob)
+    if armored:
+        body = b''.join(line.strip() for line in blob.splitlines()[1:]
+                        if not line.startswith(b'-----'))
+    else:
+        body = blob.strip()
+    body = body[:len(body) // 4 * 4]
+
+    error, _, meta_data = read_meta(ciphertext=_b64decode(body))
+    if error is not None:
+        raise MetaStringError(str(error)) from error
+
+    return {
+        'version': meta_data['version'],
+        'cipher': meta_data['cipher'],
+        'mode': meta_data['mode'],
+        'source_type': meta_data['source_type'],
+        'key_id': meta_data['key_id'],
+        'kdf': {
+            'name': 'blake2b',
+            'digest_size': 32,
+            'salt': meta_data['salt'],
+        },
+        'chunks': None,
+        'armored': armored,
+    }
+
+
+def seal_ephemeral(data: bytes,
+                   *,
+                   mode: EncryptMode = EncryptMode.CTR) -> tuple[bytes, bytes]:
+    """Зашифровать данные случайным одноразовым ключом с имитовставкой.
+
+    Ключ возвращается отдельно, чтобы передать его по другому каналу.
+    Из ключа выводятся ключи шифрования и HMAC-Стрибог, схема
+    encrypt-then-MAC: ``режим (3) + шифротекст + имитовставка (32)``.
+
+    :param data: Данные для шифрования.
+    :param mode: Режим шифрования.
+    :returns:
+        Кортеж: ключ (32 байта) и запечатанные данные.
+    :raises ValueError: При предоставлении неверных аргументов.
+    """
+
+    if not isinstance(data, bytes) or not data:
+        raise ValueError('data must be bytes and cannot be empty')
+    if not isinstance(mode, EncryptMode):
+        raise ValueError('mode must be an instance of EncryptMode')
+
+    key = os.urandom(32)
+    enc_key, mac_key = _split_key(key)
+    body = mode.as_bytes() + encrypting_rust(data, code=enc_key, mode=mode)
+
+    return key, body + mac_rust(body, key=mac_key)
+
+
+def open_ephemeral(key: bytes, sealed: bytes) -> bytes:
+    """Проверить и расшифровать данные, запечатанные ``seal_ephemeral``.
 
-    if meta_data['source_type'] == bytes:
-        return decoded
+    :param key: Ключ, полученный от ``seal_ephemeral``.
+    :param sealed: Запечатанные данные.
+    :returns:
+        Исходные данные.
+    :raises ValueError: При предоставлении неверных аргументов.
+    :raises AuthenticationError: Если данные повреждены или ключ неверный.
+    """
+
+    if not isinstance(key, bytes) or len(key) != 32:
+        raise ValueError('key must be 32 bytes')
+    if not isinstance(sealed, bytes) or len(sealed) <= 3 + _TAG_LEN:
+        raise AuthenticationError('sealed data is too short')
+
+    enc_key, mac_key = _split_key(key)
+    body, tag = sealed[:-_TAG_LEN], sealed[-_TAG_LEN:]
+    if not hmac.compare_digest(mac_rust(body, key=mac_key), tag):
+        raise AuthenticationError('the authentication tag is invalid')
+
+    mode = EncryptMode.me_from_value(body[:3].decode('utf-8'))
+    return decrypting_rust(body[3:], code=enc_key, mode=mode)
+
+
+def encrypted_len(plaintext_len: int,
+                  *,
+                  mode: EncryptMode = EncryptMode.ECB,
+                  key_id_len: int = KEY_ID_LEN,
+                  cipher: str = DEFAULT_CIPHER) -> int:
+    """Точный размер результата ``encrypt`` для данных заданной длины.
+
+    Учитывает выравнивание режима, строку метаданных и кодирование base64.
+
+    :param plaintext_len: Длина открытого текста в байтах (для строк —
+                          длина в кодировке UTF-8).
+    :param mode: Режим шифрования.
+    :param key_id_len: Длина идентификатора ключа в метаданных.
+    :param cipher: Шифр.
+    :returns:
+        Длина зашифрованной bytes-строки.
+    :raises ValueError: При отрицательной или слишком большой длине.
+    """
+
+    if not isinstance(plaintext_len, int) or plaintext_len < 0:
+        raise ValueError('plaintext_len must be a non-negative int')
+
+    if cipher in AEAD_CIPHERS:
+        body_len = AEAD_NONCE_LEN + plaintext_len + AEAD_TAG_LEN
     else:
-        return decoded.decode('utf-8')
+        body_len = ciphertext_len_rust(plaintext_len, mode=mode,
+                                       cipher=cipher)
+    raw_len = meta_len(key_id_len) + body_len
+    return 4 * math.ceil(raw_len / 3)
+
+
+def overhead(mode: EncryptMode = EncryptMode.ECB,
+             data_format: str = 'container',
+             cipher: str = DEFAULT_CIPHER) -> dict[str, Any]:
+    """Описание расширения данных при шифровании.
+
+    Форматы:
+
+    - ``raw`` — шифротекст ``cryptor.do_encrypt`` без метаданных;
+    - ``container`` — результат ``encrypt`` (метаданные и base64).
+
+    :param mode: Режим шифрования.
+    :param data_format: Формат результата: ``raw`` или ``container``.
+    :param cipher: Шифр.
+    :returns:
+        Словарь: ``header`` — фиксированный заголовок в байтах, ``iv`` и
+        ``tag`` — байты вектора инициализации и имитовставки в результате,
+        ``padding_max`` — максимальное выравнивание, ``per_chunk`` —
+        накладные расходы на каждый фрагмент данных, ``encoding`` —
+        внешнее кодирование (``None`` или ``base64``).
+    :raises ValueError: При неизвестном формате.
+    """
+
+    if not isinstance(mode, EncryptMode):
+        raise ValueError('mode must be an instance of EncryptMode')
+    if data_format not in ('raw', 'container'):
+        raise ValueError("data_format must be 'raw' or 'container'")
+
+    aead = cipher in AEAD_CIPHERS
+    mgm = not aead and mode is EncryptMode.MGM
+    padded = not aead and not mgm and ciphertext_len_rust(
+        0, mode=mode, cipher=cipher) > 0
+    container = data_format == 'container'
+
+    return {
+        'header': meta_len() if container else 0,
+        'iv': AEAD_NONCE_LEN if aead else MGM_NONCE_LEN if mgm else 0,
+        'tag': AEAD_TAG_LEN if aead else MGM_TAG_LEN if mgm else 0,
+        'padding_max': BLOCK_LEN[cipher] if padded else 0,
+        'per_chunk': 0,
+        'encoding': 'base64' if container else None,
+    }
 
 
 def encrypt_file(*,
@@ -91,7 +559,11 @@ def encrypt_file(*,
                  output_path: Optional[str | Path] = None,
                  overwrite_output: bool = False,
                  code: str,
-                 mode: EncryptMode = EncryptMode.ECB) -> Path:
+                 mode: EncryptMode = EncryptMode.ECB,
+                 key_id: Optional[str | bytes] = None,
+                 armor: bool = False,
+                 cipher: str = DEFAULT_CIPHER,
+                 seed: Optional[bytes] = None) -> Path:
     """Зашифровать предоставленный файл.
 
     :param input_path: Ссылка на файл для шифрования.
@@ -101,6 +573,12 @@ def encrypt_file(*,
                              ``output_path`` существует.
     :param code: Код шифрования.
     :param mode: Режим шифрования.
+    :param key_id: Идентификатор ключа для метаданных.
+    :param armor: Записать результат в PEM-подобной "броне" (строки base64
+                  с заголовком), пригодной для текстовых каналов.
+    :param cipher: Шифр: ``kuznyechik``, ``aes256``, ``magma`` или
+                   ``chacha20poly1305``.
+    :param seed: Зерно для воспроизводимого результата (см. ``encrypt``).
     :returns:
         Экземпляр Path с путём к зашифрованному файлу.
     """
@@ -113,8 +591,13 @@ def encrypt_file(*,
     except UnicodeDecodeError:
         plaintext = input_path.read_bytes()
 
-    ciphertext = encrypt(plaintext=plaintext, code=code, mode=mode)
-    output_path.write_bytes(ciphertext)
+    ciphertext = encrypt(plaintext=plaintext, code=code, mode=mode,
+                         key_id=key_id, cipher=cipher, seed=seed)
+    if armor:
+        with output_path.open('wb') as file:
+            file.writelines(armor_lines(ciphertext))
+    else:
+        output_path.write_bytes(ciphertext)
 
     return output_path
 
@@ -127,6 +610,7 @@ def decrypt_file(*,
     """Расшифровать предоставленный файл.
 
     Режим шифрования извлекается из метаданных зашифрованного файла.
+    Файлы в "броне" (``encrypt_file(armor=True)``) распознаются автоматически.
 
     :param input_path: Ссылка на файл для дешифровки.
     :param output_path: Ссылка для сохранения дешифрованного файла. Если
@@ -140,7 +624,14 @@ def decrypt_file(*,
     input_path, output_path = _valid_path(
         input_path, output_path, overwrite_output)
 
-    decrypted = decrypt(ciphertext=input_path.read_bytes(), code=code)
+    with input_path.open('rb') as file:
+        first_line = file.readline()
+        if is_armored(first_line):
+            ciphertext = dearmor_lines([first_line, *file])
+        else:
+            ciphertext = first_line + file.read()
+
+    decrypted = decrypt(ciphertext=ciphertext, code=code)
     if isinstance(decrypted, bytes):
         output_path.write_bytes(decrypted)
     else:
@@ -149,6 +640,205 @@ def decrypt_file(*,
     return output_path
 
 
+def decrypt_env(path: str | Path, *, code: str) -> dict[str, str]:
+    """Расшифровать файл конфигурации формата ``.env``.
+
+    Файл должен быть зашифрован ``encrypt_file``. Пустые строки и
+    комментарии (``#``) пропускаются, префикс ``export`` и кавычки вокруг
+    значений удаляются.
+
+    :param path: Путь к зашифрованному файлу.
+    :param code: Код шифрования.
+    :returns:
+        Словарь с парами KEY=VALUE.
+    :raises FileNotFoundError: При отсутствии файла.
+    :raises ValueError: При ошибках формата файла.
+    :raises MetaStringError: Если предоставлен неверный код шифрования.
+    """
+
+    path = Path(path)
+    if not path.is_file():
+        raise FileNotFoundError('path does not exist or is not a file')
+
+    content = decrypt(path.read_bytes(), code=code)
+    if isinstance(content, bytes):
+        content = content.decode('utf-8')
+
+    env: dict[str, str] = {}
+    for number, line in enumerate(content.splitlines(), start=1):
+        line = line.strip()
+        if not line or line.startswith('#'):
+            continue
+        if line.startswith('export '):
+            line = line[len('export '):].lstrip()
+
+        name, sep, value = line.partition('=')
+        name, value = name.strip(), value.strip()
+        if not sep or not name:
+            raise ValueError(f'line {number}: expected KEY=VALUE')
+        if len(value) >= 2 and value[0] == value[-1] and value[0] in '"\'':
+            value = value[1:-1]
+        env[name] = value
+
+    return env
+
+
+SIGNATURE_PREFIX = b'HMAC-STREEBOG256:'
+
+
+def sign(data_or_path: bytes | str | Path,
+         *,
+         key: bytes,
+         signature_path: Optional[str | Path] = None) -> bytes:
+    """Создать отсоединённую подпись HMAC-Стрибог для данных или файла.
+
+    Для файлов (``str`` или ``Path``) подпись сохраняется в
+    ``signature_path``, а если он не указан — рядом с файлом с суффиксом
+    ``.sig``. Для bytes-данных файл подписи создаётся, только если указан
+    ``signature_path``.
+
+    :param data_or_path: Данные (bytes) или путь к файлу.
+    :param key: Ключ подписи.
+    :param signature_path: Путь для сохранения подписи.
+    :returns:
+        Содержимое подписи: ``HMAC-STREEBOG256:<hex>``.
+    :raises ValueError: При предоставлении неверных аргументов.
+    :raises FileNotFoundError: При отсутствии подписываемого файла.
+    """
+
+    tag, default_path = _mac_of(data_or_path, key)
+    signature = SIGNATURE_PREFIX + tag.hex().encode('ascii') + b'\n'
+
+    signature_path = signature_path or default_path
+    if signature_path is not None:
+        Path(signature_path).write_bytes(signature)
+
+    return signature
+
+
+def verify(data_or_path: bytes | str | Path,
+           *,
+           key: bytes,
+           signature: Optional[bytes] = None,
+           signature_path: Optional[str | Path] = None) -> bool:
+    """Проверить отсоединённую подпись, созданную ``sign``.
+
+    Подпись берётся из ``signature``, иначе читается из ``signature_path``,
+    а для файлов по умолчанию — из ``<файл>.sig``.
+
+    :param data_or_path: Данные (bytes) или путь к файлу.
+    :param key: Ключ подписи.
+    :param signature: Содержимое подписи.
+    :param signature_path: Путь к файлу подписи.
+    :returns:
+        ``True``, если подпись верна.
+    :raises ValueError: Если подпись не предоставлена или повреждена.
+    """
+
+    tag, default_path = _mac_of(data_or_path, key)
+
+    if signature is None:
+        signature_path = signature_path or default_path
+        if signature_path is None:
+            raise ValueError('signature or signature_path must be provided')
+        signature = Path(signature_path).read_bytes()
+
+    signature = signature.strip()
+    if not signature.startswith(SIGNATURE_PREFIX):
+        raise ValueError('signature format is not supported')
+    try:
+        expected = bytes.fromhex(
+            signature[len(SIGNATURE_PREFIX):].decode('ascii'))
+    except ValueError as err:
+        raise ValueError('signature is corrupted') from err
+
+    return hmac.compare_digest(tag, expected)
+
+
+def _mac_of(data_or_path: bytes | str | Path,
+            key: bytes) -> tuple[bytes, Optional[Path]]:
+    """Имитовставка данных или файла.
+
+    :returns:
+        Имитовставка и путь к подписи по умолчанию (только для файлов).
+    """
+    if not isinstance(key, bytes) or not key:
+        raise ValueError('key must be bytes and cannot be empty')
+
+    if isinstance(data_or_path, bytes):
+        return mac_rust(data_or_path, key=key), None
+    if isinstance(data_or_path, (str, Path)):
+        path = Path(data_or_path)
+        if not path.is_file():
+            raise FileNotFoundError('path does not exist or is not a file')
+        return (mac_file_rust(path, key=key),
+                path.with_name(path.name + '.sig'))
+
+    raise ValueError('data_or_path must be bytes, str or Path')
+
+
+# Длина имитовставки HMAC-Стрибог-256.
+_TAG_LEN = 32
+
+
+def _split_key(key: bytes) -> tuple[bytes, bytes]:
+    """Вывести из ключа независимые ключи шифрования и имитовставки."""
+    return mac_rust(b'grass-enc', key=key), mac_rust(b'grass-mac', key=key)
+
+
+def _seeded(seed: bytes, data: bytes) -> tuple[bytes, bytes]:
+    """Соль и одноразовый номер, выведенные из зерна и данных.
+
+    Разные данные дают разную соль, а значит и ключ, поэтому одноразовый
+    номер не повторяется для одного ключа.
+    """
+    digest = mac_rust(b'grass-seed' + data, key=seed)
+    return digest[:16], digest[16:16 + AEAD_NONCE_LEN]
+
+
+def _check_header(meta_data: dict[str, Any], key: bytes) -> None:
+    """Проверить имитовставку метаданных версии 4.
+
+    :raises MetaStringError: Если метаданные изменены или ключ неверен.
+    """
+    if meta_data['header_tag'] is None:
+        return
+    if not hmac.compare_digest(header_tag(meta_data['header'], key),
+                               meta_data['header_tag']):
+        raise MetaStringError('metadata authentication failed')
+
+
+def _legacy_key_id(code: str) -> bytes:
+    """Отпечаток кодовой фразы без соли (идентификатор версий 2–3)."""
+    return blake2b(code.encode('utf-8'), digest_size=KEY_ID_LEN,
+                   person=b'grass-key-id').digest()
+
+
+def _check_trailing(trailing: str) -> None:
+    if trailing not in TRAILING_POLICIES:
+        raise ValueError(
+            f'trailing must be one of {", ".join(TRAILING_POLICIES)}')
+
+
+def _check_timeout(cipher: str, mode: EncryptMode) -> None:
+    if cipher in AEAD_CIPHERS or mode is EncryptMode.MGM:
+        raise ValueError('timeout is not supported for authenticated ciphers '
+                         'and MGM')
+
+
+def _b64decode(ciphertext: bytes) -> bytes:
+    """Декодировать base64-шифровку, при необходимости сняв "броню".
+
+    :raises MetaStringError: Если "броня" или base64 повреждены.
+    """
+    try:
+        if is_armored(ciphertext):
+            ciphertext = dearmor_lines(ciphertext.splitlines())
+        return base64.b64decode(ciphertext)
+    except ValueError as err:
+        raise MetaStringError(f'message encoding is corrupted: {err}') from err
+
+
 def _valid_path(input_path: str | Path,
                 output_path: str | Path,
                 overwrite: bool) -> tuple[Path, Path]:
@@ -0,0 +1,176 @@
+"""
+Набор ключей для расшифровки в период смены ключей.
+"""
+import os
+from typing import Iterator, Optional
+
+from .exceptions import MetaStringError
+from .interfaces import decrypt, encrypt, peek_header
+from .keystore import KeyStore
+from .tools import EncryptMode, KEY_ID_LEN, get_hash_blake2b, get_key_id
+
+
+class KeyRing:
+    """Набор кодовых фраз с идентификаторами.
+
+    Шифрование выполняется ключом по умолчанию (последним добавленным, если
+    не указан явно). При расшифровке ключ выбирается по идентификатору из
+    метаданных или по отпечатку ключа шифровки (``encrypt`` без
+    ``key_id``), а если он не найден (или отсутствует в шифровках версии 1),
+    последовательно перебираются все ключи набора.
+
+    **Важно**: перебор надёжен только для режимов с выравниванием (ECB, CBC)
+    и шифров с аутентификацией, потоковые режимы не обнаруживают неверный
+    ключ.
+
+    С хранилищем (``keystore``) набор загружается из него при создании, а
+    ``add`` и ``remove`` сохраняют изменения. Кодовые фразы хранятся
+    зашифрованными ``master_code`` в режиме MGM, идентификатор ключа
+    защищён имитовставкой, и подмена записей обнаруживается при загрузке.
+    Ключом по умолчанию становится последний загруженный.
+
+    :param keys: Кодовые фразы по идентификаторам.
+    :param store: Хранилище ключей (``keystore.FileKeyStore``,
+                  ``keystore.CallbackKeyStore`` и др.).
+    :param master_code: Код шифрования ключей в хранилище.
+    :raises ValueError: Если ``store`` и ``master_code`` заданы не вместе.
+    :raises MetaStringError: Если мастер-код неверен или запись хранилища
+                             повреждена.
+    """
+
+    def __init__(self,
+                 keys: Optional[dict[str | bytes, str]] = None,
+                 *,
+                 store: Optional[KeyStore] = None,
+                 master_code: Optional[str] = None):
+        if (store is None) != (master_code is None):
+            raise ValueError('store and master_code must be given together')
+        self._store = store
+        self._master_code = master_code
+        self._keys: dict[bytes, str] = {}
+        self._default: Optional[bytes] = None
+        if store is not None:
+            self._load()
+        for key_id, code in (keys or {}).items():
+            self.add(code, key_id=key_id)
+
+    def add(self,
+            code: str,
+            *,
+            key_id: Optional[str | bytes] = None,
+            default: bool = True) -> bytes:
+        """Добавить кодовую фразу в набор.
+
+        :param code: Код шифрования.
+        :param key_id: Идентификатор ключа. Если не предоставлен,
+                       генерируется случайный: отпечаток кодовой фразы
+                       позволил бы подбирать её по метаданным.
+        :param default: Использовать ключ для шифрования.
+        :returns:
+            Идентификатор добавленного ключа.
+        :raises ValueError: При предоставлении неверных аргументов.
+        """
+        if not isinstance(code, str) or not code:
+            raise ValueError('code must be str and cannot be empty')
+
+        if key_id is None:
+            key_id = os.urandom(KEY_ID_LEN)
+        key_id = self._as_key_id(key_id)
+        if self._store is not None:
+            self._store.put(key_id, encrypt(
+                code, code=self._master_code, mode=EncryptMode.MGM,
+                key_id=key_id))
+        self._keys[key_id] = code
+        if default or self._default is None:
+            self._default = key_id
+
+        return key_id
+
+    def remove(self, key_id: str | bytes) -> None:
+        """Удалить ключ из набора.
+
+        :raises KeyError: При отсутствии ключа.
+        """
+        key_id = self._as_key_id(key_id)
+        if key_id not in self._keys:
+            raise KeyError(key_id)
+        if self._store is not None:
+            self._store.delete(key_id)
+        del self._keys[key_id]
+        if self._default == key_id:
+            self._default = next(reversed(self._keys), None)
+
+    def encrypt(self,
+                plaintext: str | bytes,
+                *,
+                mode: EncryptMode = EncryptMode.ECB,
+                key_id: Optional[str | bytes] = None) -> bytes:
+        """Зашифровать данные ключом набора.
+
+        :param plaintext: Данные для шифрования.
+        :param mode: Режим шифрования.
+        :param key_id: Идентификатор ключа; по умолчанию — ключ по умолчанию.
+        :returns:
+            Результат ``interfaces.encrypt`` с идентификатором ключа.
+        :raises KeyError: При отсутствии ключа.
+        """
+        key_id = self._default if key_id is None else self._as_key_id(key_id)
+        if key_id is None:
+            raise KeyError('keyring is empty')
+
+        return encrypt(plaintext, code=self._keys[key_id], mode=mode,
+                       key_id=key_id)
+
+    def decrypt(self, ciphertext: bytes) -> str | bytes:
+        """Расшифровать данные подходящим ключом набора.
+
+        :param ciphertext: Результат ``encrypt``.
+        :returns:
+            Расшифрованные данные.
+        :raises MetaStringError: Если ни один ключ не подошёл.
+        """
+        header = peek_header(ciphertext)
+        key_id, salt = header['key_id'], header['kdf']['salt']
+        if key_id in self._keys:
+            return decrypt(ciphertext, code=self._keys[key_id])
+        for code in self._keys.values():
+            if get_key_id(get_hash_blake2b(code, salt=salt)[0]) == key_id:
+                return decrypt(ciphertext, code=code)
+
+        for code in self._keys.values():
+            try:
+                return decrypt(ciphertext, code=code)
+            except (MetaStringError, UnicodeDecodeError):
+                continue
+
+        raise MetaStringError('no key in the keyring matches the ciphertext')
+
+    def __contains__(self, key_id: str | bytes) -> bool:
+        return self._as_key_id(key_id) in self._keys
+
+    def __iter__(self) -> Iterator[bytes]:
+        return iter(self._keys)
+
+    def __len__(self) -> int:
+        return len(self._keys)
+
+    def _load(self) -> None:
+        """Загрузить ключи из хранилища."""
+        for key_id in self._store.list():
+            blob = self._store.get(key_id)
+            if blob is None:
+                continue
+            if peek_header(blob)['key_id'] != key_id:
+                raise MetaStringError('key store entry does not match its id')
+            self._keys[key_id] = decrypt(blob, code=self._master_code)
+            self._default = key_id
+
+    @staticmethod
+    def _as_key_id(key_id: str | bytes) -> bytes:
+        """Привести идентификатор ключа к bytes."""
+        if isinstance(key_id, str):
+            key_id = key_id.encode('utf-8')
+        if not isinstance(key_id, bytes) or not key_id:
+            raise ValueError('key_id must be str, bytes and cannot be empty')
+
+        return key_id
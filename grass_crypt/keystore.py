@@ -0,0 +1,110 @@
+"""
+Хранилища ключей для ``KeyRing``.
+
+Хранилище сохраняет кодовые фразы набора уже зашифрованными мастер-кодом
+(``interfaces.encrypt`` в режиме MGM) и не работает с открытыми ключами,
+поэтому для Redis, базы данных или менеджера секретов достаточно
+``CallbackKeyStore`` с функциями чтения и записи.
+"""
+import json
+import os
+import tempfile
+from pathlib import Path
+from typing import Callable, Iterable, Optional, Protocol
+
+FORMAT_VERSION = 1
+
+
+class KeyStore(Protocol):
+    """Хранилище зашифрованных ключей по идентификаторам."""
+
+    def get(self, key_id: bytes) -> Optional[bytes]: ...
+
+    def put(self, key_id: bytes, blob: bytes) -> None: ...
+
+    def list(self) -> Iterable[bytes]: ...
+
+    def delete(self, key_id: bytes) -> None: ...
+
+
+class FileKeyStore:
+    """Хранилище в JSON-файле.
+
+    Формат (версия 1): ``{"format": 1, "keys": {hex(key_id): blob}}``.
+    Файл перезаписывается атомарно при каждом изменении.
+    """
+
+    def __init__(self, path: str | Path):
+        self.path = Path(path)
+
+    def get(self, key_id: bytes) -> Optional[bytes]:
+        blob = self._read().get(key_id.hex())
+        return blob.encode('ascii') if blob is not None else None
+
+    def put(self, key_id: bytes, blob: bytes) -> None:
+        keys = self._read()
+        keys[key_id.hex()] = blob.decode('ascii')
+        self._write(keys)
+
+    def list(self) -> list[bytes]:
+        return [bytes.fromhex(key_id) for key_id in self._read()]
+
+    def delete(self, key_id: bytes) -> None:
+        keys = self._read()
+        if keys.pop(key_id.hex(), None) is not None:
+            self._write(keys)
+
+    def _read(self) -> dict[str, str]:
+        if not self.path.exists():
+            return {}
+        data = json.loads(self.path.read_text(encoding='utf-8'))
+        if data.get('format') != FORMAT_VERSION:
+            raise ValueError(f'unsupported key store format: '
+                             f'{data.get("format")}')
+        return data['keys']
+
+    def _write(self, keys: dict[str, str]) -> None:
+        data = json.dumps({'format': FORMAT_VERSION, 'keys': keys}, indent=2)
+        fd, temp_name = tempfile.mkstemp(dir=self.path.parent,
+                                         prefix=f'.{self.path.name}.')
+        try:
+            with os.fdopen(fd, 'w', encoding='utf-8') as sink:
+                sink.write(data + '\n')
+            os.replace(temp_name, self.path)
+        except BaseException:
+            os.unlink(temp_name)
+            raise
+
+
+class CallbackKeyStore:
+    """Хранилище на функциях Python (Redis, база данных, менеджер
+    секретов).
+
+    :param get: ``get(key_id) -> bytes | None``.
+    :param put: ``put(key_id, blob)``.
+    :param list: ``list() -> Iterable[bytes]`` — идентификаторы ключей.
+    :param delete: ``delete(key_id)``; без него ``KeyRing.remove`` не
+                   удаляет ключ из хранилища и поднимает исключение.
+    """
+
+    def __init__(self,
+                 get: Callable[[bytes], Optional[bytes]],
+                 put: Callable[[bytes, bytes], None],
+                 list: Callable[[], Iterable[bytes]],
+                 delete: Optional[Callable[[bytes], None]] = None):
+        self._get, self._put, self._list = get, put, list
+        self._delete = delete
+
+    def get(self, key_id: bytes) -> Optional[bytes]:
+        return self._get(key_id)
+
+    def put(self, key_id: bytes, blob: bytes) -> None:
+        self._put(key_id, blob)
+
+    def list(self) -> Iterable[bytes]:
+        return self._list()
+
+    def delete(self, key_id: bytes) -> None:
+        if self._delete is None:
+            raise NotImplementedError('the key store has no delete callback')
+        self._delete(key_id)
@@ -0,0 +1,164 @@
+"""
+Шифрование Python-объектов: сериализация, сжатие и шифрование за один вызов.
+
+Формат: ``маркер (3) + флаги (1) + режим (3) + соль (16) + шифротекст +
+имитовставка (32)``. Схема encrypt-then-MAC: имитовставка HMAC-Стрибог
+проверяется до распаковки и десериализации.
+"""
+import hmac
+import io
+import pickle
+import zlib
+from typing import Any, Iterable, Optional
+
+from ._engine import decrypting_rust, encrypting_rust, mac_rust
+from .exceptions import AuthenticationError
+from .interfaces import _TAG_LEN, _split_key
+from .tools import EncryptMode, get_hash_blake2b
+
+_MAGIC = b'GOB'
+_SALT_LEN = 16
+_HEADER_LEN = len(_MAGIC) + 1 + 3 + _SALT_LEN
+# Флаги: младшие биты — сериализатор, старший — сжатие.
+_SERIALIZERS = {'pickle': 1, 'msgpack': 2}
+_COMPRESSED = 0x80
+# Ограничение размера распакованных данных по умолчанию.
+MAX_OBJECT_SIZE = 64 * 1024 * 1024
+
+
+def encrypt_object(obj: Any,
+                   *,
+                   code: str,
+                   serializer: str = 'pickle',
+                   compress: bool = False,
+                   mode: EncryptMode = EncryptMode.CTR,
+                   protocol: int = pickle.DEFAULT_PROTOCOL) -> bytes:
+    """Сериализовать, при необходимости сжать и зашифровать объект.
+
+    :param obj: Объект.
+    :param code: Код шифрования.
+    :param serializer: ``pickle`` или ``msgpack`` (нужен пакет msgpack).
+    :param compress: Сжать данные zlib перед шифрованием.
+    :param mode: Режим шифрования.
+    :param protocol: Протокол pickle.
+    :returns:
+        Зашифрованный объект с имитовставкой.
+    :raises ValueError: При предоставлении неверных аргументов.
+    """
+
+    if not isinstance(code, str) or not code:
+        raise ValueError('code must be str and cannot be empty')
+    if serializer not in _SERIALIZERS:
+        raise ValueError("serializer must be 'pickle' or 'msgpack'")
+    if not isinstance(mode, EncryptMode):
+        raise ValueError('mode must be an instance of EncryptMode')
+
+    if serializer == 'pickle':
+        data = pickle.dumps(obj, protocol=protocol)
+    else:
+        data = _msgpack().packb(obj, use_bin_type=True)
+
+    flags = _SERIALIZERS[serializer]
+    if compress:
+        data = zlib.compress(data)
+        flags |= _COMPRESSED
+
+    key, salt = get_hash_blake2b(code)
+    enc_key, mac_key = _split_key(key)
+    body = (_MAGIC + bytes([flags]) + mode.as_bytes() + salt
+            + encrypting_rust(data, code=enc_key, mode=mode))
+
+    return body + mac_rust(body, key=mac_key)
+
+
+def decrypt_object(blob: bytes,
+                   *,
+                   code: str,
+                   allowed: Optional[Iterable[str]] = None,
+                   serializer: Optional[str] = None,
+                   max_size: int = MAX_OBJECT_SIZE) -> Any:
+    """Проверить, расшифровать и восстановить объект ``encrypt_object``.
+
+    Для pickle разрешены только встроенные типы без глобальных имён
+    (числа, строки, bytes, списки, словари и т. п.). Остальные классы и
+    функции нужно явно перечислить в ``allowed`` в виде ``модуль.имя``.
+
+    :param blob: Результат ``encrypt_object``.
+    :param code: Код шифрования.
+    :param allowed: Разрешённые глобальные имена для pickle.
+    :param serializer: Ожидаемый сериализатор; если указан, другой
+                       отклоняется.
+    :param max_size: Максимальный размер распакованных данных.
+    :returns:
+        Восстановленный объект.
+    :raises ValueError: При предоставлении неверных аргументов.
+    :raises AuthenticationError: Если данные повреждены или код неверный.
+    :raises pickle.UnpicklingError: При обращении к неразрешённому имени.
+    """
+
+    if not isinstance(code, str) or not code:
+        raise ValueError('code must be str and cannot be empty')
+    if (not isinstance(blob, bytes) or len(blob) <= _HEADER_LEN + _TAG_LEN
+            or not blob.startswith(_MAGIC)):
+        raise AuthenticationError('not an encrypted object')
+
+    body, tag = blob[:-_TAG_LEN], blob[-_TAG_LEN:]
+    salt = body[_HEADER_LEN - _SALT_LEN:_HEADER_LEN]
+    key = get_hash_blake2b(code, salt=salt)[0]
+    enc_key, mac_key = _split_key(key)
+    if not hmac.compare_digest(mac_rust(body, key=mac_key), tag):
+        raise AuthenticationError('the authentication tag is invalid')
+
+    flags = body[len(_MAGIC)]
+    names = {v: k for k, v in _SERIALIZERS.items()}
+    kind = names.get(flags & ~_COMPRESSED)
+    if kind is None:
+        raise ValueError('unknown serializer')
+    if serializer is not None and serializer != kind:
+        raise ValueError(f'expected {serializer}, got {kind}')
+
+    mode = EncryptMode.me_from_value(body[4:7].decode('utf-8'))
+    data = decrypting_rust(body[_HEADER_LEN:], code=enc_key, mode=mode)
+    if flags & _COMPRESSED:
+        data = _decompress(data, max_size)
+    elif len(data) > max_size:
+        raise ValueError('object exceeds max_size')
+
+    if kind == 'pickle':
+        return _RestrictedUnpickler(io.BytesIO(data), allowed or ()).load()
+    return _msgpack().unpackb(data, raw=False)
+
+
+class _RestrictedUnpickler(pickle.Unpickler):
+    """Распаковщик pickle со списком разрешённых глобальных имён."""
+
+    def __init__(self, file, allowed: Iterable[str]):
+        super().__init__(file)
+        self._allowed = frozenset(allowed)
+
+    def find_class(self, module: str, name: str) -> Any:
+        if f'{module}.{name}' not in self._allowed:
+            raise pickle.UnpicklingError(
+                f'global {module}.{name} is not allowed')
+        return super().find_class(module, name)
+
+
+def _decompress(data: bytes, max_size: int) -> bytes:
+    """Распаковать zlib с ограничением размера результата."""
+    decompressor = zlib.decompressobj()
+    result = decompressor.decompress(data, max_size)
+    if decompressor.unconsumed_tail or not decompressor.eof:
+        raise ValueError('object exceeds max_size or is truncated')
+
+    return result
+
+
+def _msgpack():
+    """Модуль msgpack (необязательная зависимость)."""
+    try:
+        import msgpack
+    except ImportError:
+        raise ImportError('serializer msgpack requires the msgpack package'
+                          ) from None
+
+    return msgpack
@@ -0,0 +1,189 @@
+"""
+Контейнер множества небольших записей со сжатием по словарю zstd.
+
+Похожие короткие записи (например, JSON-события) плохо сжимаются по
+отдельности. Словарь zstd, обученный на образцах, заметно улучшает сжатие;
+каждая запись сжимается отдельно, поэтому словарь нужен и при распаковке.
+
+Формат: ``маркер (3) + версия (1) + флаги (1) + режим (3) + соль (16) +
+длина словаря (4) + словарь или его хеш Стрибог-256 + шифротекст +
+имитовставка (32)``. Заголовок вместе со словарём защищён имитовставкой
+HMAC-Стрибог. Открытые данные — записи с префиксом длины (4 байта).
+"""
+import hmac
+import struct
+from typing import Iterable, Optional
+
+from ._engine import decrypting_rust, encrypting_rust, hash_rust, mac_rust
+from .exceptions import AuthenticationError, MetaStringError
+from .interfaces import _TAG_LEN, _split_key
+from .tools import EncryptMode, RECORD_HEADER, get_hash_blake2b
+
+_MAGIC = b'GPK'
+_VERSION = 1
+_HEADER = struct.Struct('>3sBB3s16sI')
+# Флаги: сжатие zstd, словарь в заголовке, ссылка на словарь по хешу.
+_COMPRESSED = 0x01
+_DICT_EMBEDDED = 0x02
+_DICT_REFERENCED = 0x04
+_DICT_HASH_LEN = 32
+# Ограничение размера распакованной записи по умолчанию.
+MAX_RECORD_SIZE = 16 * 1024 * 1024
+
+
+def train_dictionary(samples: Iterable[bytes], size: int = 16 * 1024) -> bytes:
+    """Обучить словарь zstd на образцах записей.
+
+    :param samples: Образцы, похожие на будущие записи.
+    :param size: Максимальный размер словаря в байтах.
+    :returns:
+        Словарь для ``encrypt_records``.
+    """
+    return _zstd().train_dictionary(size, list(samples)).as_bytes()
+
+
+def encrypt_records(records: Iterable[bytes],
+                    *,
+                    code: str,
+                    compress: bool = False,
+                    dictionary: Optional[bytes] = None,
+                    embed_dictionary: bool = True,
+                    mode: EncryptMode = EncryptMode.CTR) -> bytes:
+    """Зашифровать записи в один контейнер.
+
+    :param records: Записи.
+    :param code: Код шифрования.
+    :param compress: Сжать каждую запись zstd (нужен пакет zstandard).
+    :param dictionary: Словарь zstd; требует ``compress``.
+    :param embed_dictionary: Сохранить словарь в заголовке. Иначе в
+                             заголовок записывается его хеш, а словарь
+                             передаётся в ``decrypt_records``.
+    :param mode: Режим шифрования.
+    :returns:
+        Контейнер с имитовставкой.
+    :raises ValueError: При предоставлении неверных аргументов.
+    """
+
+    if not isinstance(code, str) or not code:
+        raise ValueError('code must be str and cannot be empty')
+    if not isinstance(mode, EncryptMode):
+        raise ValueError('mode must be an instance of EncryptMode')
+    if dictionary is not None and not compress:
+        raise ValueError('dictionary requires compress=True')
+
+    flags, stored = 0, b''
+    pack = bytes
+    if compress:
+        flags |= _COMPRESSED
+        pack = _compressor(dictionary).compress
+    if dictionary is not None:
+        if embed_dictionary:
+            flags, stored = flags | _DICT_EMBEDDED, dictionary
+        else:
+            flags, stored = flags | _DICT_REFERENCED, hash_rust(dictionary)
+
+    parts = []
+    for record in records:
+        data = pack(record)
+        parts.append(RECORD_HEADER.pack(len(data)) + data)
+
+    key, salt = get_hash_blake2b(code)
+    enc_key, mac_key = _split_key(key)
+    body = (_HEADER.pack(_MAGIC, _VERSION, flags, mode.as_bytes(), salt,
+                         len(stored)) + stored
+            + encrypting_rust(b''.join(parts), code=enc_key, mode=mode))
+
+    return body + mac_rust(body, key=mac_key)
+
+
+def decrypt_records(blob: bytes,
+                    *,
+                    code: str,
+                    dictionary: Optional[bytes] = None,
+                    max_size: int = MAX_RECORD_SIZE) -> list[bytes]:
+    """Проверить и расшифровать контейнер ``encrypt_records``.
+
+    :param blob: Контейнер.
+    :param code: Код шифрования.
+    :param dictionary: Словарь, если он не сохранён в заголовке.
+    :param max_size: Максимальный размер распакованной записи.
+    :returns:
+        Записи в исходном порядке.
+    :raises ValueError: Если словарь не передан или не совпал.
+    :raises MetaStringError: Если данные не являются контейнером.
+    :raises AuthenticationError: Если данные повреждены или код неверный.
+    """
+
+    if not isinstance(code, str) or not code:
+        raise ValueError('code must be str and cannot be empty')
+    if (not isinstance(blob, bytes)
+            or len(blob) < _HEADER.size + _TAG_LEN
+            or not blob.startswith(_MAGIC)):
+        raise MetaStringError('not a records container')
+
+    _, version, flags, mode, salt, stored_len = _HEADER.unpack(
+        blob[:_HEADER.size])
+    if version != _VERSION:
+        raise MetaStringError(f'unsupported version: {version}')
+    body, tag = blob[:-_TAG_LEN], blob[-_TAG_LEN:]
+    key = get_hash_blake2b(code, salt=salt)[0]
+    enc_key, mac_key = _split_key(key)
+    if not hmac.compare_digest(mac_rust(body, key=mac_key), tag):
+        raise AuthenticationError('the authentication tag is invalid')
+
+    start = _HEADER.size + stored_len
+    stored = body[_HEADER.size:start]
+    if flags & _DICT_EMBEDDED:
+        dictionary = stored
+    elif flags & _DICT_REFERENCED:
+        if dictionary is None:
+            raise ValueError('container requires a compression dictionary')
+        if not hmac.compare_digest(hash_rust(dictionary), stored):
+            raise ValueError('compression dictionary does not match')
+
+    data = decrypting_rust(body[start:], code=enc_key,
+                           mode=EncryptMode.me_from_value(mode.decode()))
+    unpack = bytes
+    if flags & _COMPRESSED:
+        decompressor = _decompressor(dictionary)
+
+        def unpack(record: bytes) -> bytes:
+            size = _zstd().frame_content_size(record)
+            if not 0 <= size <= max_size:
+                raise ValueError('record exceeds max_size')
+            return decompressor.decompress(record)
+
+    records, offset = [], 0
+    while offset < len(data):
+        length = RECORD_HEADER.unpack_from(data, offset)[0]
+        offset += RECORD_HEADER.size
+        records.append(unpack(data[offset:offset + length]))
+        offset += length
+
+    return records
+
+
+def _compressor(dictionary: Optional[bytes]):
+    zstd = _zstd()
+    if dictionary is None:
+        return zstd.ZstdCompressor()
+    return zstd.ZstdCompressor(dict_data=zstd.ZstdCompressionDict(dictionary))
+
+
+def _decompressor(dictionary: Optional[bytes]):
+    zstd = _zstd()
+    if dictionary is None:
+        return zstd.ZstdDecompressor()
+    return zstd.ZstdDecompressor(
+        dict_data=zstd.ZstdCompressionDict(dictionary))
+
+
+def _zstd():
+    """Модуль zstandard (необязательная зависимость)."""
+    try:
+        import zstandard
+    except ImportError:
+        raise ImportError('compression requires the zstandard package'
+                          ) from None
+
+    return zstandard
@@ -0,0 +1,140 @@
+"""
+Политика нештатных, но не фатальных ситуаций.
+
+Каждая ситуация может быть ошибкой (``error``, ``PolicyError``),
+предупреждением (``warn``, ``PolicyWarning``) или игнорироваться
+(``ignore``), поэтому одна сборка служит и строгим, и унаследованным
+окружениям::
+
+    from grass_crypt import policy
+
+    policy.configure(ecb='error', legacy_iv='warn')
+    with policy.override(ecb='ignore'):
+        ...  # чтение и запись старых данных
+
+Ситуации и действия по умолчанию:
+
+- ``weak_code`` (``warn``) — кодовая фраза похожа на слабую: меньше
+  ``MIN_CODE_BITS`` бит по оценке из длины и классов символов или меньше
+  ``MIN_CODE_CHARS`` разных символов. Проверяется только при шифровании;
+- ``ecb`` (``ignore``) — шифрование в режиме ECB (``encrypt`` и
+  ``hazmat``);
+- ``legacy_iv`` (``ignore``) — шифрование со встроенным вектором движка.
+  Политика ``iv_policy='strict'`` шифра ``hazmat`` строже и её не
+  отменяет.
+
+``override`` действует в текущем потоке или задаче ``asyncio``.
+"""
+import math
+import warnings
+from contextlib import contextmanager
+from contextvars import ContextVar
+from typing import Iterator
+
+from .exceptions import PolicyError, PolicyWarning
+from .tools import POLICY_ACTIONS
+
+DEFAULTS = {'weak_code': 'warn', 'ecb': 'ignore', 'legacy_iv': 'ignore'}
+# Порог оценки стойкости кодовой фразы.
+MIN_CODE_BITS = 60
+MIN_CODE_CHARS = 6
+
+_actions = dict(DEFAULTS)
+_overrides: ContextVar[dict[str, str]] = ContextVar('grass_policy',
+                                                    default={})
+
+
+def configure(**actions: str) -> None:
+    """ Задать действия для ситуаций во всём процессе.
+
+    :param actions: Ситуация и действие: ``error``, ``warn`` или ``ignore``.
+    :raises ValueError: При неизвестной ситуации или действии.
+    """
+
+    _actions.update(_checked(actions))
+
+
+def reset() -> None:
+    """ Вернуть действия по умолчанию. """
+
+    _actions.clear()
+    _actions.update(DEFAULTS)
+
+
+def get_action(condition: str) -> str:
+    """ Действие для ситуации с учётом ``override``.
+
+    :raises ValueError: При неизвестной ситуации.
+    """
+
+    _checked({condition: 'ignore'})
+    return _overrides.get().get(condition, _actions[condition])
+
+
+@contextmanager
+def override(**actions: str) -> Iterator[None]:
+    """ Временно заменить действия для ситуаций.
+
+    :param actions: Ситуация и действие, как в ``configure``.
+    :raises ValueError: При неизвестной ситуации или действии.
+    """
+
+    token = _overrides.set({**_overrides.get(), **_checked(actions)})
+    try:
+        yield
+    finally:
+        _overrides.reset(token)
+
+
+def check(condition: str, message: str, *, stacklevel: int = 3) -> None:
+    """ Применить действие политики к наступившей ситуации.
+
+    :param condition: Ситуация.
+    :param message: Описание для исключения или предупреждения.
+    :param stacklevel: Уровень стека предупреждения, считая от ``check``.
+    :raises PolicyError: Если действие — ``error``.
+    """
+
+    action = get_action(condition)
+    if action == 'error':
+        raise PolicyError(f'{message} ({condition} policy)')
+    if action == 'warn':
+        warnings.warn(message, PolicyWarning, stacklevel=stacklevel)
+
+
+def check_code(code: str, *, stacklevel: int = 4) -> None:
+    """ Проверить кодовую фразу на слабость (ситуация ``weak_code``).
+
+    Кодовая фраза в сообщение не попадает.
+    """
+
+    if code_bits(code) < MIN_CODE_BITS or len(set(code)) < MIN_CODE_CHARS:
+        check('weak_code', 'The code looks weak', stacklevel=stacklevel)
+
+
+def code_bits(code: str) -> float:
+    """ Грубая оценка стойкости кодовой фразы в битах.
+
+    Длина, умноженная на логарифм размера алфавита по встреченным классам
+    символов (строчные, заглавные, цифры, прочие). Словарные фразы
+    переоцениваются, поэтому это только эвристика.
+    """
+
+    alphabet = sum(size for found, size in (
+        (any(c.islower() for c in code), 26),
+        (any(c.isupper() for c in code), 26),
+        (any(c.isdigit() for c in code), 10),
+        (any(not c.isalnum() for c in code), 33),
+    ) if found)
+    return len(code) * math.log2(alphabet) if alphabet else 0.0
+
+
+def _checked(actions: dict[str, str]) -> dict[str, str]:
+    for condition, action in actions.items():
+        if condition not in DEFAULTS:
+            raise ValueError(
+                f'condition must be one of {", ".join(DEFAULTS)}')
+        if action not in POLICY_ACTIONS:
+            raise ValueError(
+                f'action must be one of {", ".join(POLICY_ACTIONS)}')
+    return actions
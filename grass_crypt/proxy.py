@@ -0,0 +1,117 @@
+"""
+Шифрующий ретранслятор между двумя соединениями.
+
+Протокол записей: каждая запись — 4 байта длины (big-endian) и результат
+``interfaces.encrypt`` для очередного фрагмента данных.
+"""
+import selectors
+import socket
+from typing import Optional
+
+from .interfaces import decrypt, encrypt
+from .tools import EncryptMode, MAX_RECORD, RECORD_HEADER
+
+
+def send_record(sock: socket.socket,
+                data: bytes,
+                *,
+                code: str,
+                mode: EncryptMode = EncryptMode.ECB) -> None:
+    """Зашифровать данные и отправить одной записью."""
+    record = encrypt(data, code=code, mode=mode)
+    sock.sendall(RECORD_HEADER.pack(len(record)) + record)
+
+
+def recv_record(sock: socket.socket, *, code: str) -> Optional[bytes]:
+    """Принять и расшифровать одну запись.
+
+    :returns:
+        Данные записи или ``None``, если соединение закрыто.
+    :raises ConnectionError: При обрыве соединения внутри записи.
+    """
+    header = _recv_exact(sock, RECORD_HEADER.size)
+    if header is None:
+        return None
+    length = RECORD_HEADER.unpack(header)[0]
+    if not 0 < length <= MAX_RECORD:
+        raise ConnectionError(f'invalid record length: {length}')
+    record = _recv_exact(sock, length)
+    if record is None:
+        raise ConnectionError('connection closed inside a record')
+
+    data = decrypt(record, code=code)
+    return data.encode('utf-8') if isinstance(data, str) else data
+
+
+def relay(plain: socket.socket | int,
+          secure: socket.socket | int,
+          *,
+          code: str,
+          mode: EncryptMode = EncryptMode.ECB,
+          buffer_size: int = 64 * 1024) -> None:
+    """Ретранслировать данные между соединениями до их закрытия.
+
+    Данные из ``plain`` шифруются и отправляются в ``secure`` записями,
+    записи из ``secure`` расшифровываются и отправляются в ``plain``.
+    Закрытие одной стороны передаётся другой через ``shutdown(SHUT_WR)``.
+
+    :param plain: Соединение с открытыми данными (сокет или дескриптор).
+    :param secure: Соединение с зашифрованными записями.
+    :param code: Код шифрования.
+    :param mode: Режим шифрования.
+    :param buffer_size: Максимальный размер фрагмента открытых данных.
+    """
+    plain, secure = _as_socket(plain), _as_socket(secure)
+
+    selector = selectors.DefaultSelector()
+    selector.register(plain, selectors.EVENT_READ)
+    selector.register(secure, selectors.EVENT_READ)
+    try:
+        while selector.get_map():
+            for key, _ in selector.select():
+                sock = key.fileobj
+                if sock is plain:
+                    data = plain.recv(buffer_size)
+                    if data:
+                        send_record(secure, data, code=code, mode=mode)
+                        continue
+                    target = secure
+                else:
+                    data = recv_record(secure, code=code)
+                    if data is not None:
+                        plain.sendall(data)
+                        continue
+                    target = plain
+
+                selector.unregister(sock)
+                try:
+                    target.shutdown(socket.SHUT_WR)
+                except OSError:
+                    pass
+    finally:
+        selector.close()
+
+
+def _recv_exact(sock: socket.socket, size: int) -> Optional[bytes]:
+    """Принять ровно ``size`` байт или ``None`` при закрытии до начала."""
+    chunks, received = [], 0
+    while received < size:
+        chunk = sock.recv(size - received)
+        if not chunk:
+            if received:
+                raise ConnectionError('connection closed unexpectedly')
+            return None
+        chunks.append(chunk)
+        received += len(chunk)
+
+    return b''.join(chunks)
+
+
+def _as_socket(sock: socket.socket | int) -> socket.socket:
+    """Сокет из сокета или файлового дескриптора."""
+    if isinstance(sock, socket.socket):
+        return sock
+    if isinstance(sock, int):
+        return socket.socket(fileno=sock)
+
+    raise TypeError('expected a socket or a file descriptor')
@@ -0,0 +1,52 @@
+"""
+Словарь с прозрачным шифрованием значений.
+"""
+import secrets
+from collections.abc import MutableMapping
+from typing import Hashable, Iterator, Optional
+
+from .interfaces import decrypt, encrypt
+from .tools import EncryptMode
+
+
+class SecureDict(MutableMapping):
+    """Словарь, значения которого хранятся зашифрованными.
+
+    Значения (``str`` или ``bytes``) шифруются при записи и расшифровываются
+    при чтении сеансовым кодом, который по умолчанию генерируется случайно
+    и живёт только в экземпляре. Ключи словаря не шифруются.
+
+    Подходит для кэширования токенов и учётных данных в долгоживущих
+    процессах.
+    """
+
+    def __init__(self,
+                 data: Optional[dict] = None,
+                 *,
+                 code: Optional[str] = None,
+                 mode: EncryptMode = EncryptMode.ECB):
+        self._code = code or secrets.token_urlsafe(32)
+        self._mode = mode
+        self._data: dict[Hashable, bytes] = {}
+        if data:
+            self.update(data)
+
+    def __setitem__(self, key: Hashable, value: str | bytes) -> None:
+        if not isinstance(value, (str, bytes)):
+            raise TypeError('value must be str or bytes')
+        self._data[key] = encrypt(value, code=self._code, mode=self._mode)
+
+    def __getitem__(self, key: Hashable) -> str | bytes:
+        return decrypt(self._data[key], code=self._code)
+
+    def __delitem__(self, key: Hashable) -> None:
+        del self._data[key]
+
+    def __iter__(self) -> Iterator[Hashable]:
+        return iter(self._data)
+
+    def __len__(self) -> int:
+        return len(self._data)
+
+    def __repr__(self) -> str:
+        return f'{type(self).__name__}(<{len(self)} encrypted values>)'
@@ -0,0 +1,160 @@
+"""
+Подключи периодов для уничтожения записей (crypto-shredding).
+
+Время делится на периоды по ``period`` секунд (по умолчанию сутки). Для
+каждого периода создаётся случайный подключ, а каждая запись шифруется
+собственным ключом, выведенным из подключа и случайной соли записи.
+Подключи хранятся в хранилище (``keystore``) зашифрованными мастер-кодом.
+Удаление подключа (``shred``) делает записи его периода невосстановимыми,
+не затрагивая остальные, — копии самих записей в резервных копиях
+удалять не нужно.
+
+Формат записи: ``период (8) + соль (16) + результат interfaces.encrypt``
+в режиме MGM. Подмена периода или соли меняет ключ записи и обнаруживается
+при расшифровке.
+"""
+import math
+import os
+import struct
+import threading
+import time
+from typing import Optional
+
+from ._engine import mac_rust
+from .exceptions import KeyShreddedError, MetaStringError
+from .interfaces import decrypt, encrypt, peek_header
+from .keystore import KeyStore
+from .tools import EncryptMode
+
+_EPOCH = struct.Struct('>Q')
+_SALT_LEN = 16
+DEFAULT_PERIOD = 24 * 60 * 60
+
+
+class EpochKeys:
+    """Подключи периодов в хранилище.
+
+    Безопасен для нескольких потоков. Подключ создаётся при первой записи
+    в период; загруженные подключи кешируются до ``shred``.
+
+    :param store: Хранилище ключей (``keystore.FileKeyStore`` и др.).
+    :param master_code: Код шифрования подключей в хранилище.
+    :param period: Длительность периода в секундах.
+    :raises ValueError: При предоставлении неверных аргументов.
+    """
+
+    def __init__(self,
+                 store: KeyStore,
+                 *,
+                 master_code: str,
+                 period: int = DEFAULT_PERIOD):
+        if not isinstance(master_code, str) or not master_code:
+            raise ValueError('master_code must be str and cannot be empty')
+        if not isinstance(period, int) or period <= 0:
+            raise ValueError('period must be a positive int')
+        self._store = store
+        self._master_code = master_code
+        self.period = period
+        self._lock = threading.Lock()
+        self._cache: dict[int, bytes] = {}
+
+    def epoch(self, timestamp: Optional[float] = None) -> int:
+        """Номер периода момента ``timestamp`` (по умолчанию — текущего)."""
+        if timestamp is None:
+            timestamp = time.time()
+        if timestamp < 0:
+            raise ValueError('timestamp cannot be negative')
+
+        return int(timestamp // self.period)
+
+    def epochs(self) -> list[int]:
+        """Номера периодов с сохранёнными подключами по возрастанию."""
+        return sorted(_EPOCH.unpack(key_id)[0]
+                      for key_id in self._store.list()
+                      if len(key_id) == _EPOCH.size)
+
+    def shred(self, start: float, end: float) -> list[int]:
+        """Уничтожить подключи периодов, пересекающих ``[start, end)``.
+
+        Периоды удаляются целиком, поэтому вместе с диапазоном уничтожаются
+        и записи его неполных периодов на краях.
+
+        :param start: Начало диапазона (Unix time).
+        :param end: Конец диапазона.
+        :returns:
+            Номера уничтоженных периодов.
+        """
+        if end <= start:
+            return []
+        first, last = self.epoch(start), math.ceil(end / self.period) - 1
+        shredded = [epoch for epoch in self.epochs()
+                    if first <= epoch <= last]
+        with self._lock:
+            for epoch in shredded:
+                self._store.delete(_EPOCH.pack(epoch))
+                self._cache.pop(epoch, None)
+
+        return shredded
+
+    def seal(self,
+             entry: str | bytes,
+             *,
+             timestamp: Optional[float] = None) -> bytes:
+        """Зашифровать запись ключом её периода.
+
+        :param entry: Данные записи.
+        :param timestamp: Момент записи; по умолчанию текущий.
+        :returns:
+            Запись в формате модуля.
+        """
+        epoch = self.epoch(timestamp)
+        salt = os.urandom(_SALT_LEN)
+        code = _record_code(self._subkey(epoch, create=True), salt)
+
+        return (_EPOCH.pack(epoch) + salt
+                + encrypt(entry, code=code, mode=EncryptMode.MGM))
+
+    def open(self, record: bytes) -> str | bytes:
+        """Расшифровать запись ``seal``.
+
+        :raises KeyShreddedError: Если подключ периода уничтожен.
+        :raises MetaStringError: При повреждённой записи.
+        """
+        if not isinstance(record, bytes) or len(record) <= (
+                _EPOCH.size + _SALT_LEN):
+            raise MetaStringError('record is truncated')
+
+        epoch = _EPOCH.unpack_from(record)[0]
+        salt = record[_EPOCH.size:_EPOCH.size + _SALT_LEN]
+        code = _record_code(self._subkey(epoch), salt)
+
+        return decrypt(record[_EPOCH.size + _SALT_LEN:], code=code)
+
+    def _subkey(self, epoch: int, *, create: bool = False) -> bytes:
+        """Подключ периода; при ``create`` отсутствующий создаётся."""
+        with self._lock:
+            if epoch in self._cache:
+                return self._cache[epoch]
+
+            key_id = _EPOCH.pack(epoch)
+            blob = self._store.get(key_id)
+            if blob is not None:
+                if peek_header(blob)['key_id'] != key_id:
+                    raise MetaStringError(
+                        'key store entry does not match its id')
+                subkey = decrypt(blob, code=self._master_code)
+            elif create:
+                subkey = os.urandom(32)
+                self._store.put(key_id, encrypt(
+                    subkey, code=self._master_code, mode=EncryptMode.MGM,
+                    key_id=key_id))
+            else:
+                raise KeyShreddedError(f'subkey of epoch {epoch} is shredded')
+
+            self._cache[epoch] = subkey
+            return subkey
+
+
+def _record_code(subkey: bytes, salt: bytes) -> str:
+    """Код шифрования записи из подключа периода и соли."""
+    return mac_rust(b'grass-record' + salt, key=subkey).hex()
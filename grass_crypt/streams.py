@@ -0,0 +1,228 @@
+"""
+Потоковое шифрование файловых объектов и каналов (``sys.stdin.buffer``).
+
+Формат записей тот же, что у ``aio`` и ``proxy``: 4 байта длины
+(big-endian) и результат ``interfaces.encrypt`` для фрагмента.
+
+Память ограничена несколькими записями независимо от размера потока:
+длина записи проверяется до чтения её тела, а данные читаются ровно по
+нужному размеру, поэтому поток, приходящий по одному байту, не накапливает
+буферы.
+
+Чтение и запись выполняются в фоновых потоках с двумя буферами: пока
+шифруется текущая запись, следующая уже читается, а предыдущая
+записывается. Это скрывает задержки сетевых файловых систем и каналов.
+При дешифровке фоновый поток заранее читает и расшифровывает следующую
+запись (``iter_decrypt``).
+"""
+import queue
+import threading
+from typing import BinaryIO, Callable, Iterator, Optional
+
+from .exceptions import MetaStringError
+from .interfaces import decrypt, encrypt
+from .tools import EncryptMode, MAX_RECORD, RECORD_HEADER
+
+# Период проверки остановки потребителя фоновым потоком, секунды.
+_POLL_INTERVAL = 0.05
+
+
+def encrypt_stream(source: BinaryIO,
+                   sink: BinaryIO,
+                   *,
+                   code: str,
+                   mode: EncryptMode = EncryptMode.ECB,
+                   chunk_size: int = 64 * 1024) -> int:
+    """Шифровать поток записями до его окончания.
+
+    :param source: Источник с ``read(size)``.
+    :param sink: Приёмник с ``write(data)``.
+    :param code: Код шифрования.
+    :param mode: Режим шифрования.
+    :param chunk_size: Размер фрагмента открытых данных.
+    :returns:
+        Количество прочитанных байт открытых данных.
+    :raises ValueError: При неверном размере фрагмента.
+    """
+    if not 0 < chunk_size <= MAX_RECORD // 2:
+        raise ValueError(f'chunk_size must be from 1 to {MAX_RECORD // 2}')
+
+    total = 0
+    chunks = _prefetch(
+        lambda: _read_exact(source, chunk_size, allow_partial=True))
+    with _Writer(sink) as writer:
+        for chunk in chunks:
+            record = encrypt(chunk, code=code, mode=mode)
+            writer.write(RECORD_HEADER.pack(len(record)) + record)
+            total += len(chunk)
+
+    return total
+
+
+def decrypt_stream(source: BinaryIO, sink: BinaryIO, *, code: str) -> int:
+    """Расшифровать поток записей ``encrypt_stream``.
+
+    :param source: Источник записей.
+    :param sink: Приёмник расшифрованных данных.
+    :param code: Код шифрования.
+    :returns:
+        Количество записанных байт.
+    :raises MetaStringError: При повреждённом потоке или неверном коде.
+    """
+    total = 0
+    with _Writer(sink) as writer:
+        for data in iter_decrypt(source, code=code):
+            writer.write(data)
+            total += len(data)
+
+    return total
+
+
+def iter_decrypt(source: BinaryIO, *, code: str) -> Iterator[bytes]:
+    """Расшифрованные фрагменты потока записей ``encrypt_stream``.
+
+    Следующая запись читается и расшифровывается в фоновом потоке, пока
+    вызывающий обрабатывает текущую. Дешифровка в Rust не удерживает GIL,
+    поэтому её время скрывается за обработкой данных::
+
+        for chunk in iter_decrypt(source, code=code):
+            load(transform(chunk))
+
+    :param source: Источник записей.
+    :param code: Код шифрования.
+    :returns:
+        Итератор фрагментов открытых данных.
+    :raises MetaStringError: При повреждённом потоке или неверном коде.
+    """
+
+    def decrypt_next() -> Optional[bytes]:
+        record = _read_record(source)
+        if record is None:
+            return None
+        data = decrypt(record, code=code)
+        return data.encode('utf-8') if isinstance(data, str) else data
+
+    return _prefetch(decrypt_next)
+
+
+def _read_record(source: BinaryIO) -> Optional[bytes]:
+    """Прочитать тело очередной записи или ``None`` в конце потока."""
+    header = _read_exact(source, RECORD_HEADER.size)
+    if header is None:
+        return None
+    length = RECORD_HEADER.unpack(header)[0]
+    if not 0 < length <= MAX_RECORD:
+        raise MetaStringError(f'invalid record length: {length}')
+    record = _read_exact(source, length)
+    if record is None:
+        raise MetaStringError('stream ended inside a record')
+
+    return record
+
+
+def _prefetch(read_next: Callable[[], Optional[bytes]]) -> Iterator[bytes]:
+    """Читать следующий блок в фоновом потоке, пока обрабатывается текущий.
+
+    Блоки читаются до ``None``. Ошибка чтения поднимается у потребителя.
+    Если потребитель остановился раньше, поток больше не читает, а поток,
+    ожидающий ``read`` источника, не присоединяется: он завершится сам,
+    когда чтение вернётся.
+    """
+    blocks: queue.Queue = queue.Queue(maxsize=1)
+    stop = threading.Event()
+
+    def put(item: bytes | BaseException | None) -> bool:
+        while not stop.is_set():
+            try:
+                blocks.put(item, timeout=_POLL_INTERVAL)
+                return True
+            except queue.Full:
+                continue
+        return False
+
+    def worker() -> None:
+        try:
+            while not stop.is_set():
+                block = read_next()
+                if not put(block) or block is None:
+                    return
+        except BaseException as err:
+            put(err)
+
+    thread = threading.Thread(target=worker, daemon=True)
+    thread.start()
+    try:
+        while (block := blocks.get()) is not None:
+            if isinstance(block, BaseException):
+                raise block
+            yield block
+    finally:
+        stop.set()
+
+
+class _Writer:
+    """Запись в приёмник в фоновом потоке (не более одного блока в очереди).
+
+    Ошибка записи поднимается при следующем ``write`` или при выходе из
+    контекста.
+    """
+
+    def __init__(self, sink: BinaryIO):
+        self._sink = sink
+        self._blocks: queue.Queue = queue.Queue(maxsize=1)
+        self._error: Optional[BaseException] = None
+        self._thread = threading.Thread(target=self._run, daemon=True)
+
+    def __enter__(self) -> '_Writer':
+        self._thread.start()
+        return self
+
+    def write(self, data: bytes) -> None:
+        self._raise_error()
+        self._blocks.put(data)
+
+    def __exit__(self, *exc_info) -> None:
+        self._blocks.put(None)
+        self._thread.join()
+        if exc_info[0] is None:
+            self._raise_error()
+
+    def _run(self) -> None:
+        while (block := self._blocks.get()) is not None:
+            if self._error is not None:
+                continue
+            try:
+                self._sink.write(block)
+            except BaseException as err:
+                self._error = err
+
+    def _raise_error(self) -> None:
+        if self._error is not None:
+            raise self._error
+
+
+def _read_exact(source: BinaryIO,
+                size: int,
+                allow_partial: bool = False) -> Optional[bytes]:
+    """Прочитать ровно ``size`` байт с учётом коротких чтений каналов.
+
+    :returns:
+        Данные или ``None``, если поток закончился до первого байта. При
+        ``allow_partial`` возвращает остаток потока короче ``size``.
+    :raises MetaStringError: Если поток закончился внутри блока.
+    """
+    buffer = bytearray(size)
+    view, received = memoryview(buffer), 0
+    while received < size:
+        chunk = source.read(size - received)
+        if not chunk:
+            break
+        view[received:received + len(chunk)] = chunk
+        received += len(chunk)
+
+    if received == 0:
+        return None
+    if received < size and not allow_partial:
+        raise MetaStringError('stream ended inside a record')
+
+    return bytes(view[:received])
@@ -0,0 +1,153 @@
+"""
+Временные файлы с шифрованием содержимого.
+"""
+import os
+import secrets
+import tempfile
+from typing import BinaryIO, Optional
+
+from .interfaces import decrypt, encrypt
+from .tools import EncryptMode, RECORD_HEADER
+
+
+class EncryptedTempFile:
+    """Временный файл, содержимое которого хранится только зашифрованным.
+
+    Каждая запись ``write()`` шифруется отдельно случайным сеансовым кодом.
+    Пока объём данных не превышает ``max_size``, зашифрованные записи
+    хранятся в памяти, затем переносятся во временный файл на диске.
+    При закрытии хранилище перезаписывается нулями и удаляется.
+
+    Чтение последовательное: ``read()`` возвращает данные с текущей позиции,
+    ``seek(0)`` возвращает к началу.
+    """
+
+    def __init__(self,
+                 max_size: int = 1024 * 1024,
+                 *,
+                 mode: EncryptMode = EncryptMode.ECB,
+                 dir: Optional[str] = None):
+        self._code = secrets.token_urlsafe(32)
+        self._mode = mode
+        self._max_size = max_size
+        self._dir = dir
+        self._memory: Optional[bytearray] = bytearray()
+        self._file: Optional[BinaryIO] = None
+        self._size = 0
+        self._read_offset = 0
+        self._pending = b''
+        self.closed = False
+
+    @property
+    def rolled(self) -> bool:
+        """Данные перенесены на диск."""
+        return self._file is not None
+
+    def write(self, data: bytes) -> int:
+        """Зашифровать и добавить данные в конец файла.
+
+        :returns:
+            Количество записанных байт открытого текста.
+        """
+        self._check_open()
+        if not data:
+            return 0
+
+        record = encrypt(bytes(data), code=self._code, mode=self._mode)
+        self._append(RECORD_HEADER.pack(len(record)) + record)
+
+        return len(data)
+
+    def read(self, size: int = -1) -> bytes:
+        """Прочитать расшифрованные данные с текущей позиции."""
+        self._check_open()
+        chunks, total = [self._pending], len(self._pending)
+
+        while size < 0 or total < size:
+            record = self._next_record()
+            if record is None:
+                break
+            chunks.append(record)
+            total += len(record)
+
+        data = b''.join(chunks)
+        if size < 0:
+            size = len(data)
+        self._pending = data[size:]
+
+        return data[:size]
+
+    def seek(self, offset: int) -> int:
+        """Вернуться к началу файла (поддерживается только ``offset=0``)."""
+        self._check_open()
+        if offset != 0:
+            raise ValueError('only seek(0) is supported')
+        self._read_offset, self._pending = 0, b''
+
+        return 0
+
+    def close(self) -> None:
+        """Стереть и удалить хранилище."""
+        if self.closed:
+            return
+        if self._memory is not None:
+            self._memory[:] = bytes(len(self._memory))
+            self._memory = None
+        if self._file is not None:
+            self._file.seek(0)
+            self._file.write(bytes(self._size))
+            self._file.flush()
+            os.fsync(self._file.fileno())
+            self._file.close()
+            self._file = None
+        self._pending = b''
+        self.closed = True
+
+    def __enter__(self) -> 'EncryptedTempFile':
+        return self
+
+    def __exit__(self, *exc_info) -> None:
+        self.close()
+
+    def __del__(self):
+        if not getattr(self, 'closed', True):
+            self.close()
+
+    def _append(self, record: bytes) -> None:
+        """Добавить запись в хранилище, при необходимости перенести на диск."""
+        if self._file is None and self._size + len(record) > self._max_size:
+            self._file = tempfile.TemporaryFile(dir=self._dir)
+            self._file.write(self._memory)
+            self._memory[:] = bytes(len(self._memory))
+            self._memory = None
+
+        if self._file is not None:
+            self._file.seek(0, os.SEEK_END)
+            self._file.write(record)
+        else:
+            self._memory += record
+        self._size += len(record)
+
+    def _next_record(self) -> Optional[bytes]:
+        """Прочитать и расшифровать очередную запись."""
+        if self._read_offset >= self._size:
+            return None
+
+        raw = self._raw(self._read_offset, RECORD_HEADER.size)
+        length = RECORD_HEADER.unpack(raw)[0]
+        record = self._raw(self._read_offset + RECORD_HEADER.size, length)
+        self._read_offset += RECORD_HEADER.size + length
+
+        return decrypt(record, code=self._code)
+
+    def _raw(self, offset: int, length: int) -> bytes:
+        """Прочитать байты хранилища."""
+        if self._file is None:
+            return bytes(self._memory[offset:offset + length])
+        self._file.seek(offset)
+
+        return self._file.read(length)
+
+    def _check_open(self) -> None:
+        if self.closed:
+            raise ValueError('I/O operation on closed file')
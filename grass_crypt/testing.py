@@ -0,0 +1,111 @@
+"""
+Проверка обработки ошибок на повреждённых шифровках.
+
+Модуль доступен, только если расширение собрано с функцией ``testing``
+(``maturin develop --features testing``)::
+
+    from grass_crypt.testing import corrupt, expect_failure
+
+    bad = corrupt(blob, offset=40, base64_encoded=True)
+    expect_failure(lambda b: decrypt(b, code=code), bad, MetaStringError)
+"""
+import base64
+import re
+from typing import Any, Callable, Optional
+
+from .chunked import _HEADER, _HEADER_V1, _MAGIC as _CHUNKED_MAGIC
+from .exceptions import GrassCryptException
+from .interfaces import _TAG_LEN
+
+try:
+    from cryptor import tamper_byte, tamper_range  # noqa
+except ImportError:
+    raise ImportError('grass_crypt.testing requires cryptor built with '
+                      'the "testing" feature') from None
+
+
+def corrupt(blob: bytes,
+            *,
+            offset: int = -1,
+            length: int = 1,
+            mask: int = 0xFF,
+            base64_encoded: bool = False) -> bytes:
+    """Повредить байты шифровки.
+
+    :param blob: Шифровка.
+    :param offset: Смещение первого байта; отрицательное — от конца.
+    :param length: Количество байт.
+    :param mask: Маска XOR.
+    :param base64_encoded: Шифровка в base64 (результат ``encrypt``):
+                           смещение относится к декодированным данным, а
+                           результат снова кодируется, чтобы повреждение
+                           дошло до проверок шифротекста.
+    :returns:
+        Повреждённая копия.
+    :raises IndexError: Если диапазон выходит за данные.
+    """
+
+    data = base64.b64decode(blob) if base64_encoded else blob
+    data = tamper_range(data, offset, length, mask=mask)
+
+    return base64.b64encode(data) if base64_encoded else data
+
+
+def corrupt_chunk(blob: bytes,
+                  index: int,
+                  *,
+                  byte: int = 0,
+                  tag: bool = False) -> bytes:
+    """Повредить фрагмент контейнера ``chunked``.
+
+    :param blob: Содержимое файла контейнера.
+    :param index: Номер фрагмента.
+    :param byte: Смещение внутри шифротекста или имитовставки фрагмента.
+    :param tag: Повредить имитовставку вместо шифротекста.
+    :returns:
+        Повреждённая копия.
+    :raises ValueError: Если данные не являются контейнером.
+    :raises IndexError: При отсутствии фрагмента.
+    """
+
+    if not blob.startswith(_CHUNKED_MAGIC) or len(blob) < _HEADER_V1.size:
+        raise ValueError('not a chunked container')
+    header = _HEADER_V1 if blob[len(_CHUNKED_MAGIC)] == 1 else _HEADER
+    chunk_size, size = header.unpack_from(blob)[2:4]
+    if not 0 <= index * chunk_size < size:
+        raise IndexError('chunk index out of range')
+    start = header.size + _TAG_LEN + index * (chunk_size + _TAG_LEN)
+    length = min(chunk_size, size - index * chunk_size)
+
+    return tamper_byte(blob, start + (length if tag else 0) + byte)
+
+
+def expect_failure(decrypt: Callable[[bytes], Any],
+                   blob: bytes,
+                   expected: type[BaseException] = GrassCryptException,
+                   match: Optional[str] = None) -> BaseException:
+    """Убедиться, что расшифровка повреждённых данных завершается ошибкой.
+
+    :param decrypt: Расшифровка, принимающая данные.
+    :param blob: Повреждённые данные.
+    :param expected: Ожидаемый класс исключения.
+    :param match: Регулярное выражение для сообщения исключения.
+    :returns:
+        Пойманное исключение.
+    :raises AssertionError: Если расшифровка прошла или ошибка другая.
+    """
+
+    try:
+        decrypt(blob)
+    except expected as err:
+        if match is not None and not re.search(match, str(err)):
+            raise AssertionError(
+                f'{type(err).__name__} message {str(err)!r} does not '
+                f'match {match!r}') from err
+        return err
+    except BaseException as err:
+        raise AssertionError(
+            f'expected {expected.__name__}, got {type(err).__name__}: {err}'
+        ) from err
+
+    raise AssertionError('decryption of tampered data succeeded')
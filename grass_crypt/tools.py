@@ -2,9 +2,58 @@
 Вспомогательные утилиты.
 """
 import os
+import re
+import struct
 from enum import Enum
-from hashlib import blake2b
-from typing import Optional, Any
+from hashlib import blake2b, scrypt
+from typing import Optional, Any, Iterable, Iterator
+
+# Длина основной части метаданных: тип данных (3), режим (3), соль (16).
+META_LEN = 22
+# Маркер и текущая версия строки метаданных. Метаданные без маркера
+# относятся к версии 1 (до появления идентификатора ключа), версия 2 — до
+# появления выбора шифра, версия 3 — до имитовставки заголовка.
+META_MAGIC = b'GRH'
+META_VERSION = 4
+# Длина имитовставки метаданных (версия 4).
+HEADER_TAG_LEN = 16
+# Идентификаторы шифров в метаданных; до версии 3 — только "Кузнечик".
+CIPHERS = {'kuznyechik': 1, 'aes256': 2, 'chacha20poly1305': 3, 'magma': 4}
+DEFAULT_CIPHER = 'kuznyechik'
+# Размер блока (выравнивания и вектора инициализации) блочных шифров.
+BLOCK_LEN = {'kuznyechik': 16, 'aes256': 16, 'magma': 8}
+# Шифры с аутентификацией: режим не используется, к шифротексту
+# добавляются одноразовый номер и имитовставка.
+AEAD_CIPHERS = frozenset({'chacha20poly1305'})
+AEAD_NONCE_LEN = 12
+AEAD_TAG_LEN = 16
+# Режим MGM: nonce перед шифротекстом и имитовставка после.
+MGM_NONCE_LEN = 16
+MGM_TAG_LEN = 16
+# Длина идентификатора ключа, вычисляемого по ключу шифровки.
+KEY_ID_LEN = 8
+# Границы PEM-подобной "брони" для текстовых каналов.
+ARMOR_BEGIN = b'-----BEGIN GRASS ENCRYPTED MESSAGE-----'
+ARMOR_END = b'-----END GRASS ENCRYPTED MESSAGE-----'
+ARMOR_WIDTH = 64
+# Префикс длины записи в потоковых протоколах (4 байта, big-endian) и
+# максимальная длина записи: защита от некорректного источника.
+RECORD_HEADER = struct.Struct('>I')
+MAX_RECORD = 16 * 1024 * 1024
+# Политики обработки данных после сообщения при расшифровке: ошибка или
+# возврат остатка вызывающей стороне.
+TRAILING_POLICIES = ('strict', 'lenient')
+# Политики векторов инициализации: встроенный вектор допустим или вектор
+# обязан передать вызывающий (встроенный принимается только при дешифровке
+# с явным ``legacy_iv=True``).
+IV_POLICIES = ('legacy', 'strict')
+# Действия политики для нештатных, но не фатальных ситуаций (см.
+# ``grass_crypt.policy``).
+POLICY_ACTIONS = ('error', 'warn', 'ignore')
+# Функции выработки ключа из кодовой фразы для контейнеров с записью
+# параметров в заголовке и параметры scrypt по умолчанию.
+KDFS = {'blake2b': 1, 'scrypt': 2}
+SCRYPT_DEFAULTS = {'n': 2 ** 15, 'r': 8, 'p': 1}
 
 
 class EncryptMode(Enum):
@@ -13,6 +62,7 @@ class EncryptMode(Enum):
     CFB = 'CFB'
     OFB = 'OFB'
     CTR = 'CTR'
+    MGM = 'MGM'
 
     def as_bytes(self) -> bytes:
         return self.value.encode('utf-8')
@@ -64,10 +114,12 @@ def get_hash_blake2b(value: str,
     :raise ValueError: При неверном значении digest_size.
     """
 
+    # Значение не передаётся в исключение: это кодовая фраза.
     if not isinstance(value, str) or not isinstance(digest_size, int):
-        raise TypeError(value)
+        raise TypeError('value must be str and digest_size must be int')
     if not (1 <= digest_size <= 64):
-        raise ValueError(digest_size)
+        raise ValueError(
+            f'digest_size must be from 1 to 64, not {digest_size}')
 
     if salt is None:
         salt = get_salt()
@@ -78,6 +130,37 @@ def get_hash_blake2b(value: str,
                    ).digest(), salt
 
 
+def derive_key(value: str,
+               *,
+               salt: bytes,
+               kdf: str = 'blake2b',
+               params: Optional[dict[str, int]] = None) -> bytes:
+    """ Выработать ключ (32 байта) из кодовой фразы функцией ``kdf``.
+
+    :param value: Кодовая фраза.
+    :param salt: Соль.
+    :param kdf: Функция из ``KDFS``: ``blake2b`` (без параметров стоимости)
+                или ``scrypt``.
+    :param params: Параметры scrypt ``n``, ``r``, ``p``; по умолчанию
+                   ``SCRYPT_DEFAULTS``.
+    :returns:
+        Ключ.
+    :raise ValueError: При неизвестной функции или неверных параметрах.
+    """
+
+    if kdf == 'blake2b':
+        return get_hash_blake2b(value, salt=salt)[0]
+    if kdf != 'scrypt':
+        raise ValueError(f'kdf must be one of {", ".join(KDFS)}')
+    if not isinstance(value, str):
+        raise TypeError('value must be str')
+
+    params = {**SCRYPT_DEFAULTS, **(params or {})}
+    n, r, p = params['n'], params['r'], params['p']
+    return scrypt(value.encode('utf-8'), salt=salt, n=n, r=r, p=p,
+                  maxmem=128 * r * (n + p + 2) + 1024 * 1024, dklen=32)
+
+
 def get_salt() -> bytes:
     """ Предоставить соли для хеша.
 
@@ -120,18 +203,71 @@ def save_file(filepath: str, *, data: str | bytes) -> str:
     return filepath
 
 
+def get_key_id(key: bytes) -> bytes:
+    """ Идентификатор ключа по умолчанию: отпечаток ключа шифровки.
+
+    Ключ выработан из кодовой фразы с солью, поэтому идентификатор разный
+    для каждой шифровки, не связывает их между собой и не позволяет
+    подбирать кодовую фразу без ключа.
+
+    :param key: Ключ шифровки (``derive_key`` или ``get_hash_blake2b``).
+    :returns:
+        Байт-строка длиной ``KEY_ID_LEN``.
+    """
+
+    if not isinstance(key, bytes) or not key:
+        raise TypeError('key must be bytes and cannot be empty')
+
+    return blake2b(digest_size=KEY_ID_LEN, key=key,
+                   person=b'grass-key-id').digest()
+
+
+def header_tag(meta: bytes, key: bytes) -> bytes:
+    """ Имитовставка строки метаданных версии 4.
+
+    :param meta: Строка метаданных ``make_meta``.
+    :param key: Ключ шифровки.
+    :returns:
+        Байт-строка длиной ``HEADER_TAG_LEN``.
+    """
+
+    return blake2b(meta, digest_size=HEADER_TAG_LEN, key=key,
+                   person=b'grass-header').digest()
+
+
+def meta_len(key_id_len: int = KEY_ID_LEN) -> int:
+    """ Длина строки метаданных текущей версии с имитовставкой.
+
+    :param key_id_len: Длина идентификатора ключа.
+    :returns:
+        Количество байт метаданных.
+    """
+
+    return len(META_MAGIC) + 2 + META_LEN + 1 + key_id_len + HEADER_TAG_LEN
+
+
 def make_meta(*,
               plaintext_type: type[bytes | str],
               salt: bytes,
-              mode: EncryptMode) -> bytes:
+              mode: EncryptMode,
+              key_id: bytes,
+              cipher: str = DEFAULT_CIPHER) -> bytes:
     """
     Создать строку метаданных для добавления к шифровке.
 
     Схема:
 
+    - 3 байта — GRH — маркер метаданных с версией
+    - 1 байт — версия метаданных
+    - 1 байт — идентификатор шифра из ``CIPHERS``
     - 3 байта — STR/BYT — тип входных данных на шифровку (str, bytes)
     - 3 байта — EncryptMode
     - 16 байтов — соль для хеша кодовой фразы
+    - 1 байт — длина идентификатора ключа
+    - до 255 байтов — идентификатор ключа
+
+    Имитовставку (``header_tag``, 16 байтов) добавляет ``encrypt``: она
+    требует ключа.
 
     :returns:
         Байтовая строка с основными данными.
@@ -141,8 +277,14 @@ def make_meta(*,
         data_type = str(plaintext_type.__name__)[:3].upper()
     else:
         raise ValueError('plaintext_type must be str or bytes')
+    if not isinstance(key_id, bytes) or not 1 <= len(key_id) <= 255:
+        raise ValueError('key_id must be bytes of 1 to 255 length')
+    if cipher not in CIPHERS:
+        raise ValueError(f'unknown cipher: {cipher}')
 
-    return data_type.encode('utf-8') + mode.as_bytes() + salt
+    return (META_MAGIC + bytes([META_VERSION, CIPHERS[cipher]])
+            + data_type.encode('utf-8') + mode.as_bytes() + salt
+            + bytes([len(key_id)]) + key_id)
 
 
 def read_meta(*,
@@ -151,6 +293,12 @@ def read_meta(*,
     """
     Считать и распаковать метаданные из зашифрованного текста.
 
+    Поддерживаются метаданные версии 1 (без маркера и идентификатора
+    ключа), для них ``key_id`` равен ``None``, версии 2 (без шифра) и
+    версии 3 (без имитовставки). Для версии 4 ``header`` содержит
+    защищённую строку метаданных, а ``header_tag`` — её имитовставку;
+    проверяет её вызывающая сторона.
+
     :param ciphertext: Шифрованный текст.
     :returns:
         Три элемента: экземпляр исключения, если возникли ошибки при
@@ -159,20 +307,125 @@ def read_meta(*,
     """
 
     try:
-        if len(ciphertext) < 22:
+        version, offset, cipher = 1, 0, DEFAULT_CIPHER
+        if ciphertext.startswith(META_MAGIC):
+            version = ciphertext[len(META_MAGIC)]
+            if not 2 <= version <= META_VERSION:
+                raise ValueError(f'metadata version {version} is unknown')
+            offset = len(META_MAGIC) + 1
+        if version >= 3:
+            names = {v: k for k, v in CIPHERS.items()}
+            cipher = names.get(ciphertext[offset:offset + 1][0]
+                               if len(ciphertext) > offset else None)
+            if cipher is None:
+                raise ValueError('metadata string is incorrect (cipher)')
+            offset += 1
+
+        if len(ciphertext) < offset + META_LEN:
             raise ValueError('metadata string is incorrect (short line)')
 
         source_type = {
             'STR': str, 'BYT': bytes
-        }[ciphertext[:3].decode('utf-8')]
+        }[ciphertext[offset:offset + 3].decode('utf-8')]
         meta_data: dict[str, Any] = {
+            'version': version,
+            'cipher': cipher,
             'source_type': source_type,
             'mode': EncryptMode.me_from_value(
-                ciphertext[3:6].decode('utf-8')),
-            'salt': ciphertext[6:22],
+                ciphertext[offset + 3:offset + 6].decode('utf-8')),
+            'salt': ciphertext[offset + 6:offset + META_LEN],
+            'key_id': None,
+            'header': None,
+            'header_tag': None,
         }
-
-        return None, ciphertext[22:], meta_data
+        offset += META_LEN
+
+        if version >= 2:
+            if len(ciphertext) <= offset:
+                raise ValueError('metadata string is incorrect (no key id)')
+            key_id_len = ciphertext[offset]
+            key_id = ciphertext[offset + 1:offset + 1 + key_id_len]
+            if len(key_id) != key_id_len:
+                raise ValueError('metadata string is incorrect (key id)')
+            meta_data['key_id'] = key_id
+            offset += 1 + key_id_len
+
+        if version >= 4:
+            tag = ciphertext[offset:offset + HEADER_TAG_LEN]
+            if len(tag) != HEADER_TAG_LEN:
+                raise ValueError('metadata string is incorrect (header tag)')
+            meta_data['header'] = ciphertext[:offset]
+            meta_data['header_tag'] = tag
+            offset += HEADER_TAG_LEN
+
+        return None, ciphertext[offset:], meta_data
 
     except Exception as err:
         return err, b'', {}
+
+
+def armor_lines(ciphertext: bytes) -> Iterator[bytes]:
+    """
+    Построчно обернуть base64-шифровку в PEM-подобную "броню".
+
+    Строки формируются по мере записи, без второй копии данных.
+
+    :param ciphertext: Результат ``interfaces.encrypt`` (base64).
+    :returns:
+        Итератор строк с завершающим переводом строки.
+    """
+
+    yield ARMOR_BEGIN + b'\n'
+    view = memoryview(ciphertext)
+    for start in range(0, len(view), ARMOR_WIDTH):
+        yield bytes(view[start:start + ARMOR_WIDTH]) + b'\n'
+    yield ARMOR_END + b'\n'
+
+
+def dearmor_lines(lines: Iterable[bytes]) -> bytes:
+    """
+    Извлечь base64-шифровку из строк "брони".
+
+    :param lines: Строки, начиная с ``ARMOR_BEGIN``.
+    :returns:
+        Шифровка в формате base64.
+    :raises ValueError: При нарушении формата.
+    """
+
+    lines = iter(lines)
+    if next(lines, b'').strip() != ARMOR_BEGIN:
+        raise ValueError('armor header is missing')
+
+    body = []
+    for line in lines:
+        line = line.strip()
+        if line == ARMOR_END:
+            return b''.join(body)
+        body.append(line)
+
+    raise ValueError('armor footer is missing')
+
+
+def is_armored(data: bytes) -> bool:
+    """Данные начинаются с заголовка "брони"."""
+    return data.lstrip().startswith(ARMOR_BEGIN)
+
+
+def split_message(ciphertext: bytes) -> tuple[bytes, bytes]:
+    """Отделить первое сообщение (base64 или "броня") от следующих данных.
+
+    :param ciphertext: Данные, начинающиеся с результата
+                       ``interfaces.encrypt``.
+    :returns:
+        Сообщение и остаток после него без начальных пробельных символов.
+    """
+
+    data = ciphertext.lstrip()
+    if data.startswith(ARMOR_BEGIN):
+        end = data.find(ARMOR_END)
+        end = len(data) if end < 0 else end + len(ARMOR_END)
+    else:
+        match = re.search(rb'\s', data)
+        end = match.start() if match else len(data)
+
+    return data[:end], data[end:].lstrip()
@@ -0,0 +1,91 @@
+//! Шифрование с аутентификацией (AEAD).
+//!
+//! Результат: `nonce (12) + шифротекст + имитовставка (16)`. Одноразовый
+//! номер генерирует вызывающая сторона.
+
+use block_encryption::traits::CipherError;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+/// Размер одноразового номера в байтах.
+pub const NONCE_SIZE: usize = 12;
+
+/// Размер имитовставки в байтах.
+pub const TAG_SIZE: usize = 16;
+
+/// Алгоритм шифрования с аутентификацией.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    ChaCha20Poly1305,
+}
+
+impl Algorithm {
+    /// Алгоритм по имени (`chacha20poly1305`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "chacha20poly1305" => Some(Algorithm::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+}
+
+/// Ошибки шифрования с аутентификацией.
+#[derive(Debug)]
+pub enum Error {
+    /// Неверные параметры шифра.
+    Cipher(CipherError),
+    /// Имитовставка не совпала: данные повреждены или ключ неверный.
+    Authentication,
+}
+
+/// Шифрование с аутентификацией.
+///
+/// - algorithm — Алгоритм
+/// - key — Ключ (32 байта)
+/// - nonce — Одноразовый номер, не повторяется для одного ключа
+/// - plaintext — Открытый текст
+/// - aad — Дополнительные данные, защищаемые имитовставкой
+pub fn seal(
+    algorithm: Algorithm,
+    key: &[u8],
+    nonce: &[u8; NONCE_SIZE],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let payload = Payload {
+        msg: plaintext,
+        aad,
+    };
+    let sealed = match algorithm {
+        Algorithm::ChaCha20Poly1305 => {
+            chacha20poly1305(key)?.encrypt(Nonce::from_slice(nonce), payload)
+        }
+    }
+    .map_err(|_| Error::Cipher(CipherError::InvalidBlockSize))?;
+
+    let mut out = Vec::with_capacity(NONCE_SIZE + sealed.len());
+    out.extend_from_slice(nonce);
+    out.extend_from_slice(&sealed);
+
+    Ok(out)
+}
+
+/// Проверка имитовставки и дешифровка результата `seal`.
+pub fn open(algorithm: Algorithm, key: &[u8], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < NONCE_SIZE + TAG_SIZE {
+        return Err(Error::Cipher(CipherError::DataTooShort));
+    }
+    let (nonce, sealed) = data.split_at(NONCE_SIZE);
+    let payload = Payload { msg: sealed, aad };
+
+    match algorithm {
+        Algorithm::ChaCha20Poly1305 => {
+            chacha20poly1305(key)?.decrypt(Nonce::from_slice(nonce), payload)
+        }
+    }
+    .map_err(|_| Error::Authentication)
+}
+
+fn chacha20poly1305(key: &[u8]) -> Result<ChaCha20Poly1305, Error> {
+    ChaCha20Poly1305::new_from_slice(key).map_err(|_| Error::Cipher(CipherError::InvalidKeyLenght))
+}
@@ -0,0 +1,101 @@
+//! Минимальный сериализатор и разборщик DER (X.690) для структур CMS.
+//!
+//! Поддерживаются только однобайтовые теги и определённая длина до 4 байт;
+//! разборщик отвергает неминимальную запись длины, как требует DER.
+
+pub const OCTET_STRING: u8 = 0x04;
+pub const OBJECT_IDENTIFIER: u8 = 0x06;
+pub const PRINTABLE_STRING: u8 = 0x13;
+pub const SEQUENCE: u8 = 0x30;
+/// `[0] IMPLICIT` для примитивного значения.
+pub const CONTEXT_0: u8 = 0x80;
+
+/// Запись элемента `tag`, длины и содержимого в `out`.
+pub fn write(out: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    out.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+}
+
+/// Элемент `tag` с содержимым `content` отдельным буфером.
+pub fn element(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len() + 6);
+    write(&mut out, tag, content);
+    out
+}
+
+/// Содержимое OBJECT IDENTIFIER по номерам дуг (первых дуг не меньше двух).
+pub fn oid(arcs: &[u64]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let first = arcs[0] * 40 + arcs[1];
+    for &arc in std::iter::once(&first).chain(&arcs[2..]) {
+        let groups = (64 - arc.leading_zeros()).div_ceil(7).max(1);
+        for i in (0..groups).rev() {
+            let more = if i > 0 { 0x80 } else { 0 };
+            out.push(more | (arc >> (7 * i)) as u8 & 0x7F);
+        }
+    }
+
+    out
+}
+
+/// Последовательный разбор элементов DER.
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Тег следующего элемента.
+    pub fn peek(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Содержимое следующего элемента; `None`, если тег другой или запись
+    /// неверна.
+    pub fn read(&mut self, tag: u8) -> Option<&'a [u8]> {
+        let (&actual, rest) = self.data.split_first()?;
+        if actual != tag {
+            return None;
+        }
+        let (&first, mut rest) = rest.split_first()?;
+        let len = if first < 0x80 {
+            usize::from(first)
+        } else {
+            let count = usize::from(first & 0x7F);
+            if count == 0 || count > 4 || rest.len() < count || rest[0] == 0 {
+                return None;
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0usize, |len, &b| len << 8 | usize::from(b));
+            rest = &rest[count..];
+            if len < 0x80 {
+                return None;
+            }
+            len
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let (content, rest) = rest.split_at(len);
+        self.data = rest;
+
+        Some(content)
+    }
+}
@@ -0,0 +1,42 @@
+//! Параллельная обработка наборов сообщений.
+
+use std::thread;
+
+/// Параллельное применение `f` к элементам с сохранением порядка.
+///
+/// Элементы делятся на непрерывные части по числу доступных ядер, каждая
+/// часть обрабатывается в отдельном потоке.
+pub fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    parallel_map_in(items, threads, f)
+}
+
+/// `parallel_map` не более чем в `threads` потоках; `1` — в текущем.
+pub fn parallel_map_in<T, R, F>(items: &[T], threads: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    if threads <= 1 || items.len() < 2 {
+        return items.iter().map(&f).collect();
+    }
+
+    let chunk = items.len().div_ceil(threads);
+    thread::scope(|s| {
+        let handles: Vec<_> = items
+            .chunks(chunk)
+            .map(|part| s.spawn(|| part.iter().map(&f).collect::<Vec<R>>()))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("worker thread panicked"))
+            .collect()
+    })
+}
@@ -0,0 +1,197 @@
+//! Замеры производительности основных путей движка.
+//!
+//! Собирается только с функцией `benchmarks`. Одни и те же случаи замеряют
+//! criterion (`cargo bench --features benchmarks`) и `run_benchmarks`
+//! (`maturin develop --features benchmarks`), поэтому пользователи могут
+//! сравнивать выпуски на своём оборудовании.
+//!
+//! Группы случаев:
+//!
+//! - `block` — блочные шифры (ECB);
+//! - `modes` — режимы "Кузнечика" и MGM;
+//! - `streaming` — пошаговое шифрование частями по `READ_CHUNK`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::engine::{self, Algorithm};
+use crate::mac::READ_CHUNK;
+use crate::modes;
+
+const KEY: [u8; engine::KEY_SIZE] = [0x42; engine::KEY_SIZE];
+const NONCE: [u8; engine::BLOCK_SIZE] = [0x24; engine::BLOCK_SIZE];
+const CIPHERS: [(&str, Algorithm); 3] = [
+    ("kuznyechik", Algorithm::Kuznyechik),
+    ("aes256", Algorithm::Aes256),
+    ("magma", Algorithm::Magma),
+];
+
+type Run = Box<dyn Fn(&[u8]) + Send + Sync>;
+
+/// Случай замера: однократная обработка данных.
+pub struct Case {
+    pub group: &'static str,
+    pub name: String,
+    run: Run,
+}
+
+impl Case {
+    fn new<F>(group: &'static str, name: String, run: F) -> Self
+    where
+        F: Fn(&[u8]) + Send + Sync + 'static,
+    {
+        Case {
+            group,
+            name,
+            run: Box::new(run),
+        }
+    }
+
+    /// Полное имя: `group/name`.
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.group, self.name)
+    }
+
+    /// Обработка `data`; результат не используется.
+    pub fn run(&self, data: &[u8]) {
+        (self.run)(black_box(data))
+    }
+}
+
+/// Все случаи замера.
+pub fn cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+
+    for (name, algorithm) in CIPHERS {
+        cases.push(Case::new("block", name.to_string(), move |data| {
+            black_box(engine::encrypting_with(algorithm, data, &KEY, modes::Mode::Ecb, None).ok());
+        }));
+    }
+
+    for mode in modes::Mode::ALL.into_iter().skip(1) {
+        cases.push(Case::new(
+            "modes",
+            format!("{}/encrypt", mode.name()),
+            move |data| {
+                black_box(
+                    engine::encrypting_with(Algorithm::Kuznyechik, data, &KEY, mode, None).ok(),
+                );
+            },
+        ));
+    }
+    cases.push(Case::new("modes", "CBC/decrypt".to_string(), |data| {
+        let aligned = &data[..data.len() - data.len() % engine::BLOCK_SIZE];
+        black_box(
+            engine::decrypting_with(Algorithm::Kuznyechik, aligned, &KEY, modes::Mode::Cbc, None)
+                .ok(),
+        );
+    }));
+    cases.push(Case::new("modes", "MGM/encrypt".to_string(), |data| {
+        black_box(engine::encrypting_mgm(Algorithm::Kuznyechik, data, &KEY, &NONCE, &[], 16).ok());
+    }));
+
+    for mode in [modes::Mode::Cbc, modes::Mode::Ctr] {
+        cases.push(Case::new(
+            "streaming",
+            mode.name().to_string(),
+            move |data| {
+                let Ok(mut stream) = engine::Streaming::new(
+                    Algorithm::Kuznyechik,
+                    &KEY,
+                    mode,
+                    None,
+                    modes::Options::default(),
+                    true,
+                ) else {
+                    return;
+                };
+                for chunk in data.chunks(READ_CHUNK) {
+                    black_box(stream.update(chunk));
+                }
+                black_box(stream.finalize().ok());
+            },
+        ));
+    }
+
+    cases
+}
+
+/// Результат замера случая.
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    pub iterations: u64,
+    pub elapsed: Duration,
+}
+
+/// Повторение случая не менее `min_time` (после одного прогрева).
+pub fn measure(case: &Case, data: &[u8], min_time: Duration) -> Measurement {
+    case.run(data);
+
+    let started = Instant::now();
+    let mut iterations = 0;
+    while iterations == 0 || started.elapsed() < min_time {
+        case.run(data);
+        iterations += 1;
+    }
+
+    Measurement {
+        iterations,
+        elapsed: started.elapsed(),
+    }
+}
+
+/// Замеры производительности движка.
+///
+/// Случаи выполняются по очереди без удержания GIL, между ними проверяются
+/// сигналы Python.
+///
+/// - size — Размер данных одного прохода в байтах
+/// - min_time — Минимальное время замера каждого случая в секундах
+/// - filter — Подстрока полного имени случая (`modes/CBC`)
+///
+/// Возвращает список словарей: `group`, `name`, `bytes`, `iterations`,
+/// `seconds` и `throughput` (байт в секунду).
+#[pyfunction]
+#[pyo3(name = "run_benchmarks")]
+#[pyo3(signature = (*, size=64 * 1024, min_time=0.5, filter=None))]
+pub fn run_benchmarks(
+    py: Python<'_>,
+    size: usize,
+    min_time: f64,
+    filter: Option<&str>,
+) -> PyResult<Vec<Py<PyDict>>> {
+    if size == 0 {
+        return Err(PyValueError::new_err("size must be positive"));
+    }
+    let min_time = Duration::try_from_secs_f64(min_time)
+        .map_err(|e| PyValueError::new_err(format!("invalid min_time: {e}")))?;
+    let data = vec![0x5a; size];
+
+    let mut results = Vec::new();
+    for case in cases() {
+        if filter.is_some_and(|filter| !case.id().contains(filter)) {
+            continue;
+        }
+        py.check_signals()?;
+        let measurement = py.allow_threads(|| measure(&case, &data, min_time));
+
+        let seconds = measurement.elapsed.as_secs_f64();
+        let result = PyDict::new(py);
+        result.set_item("group", case.group)?;
+        result.set_item("name", &case.name)?;
+        result.set_item("bytes", size)?;
+        result.set_item("iterations", measurement.iterations)?;
+        result.set_item("seconds", seconds)?;
+        result.set_item(
+            "throughput",
+            (size as u64 * measurement.iterations) as f64 / seconds,
+        )?;
+        results.push(result.unbind());
+    }
+
+    Ok(results)
+}
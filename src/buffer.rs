@@ -0,0 +1,248 @@
+//! Буфер в памяти, содержимое которого хранится в куче Rust зашифрованным.
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PySlice};
+use zeroize::Zeroizing;
+
+use crate::{decrypt_to_py_err, engine, mac, modes, rust_to_py_err};
+
+/// Размер фрагмента открытого текста, шифруемого отдельно.
+const CHUNK_SIZE: usize = 4096;
+
+/// Зашифрованный фрагмент: поколение (меняется при каждой перезаписи) и
+/// шифротекст.
+struct Chunk {
+    generation: u64,
+    data: Vec<u8>,
+}
+
+/// Аналог `io.BytesIO`, хранящий данные зашифрованными.
+///
+/// Данные делятся на фрагменты по `CHUNK_SIZE` байт. Каждый фрагмент
+/// шифруется в режиме CTR собственным ключом, производным от случайного
+/// сеансового ключа, номера фрагмента и поколения. При чтении (`read`,
+/// срезы `buf[a:b]`) расшифровываются только затронутые фрагменты.
+#[pyclass(name = "EncryptedBytesIO")]
+pub struct EncryptedBuffer {
+    key: Zeroizing<Vec<u8>>,
+    chunks: Vec<Chunk>,
+    len: usize,
+    pos: usize,
+    closed: bool,
+}
+
+impl EncryptedBuffer {
+    /// Ключ фрагмента: HMAC-Стрибог от номера и поколения.
+    fn chunk_key(&self, index: usize, generation: u64) -> Zeroizing<Vec<u8>> {
+        let mut label = [0u8; 16];
+        label[..8].copy_from_slice(&(index as u64).to_be_bytes());
+        label[8..].copy_from_slice(&generation.to_be_bytes());
+
+        Zeroizing::new(mac::mac_data(&label, &self.key))
+    }
+
+    /// Открытый текст фрагмента (пустой для отсутствующих).
+    fn load(&self, index: usize) -> PyResult<Zeroizing<Vec<u8>>> {
+        let Some(chunk) = self.chunks.get(index) else {
+            return Ok(Zeroizing::new(Vec::new()));
+        };
+        let key = self.chunk_key(index, chunk.generation);
+        let plain = engine::decrypting(&chunk.data, &key, modes::Mode::Ctr, None);
+
+        Ok(Zeroizing::new(decrypt_to_py_err(plain)?))
+    }
+
+    /// Шифрование и сохранение фрагмента с новым поколением.
+    fn store(&mut self, index: usize, plain: &[u8]) -> PyResult<()> {
+        let generation = self.chunks.get(index).map_or(0, |c| c.generation + 1);
+        let key = self.chunk_key(index, generation);
+        let data = rust_to_py_err(engine::encrypting(plain, &key, modes::Mode::Ctr, None))?;
+
+        let chunk = Chunk { generation, data };
+        if index < self.chunks.len() {
+            self.chunks[index] = chunk;
+        } else {
+            self.chunks.push(chunk);
+        }
+
+        Ok(())
+    }
+
+    /// Расшифровка диапазона `[start, end)`.
+    fn read_range(&self, start: usize, end: usize) -> PyResult<Vec<u8>> {
+        let end = end.min(self.len);
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let mut out = Vec::with_capacity(end - start);
+        for index in start / CHUNK_SIZE..=(end - 1) / CHUNK_SIZE {
+            let plain = self.load(index)?;
+            let base = index * CHUNK_SIZE;
+            let from = start.max(base) - base;
+            let to = end.min(base + plain.len()) - base;
+            out.extend_from_slice(&plain[from..to]);
+        }
+
+        Ok(out)
+    }
+
+    fn check_open(&self) -> PyResult<()> {
+        if self.closed {
+            return Err(PyValueError::new_err("I/O operation on closed file"));
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl EncryptedBuffer {
+    /// - initial — Начальное содержимое
+    #[new]
+    #[pyo3(signature = (initial=None))]
+    fn new(initial: Option<&[u8]>) -> PyResult<Self> {
+        let mut key = Zeroizing::new(vec![0u8; engine::KEY_SIZE]);
+        getrandom::getrandom(&mut key).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+        let mut buffer = EncryptedBuffer {
+            key,
+            chunks: Vec::new(),
+            len: 0,
+            pos: 0,
+            closed: false,
+        };
+        if let Some(initial) = initial {
+            buffer.write(initial)?;
+            buffer.pos = 0;
+        }
+
+        Ok(buffer)
+    }
+
+    /// Запись с текущей позиции (с перезаписью и расширением).
+    fn write(&mut self, data: &[u8]) -> PyResult<usize> {
+        self.check_open()?;
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        let (start, end) = (self.pos, self.pos + data.len());
+        for index in start.min(self.len) / CHUNK_SIZE..=(end - 1) / CHUNK_SIZE {
+            let base = index * CHUNK_SIZE;
+            let mut plain = self.load(index)?;
+            let needed = end.min(base + CHUNK_SIZE) - base;
+            if plain.len() < needed {
+                plain.resize(needed, 0);
+            }
+
+            let from = start.max(base);
+            let to = end.min(base + CHUNK_SIZE);
+            if from < to {
+                plain[from - base..to - base].copy_from_slice(&data[from - start..to - start]);
+            }
+            self.store(index, &plain)?;
+        }
+
+        self.len = self.len.max(end);
+        self.pos = end;
+
+        Ok(data.len())
+    }
+
+    /// Чтение с текущей позиции.
+    #[pyo3(signature = (size=-1))]
+    fn read<'py>(&mut self, py: Python<'py>, size: isize) -> PyResult<Bound<'py, PyBytes>> {
+        self.check_open()?;
+        let end = if size < 0 {
+            self.len
+        } else {
+            self.pos.saturating_add(size as usize)
+        };
+
+        let data = Zeroizing::new(self.read_range(self.pos, end)?);
+        self.pos += data.len();
+
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Переход к позиции (`whence` как у `io.BytesIO`).
+    #[pyo3(signature = (offset, whence=0))]
+    fn seek(&mut self, offset: isize, whence: u8) -> PyResult<usize> {
+        self.check_open()?;
+        let base = match whence {
+            0 => 0,
+            1 => self.pos as isize,
+            2 => self.len as isize,
+            _ => return Err(PyValueError::new_err("invalid whence")),
+        };
+        let pos = base + offset;
+        if pos < 0 {
+            return Err(PyValueError::new_err("negative seek position"));
+        }
+        self.pos = pos as usize;
+
+        Ok(self.pos)
+    }
+
+    fn tell(&self) -> PyResult<usize> {
+        self.check_open()?;
+        Ok(self.pos)
+    }
+
+    /// Полное расшифрованное содержимое.
+    fn getvalue<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        self.check_open()?;
+        let data = Zeroizing::new(self.read_range(0, self.len)?);
+
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Уничтожение содержимого и сеансового ключа.
+    fn close(&mut self) {
+        self.chunks.clear();
+        self.key = Zeroizing::new(Vec::new());
+        self.len = 0;
+        self.pos = 0;
+        self.closed = true;
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed
+    }
+
+    fn __len__(&self) -> usize {
+        self.len
+    }
+
+    /// Срез расшифрованных данных (только с шагом 1).
+    fn __getitem__<'py>(
+        &self,
+        py: Python<'py>,
+        index: &Bound<'py, PySlice>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        self.check_open()?;
+        let indices = index.indices(self.len as isize)?;
+        if indices.step != 1 {
+            return Err(PyValueError::new_err("slice step must be 1"));
+        }
+
+        let (start, stop) = (
+            indices.start as usize,
+            indices.stop.max(indices.start) as usize,
+        );
+        let data = Zeroizing::new(self.read_range(start, stop)?);
+
+        Ok(PyBytes::new(py, &data))
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&mut self, _args: &Bound<'_, pyo3::types::PyTuple>) {
+        self.close();
+    }
+}
@@ -0,0 +1,281 @@
+//! Потоковый (thread-local) кеш подготовленных шифровальщиков.
+//!
+//! Подготовка "Кузнечика" (развёртывание ключа) и режима повторяется при
+//! каждом вызове `do_encrypt`/`do_decrypt`. Кеш хранит шифровальщики по
+//! ключу, режиму и вектору инициализации отдельно для каждого потока; его
+//! блокировка общая только с очисткой, поэтому обычно свободна.
+//!
+//! Ключи в кеше хранятся в `Zeroizing` и сравниваются целиком за
+//! постоянное время, развёрнутые ключи стираются при удалении записи.
+//! Кеши всех потоков зарегистрированы в общем реестре: `clear` стирает
+//! их сразу, а записи, не использованные дольше `idle_ttl`, удаляет
+//! обращение к кешу из любого потока (не чаще раза в `SWEEP_INTERVAL`).
+//! В процессе без обращений к кешу записи хранятся до `clear`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::time::{Duration, Instant};
+
+use block_encryption::traits::CipherError;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::engine::{self, Encryptor, BLOCK_SIZE};
+use crate::modes::Mode;
+
+/// Количество шифровальщиков в кеше одного потока.
+pub const CAPACITY: usize = 16;
+
+/// Время хранения неиспользуемого шифровальщика по умолчанию.
+pub const IDLE_TTL: Duration = Duration::from_secs(60);
+
+/// Минимальный интервал между проверками кешей других потоков.
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Текущее время хранения в миллисекундах (`set_idle_ttl`).
+static IDLE_TTL_MS: AtomicU64 = AtomicU64::new(IDLE_TTL.as_millis() as u64);
+
+/// Реестр кешей потоков.
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    caches: Vec::new(),
+    last_sweep: None,
+});
+
+struct Entry {
+    key: Zeroizing<Vec<u8>>,
+    mode: Mode,
+    iv: [u8; BLOCK_SIZE],
+    encryptor: Encryptor,
+    last_used: Instant,
+}
+
+type Entries = Mutex<Vec<Entry>>;
+
+struct Registry {
+    caches: Vec<Weak<Entries>>,
+    last_sweep: Option<Instant>,
+}
+
+thread_local! {
+    static CACHE: Arc<Entries> = register();
+}
+
+/// Новый кеш потока, добавленный в реестр.
+fn register() -> Arc<Entries> {
+    let cache = Arc::new(Mutex::new(Vec::new()));
+    let mut registry = lock(&REGISTRY);
+    registry.caches.retain(|c| c.strong_count() > 0);
+    registry.caches.push(Arc::downgrade(&cache));
+
+    cache
+}
+
+/// Блокировка без учёта паники в другом потоке: данные кеша остаются
+/// согласованными (записи только добавляются и удаляются целиком).
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Время хранения неиспользуемого шифровальщика.
+pub fn idle_ttl() -> Duration {
+    Duration::from_millis(IDLE_TTL_MS.load(Ordering::Relaxed))
+}
+
+/// Установка времени хранения неиспользуемого шифровальщика; нулевое
+/// время отключает кеш. Записи старше нового времени удаляются сразу.
+///
+/// - ttl — Время хранения
+pub fn set_idle_ttl(ttl: Duration) {
+    let ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+    IDLE_TTL_MS.store(ms, Ordering::Relaxed);
+    sweep(Instant::now(), ttl, true);
+}
+
+/// Удаление устаревших записей из кешей всех потоков.
+///
+/// Без `force` проверка выполняется не чаще раза в `SWEEP_INTERVAL` и
+/// пропускает занятые кеши: их записи используются.
+fn sweep(now: Instant, ttl: Duration, force: bool) {
+    let mut registry = match force {
+        true => lock(&REGISTRY),
+        false => match REGISTRY.try_lock() {
+            Ok(registry) => registry,
+            Err(_) => return,
+        },
+    };
+    if !force
+        && registry
+            .last_sweep
+            .is_some_and(|t| now.duration_since(t) < SWEEP_INTERVAL)
+    {
+        return;
+    }
+    registry.last_sweep = Some(now);
+    registry.caches.retain(|cache| {
+        let Some(cache) = cache.upgrade() else {
+            return false;
+        };
+        let entries = match force {
+            true => Some(lock(&cache)),
+            false => cache.try_lock().ok(),
+        };
+        if let Some(mut entries) = entries {
+            entries.retain(|e| now.duration_since(e.last_used) < ttl);
+        }
+        true
+    });
+}
+
+/// Выполнение `f` с шифровальщиком для ключа, режима и вектора из кеша потока.
+///
+/// - key — Ключ шифрования
+/// - mode — Режим шифрования
+/// - iv — Вектор инициализации
+/// - f — Операция над шифровальщиком
+pub fn with_encryptor<R, F>(
+    key: &[u8],
+    mode: Mode,
+    iv: &[u8; BLOCK_SIZE],
+    f: F,
+) -> Result<R, CipherError>
+where
+    F: FnOnce(&Encryptor) -> Result<R, CipherError>,
+{
+    let now = Instant::now();
+    let ttl = idle_ttl();
+    if ttl.is_zero() {
+        return f(&engine::get_encryptor(key, mode, iv)?);
+    }
+    // Реестр проверяется до блокировки своего кеша: поток с заблокированным
+    // кешем никогда не ждёт реестр.
+    sweep(now, ttl, false);
+
+    CACHE.with(|cache| {
+        let mut entries = lock(cache);
+        entries.retain(|e| now.duration_since(e.last_used) < ttl);
+
+        let found = entries
+            .iter()
+            .position(|e| e.mode == mode && e.iv == *iv && bool::from(e.key.ct_eq(key)));
+        let index = match found {
+            Some(index) => index,
+            None => {
+                if entries.len() >= CAPACITY {
+                    let oldest = (0..entries.len())
+                        .min_by_key(|&i| entries[i].last_used)
+                        .expect("cache is not empty");
+                    entries.swap_remove(oldest);
+                }
+                entries.push(Entry {
+                    key: Zeroizing::new(key.to_vec()),
+                    mode,
+                    iv: *iv,
+                    encryptor: engine::get_encryptor(key, mode, iv)?,
+                    last_used: now,
+                });
+                entries.len() - 1
+            }
+        };
+
+        let entry = &mut entries[index];
+        entry.last_used = now;
+        f(&entry.encryptor)
+    })
+}
+
+/// Стирание кешей всех потоков.
+///
+/// Кеш, занятый операцией в другом потоке, стирается после её завершения.
+pub fn clear() {
+    let registry = lock(&REGISTRY);
+    for cache in registry.caches.iter().filter_map(Weak::upgrade) {
+        lock(&cache).clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Потоки шифруют и дешифруют ключами, которых больше `CAPACITY`
+    /// (записи вытесняются), пока другой поток сбрасывает кеши; результат
+    /// совпадает с шифровальщиком без кеша.
+    #[test]
+    fn thread_local_caches_stay_consistent() {
+        let plaintext: Vec<u8> = (0..100u8).collect();
+        let iv = [7u8; BLOCK_SIZE];
+        let cases: Vec<(Vec<u8>, Mode)> = (0..2 * CAPACITY as u8)
+            .flat_map(|k| Mode::ALL.map(|mode| (vec![k; engine::KEY_SIZE], mode)))
+            .collect();
+        let expected: Vec<Vec<u8>> = cases
+            .iter()
+            .map(|(key, mode)| {
+                let encryptor = engine::get_encryptor(key, *mode, &iv).unwrap();
+                encryptor.encrypt(&plaintext).unwrap()
+            })
+            .collect();
+
+        std::thread::scope(|scope| {
+            for shift in 0..8 {
+                let (cases, expected, plaintext) = (&cases, &expected, &plaintext);
+                scope.spawn(move || {
+                    for round in 0..4 {
+                        for i in 0..cases.len() {
+                            let i = (i * (shift + 1) + round) % cases.len();
+                            let (key, mode) = &cases[i];
+                            let ct = with_encryptor(key, *mode, &iv, |e| e.encrypt(plaintext));
+                            assert_eq!(ct.unwrap(), expected[i]);
+                            let pt = with_encryptor(key, *mode, &iv, |e| e.decrypt(&expected[i]));
+                            assert_eq!(&pt.unwrap(), plaintext);
+                        }
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..16 {
+                    clear();
+                    std::thread::yield_now();
+                }
+            });
+        });
+    }
+
+    /// Кеш потока, который заполняет его и ждёт сигнала завершения.
+    fn idle_thread_cache(done: std::sync::mpsc::Receiver<()>) -> Arc<Entries> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let key = [3u8; engine::KEY_SIZE];
+            with_encryptor(&key, Mode::Cbc, &[0u8; BLOCK_SIZE], |e| e.encrypt(b"data")).unwrap();
+            tx.send(CACHE.with(Arc::clone)).unwrap();
+            let _ = done.recv();
+        });
+        rx.recv().unwrap()
+    }
+
+    /// `clear` стирает кеш потока, который к нему больше не обращается.
+    #[test]
+    fn clear_wipes_idle_threads() {
+        let (done, wait) = std::sync::mpsc::channel();
+        let cache = idle_thread_cache(wait);
+        assert_eq!(lock(&cache).len(), 1);
+
+        clear();
+        assert!(lock(&cache).is_empty());
+        done.send(()).unwrap();
+    }
+
+    /// Уменьшение времени хранения сразу удаляет устаревшие записи других
+    /// потоков.
+    #[test]
+    fn shorter_ttl_expires_idle_threads() {
+        let (done, wait) = std::sync::mpsc::channel();
+        let cache = idle_thread_cache(wait);
+        std::thread::sleep(Duration::from_millis(20));
+
+        set_idle_ttl(Duration::from_millis(10));
+        assert!(lock(&cache).is_empty());
+        set_idle_ttl(IDLE_TTL);
+        assert_eq!(idle_ttl(), IDLE_TTL);
+        done.send(()).unwrap();
+    }
+}
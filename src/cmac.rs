@@ -0,0 +1,106 @@
+//! Режим выработки имитовставки ГОСТ Р 34.13-2015 (CMAC, OMAC1).
+//!
+//! Вспомогательные ключи `K1`, `K2` выводятся из `R = E(0)` сдвигом влево
+//! с константой `B` (0x87 для блока 16 байт, 0x1B для 8). Последний блок
+//! складывается с `K1`, если он полный, иначе дополняется `1 0..0` и
+//! складывается с `K2`. Имитовставка — первые `tag_len` байт результата.
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::typenum::Unsigned;
+use aes::cipher::BlockEncrypt;
+
+/// Расчёт имитовставки для данных частями.
+pub struct Cmac<'a, C> {
+    cipher: &'a C,
+    state: Vec<u8>,
+    /// Последний блок: обрабатывается, только когда за ним есть данные.
+    buffer: Vec<u8>,
+}
+
+impl<'a, C: BlockEncrypt> Cmac<'a, C> {
+    pub fn new(cipher: &'a C) -> Self {
+        let block_size = C::BlockSize::USIZE;
+        Cmac {
+            cipher,
+            state: vec![0u8; block_size],
+            buffer: Vec::with_capacity(block_size),
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        let block_size = self.state.len();
+        while !data.is_empty() {
+            if self.buffer.len() == block_size {
+                xor(&mut self.state, &self.buffer);
+                encrypt_block(self.cipher, &mut self.state);
+                self.buffer.clear();
+            }
+            let take = (block_size - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..take]);
+            data = &data[take..];
+        }
+    }
+
+    /// Имитовставка длиной `tag_len` (от 1 до размера блока) байт.
+    pub fn finalize(mut self, tag_len: usize) -> Vec<u8> {
+        let block_size = self.state.len();
+        let (k1, k2) = subkeys(self.cipher, block_size);
+        if self.buffer.len() == block_size {
+            xor(&mut self.buffer, &k1);
+        } else {
+            self.buffer.push(0x80);
+            self.buffer.resize(block_size, 0);
+            xor(&mut self.buffer, &k2);
+        }
+        xor(&mut self.state, &self.buffer);
+        encrypt_block(self.cipher, &mut self.state);
+        self.state.truncate(tag_len);
+
+        self.state
+    }
+}
+
+/// Имитовставка данных в памяти.
+///
+/// - cipher — Блочный шифр
+/// - parts — Данные, возможно из нескольких частей
+/// - tag_len — Длина имитовставки (от 1 до размера блока)
+pub fn cmac<C: BlockEncrypt>(cipher: &C, parts: &[&[u8]], tag_len: usize) -> Vec<u8> {
+    let mut mac = Cmac::new(cipher);
+    parts.iter().for_each(|part| mac.update(part));
+
+    mac.finalize(tag_len)
+}
+
+/// Вспомогательные ключи `K1`, `K2`.
+fn subkeys<C: BlockEncrypt>(cipher: &C, block_size: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut r = vec![0u8; block_size];
+    encrypt_block(cipher, &mut r);
+    let k1 = shift(&r);
+    let k2 = shift(&k1);
+
+    (k1, k2)
+}
+
+/// Сдвиг влево на бит со сложением с `B` при переносе.
+fn shift(block: &[u8]) -> Vec<u8> {
+    let b = if block.len() == 16 { 0x87 } else { 0x1b };
+    let mut out: Vec<u8> = block
+        .iter()
+        .zip(block.iter().skip(1).chain([&0]))
+        .map(|(byte, next)| (byte << 1) | (next >> 7))
+        .collect();
+    if block[0] & 0x80 != 0 {
+        out[block.len() - 1] ^= b;
+    }
+
+    out
+}
+
+fn xor(data: &mut [u8], other: &[u8]) {
+    data.iter_mut().zip(other).for_each(|(d, o)| *d ^= o);
+}
+
+fn encrypt_block<C: BlockEncrypt>(cipher: &C, block: &mut [u8]) {
+    cipher.encrypt_block(GenericArray::from_mut_slice(block));
+}
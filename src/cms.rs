@@ -0,0 +1,279 @@
+//! Шифротекст в структуре CMS `EncryptedContentInfo` (RFC 5652, раздел 6.1)
+//! в кодировке DER для обмена с системами CMS (КриптоПро и др.).
+//!
+//! ```text
+//! EncryptedContentInfo ::= SEQUENCE {
+//!   contentType                ContentType,          -- id-data
+//!   contentEncryptionAlgorithm AlgorithmIdentifier,
+//!   encryptedContent       [0] IMPLICIT OCTET STRING }
+//! ```
+//!
+//! AES-256 обозначается OID NIST для режима (`aes256-ECB`, `-CBC`, `-OFB`,
+//! `-CFB`, RFC 3565), параметры — вектор инициализации OCTET STRING (в ECB
+//! параметров нет). "Кузнечик" и "Магма" обозначаются OID шифров ТК 26
+//! (1.2.643.7.1.1.5.2 и 1.2.643.7.1.1.5.1). Профиль CMS ГОСТ определяет
+//! для них только режимы CTR-ACPKM, которых здесь нет, поэтому режим и
+//! вектор записываются в параметры явно:
+//!
+//! ```text
+//! Parameters ::= SEQUENCE {
+//!   mode PrintableString,          -- "CBC", "CTR", ...
+//!   iv   OCTET STRING OPTIONAL }   -- нет в ECB
+//! ```
+//!
+//! Выравнивание ECB и CBC — PKCS7, как и требует CMS. MGM не
+//! поддерживается: для аутентифицированного шифрования в CMS есть
+//! отдельная структура `AuthEnvelopedData`.
+
+use block_encryption::traits::CipherError;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::asn1::{self, OBJECT_IDENTIFIER, OCTET_STRING, PRINTABLE_STRING, SEQUENCE};
+use crate::engine::{self, Algorithm, EncryptMode};
+use crate::modes::Mode;
+use crate::{allow_threads_for, decrypt_to_py_err, extract_text_and_key, rust_to_py_err, Output};
+
+/// id-data (RFC 5652).
+const ID_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 1];
+const KUZNYECHIK: &[u64] = &[1, 2, 643, 7, 1, 1, 5, 2];
+const MAGMA: &[u64] = &[1, 2, 643, 7, 1, 1, 5, 1];
+/// Дуга aes (NIST): последний номер задаёт размер ключа и режим.
+const AES: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 1];
+/// Режимы AES-256 с OID: `aes256-ECB` = `AES.41` и далее.
+const AES256_MODES: [Mode; 4] = [Mode::Ecb, Mode::Cbc, Mode::Ofb, Mode::Cfb];
+const AES256_ARC: u64 = 41;
+
+/// Ошибка разбора или дешифровки.
+#[derive(Debug)]
+pub enum Error {
+    Cipher(CipherError),
+    /// Данные — не DER `EncryptedContentInfo` с `id-data`.
+    Malformed,
+}
+
+/// Разобранная структура; срезы указывают в исходные данные.
+pub struct Content<'a> {
+    pub algorithm: Algorithm,
+    pub mode: Mode,
+    pub iv: Option<&'a [u8]>,
+    pub ciphertext: &'a [u8],
+}
+
+/// Структура `EncryptedContentInfo` для шифротекста.
+///
+/// - iv — Вектор инициализации; `None` только в ECB
+pub fn encode(
+    algorithm: Algorithm,
+    mode: Mode,
+    iv: Option<&[u8]>,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    let mut info = asn1::element(OBJECT_IDENTIFIER, &asn1::oid(ID_DATA));
+    asn1::write(
+        &mut info,
+        SEQUENCE,
+        &algorithm_identifier(algorithm, mode, iv)?,
+    );
+    asn1::write(&mut info, asn1::CONTEXT_0, ciphertext);
+
+    Ok(asn1::element(SEQUENCE, &info))
+}
+
+/// Разбор `EncryptedContentInfo`; неизвестный алгоритм — `InvalidMode`.
+pub fn decode(der: &[u8]) -> Result<Content<'_>, Error> {
+    let mut outer = asn1::Reader::new(der);
+    let mut info = asn1::Reader::new(read(&mut outer, SEQUENCE)?);
+    if !outer.is_empty() || read(&mut info, OBJECT_IDENTIFIER)? != asn1::oid(ID_DATA) {
+        return Err(Error::Malformed);
+    }
+    let mut algorithm_id = asn1::Reader::new(read(&mut info, SEQUENCE)?);
+    let ciphertext = read(&mut info, asn1::CONTEXT_0)?;
+    if !info.is_empty() {
+        return Err(Error::Malformed);
+    }
+
+    let oid = read(&mut algorithm_id, OBJECT_IDENTIFIER)?;
+    let (algorithm, mode, iv) = if let Some(mode) = aes256_mode(oid) {
+        (
+            Algorithm::Aes256,
+            mode,
+            optional(&mut algorithm_id, OCTET_STRING)?,
+        )
+    } else if let Some(algorithm) = gost_algorithm(oid) {
+        let mut params = asn1::Reader::new(read(&mut algorithm_id, SEQUENCE)?);
+        let mode = read(&mut params, PRINTABLE_STRING)?;
+        let mode = std::str::from_utf8(mode)
+            .map_err(|_| CipherError::InvalidMode)
+            .and_then(Mode::from_name)
+            .map_err(Error::Cipher)?;
+        let iv = optional(&mut params, OCTET_STRING)?;
+        if !params.is_empty() {
+            return Err(Error::Malformed);
+        }
+        (algorithm, mode, iv)
+    } else {
+        return Err(Error::Cipher(CipherError::InvalidMode));
+    };
+    // Без вектора дешифровка шла бы со встроенным и давала бы мусор.
+    if !algorithm_id.is_empty() || mode.uses_iv() != iv.is_some() {
+        return Err(Error::Malformed);
+    }
+
+    Ok(Content {
+        algorithm,
+        mode,
+        iv,
+        ciphertext,
+    })
+}
+
+/// Содержимое `AlgorithmIdentifier`.
+fn algorithm_identifier(
+    algorithm: Algorithm,
+    mode: Mode,
+    iv: Option<&[u8]>,
+) -> Result<Vec<u8>, CipherError> {
+    let mut params = Vec::new();
+    let oid = match algorithm {
+        Algorithm::Aes256 => {
+            let index = AES256_MODES
+                .iter()
+                .position(|&m| m == mode)
+                .ok_or(CipherError::InvalidMode)?;
+            if let Some(iv) = iv {
+                asn1::write(&mut params, OCTET_STRING, iv);
+            }
+            asn1::oid(&[AES, &[AES256_ARC + index as u64]].concat())
+        }
+        Algorithm::Kuznyechik | Algorithm::Magma => {
+            let mut sequence = asn1::element(PRINTABLE_STRING, mode.name().as_bytes());
+            if let Some(iv) = iv {
+                asn1::write(&mut sequence, OCTET_STRING, iv);
+            }
+            asn1::write(&mut params, SEQUENCE, &sequence);
+            asn1::oid(if algorithm == Algorithm::Kuznyechik {
+                KUZNYECHIK
+            } else {
+                MAGMA
+            })
+        }
+    };
+
+    let mut identifier = asn1::element(OBJECT_IDENTIFIER, &oid);
+    identifier.extend(params);
+
+    Ok(identifier)
+}
+
+fn aes256_mode(oid: &[u8]) -> Option<Mode> {
+    AES256_MODES
+        .into_iter()
+        .enumerate()
+        .find(|&(i, _)| asn1::oid(&[AES, &[AES256_ARC + i as u64]].concat()) == oid)
+        .map(|(_, mode)| mode)
+}
+
+fn gost_algorithm(oid: &[u8]) -> Option<Algorithm> {
+    if oid == asn1::oid(KUZNYECHIK) {
+        Some(Algorithm::Kuznyechik)
+    } else if oid == asn1::oid(MAGMA) {
+        Some(Algorithm::Magma)
+    } else {
+        None
+    }
+}
+
+fn read<'a>(reader: &mut asn1::Reader<'a>, tag: u8) -> Result<&'a [u8], Error> {
+    reader.read(tag).ok_or(Error::Malformed)
+}
+
+/// Необязательный элемент `tag`.
+fn optional<'a>(reader: &mut asn1::Reader<'a>, tag: u8) -> Result<Option<&'a [u8]>, Error> {
+    match reader.peek() {
+        Some(actual) if actual == tag => read(reader, tag).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Шифрование в структуру CMS `EncryptedContentInfo` (DER).
+///
+/// - plaintext — Текст для шифрования
+/// - key — Ключ (32 байта)
+/// - encrypt_mode — Режим: ECB, CBC, CFB, OFB или CTR (для `aes256` —
+///   кроме CTR)
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - iv — Вектор инициализации размером в блок шифра; по умолчанию
+///   случайный. Записывается в структуру, в ECB не используется
+#[pyfunction]
+#[pyo3(name = "encrypt_cms")]
+#[pyo3(signature = (plaintext, key, encrypt_mode, *, cipher=Algorithm::Kuznyechik, iv=None))]
+pub fn encrypt_cms<'py>(
+    plaintext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: EncryptMode,
+    cipher: Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mode = rust_to_py_err(encrypt_mode.block())?;
+    let py = plaintext.py();
+    let algorithm = cipher;
+    let (pt, k) = extract_text_and_key(&plaintext, &key, 0, None)?;
+    let iv = match iv {
+        _ if !mode.uses_iv() => None,
+        Some(iv) => Some(iv.as_bytes().to_vec()),
+        None => {
+            let mut iv = vec![0u8; algorithm.block_size()];
+            getrandom::getrandom(&mut iv).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+            Some(iv)
+        }
+    };
+    let der = allow_threads_for(py, pt.len(), || {
+        let ct = engine::encrypting_with(algorithm, &pt, &k, mode, iv.as_deref())?;
+        encode(algorithm, mode, iv.as_deref(), &ct)
+    });
+
+    Ok(PyBytes::new(py, &rust_to_py_err(der)?))
+}
+
+/// Дешифровка структуры CMS `EncryptedContentInfo` (DER) из
+/// `encrypt_cms` или другой системы с тем же профилем.
+///
+/// Шифр, режим и вектор берутся из структуры. Неверная структура вызывает
+/// `ValueError`, неизвестный алгоритм — `InvalidModeError`.
+///
+/// - data — Структура в DER
+/// - key — Ключ (32 байта)
+#[pyfunction]
+#[pyo3(name = "decrypt_cms")]
+#[pyo3(signature = (data, key))]
+pub fn decrypt_cms<'py>(
+    data: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let py = data.py();
+    let (der, k) = extract_text_and_key(&data, &key, 0, None)?;
+    let content = cms_to_py_err(decode(&der))?;
+    let pt = allow_threads_for(py, content.ciphertext.len(), || {
+        engine::decrypting_with(
+            content.algorithm,
+            content.ciphertext,
+            &k,
+            content.mode,
+            content.iv,
+        )
+    });
+
+    Ok(Output::from_vec(py, decrypt_to_py_err(pt)?))
+}
+
+fn cms_to_py_err<T>(result: Result<T, Error>) -> PyResult<T> {
+    match result {
+        Ok(r) => Ok(r),
+        Err(Error::Cipher(e)) => decrypt_to_py_err(Err(e)),
+        Err(Error::Malformed) => Err(PyValueError::new_err(
+            "The data is not a DER EncryptedContentInfo",
+        )),
+    }
+}
@@ -0,0 +1,118 @@
+//! Совместимость с интерфейсом PyCryptodome.
+//!
+//! ```python
+//! from cryptor import Cipher, MODE_CBC
+//!
+//! cipher = Cipher.new(key, MODE_CBC)
+//! ct = cipher.encrypt(data)
+//! ```
+//!
+//! В отличие от PyCryptodome, каждый вызов `encrypt`/`decrypt` обрабатывает
+//! сообщение целиком (с выравниванием PKCS7 в ECB и CBC), а состояние между
+//! вызовами не сохраняется.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use zeroize::Zeroizing;
+
+use crate::{
+    allow_threads_for, decrypt_to_py_err, engine, extract_key, modes, rust_to_py_err,
+    IVLengthError, KeyLengthError,
+};
+
+pub const MODE_ECB: u8 = 1;
+pub const MODE_CBC: u8 = 2;
+pub const MODE_CFB: u8 = 3;
+pub const MODE_OFB: u8 = 5;
+pub const MODE_CTR: u8 = 6;
+
+/// Шифр, создаваемый через `Cipher.new(key, mode, iv=None)`.
+#[pyclass(name = "Cipher", frozen)]
+pub struct CompatCipher {
+    key: Zeroizing<Vec<u8>>,
+    mode: u8,
+    iv: [u8; engine::BLOCK_SIZE],
+}
+
+impl CompatCipher {
+    /// Режим для движка.
+    fn block_mode(&self) -> modes::Mode {
+        match self.mode {
+            MODE_ECB => modes::Mode::Ecb,
+            MODE_CBC => modes::Mode::Cbc,
+            MODE_CFB => modes::Mode::Cfb,
+            MODE_OFB => modes::Mode::Ofb,
+            _ => modes::Mode::Ctr,
+        }
+    }
+}
+
+#[pymethods]
+impl CompatCipher {
+    /// Размер блока в байтах.
+    #[classattr]
+    fn block_size() -> usize {
+        engine::BLOCK_SIZE
+    }
+
+    /// Создание шифра.
+    ///
+    /// - key — Ключ (32 байта)
+    /// - mode — Одна из констант `MODE_*`
+    /// - iv — Вектор инициализации (16 байт); по умолчанию встроенный
+    #[staticmethod]
+    #[pyo3(signature = (key, mode, iv=None))]
+    fn new(key: Bound<'_, PyBytes>, mode: u8, iv: Option<Bound<'_, PyBytes>>) -> PyResult<Self> {
+        let key = extract_key(&key)?;
+        if key.len() != engine::KEY_SIZE {
+            return Err(KeyLengthError::new_err(
+                "The key length is invalid (must be 32 bytes)",
+            ));
+        }
+        if !matches!(mode, MODE_ECB | MODE_CBC | MODE_CFB | MODE_OFB | MODE_CTR) {
+            return Err(PyValueError::new_err("Unsupported mode"));
+        }
+        let iv = match iv {
+            Some(iv) => iv
+                .as_bytes()
+                .try_into()
+                .map_err(|_| IVLengthError::new_err("The IV length is invalid"))?,
+            None => engine::DEFAULT_IV,
+        };
+
+        Ok(CompatCipher { key, mode, iv })
+    }
+
+    /// Режим шифра (константа `MODE_*`).
+    #[getter]
+    fn mode(&self) -> u8 {
+        self.mode
+    }
+
+    /// Используемый вектор инициализации (`None` для ECB).
+    #[getter]
+    fn iv(&self) -> Option<Vec<u8>> {
+        (self.mode != MODE_ECB).then(|| self.iv.to_vec())
+    }
+
+    /// Шифрование сообщения.
+    fn encrypt(&self, plaintext: Bound<'_, PyBytes>) -> PyResult<Vec<u8>> {
+        let data = plaintext.as_bytes();
+        let result = allow_threads_for(plaintext.py(), data.len(), || {
+            engine::encrypting(data, &self.key, self.block_mode(), Some(&self.iv))
+        });
+
+        rust_to_py_err(result)
+    }
+
+    /// Дешифровка сообщения.
+    fn decrypt(&self, ciphertext: Bound<'_, PyBytes>) -> PyResult<Vec<u8>> {
+        let data = ciphertext.as_bytes();
+        let result = allow_threads_for(ciphertext.py(), data.len(), || {
+            engine::decrypting(data, &self.key, self.block_mode(), Some(&self.iv))
+        });
+
+        decrypt_to_py_err(result)
+    }
+}
@@ -0,0 +1,307 @@
+//! Самоописываемый контейнер шифротекста: всё, кроме пароля, записано в
+//! заголовке.
+//!
+//! ```text
+//! GCT | версия (1) | шифр (1) | режим (1) | KDF (1) | итерации (4, BE)
+//!     | длина соли (1) | соль | длина вектора (1) | вектор
+//!     | шифротекст | имитовставка
+//! ```
+//!
+//! Заголовок до вектора защищён имитовставкой как дополнительные данные:
+//! в MGM — самого режима (16 байт, вектор — nonce), в остальных режимах —
+//! HMAC-Стрибог-256 по схеме encrypt-then-MAC (`etm`), которая покрывает
+//! и вектор. Поэтому неверный пароль или повреждённый заголовок вызывают
+//! ошибку имитовставки, а не дают тихо неверный текст.
+//!
+//! KDF: 0 — ключ используется как есть, 1 — PBKDF2-HMAC-Стрибог-512,
+//! 2 — KDF_GOSTR3411_2012_256 (соль — `seed`, итерации не используются).
+
+use block_encryption::traits::CipherError;
+use zeroize::Zeroizing;
+
+use crate::aead::Error;
+use crate::engine::{self, Algorithm, EncryptMode};
+use crate::{etm, kdf, mgm, modes};
+
+/// Маркер контейнера.
+pub const MAGIC: &[u8; 3] = b"GCT";
+
+/// Версия формата контейнера.
+pub const VERSION: u8 = 1;
+
+/// Размер случайной соли для выработки ключа.
+pub const SALT_SIZE: usize = 16;
+
+/// Число итераций PBKDF2 по умолчанию.
+pub const ITERATIONS: u32 = 100_000;
+
+/// Наибольшее число итераций PBKDF2: заголовок проверяется только после
+/// выработки ключа, и подменённое число не должно останавливать
+/// дешифровку надолго.
+pub const MAX_ITERATIONS: u32 = 10_000_000;
+
+/// Шифры по номеру (с 1).
+const CIPHERS: [Algorithm; 3] = [Algorithm::Kuznyechik, Algorithm::Aes256, Algorithm::Magma];
+
+/// Режимы по номеру (с 1).
+const MODES: [EncryptMode; 6] = [
+    EncryptMode::Block(modes::Mode::Ecb),
+    EncryptMode::Block(modes::Mode::Cbc),
+    EncryptMode::Block(modes::Mode::Cfb),
+    EncryptMode::Block(modes::Mode::Ofb),
+    EncryptMode::Block(modes::Mode::Ctr),
+    EncryptMode::Mgm,
+];
+
+/// Параметры шифрования, записываемые в заголовок.
+pub struct Header {
+    pub algorithm: Algorithm,
+    pub mode: EncryptMode,
+    /// `None` — ключ без выработки.
+    pub kdf: Option<kdf::Kdf>,
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    /// Вектор инициализации (nonce в MGM); в ECB пустой.
+    pub iv: Vec<u8>,
+}
+
+impl Header {
+    /// - mode — Режим; SIV и CTR-ACPKM — `InvalidMode`
+    pub fn new(
+        algorithm: Algorithm,
+        mode: EncryptMode,
+        kdf: Option<kdf::Kdf>,
+        iterations: u32,
+        salt: Vec<u8>,
+        iv: Vec<u8>,
+    ) -> Result<Self, CipherError> {
+        if !MODES.contains(&mode) {
+            return Err(CipherError::InvalidMode);
+        }
+        if salt.len() > usize::from(u8::MAX) {
+            return Err(CipherError::InvalidKeyFormat);
+        }
+
+        Ok(Header {
+            algorithm,
+            mode,
+            kdf,
+            iterations,
+            salt,
+            iv,
+        })
+    }
+
+    /// Заголовок до вектора: он же дополнительные данные имитовставки.
+    fn head(&self) -> Vec<u8> {
+        let mut head = MAGIC.to_vec();
+        head.push(VERSION);
+        head.push(id(CIPHERS.iter().position(|&a| a == self.algorithm)));
+        head.push(id(MODES.iter().position(|&m| m == self.mode)));
+        head.push(match self.kdf {
+            None => 0,
+            Some(kdf::Kdf::Pbkdf2) => 1,
+            Some(kdf::Kdf::GostR3411) => 2,
+        });
+        head.extend(self.iterations.to_be_bytes());
+        head.push(self.salt.len() as u8);
+        head.extend(&self.salt);
+        head.push(self.iv.len() as u8);
+
+        head
+    }
+
+    /// Ключ шифрования из пароля (или ключа) по параметрам заголовка.
+    fn key(&self, secret: &[u8]) -> Zeroizing<Vec<u8>> {
+        match self.kdf {
+            None => Zeroizing::new(secret.to_vec()),
+            Some(kdf::Kdf::Pbkdf2) => kdf::pbkdf2(secret, &self.salt, self.iterations),
+            Some(kdf::Kdf::GostR3411) => kdf::kdf_gostr3411(secret, b"", &self.salt),
+        }
+    }
+}
+
+/// Номер элемента таблицы (с 1); элементы `Header` всегда есть в таблицах.
+fn id(index: Option<usize>) -> u8 {
+    index.expect("known entry") as u8 + 1
+}
+
+/// Признак контейнера известной версии.
+pub fn is_container(data: &[u8]) -> bool {
+    data.len() > MAGIC.len() && data.starts_with(MAGIC) && data[MAGIC.len()] == VERSION
+}
+
+/// Шифрование в контейнер.
+///
+/// - secret — Пароль или ключ (при `kdf = None`)
+pub fn seal(header: &Header, secret: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+    let key = header.key(secret);
+    let mut sealed = header.head();
+
+    if let EncryptMode::Block(mode) = header.mode {
+        let body = etm::seal(
+            header.algorithm,
+            &key,
+            mode,
+            &header.iv,
+            plaintext,
+            &sealed,
+            etm::Options::default(),
+        )?;
+        sealed.extend(body);
+    } else {
+        let nonce = mgm_nonce(&header.iv).map_err(Error::Cipher)?;
+        let body = engine::encrypting_mgm(
+            header.algorithm,
+            plaintext,
+            &key,
+            &nonce,
+            &sealed,
+            mgm::TAG_SIZE,
+        )
+        .map_err(Error::Cipher)?;
+        sealed.extend(nonce);
+        sealed.extend(body);
+    }
+
+    Ok(sealed)
+}
+
+/// Проверка и дешифровка контейнера; параметры берутся из заголовка.
+///
+/// Усечённый заголовок — `DataTooShort`, неизвестный шифр, режим или KDF —
+/// `InvalidMode`, неверный пароль — `Authentication`.
+pub fn open(secret: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let (header, head_len) = parse(data).map_err(Error::Cipher)?;
+    let (head, body) = data.split_at(head_len);
+    let key = header.key(secret);
+
+    if let EncryptMode::Block(mode) = header.mode {
+        etm::open(
+            header.algorithm,
+            &key,
+            mode,
+            body,
+            head,
+            etm::Options::default(),
+        )
+    } else {
+        let (nonce, body) = body.split_at(header.iv.len());
+        let nonce = mgm_nonce(nonce).map_err(Error::Cipher)?;
+        engine::decrypting_mgm(header.algorithm, body, &key, &nonce, head, mgm::TAG_SIZE)
+    }
+}
+
+/// Заголовок и длина его части до вектора.
+fn parse(data: &[u8]) -> Result<(Header, usize), CipherError> {
+    let mut rest = data
+        .get(MAGIC.len() + 1..)
+        .ok_or(CipherError::DataTooShort)?;
+    let mut take = |len: usize| {
+        let (taken, tail) = rest
+            .split_at_checked(len)
+            .ok_or(CipherError::DataTooShort)?;
+        rest = tail;
+        Ok::<_, CipherError>(taken)
+    };
+
+    let ids = take(3)?;
+    let algorithm = table(&CIPHERS, ids[0])?;
+    let mode = table(&MODES, ids[1])?;
+    let kdf = match ids[2] {
+        0 => None,
+        1 => Some(kdf::Kdf::Pbkdf2),
+        2 => Some(kdf::Kdf::GostR3411),
+        _ => return Err(CipherError::InvalidMode),
+    };
+    let iterations = u32::from_be_bytes(take(4)?.try_into().expect("4 bytes"));
+    if kdf == Some(kdf::Kdf::Pbkdf2) && !(1..=MAX_ITERATIONS).contains(&iterations) {
+        return Err(CipherError::InvalidKeyFormat);
+    }
+    let salt_len = take(1)?[0];
+    let salt = take(usize::from(salt_len))?.to_vec();
+    let iv_len = take(1)?[0];
+    let iv = take(usize::from(iv_len))?.to_vec();
+    let head_len = data.len() - rest.len() - iv.len();
+
+    Ok((
+        Header {
+            algorithm,
+            mode,
+            kdf,
+            iterations,
+            salt,
+            iv,
+        },
+        head_len,
+    ))
+}
+
+fn table<T: Copy>(items: &[T], id: u8) -> Result<T, CipherError> {
+    usize::from(id)
+        .checked_sub(1)
+        .and_then(|index| items.get(index).copied())
+        .ok_or(CipherError::InvalidMode)
+}
+
+fn mgm_nonce(iv: &[u8]) -> Result<[u8; mgm::NONCE_SIZE], CipherError> {
+    iv.try_into().map_err(|_| CipherError::InvalidIVLenght)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: [u8; 32] = [0x42; 32];
+
+    /// Изменённый бит в заголовке даёт ошибку разбора или имитовставки, в
+    /// векторе и шифротексте — ошибку имитовставки; усечённый контейнер не
+    /// дешифруется.
+    #[test]
+    fn tampered_container_fails() {
+        let plaintext = [0x5a; 40];
+        for algorithm in Algorithm::ALL {
+            for mode in MODES {
+                if mode == EncryptMode::Mgm && algorithm == Algorithm::Magma {
+                    continue;
+                }
+                let iv_len = match mode {
+                    EncryptMode::Block(mode) if !mode.uses_iv() => 0,
+                    EncryptMode::Mgm => mgm::NONCE_SIZE,
+                    _ => algorithm.block_size(),
+                };
+                let header =
+                    Header::new(algorithm, mode, None, 0, Vec::new(), vec![0x11; iv_len]).unwrap();
+                let sealed = seal(&header, &SECRET, &plaintext).unwrap();
+                assert_eq!(open(&SECRET, &sealed).unwrap(), plaintext);
+                let head_len = header.head().len();
+
+                for i in 0..sealed.len() {
+                    let mut data = sealed.clone();
+                    data[i] ^= 0x01;
+                    let opened = open(&SECRET, &data);
+                    if i < head_len {
+                        assert!(opened.is_err(), "header byte {i}");
+                    } else {
+                        assert!(matches!(opened, Err(Error::Authentication)), "byte {i}");
+                    }
+                }
+                for len in 0..sealed.len() {
+                    let opened = open(&SECRET, &sealed[..len]);
+                    if len < head_len {
+                        assert!(matches!(
+                            opened,
+                            Err(Error::Cipher(CipherError::DataTooShort))
+                        ));
+                    } else {
+                        assert!(opened.is_err(), "truncated to {len} bytes");
+                    }
+                }
+                assert!(matches!(
+                    open(&[0x43; 32], &sealed),
+                    Err(Error::Authentication)
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Ограничение времени шифрования и дешифровки (`timeout`).
+//!
+//! Данные обрабатываются частями по `READ_CHUNK` байт пошаговым
+//! шифровальщиком (`engine::Streaming`), перед каждой частью проверяется
+//! срок. Результат совпадает с обработкой без ограничения, в том числе для
+//! заголовка `random_iv`.
+//!
+//! Без срока (`None`) эти функции используются для параметров режимов,
+//! отличных от схемы `block-encryption` (`modes::Options`).
+
+use std::time::{Duration, Instant};
+
+use block_encryption::traits::CipherError;
+
+use crate::engine::{self, Algorithm, Streaming};
+use crate::mac::READ_CHUNK;
+use crate::modes::{self, Options};
+
+/// Момент, после которого операция прерывается.
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// Срок через `timeout` от текущего момента; `None`, если он не
+    /// представим.
+    pub fn after(timeout: Duration) -> Option<Self> {
+        Instant::now().checked_add(timeout).map(Deadline)
+    }
+
+    /// `Expired`, если срок истёк.
+    pub fn check(&self) -> Result<(), Error> {
+        if Instant::now() >= self.0 {
+            return Err(Error::Expired);
+        }
+
+        Ok(())
+    }
+}
+
+/// Ошибки операций с ограничением времени.
+#[derive(Debug)]
+pub enum Error {
+    /// Ошибка шифра или данных.
+    Cipher(CipherError),
+    /// Срок истёк до завершения операции.
+    Expired,
+}
+
+impl From<CipherError> for Error {
+    fn from(err: CipherError) -> Self {
+        Error::Cipher(err)
+    }
+}
+
+/// Шифрование с ограничением времени (см. `engine::encrypting_with`).
+pub fn encrypting(
+    algorithm: Algorithm,
+    plaintext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    iv: Option<&[u8]>,
+    options: Options,
+    deadline: Option<Deadline>,
+) -> Result<Vec<u8>, Error> {
+    let stream = Streaming::new(algorithm, key, mode, iv, options, true)?;
+    process(stream, plaintext, deadline)
+}
+
+/// Дешифровка с ограничением времени (см. `engine::decrypting_with`).
+pub fn decrypting(
+    algorithm: Algorithm,
+    ciphertext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    iv: Option<&[u8]>,
+    options: Options,
+    deadline: Option<Deadline>,
+) -> Result<Vec<u8>, Error> {
+    let stream = Streaming::new(algorithm, key, mode, iv, options, false)?;
+    process(stream, ciphertext, deadline)
+}
+
+/// Шифрование с вектором в заголовке (см. `engine::Encryption::framed`).
+pub fn encrypting_framed(
+    algorithm: Algorithm,
+    plaintext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    iv: &[u8],
+    options: Options,
+    deadline: Option<Deadline>,
+) -> Result<Vec<u8>, Error> {
+    let mut framed = engine::frame_header(mode, iv)?;
+    framed.extend(encrypting(
+        algorithm,
+        plaintext,
+        key,
+        mode,
+        Some(iv),
+        options,
+        deadline,
+    )?);
+
+    Ok(framed)
+}
+
+/// Дешифровка с вектором из заголовка (см. `engine::Decryption::framed`).
+pub fn decrypting_framed(
+    algorithm: Algorithm,
+    ciphertext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    options: Options,
+    deadline: Option<Deadline>,
+) -> Result<Vec<u8>, Error> {
+    let (iv, body) = engine::find_frame(algorithm, mode, ciphertext)?;
+
+    decrypting(algorithm, body, key, mode, Some(iv), options, deadline)
+}
+
+fn process(
+    mut stream: Streaming,
+    data: &[u8],
+    deadline: Option<Deadline>,
+) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(data.len() + engine::BLOCK_SIZE);
+    for chunk in data.chunks(READ_CHUNK) {
+        if let Some(deadline) = deadline {
+            deadline.check()?;
+        }
+        out.extend(stream.update(chunk));
+    }
+    out.extend(stream.finalize()?);
+
+    Ok(out)
+}
@@ -0,0 +1,36 @@
+//! Раздельное хранение ключа (двойной контроль).
+//!
+//! Ключ — XOR двух долей по `KEY_SIZE` байт, которые хранят разные люди.
+//! Одна доля не даёт сведений о ключе, а объединяются доли только внутри
+//! Rust на время операции: собранный ключ в Python не передаётся.
+
+use zeroize::Zeroizing;
+
+use crate::engine::KEY_SIZE;
+
+/// Пара долей ключа.
+pub type Shares = (Zeroizing<Vec<u8>>, Zeroizing<Vec<u8>>);
+
+/// Две случайные доли нового ключа.
+pub fn generate() -> Result<Shares, getrandom::Error> {
+    let mut share_a = Zeroizing::new(vec![0u8; KEY_SIZE]);
+    let mut share_b = Zeroizing::new(vec![0u8; KEY_SIZE]);
+    getrandom::getrandom(&mut share_a)?;
+    getrandom::getrandom(&mut share_b)?;
+
+    Ok((share_a, share_b))
+}
+
+/// Разделение существующего ключа: случайная доля и её дополнение.
+pub fn split(key: &[u8]) -> Result<Shares, getrandom::Error> {
+    let mut share_a = Zeroizing::new(vec![0u8; key.len()]);
+    getrandom::getrandom(&mut share_a)?;
+    let share_b = Zeroizing::new(combine(key, &share_a).to_vec());
+
+    Ok((share_a, share_b))
+}
+
+/// Ключ из двух долей одинаковой длины.
+pub fn combine(share_a: &[u8], share_b: &[u8]) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new(share_a.iter().zip(share_b).map(|(a, b)| a ^ b).collect())
+}
@@ -0,0 +1,149 @@
+//! Текстовые кодировки шифротекста для встраивания в JSON, URL и HTML.
+//!
+//! `hex` — строчные шестнадцатеричные цифры, `base64` — стандартный
+//! алфавит с выравниванием `=` (RFC 4648, раздел 4), `base64url` —
+//! алфавит для URL и имён файлов без выравнивания (раздел 5), как в JWT.
+//! При декодировании регистр шестнадцатеричных цифр не важен, а
+//! выравнивание `=` необязательно.
+
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Текстовая кодировка.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    Base64,
+    Base64Url,
+}
+
+impl Encoding {
+    /// Кодировка по имени (`hex`, `base64`, `base64url`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hex" => Some(Encoding::Hex),
+            "base64" => Some(Encoding::Base64),
+            "base64url" => Some(Encoding::Base64Url),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Hex => "hex",
+            Encoding::Base64 => "base64",
+            Encoding::Base64Url => "base64url",
+        }
+    }
+
+    pub fn encode(self, data: &[u8]) -> String {
+        match self {
+            Encoding::Hex => data
+                .iter()
+                .flat_map(|&b| [HEX[usize::from(b >> 4)], HEX[usize::from(b & 0x0F)]])
+                .map(char::from)
+                .collect(),
+            Encoding::Base64 => encode_base64(data, BASE64, true),
+            Encoding::Base64Url => encode_base64(data, BASE64_URL, false),
+        }
+    }
+
+    /// Декодирование; `None` при недопустимых символах или длине.
+    pub fn decode(self, text: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Hex => decode_hex(text),
+            Encoding::Base64 => decode_base64(text, BASE64),
+            Encoding::Base64Url => decode_base64(text, BASE64_URL),
+        }
+    }
+}
+
+/// Декодирование `str` или буфера ASCII в `bytes`; неверный текст —
+/// `ValueError`.
+pub fn decode_text<'py>(
+    text: &Bound<'py, PyAny>,
+    encoding: Encoding,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let data = match text.downcast::<PyString>() {
+        Ok(text) => encoding.decode(text.to_str()?.as_bytes()),
+        Err(_) => encoding.decode(&PyBuffer::<u8>::get(text)?.to_vec(text.py())?),
+    };
+
+    data.map(|data| PyBytes::new(text.py(), &data))
+        .ok_or_else(|| PyValueError::new_err(format!("invalid {} input", encoding.name())))
+}
+
+/// Группы по 3 байта — 4 символа; неполная группа — на символ больше
+/// числа байт и `=` до 4 символов, если `pad`.
+fn encode_base64(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        let bits = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &b)| bits | u32::from(b) << (16 - 8 * i));
+        for i in 0..=group.len() {
+            out.push(char::from(alphabet[(bits >> (18 - 6 * i)) as usize & 0x3F]));
+        }
+        if pad {
+            out.extend(std::iter::repeat_n('=', 3 - group.len()));
+        }
+    }
+
+    out
+}
+
+fn decode_hex(text: &[u8]) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    text.chunks_exact(2)
+        .map(|pair| Some(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+        .collect()
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    char::from(c).to_digit(16).map(|d| d as u8)
+}
+
+fn decode_base64(text: &[u8], alphabet: &[u8; 64]) -> Option<Vec<u8>> {
+    let text = text
+        .strip_suffix(b"==")
+        .or_else(|| text.strip_suffix(b"="))
+        .unwrap_or(text);
+    if text.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(text.len() / 4 * 3 + 2);
+    for group in text.chunks(4) {
+        let mut bits = 0u32;
+        for (i, &c) in group.iter().enumerate() {
+            bits |= sextet(c, alphabet)? << (18 - 6 * i);
+        }
+        out.extend_from_slice(&bits.to_be_bytes()[1..group.len()]);
+    }
+
+    Some(out)
+}
+
+/// Значение символа алфавита (6 бит).
+fn sextet(c: u8, alphabet: &[u8; 64]) -> Option<u32> {
+    let value = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        _ if c == alphabet[62] => 62,
+        _ if c == alphabet[63] => 63,
+        _ => return None,
+    };
+
+    Some(value.into())
+}
@@ -1,8 +1,139 @@
-use block_encryption::cipher::kuznyechik::cipher::Kuznyechik;
-use block_encryption::mode::{CBC, CFB, CTR, ECB, OFB};
-use block_encryption::padding::PKCS7;
-use block_encryption::traits::Mode;
-use block_encryption::traits::{CipherError, Encryptor};
+use std::sync::OnceLock;
+
+use aes::cipher::consts::U16;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+use aes::Aes256;
+use block_encryption::traits::CipherError;
+use kuznyechik::Kuznyechik as KuznyechikBlock;
+use magma::Magma;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::{aead, cache, cmac, ff1, kdf, mgm, modes};
+
+/// Размер блока шифра "Кузнечик" в байтах.
+pub const BLOCK_SIZE: usize = 16;
+
+/// Размер блока шифра "Магма" в байтах.
+pub const MAGMA_BLOCK_SIZE: usize = 8;
+
+/// Размер ключа шифра "Кузнечик" в байтах.
+pub const KEY_SIZE: usize = 32;
+
+/// Встроенный вектор инициализации режимов CBC, CFB, OFB, CTR.
+///
+/// Для шифров с блоком 8 байт используется его начало.
+pub const DEFAULT_IV: [u8; BLOCK_SIZE] = [
+    0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+];
+
+/// Маркер заголовка шифротекста со случайным вектором инициализации.
+pub const FRAME_MAGIC: &[u8; 3] = b"GIV";
+
+/// Версия заголовка шифротекста со случайным вектором инициализации.
+pub const FRAME_VERSION: u8 = 1;
+
+/// Размер заголовка: маркер, версия и вектор инициализации (для шифров с
+/// блоком `BLOCK_SIZE`, см. `frame_header_size`).
+pub const FRAME_HEADER_SIZE: usize = FRAME_MAGIC.len() + 1 + BLOCK_SIZE;
+
+/// Размер сообщения по умолчанию, начиная с которого ECB и CTR
+/// обрабатываются параллельно (`Encryption::threads`).
+pub const PARALLEL_SIZE: usize = 1024 * 1024;
+
+/// Переменная окружения с порогом параллельной обработки в байтах.
+pub const PARALLEL_SIZE_ENV: &str = "GRASS_CRYPT_PARALLEL_SIZE";
+
+/// Переменная окружения с числом потоков по умолчанию; без неё —
+/// размер пула rayon (`RAYON_NUM_THREADS` или число ядер).
+pub const THREADS_ENV: &str = "GRASS_CRYPT_THREADS";
+
+/// Блочный шифр.
+///
+/// "Кузнечик" — основной шифр, AES-256 предназначен для сравнения и
+/// совместимости. "Магма" (ГОСТ 34.12-2018, блок 8 байт) — для обмена с
+/// системами ГОСТ 28147-89. Все используют одинаковые режимы и
+/// выравнивание, вектор инициализации — размером в блок шифра.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Kuznyechik,
+    Aes256,
+    Magma,
+}
+
+impl Algorithm {
+    /// Все шифры (`cryptor.supported_ciphers`).
+    pub const ALL: [Algorithm; 3] = [Algorithm::Kuznyechik, Algorithm::Aes256, Algorithm::Magma];
+
+    /// Шифр по имени (`kuznyechik`, `aes256`, `magma`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+    }
+
+    /// Имя шифра.
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Kuznyechik => "kuznyechik",
+            Algorithm::Aes256 => "aes256",
+            Algorithm::Magma => "magma",
+        }
+    }
+
+    /// Размер блока (и вектора инициализации) в байтах.
+    pub fn block_size(self) -> usize {
+        match self {
+            Algorithm::Kuznyechik | Algorithm::Aes256 => BLOCK_SIZE,
+            Algorithm::Magma => MAGMA_BLOCK_SIZE,
+        }
+    }
+}
+
+/// Режим `do_encrypt`: режим фабрики шифровальщиков (`modes::Mode`) или
+/// режим движка — MGM, SIV, CTR-ACPKM.
+///
+/// Строка режима разбирается один раз (`from_name`), а `params::Mode`
+/// переводится в него без строки, поэтому дальше движок выбирает режим
+/// сопоставлением.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncryptMode {
+    Block(modes::Mode),
+    Mgm,
+    Siv,
+    CtrAcpkm,
+}
+
+impl EncryptMode {
+    /// Режим по имени (`ECB`, `CBC`, `CFB`, `OFB`, `CTR`, `MGM`, `SIV`,
+    /// `CTR-ACPKM`).
+    pub fn from_name(name: &str) -> Result<Self, CipherError> {
+        match name {
+            MGM => Ok(EncryptMode::Mgm),
+            SIV => Ok(EncryptMode::Siv),
+            CTR_ACPKM => Ok(EncryptMode::CtrAcpkm),
+            _ => modes::Mode::from_name(name).map(EncryptMode::Block),
+        }
+    }
+
+    /// Имя режима.
+    pub fn name(self) -> &'static str {
+        match self {
+            EncryptMode::Block(mode) => mode.name(),
+            EncryptMode::Mgm => MGM,
+            EncryptMode::Siv => SIV,
+            EncryptMode::CtrAcpkm => CTR_ACPKM,
+        }
+    }
+
+    /// Режим фабрики шифровальщиков; MGM, SIV и CTR-ACPKM — `InvalidMode`.
+    pub fn block(self) -> Result<modes::Mode, CipherError> {
+        match self {
+            EncryptMode::Block(mode) => Ok(mode),
+            _ => Err(CipherError::InvalidMode),
+        }
+    }
+}
 
 /// Шифрование переданной строки с использованием ключа.
 ///
@@ -10,47 +141,1205 @@ use block_encryption::traits::{CipherError, Encryptor};
 ///
 /// - text — Текст для шифрования
 /// - key — Ключ для шифрования
-/// - encrypt_mode — Режим шифрования
+/// - mode — Режим шифрования
+/// - iv — Вектор инициализации (`BLOCK_SIZE` байт) или `None` для
+///   встроенного `DEFAULT_IV`; в ECB не используется
 pub fn encrypting(
-    plaintext: Vec<u8>,
-    key: Vec<u8>,
-    encrypt_mode: &str,
+    plaintext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    iv: Option<&[u8]>,
 ) -> Result<Vec<u8>, CipherError> {
-    let encryptor = get_encryptor(&key, encrypt_mode)?;
-    encryptor.encrypt(&plaintext)
+    let encryptor = get_encryptor(key, mode, check_iv(iv)?)?;
+    encryptor.encrypt(plaintext)
 }
 
 /// Дешифровка переданной строки с использованием ключа.
 pub fn decrypting(
-    ciphertext: Vec<u8>,
-    key: Vec<u8>,
-    encrypt_mode: &str,
+    ciphertext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    iv: Option<&[u8]>,
+) -> Result<Vec<u8>, CipherError> {
+    let encryptor = get_encryptor(key, mode, check_iv(iv)?)?;
+    encryptor.decrypt(ciphertext)
+}
+
+/// Шифрование выбранным шифром.
+///
+/// Шифровальщики "Кузнечика" берутся из кеша потока (см. `cache`).
+///
+/// - iv — Вектор инициализации размером в блок шифра или `None` для
+///   встроенного
+pub fn encrypting_with(
+    algorithm: Algorithm,
+    plaintext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    iv: Option<&[u8]>,
+) -> Result<Vec<u8>, CipherError> {
+    match algorithm {
+        Algorithm::Kuznyechik => {
+            cache::with_encryptor(key, mode, check_iv(iv)?, |e| e.encrypt(plaintext))
+        }
+        Algorithm::Aes256 => {
+            modes::encrypt(aes256(key)?, plaintext, mode, check_iv::<BLOCK_SIZE>(iv)?)
+        }
+        Algorithm::Magma => modes::encrypt(
+            magma(key)?,
+            plaintext,
+            mode,
+            check_iv::<MAGMA_BLOCK_SIZE>(iv)?,
+        ),
+    }
+}
+
+/// Дешифровка выбранным шифром.
+pub fn decrypting_with(
+    algorithm: Algorithm,
+    ciphertext: &[u8],
+    key: &[u8],
+    mode: modes::Mode,
+    iv: Option<&[u8]>,
+) -> Result<Vec<u8>, CipherError> {
+    match algorithm {
+        Algorithm::Kuznyechik => {
+            cache::with_encryptor(key, mode, check_iv(iv)?, |e| e.decrypt(ciphertext))
+        }
+        Algorithm::Aes256 => {
+            modes::decrypt(aes256(key)?, ciphertext, mode, check_iv::<BLOCK_SIZE>(iv)?)
+        }
+        Algorithm::Magma => modes::decrypt(
+            magma(key)?,
+            ciphertext,
+            mode,
+            check_iv::<MAGMA_BLOCK_SIZE>(iv)?,
+        ),
+    }
+}
+
+/// Шифрование сообщения целиком сразу в буфер вызывающего.
+///
+/// Буфер длиной `output_len` выделяет вызывающий, например в объекте
+/// Python, поэтому шифротекст не копируется. Выравнивание — PKCS7, MGM не
+/// поддерживается.
+pub struct Encryption {
+    header: Vec<u8>,
+    stream: Streaming,
+    len: usize,
+}
+
+impl Encryption {
+    /// - iv — Вектор инициализации размером в блок шифра или `None` для
+    ///   встроенного
+    /// - plaintext_len — Длина открытого текста
+    pub fn new(
+        algorithm: Algorithm,
+        key: &[u8],
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        plaintext_len: usize,
+    ) -> Result<Self, CipherError> {
+        Self::with_header(algorithm, key, mode, iv, Vec::new(), plaintext_len)
+    }
+
+    /// С вектором инициализации в заголовке результата:
+    /// `FRAME_MAGIC + FRAME_VERSION + iv + шифротекст`. Режим ECB вектор не
+    /// использует и не поддерживается.
+    ///
+    /// - iv — Вектор инициализации размером в блок шифра, обычно случайный
+    pub fn framed(
+        algorithm: Algorithm,
+        key: &[u8],
+        mode: modes::Mode,
+        iv: &[u8],
+        plaintext_len: usize,
+    ) -> Result<Self, CipherError> {
+        let header = frame_header(mode, iv)?;
+        Self::with_header(algorithm, key, mode, Some(iv), header, plaintext_len)
+    }
+
+    fn with_header(
+        algorithm: Algorithm,
+        key: &[u8],
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        header: Vec<u8>,
+        plaintext_len: usize,
+    ) -> Result<Self, CipherError> {
+        let stream = Streaming::cached(algorithm, key, mode, iv, true)?;
+        Self::from_stream(algorithm, stream, mode, header, plaintext_len)
+    }
+
+    fn from_stream(
+        algorithm: Algorithm,
+        stream: Streaming,
+        mode: modes::Mode,
+        header: Vec<u8>,
+        plaintext_len: usize,
+    ) -> Result<Self, CipherError> {
+        // Длина данных в памяти не больше `isize::MAX`, поэтому
+        // выравнивание и заголовок `usize` не переполняют.
+        let len = ciphertext_len(
+            algorithm,
+            modes::Padding::default(),
+            plaintext_len,
+            EncryptMode::Block(mode),
+        )
+        .and_then(|len| len.checked_add(header.len()))
+        .expect("ciphertext length of data in memory fits usize");
+
+        Ok(Encryption {
+            header,
+            stream,
+            len,
+        })
+    }
+
+    /// Длина результата в байтах.
+    pub fn output_len(&self) -> usize {
+        self.len
+    }
+
+    /// Параллельная обработка ECB и CTR (см. `parallel_threads`).
+    pub fn threads(mut self, threads: Option<usize>) -> Self {
+        self.stream.set_threads(parallel_threads(self.len, threads));
+        self
+    }
+
+    /// Шифрование `plaintext` (длиной, переданной при подготовке) в `out`.
+    pub fn finish(self, plaintext: &[u8], out: &mut [u8]) {
+        self.finish_parts(&[plaintext], out)
+    }
+
+    /// Шифрование сообщения из частей `parts` (общей длиной, переданной
+    /// при подготовке) в `out`.
+    pub fn finish_parts(self, parts: &[&[u8]], out: &mut [u8]) {
+        let (header, body) = out.split_at_mut(self.header.len());
+        header.copy_from_slice(&self.header);
+        self.stream.encrypt_into(parts, body);
+    }
+}
+
+/// Заголовок шифротекста со случайным вектором: `FRAME_MAGIC +
+/// FRAME_VERSION + iv`.
+pub fn frame_header(mode: modes::Mode, iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    if !mode.uses_iv() {
+        return Err(CipherError::InvalidMode);
+    }
+
+    Ok([&FRAME_MAGIC[..], &[FRAME_VERSION], iv].concat())
+}
+
+/// Дешифровка сообщения целиком сразу в буфер вызывающего.
+///
+/// Длина результата (`output_len`) известна после подготовки: в ECB и CBC
+/// для этого заранее дешифруется последний блок, поэтому ошибки
+/// выравнивания возникают уже при подготовке.
+pub struct Decryption<'a> {
+    stream: Streaming,
+    ciphertext: &'a [u8],
+    /// Конец открытого текста; стирается.
+    final_block: Zeroizing<Vec<u8>>,
+}
+
+impl<'a> Decryption<'a> {
+    /// - iv — Вектор инициализации размером в блок шифра или `None` для
+    ///   встроенного
+    pub fn new(
+        algorithm: Algorithm,
+        key: &[u8],
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        ciphertext: &'a [u8],
+    ) -> Result<Self, CipherError> {
+        let stream = Streaming::cached(algorithm, key, mode, iv, false)?;
+        Self::from_stream(stream, ciphertext)
+    }
+
+    fn from_stream(stream: Streaming, ciphertext: &'a [u8]) -> Result<Self, CipherError> {
+        let final_block = Zeroizing::new(stream.final_block(ciphertext)?);
+
+        Ok(Decryption {
+            stream,
+            ciphertext,
+            final_block,
+        })
+    }
+
+    /// С вектором из заголовка `Encryption::framed`.
+    ///
+    /// Заголовок ищется только по запросу вызывающего: шифротекст со
+    /// встроенным вектором может случайно начинаться как заголовок, а в
+    /// CFB, OFB и CTR дешифровка с чужим вектором не даёт ошибки. Без
+    /// заголовка возвращается `DataTooShort`.
+    pub fn framed(
+        algorithm: Algorithm,
+        key: &[u8],
+        mode: modes::Mode,
+        ciphertext: &'a [u8],
+    ) -> Result<Self, CipherError> {
+        let (iv, body) = find_frame(algorithm, mode, ciphertext)?;
+        Self::new(algorithm, key, mode, Some(iv), body)
+    }
+
+    /// Длина открытого текста в байтах.
+    pub fn output_len(&self) -> usize {
+        self.stream
+            .decrypted_len(self.ciphertext.len(), &self.final_block)
+    }
+
+    /// Параллельная обработка ECB и CTR (см. `parallel_threads`).
+    pub fn threads(mut self, threads: Option<usize>) -> Self {
+        let threads = parallel_threads(self.ciphertext.len(), threads);
+        self.stream.set_threads(threads);
+        self
+    }
+
+    /// Дешифровка в `out` (`output_len` байт).
+    pub fn finish(self, out: &mut [u8]) {
+        self.stream
+            .decrypt_into(self.ciphertext, &self.final_block, out);
+    }
+}
+
+/// Вектор инициализации и тело шифротекста с заголовком.
+pub type Frame<'a> = (&'a [u8], &'a [u8]);
+
+/// Вектор и тело шифротекста с заголовком `Encryption::framed`.
+///
+/// Режим ECB заголовок не использует (`InvalidMode`); данные без заголовка
+/// известной версии — `DataTooShort`.
+pub fn find_frame<'a>(
+    algorithm: Algorithm,
+    mode: modes::Mode,
+    data: &'a [u8],
+) -> Result<Frame<'a>, CipherError> {
+    if !mode.uses_iv() {
+        return Err(CipherError::InvalidMode);
+    }
+
+    split_frame(data, frame_header_size(algorithm)).ok_or(CipherError::DataTooShort)
+}
+
+/// Размер заголовка `Encryption::framed` для шифра `algorithm`.
+pub fn frame_header_size(algorithm: Algorithm) -> usize {
+    FRAME_MAGIC.len() + 1 + algorithm.block_size()
+}
+
+/// Вектор и тело шифротекста с заголовком известной версии.
+fn split_frame(data: &[u8], header_size: usize) -> Option<Frame<'_>> {
+    if data.len() < header_size
+        || !data.starts_with(FRAME_MAGIC)
+        || data[FRAME_MAGIC.len()] != FRAME_VERSION
+    {
+        return None;
+    }
+    let (header, body) = data.split_at(header_size);
+
+    Some((&header[FRAME_MAGIC.len() + 1..], body))
+}
+
+/// Режим шифрования с аутентификацией (`mgm`).
+pub const MGM: &str = "MGM";
+
+/// Шифрование в режиме MGM: `шифротекст + имитовставка (tag_len байт)`.
+///
+/// Поддерживаются шифры с блоком 16 байт; для "Магмы" — `InvalidMode`.
+///
+/// - nonce — Nonce (старший бит равен нулю), не повторяется для ключа
+/// - aad — Дополнительные данные, защищаемые имитовставкой
+/// - tag_len — Длина имитовставки
+pub fn encrypting_mgm(
+    algorithm: Algorithm,
+    plaintext: &[u8],
+    key: &[u8],
+    nonce: &[u8; BLOCK_SIZE],
+    aad: &[u8],
+    tag_len: usize,
 ) -> Result<Vec<u8>, CipherError> {
-    let encryptor = get_encryptor(&key, encrypt_mode)?;
-    encryptor.decrypt(&ciphertext)
+    Ok(match algorithm {
+        Algorithm::Kuznyechik => mgm::seal(&kuznyechik(key)?, nonce, plaintext, aad, tag_len),
+        Algorithm::Aes256 => mgm::seal(&aes256(key)?, nonce, plaintext, aad, tag_len),
+        Algorithm::Magma => return Err(CipherError::InvalidMode),
+    })
+}
+
+/// Проверка имитовставки и дешифровка в режиме MGM.
+pub fn decrypting_mgm(
+    algorithm: Algorithm,
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8; BLOCK_SIZE],
+    aad: &[u8],
+    tag_len: usize,
+) -> Result<Vec<u8>, aead::Error> {
+    match algorithm {
+        Algorithm::Kuznyechik => {
+            let cipher = kuznyechik(key).map_err(aead::Error::Cipher)?;
+            mgm::open(&cipher, nonce, ciphertext, aad, tag_len)
+        }
+        Algorithm::Aes256 => {
+            let cipher = aes256(key).map_err(aead::Error::Cipher)?;
+            mgm::open(&cipher, nonce, ciphertext, aad, tag_len)
+        }
+        Algorithm::Magma => Err(aead::Error::Cipher(CipherError::InvalidMode)),
+    }
+}
+
+/// Режим CTR-ACPKM (Р 1323565.1.017-2018): CTR со сменой ключа в каждой
+/// секции данных.
+pub const CTR_ACPKM: &str = "CTR-ACPKM";
+
+/// Размер секции CTR-ACPKM по умолчанию в байтах.
+pub const ACPKM_SECTION_SIZE: usize = 256 * 1024;
+
+/// Шифрование или дешифровка CTR-ACPKM (операции совпадают).
+///
+/// Первая секция обрабатывается ключом `key`, каждая следующая — ключом
+/// `ACPKM` от предыдущего; счётчик CTR (весь блок, ГОСТ Р 34.13-2015)
+/// продолжается через границы секций.
+///
+/// - iv — Начальный блок счётчика или `None` для встроенного вектора
+/// - section_size — Размер секции, кратный блоку шифра
+pub fn acpkm(
+    algorithm: Algorithm,
+    data: &[u8],
+    key: &[u8],
+    iv: Option<&[u8]>,
+    section_size: usize,
+) -> Result<Vec<u8>, CipherError> {
+    match algorithm {
+        Algorithm::Kuznyechik => acpkm_with(
+            kuznyechik,
+            data,
+            key,
+            check_iv::<BLOCK_SIZE>(iv)?,
+            section_size,
+        ),
+        Algorithm::Aes256 => {
+            acpkm_with(aes256, data, key, check_iv::<BLOCK_SIZE>(iv)?, section_size)
+        }
+        Algorithm::Magma => acpkm_with(
+            magma,
+            data,
+            key,
+            check_iv::<MAGMA_BLOCK_SIZE>(iv)?,
+            section_size,
+        ),
+    }
+}
+
+fn acpkm_with<C, const N: usize>(
+    new: fn(&[u8]) -> Result<C, CipherError>,
+    data: &[u8],
+    key: &[u8],
+    iv: &[u8; N],
+    section_size: usize,
+) -> Result<Vec<u8>, CipherError>
+where
+    C: BlockEncrypt + BlockDecrypt,
+{
+    if section_size == 0 || !section_size.is_multiple_of(N) {
+        return Err(CipherError::InvalidBlockSize);
+    }
+    let mut key = Zeroizing::new(key.to_vec());
+    let mut counter = *iv;
+    let mut out = Vec::with_capacity(data.len());
+
+    for (i, section) in data.chunks(section_size).enumerate() {
+        if i > 0 {
+            key = acpkm_next_key::<C, N>(new(&key)?)?;
+        }
+        out.extend(modes::encrypt(
+            new(&key)?,
+            section,
+            modes::Mode::Ctr,
+            &counter,
+        )?);
+        modes::advance(
+            &mut counter,
+            modes::Counter::Full,
+            (section_size / N) as u64,
+        );
+    }
+
+    Ok(out)
+}
+
+/// Ключ следующей секции: `MSB_k(E_K(D_1) || ... || E_K(D_J))`, где
+/// `D_1 || ... || D_J` — байты `0x80..=0x9F`.
+fn acpkm_next_key<C, const N: usize>(cipher: C) -> Result<Zeroizing<Vec<u8>>, CipherError>
+where
+    C: BlockEncrypt + BlockDecrypt,
+{
+    let d: Vec<u8> = (0x80..).take(KEY_SIZE).collect();
+    // Блоки ECB шифруются независимо: выравнивание — лишь в конце.
+    let mut next = Zeroizing::new(modes::encrypt(cipher, &d, modes::Mode::Ecb, &[0u8; N])?);
+    next.truncate(KEY_SIZE);
+
+    Ok(next)
+}
+
+/// Детерминированный режим SIV: вектор CTR — имитовставка открытого текста.
+pub const SIV: &str = "SIV";
+
+/// Шифрование SIV: `V || CTR(K_enc, V, plaintext)`, где
+/// `V = CMAC(K_mac, len(aad) || aad || plaintext)` в размер блока шифра
+/// (`len(aad)` — 8 байт, big-endian).
+///
+/// Одинаковые тексты с одинаковыми `aad` под одним ключом дают одинаковые
+/// шифротексты (поиск по индексу), другие совпадения не раскрываются.
+/// Ключи `K_mac` и `K_enc` вырабатываются из `key` (`kdf::kdf_gostr3411`).
+pub fn encrypting_siv(
+    algorithm: Algorithm,
+    plaintext: &[u8],
+    key: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    let keys = SivKeys::new(key)?;
+    let mut sealed = siv_tag(algorithm, &keys.mac, plaintext, aad)?;
+    let ct = encrypting_with(
+        algorithm,
+        plaintext,
+        &keys.enc,
+        modes::Mode::Ctr,
+        Some(&sealed),
+    )?;
+    sealed.extend(ct);
+
+    Ok(sealed)
+}
+
+/// Дешифровка SIV и проверка вектора за постоянное время; открытый текст
+/// при неверном векторе стирается.
+pub fn decrypting_siv(
+    algorithm: Algorithm,
+    ciphertext: &[u8],
+    key: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, aead::Error> {
+    let (tag, body) = ciphertext
+        .split_at_checked(algorithm.block_size())
+        .ok_or(aead::Error::Cipher(CipherError::DataTooShort))?;
+    let keys = SivKeys::new(key).map_err(aead::Error::Cipher)?;
+    let mut plaintext = Zeroizing::new(
+        decrypting_with(algorithm, body, &keys.enc, modes::Mode::Ctr, Some(tag))
+            .map_err(aead::Error::Cipher)?,
+    );
+    let expected = siv_tag(algorithm, &keys.mac, &plaintext, aad).map_err(aead::Error::Cipher)?;
+    if !bool::from(expected.ct_eq(tag)) {
+        return Err(aead::Error::Authentication);
+    }
+
+    Ok(std::mem::take(&mut *plaintext))
+}
+
+/// Ключи имитовставки и шифрования SIV.
+struct SivKeys {
+    mac: Zeroizing<Vec<u8>>,
+    enc: Zeroizing<Vec<u8>>,
+}
+
+impl SivKeys {
+    /// - key — Ключ (32 байта)
+    fn new(key: &[u8]) -> Result<Self, CipherError> {
+        if key.len() != KEY_SIZE {
+            return Err(CipherError::InvalidKeyLenght);
+        }
+
+        Ok(SivKeys {
+            mac: kdf::kdf_gostr3411(key, b"siv mac", b""),
+            enc: kdf::kdf_gostr3411(key, b"siv enc", b""),
+        })
+    }
+}
+
+fn siv_tag(
+    algorithm: Algorithm,
+    mac_key: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    let aad_len = (aad.len() as u64).to_be_bytes();
+    cmac(
+        algorithm,
+        mac_key,
+        &[&aad_len, aad, plaintext],
+        algorithm.block_size(),
+    )
+}
+
+/// Режим `do_encrypt` в списке `cryptor.supported_modes`.
+pub struct ModeInfo {
+    pub name: &'static str,
+    /// Длина `iv` (в MGM — nonce); 0 — вектор не передаётся.
+    pub iv_length: usize,
+    pub requires_padding: bool,
+    /// Длина имитовставки по умолчанию и наименьшая допустимая; 0 — режим
+    /// без имитовставки.
+    pub tag_length: usize,
+    pub min_tag_length: usize,
+}
+
+/// Все режимы `do_encrypt` для шифра `algorithm`: режимы фабрики
+/// (`modes::Mode::ALL`), MGM (шифры с блоком 16 байт), SIV и CTR-ACPKM.
+pub fn supported_modes(algorithm: Algorithm) -> Vec<ModeInfo> {
+    let block_size = algorithm.block_size();
+    let mut supported: Vec<ModeInfo> = modes::Mode::ALL
+        .into_iter()
+        .map(|mode| ModeInfo {
+            name: mode.name(),
+            iv_length: if mode.uses_iv() { block_size } else { 0 },
+            requires_padding: mode.is_block(),
+            tag_length: 0,
+            min_tag_length: 0,
+        })
+        .collect();
+    if block_size == BLOCK_SIZE {
+        supported.push(ModeInfo {
+            name: MGM,
+            iv_length: mgm::NONCE_SIZE,
+            requires_padding: false,
+            tag_length: mgm::TAG_SIZE,
+            min_tag_length: mgm::MIN_TAG_SIZE,
+        });
+    }
+    supported.push(ModeInfo {
+        name: SIV,
+        iv_length: 0,
+        requires_padding: false,
+        tag_length: block_size,
+        min_tag_length: block_size,
+    });
+    supported.push(ModeInfo {
+        name: CTR_ACPKM,
+        iv_length: block_size,
+        requires_padding: false,
+        tag_length: 0,
+        min_tag_length: 0,
+    });
+
+    supported
+}
+
+/// Шифрование цифр с сохранением формата (`ff1`); шифры с блоком 16 байт,
+/// для "Магмы" — `InvalidMode`.
+///
+/// - digits — Значения цифр от 0 до `radix - 1`
+/// - encrypt — Шифрование (`true`) или дешифровка
+pub fn ff1(
+    algorithm: Algorithm,
+    key: &[u8],
+    radix: u32,
+    tweak: &[u8],
+    digits: &[u8],
+    encrypt: bool,
+) -> Result<Vec<u8>, CipherError> {
+    match algorithm {
+        Algorithm::Kuznyechik => ff1_with(&kuznyechik(key)?, radix, tweak, digits, encrypt),
+        Algorithm::Aes256 => ff1_with(&aes256(key)?, radix, tweak, digits, encrypt),
+        Algorithm::Magma => Err(CipherError::InvalidMode),
+    }
+}
+
+fn ff1_with<C>(
+    cipher: &C,
+    radix: u32,
+    tweak: &[u8],
+    digits: &[u8],
+    encrypt: bool,
+) -> Result<Vec<u8>, CipherError>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    if encrypt {
+        ff1::encrypt(cipher, radix, tweak, digits)
+    } else {
+        ff1::decrypt(cipher, radix, tweak, digits)
+    }
+}
+
+/// Имитовставка CMAC (ГОСТ Р 34.13-2015) выбранным шифром.
+///
+/// - parts — Данные, возможно из нескольких частей
+/// - tag_len — Длина имитовставки (от 1 до размера блока шифра)
+pub fn cmac(
+    algorithm: Algorithm,
+    key: &[u8],
+    parts: &[&[u8]],
+    tag_len: usize,
+) -> Result<Vec<u8>, CipherError> {
+    Ok(match algorithm {
+        Algorithm::Kuznyechik => cmac::cmac(&kuznyechik(key)?, parts, tag_len),
+        Algorithm::Aes256 => cmac::cmac(&aes256(key)?, parts, tag_len),
+        Algorithm::Magma => cmac::cmac(&magma(key)?, parts, tag_len),
+    })
+}
+
+/// Пошаговое шифрование или дешифровка MGM (см. `mgm::Stream`).
+///
+/// Варианты хранят развёрнутый ключ и сильно различаются по размеру;
+/// значение создаётся один раз на поток, поэтому без `Box`.
+#[allow(clippy::large_enum_variant)]
+pub enum MgmStreaming {
+    Kuznyechik(mgm::Stream<KuznyechikBlock>),
+    Aes256(mgm::Stream<Aes256>),
+}
+
+impl MgmStreaming {
+    /// Фабрика по шифру; "Магма" в MGM не поддерживается.
+    pub fn new(
+        algorithm: Algorithm,
+        key: &[u8],
+        nonce: &[u8; BLOCK_SIZE],
+        aad: &[u8],
+        tag_len: usize,
+        encrypting: bool,
+    ) -> Result<Self, aead::Error> {
+        Ok(match algorithm {
+            Algorithm::Kuznyechik => MgmStreaming::Kuznyechik(mgm::Stream::new(
+                kuznyechik(key).map_err(aead::Error::Cipher)?,
+                nonce,
+                aad,
+                tag_len,
+                encrypting,
+            )?),
+            Algorithm::Aes256 => MgmStreaming::Aes256(mgm::Stream::new(
+                aes256(key).map_err(aead::Error::Cipher)?,
+                nonce,
+                aad,
+                tag_len,
+                encrypting,
+            )?),
+            Algorithm::Magma => return Err(aead::Error::Cipher(CipherError::InvalidMode)),
+        })
+    }
+
+    /// Обработка очередной части данных.
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        match self {
+            MgmStreaming::Kuznyechik(stream) => stream.update(data),
+            MgmStreaming::Aes256(stream) => stream.update(data),
+        }
+    }
+
+    /// Завершение с имитовставкой или её проверкой.
+    pub fn finalize(self) -> Result<Vec<u8>, aead::Error> {
+        match self {
+            MgmStreaming::Kuznyechik(stream) => stream.finalize(),
+            MgmStreaming::Aes256(stream) => stream.finalize(),
+        }
+    }
+}
+
+/// Пошаговое шифрование или дешифровка выбранным шифром.
+///
+/// Формат результата тот же, что у `encrypting_with`/`decrypting_with`,
+/// но данные передаются частями (`update`) и состояние режима хранится
+/// между вызовами. Варианты хранят развёрнутый ключ и различаются по
+/// размеру; значение создаётся один раз на поток, поэтому без `Box`.
+#[allow(clippy::large_enum_variant)]
+pub enum Streaming {
+    Kuznyechik(modes::Stream<KuznyechikBlock>),
+    Aes256(modes::Stream<Aes256>),
+    Magma(modes::Stream<Magma>),
+}
+
+impl Streaming {
+    /// Фабрика по шифру и режиму.
+    ///
+    /// - key — Ключ (`KEY_SIZE` байт)
+    /// - mode — Режим шифрования
+    /// - iv — Вектор инициализации размером в блок шифра или `None` для
+    ///   встроенного `DEFAULT_IV`
+    /// - options — Выравнивание ECB и CBC, счётчик CTR
+    /// - encrypting — Шифрование (`true`) или дешифровка
+    pub fn new(
+        algorithm: Algorithm,
+        key: &[u8],
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        options: modes::Options,
+        encrypting: bool,
+    ) -> Result<Self, CipherError> {
+        Ok(match algorithm {
+            Algorithm::Kuznyechik => Streaming::Kuznyechik(modes::Stream::new(
+                kuznyechik(key)?,
+                mode,
+                check_iv::<BLOCK_SIZE>(iv)?,
+                options,
+                encrypting,
+            )?),
+            Algorithm::Aes256 => Streaming::Aes256(modes::Stream::new(
+                aes256(key)?,
+                mode,
+                check_iv::<BLOCK_SIZE>(iv)?,
+                options,
+                encrypting,
+            )?),
+            Algorithm::Magma => Streaming::Magma(modes::Stream::new(
+                magma(key)?,
+                mode,
+                check_iv::<MAGMA_BLOCK_SIZE>(iv)?,
+                options,
+                encrypting,
+            )?),
+        })
+    }
+
+    /// Как `new` со схемой режимов по умолчанию; "Кузнечик" берётся из
+    /// кеша потока (см. `cache`).
+    pub fn cached(
+        algorithm: Algorithm,
+        key: &[u8],
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        encrypting: bool,
+    ) -> Result<Self, CipherError> {
+        match algorithm {
+            Algorithm::Kuznyechik => {
+                cache::with_encryptor(key, mode, check_iv(iv)?, |e| e.stream(encrypting))
+                    .map(Streaming::Kuznyechik)
+            }
+            _ => Self::new(
+                algorithm,
+                key,
+                mode,
+                iv,
+                modes::Options::default(),
+                encrypting,
+            ),
+        }
+    }
+
+    /// Обработка очередной части данных (см. `modes::Stream::update`).
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Streaming::Kuznyechik(stream) => stream.update(data),
+            Streaming::Aes256(stream) => stream.update(data),
+            Streaming::Magma(stream) => stream.update(data),
+        }
+    }
+
+    /// Завершение с выравниванием последнего блока.
+    pub fn finalize(self) -> Result<Vec<u8>, CipherError> {
+        match self {
+            Streaming::Kuznyechik(stream) => stream.finalize(),
+            Streaming::Aes256(stream) => stream.finalize(),
+            Streaming::Magma(stream) => stream.finalize(),
+        }
+    }
+
+    /// Шифрование сообщения из частей в `out` (см.
+    /// `modes::Stream::encrypt_into`).
+    pub fn encrypt_into(self, parts: &[&[u8]], out: &mut [u8]) {
+        match self {
+            Streaming::Kuznyechik(stream) => stream.encrypt_into(parts, out),
+            Streaming::Aes256(stream) => stream.encrypt_into(parts, out),
+            Streaming::Magma(stream) => stream.encrypt_into(parts, out),
+        }
+    }
+
+    /// Последний блок открытого текста (см. `modes::Stream::final_block`).
+    pub fn final_block(&self, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+        match self {
+            Streaming::Kuznyechik(stream) => stream.final_block(data),
+            Streaming::Aes256(stream) => stream.final_block(data),
+            Streaming::Magma(stream) => stream.final_block(data),
+        }
+    }
+
+    /// Длина открытого текста (см. `modes::Stream::decrypted_len`).
+    pub fn decrypted_len(&self, data_len: usize, final_block: &[u8]) -> usize {
+        match self {
+            Streaming::Kuznyechik(stream) => stream.decrypted_len(data_len, final_block),
+            Streaming::Aes256(stream) => stream.decrypted_len(data_len, final_block),
+            Streaming::Magma(stream) => stream.decrypted_len(data_len, final_block),
+        }
+    }
+
+    /// Число частей параллельной обработки (см. `modes::Stream::set_threads`).
+    pub fn set_threads(&mut self, threads: usize) {
+        match self {
+            Streaming::Kuznyechik(stream) => stream.set_threads(threads),
+            Streaming::Aes256(stream) => stream.set_threads(threads),
+            Streaming::Magma(stream) => stream.set_threads(threads),
+        }
+    }
+
+    /// Дешифровка сообщения целиком в `out` (см. `modes::Stream::decrypt_into`).
+    pub fn decrypt_into(self, data: &[u8], final_block: &[u8], out: &mut [u8]) {
+        match self {
+            Streaming::Kuznyechik(stream) => stream.decrypt_into(data, final_block, out),
+            Streaming::Aes256(stream) => stream.decrypt_into(data, final_block, out),
+            Streaming::Magma(stream) => stream.decrypt_into(data, final_block, out),
+        }
+    }
+}
+
+/// Перешифрование: дешифровка старым ключом и шифрование новым.
+///
+/// Промежуточный открытый текст стирается.
+pub fn reencrypting(
+    ciphertext: &[u8],
+    old_key: &[u8],
+    new_key: &[u8],
+    mode: modes::Mode,
+) -> Result<Vec<u8>, CipherError> {
+    let plaintext = Zeroizing::new(decrypting(ciphertext, old_key, mode, None)?);
+    encrypting(&plaintext, new_key, mode, None)
+}
+
+/// Точный размер шифротекста для открытого текста длиной `plaintext_len`.
+///
+/// Блочные режимы (ECB, CBC) добавляют выравнивание от 1 до размера блока
+/// шифра (нулевое — от 0), потоковые сохраняют длину. MGM добавляет
+/// случайный nonce и имитовставку, SIV — вектор в размер блока.
+///
+/// `None`, если размер не помещается в `usize`.
+pub fn ciphertext_len(
+    algorithm: Algorithm,
+    padding: modes::Padding,
+    plaintext_len: usize,
+    mode: EncryptMode,
+) -> Option<usize> {
+    let block_size = algorithm.block_size();
+    match mode {
+        EncryptMode::Mgm => plaintext_len.checked_add(mgm::NONCE_SIZE + mgm::TAG_SIZE),
+        EncryptMode::Siv => plaintext_len.checked_add(block_size),
+        EncryptMode::Block(mode) if mode.is_block() => {
+            let blocks = match padding {
+                modes::Padding::Zero => plaintext_len.div_ceil(block_size),
+                _ => plaintext_len / block_size + 1,
+            };
+            blocks.checked_mul(block_size)
+        }
+        EncryptMode::Block(_) | EncryptMode::CtrAcpkm => Some(plaintext_len),
+    }
+}
+
+/// Размер открытого текста для шифротекста длиной `ciphertext_len`.
+///
+/// Для блочных режимов возвращается максимально возможная длина (точная
+/// зависит от выравнивания и известна только после дешифровки), но
+/// одновременно проверяется корректность длины шифротекста.
+pub fn plaintext_len(
+    algorithm: Algorithm,
+    padding: modes::Padding,
+    ciphertext_len: usize,
+    mode: EncryptMode,
+) -> Result<usize, CipherError> {
+    match mode {
+        EncryptMode::Mgm => {
+            return ciphertext_len
+                .checked_sub(mgm::NONCE_SIZE + mgm::TAG_SIZE)
+                .ok_or(CipherError::DataTooShort);
+        }
+        EncryptMode::Siv => {
+            return ciphertext_len
+                .checked_sub(algorithm.block_size())
+                .ok_or(CipherError::DataTooShort);
+        }
+        EncryptMode::Block(mode) if mode.is_block() => {}
+        EncryptMode::Block(_) | EncryptMode::CtrAcpkm => return Ok(ciphertext_len),
+    }
+
+    match ciphertext_len {
+        0 => Err(CipherError::DataTooShort),
+        n if n % algorithm.block_size() != 0 => Err(CipherError::DataNotAligned),
+        n if padding == modes::Padding::Zero => Ok(n),
+        n => Ok(n - 1),
+    }
+}
+
+/// Число потоков для сообщения длиной `len`.
+///
+/// Меньше порога (`PARALLEL_SIZE` или `PARALLEL_SIZE_ENV`) сообщение
+/// обрабатывается в текущем потоке, иначе — `threads` частями или числом
+/// по умолчанию (`THREADS_ENV`). Переменные окружения читаются один раз.
+pub fn parallel_threads(len: usize, threads: Option<usize>) -> usize {
+    static DEFAULTS: OnceLock<(usize, usize)> = OnceLock::new();
+    let (min_size, default) = *DEFAULTS.get_or_init(|| {
+        (
+            env_usize(PARALLEL_SIZE_ENV).unwrap_or(PARALLEL_SIZE),
+            env_usize(THREADS_ENV).unwrap_or_else(rayon::current_num_threads),
+        )
+    });
+
+    if len < min_size {
+        1
+    } else {
+        threads.unwrap_or(default).max(1)
+    }
+}
+
+fn env_usize(name: &str) -> Option<usize> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
+
+/// Проверка длины вектора инициализации (`N` — размер блока шифра);
+/// `None` — встроенный вектор.
+fn check_iv<const N: usize>(iv: Option<&[u8]>) -> Result<&[u8; N], CipherError> {
+    iv.unwrap_or(&DEFAULT_IV[..N])
+        .try_into()
+        .map_err(|_| CipherError::InvalidIVLenght)
+}
+
+/// Блочный примитив "Кузнечика" для режимов `modes` и `mgm`.
+fn kuznyechik(key: &[u8]) -> Result<KuznyechikBlock, CipherError> {
+    KuznyechikBlock::new_from_slice(key).map_err(|_| CipherError::InvalidKeyLenght)
+}
+
+/// Блочный примитив "Магмы": ключ той же длины, что и у "Кузнечика".
+fn magma(key: &[u8]) -> Result<Magma, CipherError> {
+    Magma::new_from_slice(key).map_err(|_| CipherError::InvalidKeyLenght)
+}
+
+/// Подготовка AES-256: ключ той же длины, что и у "Кузнечика".
+fn aes256(key: &[u8]) -> Result<Aes256, CipherError> {
+    Aes256::new_from_slice(key).map_err(|_| CipherError::InvalidKeyLenght)
+}
+
+/// Подготовленный шифровальщик "Кузнечика": развёрнутый ключ, режим и
+/// вектор инициализации.
+///
+/// Формат данных — схема `block-encryption` (`modes::Options::default`).
+/// Шифр и режим выбираются статически, без `Box<dyn ...>` и выделений
+/// памяти при подготовке, поэтому вызовы с короткими сообщениями дешевле.
+pub struct Encryptor {
+    cipher: KuznyechikBlock,
+    mode: modes::Mode,
+    iv: [u8; BLOCK_SIZE],
+}
+
+impl Encryptor {
+    /// Шифрование сообщения целиком.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CipherError> {
+        modes::process(self.cipher.clone(), plaintext, self.mode, &self.iv, true)
+    }
+
+    /// Дешифровка сообщения целиком.
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, CipherError> {
+        modes::process(self.cipher.clone(), ciphertext, self.mode, &self.iv, false)
+    }
+
+    /// Пошаговый шифровальщик с копией развёрнутого ключа.
+    pub fn stream(&self, encrypting: bool) -> Result<modes::Stream<KuznyechikBlock>, CipherError> {
+        modes::Stream::new(
+            self.cipher.clone(),
+            self.mode,
+            &self.iv,
+            modes::Options::default(),
+            encrypting,
+        )
+    }
+}
+
+/// Развёрнутый ключ "Кузнечика" для шифрования в любых режимах и с
+/// любыми векторами без повторного развёртывания.
+///
+/// Выравнивание — PKCS7, MGM не поддерживается.
+pub struct KeySchedule {
+    cipher: KuznyechikBlock,
+}
+
+impl KeySchedule {
+    /// - key — Ключ (`KEY_SIZE` байт)
+    pub fn new(key: &[u8]) -> Result<Self, CipherError> {
+        Ok(KeySchedule {
+            cipher: kuznyechik(key)?,
+        })
+    }
+
+    /// Шифрование сообщения целиком в буфер вызывающего.
+    ///
+    /// - iv — Вектор инициализации (`BLOCK_SIZE` байт) или `None` для
+    ///   встроенного
+    /// - counter — Счётчик CTR
+    pub fn encryption(
+        &self,
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        counter: modes::Counter,
+        plaintext_len: usize,
+    ) -> Result<Encryption, CipherError> {
+        let stream = self.stream(mode, iv, counter, true)?;
+        Encryption::from_stream(
+            Algorithm::Kuznyechik,
+            stream,
+            mode,
+            Vec::new(),
+            plaintext_len,
+        )
+    }
+
+    /// Дешифровка сообщения целиком в буфер вызывающего.
+    pub fn decryption<'a>(
+        &self,
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        counter: modes::Counter,
+        ciphertext: &'a [u8],
+    ) -> Result<Decryption<'a>, CipherError> {
+        let stream = self.stream(mode, iv, counter, false)?;
+        Decryption::from_stream(stream, ciphertext)
+    }
+
+    fn stream(
+        &self,
+        mode: modes::Mode,
+        iv: Option<&[u8]>,
+        counter: modes::Counter,
+        encrypting: bool,
+    ) -> Result<Streaming, CipherError> {
+        let options = modes::Options {
+            counter,
+            ..Default::default()
+        };
+        modes::Stream::new(
+            self.cipher.clone(),
+            mode,
+            check_iv::<BLOCK_SIZE>(iv)?,
+            options,
+            encrypting,
+        )
+        .map(Streaming::Kuznyechik)
+    }
 }
 
 /// Фабрика подготовки шифровальщика.
-fn get_encryptor(key_arr: &[u8], encrypt_mode: &str) -> Result<Encryptor, CipherError> {
-    let cipher = Box::new(Kuznyechik::new(key_arr)?);
-    let padding = Box::new(PKCS7);
-
-    let iv: Vec<u8> = vec![
-        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
-        0x08,
-    ];
-
-    let mode: Box<dyn Mode> = match encrypt_mode {
-        "ECB" => Box::new(ECB),
-        "CBC" => Box::new(CBC::new(iv)),
-        "CFB" => Box::new(CFB::new(iv)),
-        "OFB" => Box::new(OFB::new(iv)),
-        "CTR" => Box::new(CTR::new(iv)),
-        _ => return Err(CipherError::InvalidMode),
-    };
-
-    match encrypt_mode {
-        "CFB" | "OFB" | "CTR" => Encryptor::new_stream(cipher, mode),
-        _ => Encryptor::new_block(cipher, mode, padding),
+///
+/// Режим уже разобран (`modes::Mode::from_name` или `params::Mode`),
+/// поэтому ошибкой может быть только ключ.
+pub fn get_encryptor(
+    key_arr: &[u8],
+    mode: modes::Mode,
+    iv: &[u8; BLOCK_SIZE],
+) -> Result<Encryptor, CipherError> {
+    Ok(Encryptor {
+        cipher: kuznyechik(key_arr)?,
+        mode,
+        iv: *iv,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_SIZE] = [0x42; KEY_SIZE];
+    const NONCE: [u8; BLOCK_SIZE] = [0x11; BLOCK_SIZE];
+    const AAD: &[u8] = b"record 17";
+
+    /// Копии данных с изменённым битом в каждом байте.
+    fn flipped(data: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+        (0..data.len()).map(|i| {
+            let mut data = data.to_vec();
+            data[i] ^= 0x01;
+            data
+        })
+    }
+
+    #[test]
+    fn tampered_mgm_fails_authentication() {
+        let plaintext = [0x5a; 40];
+        for algorithm in [Algorithm::Kuznyechik, Algorithm::Aes256] {
+            let open = |data: &[u8], aad: &[u8]| {
+                decrypting_mgm(algorithm, data, &KEY, &NONCE, aad, mgm::TAG_SIZE)
+            };
+            let sealed =
+                encrypting_mgm(algorithm, &plaintext, &KEY, &NONCE, AAD, mgm::TAG_SIZE).unwrap();
+            assert_eq!(open(&sealed, AAD).unwrap(), plaintext);
+
+            for data in flipped(&sealed) {
+                assert!(matches!(open(&data, AAD), Err(aead::Error::Authentication)));
+            }
+            for len in 0..sealed.len() {
+                assert!(open(&sealed[..len], AAD).is_err());
+            }
+            assert!(matches!(
+                open(&sealed, b"record 18"),
+                Err(aead::Error::Authentication)
+            ));
+        }
+    }
+
+    #[test]
+    fn tampered_siv_fails_authentication() {
+        let plaintext = [0x5a; 40];
+        for algorithm in Algorithm::ALL {
+            let sealed = encrypting_siv(algorithm, &plaintext, &KEY, AAD).unwrap();
+            assert_eq!(
+                decrypting_siv(algorithm, &sealed, &KEY, AAD).unwrap(),
+                plaintext
+            );
+
+            for data in flipped(&sealed) {
+                let opened = decrypting_siv(algorithm, &data, &KEY, AAD);
+                assert!(matches!(opened, Err(aead::Error::Authentication)));
+            }
+            for len in 0..sealed.len() {
+                let opened = decrypting_siv(algorithm, &sealed[..len], &KEY, AAD);
+                let expected = if len < algorithm.block_size() {
+                    matches!(opened, Err(aead::Error::Cipher(CipherError::DataTooShort)))
+                } else {
+                    matches!(opened, Err(aead::Error::Authentication))
+                };
+                assert!(expected, "truncated to {len} bytes");
+            }
+        }
+    }
+
+    fn hex(text: &str) -> Vec<u8> {
+        crate::encoding::Encoding::Hex
+            .decode(text.as_bytes())
+            .unwrap()
+    }
+
+    /// Р 1323565.1.017-2018, пример CTR-ACPKM для "Кузнечика": секция в
+    /// два блока, счётчик `nonce || 0`. Первые три блока примера — третий
+    /// блок уже шифруется ключом второй секции.
+    #[test]
+    fn acpkm_reference_example() {
+        let key = hex("8899aabbccddeeff0011223344556677fedcba98765432100123456789abcdef");
+        let iv = hex("1234567890abcef00000000000000000");
+        let plaintext = hex(concat!(
+            "1122334455667700ffeeddccbbaa9988",
+            "00112233445566778899aabbcceeff0a",
+            "112233445566778899aabbcceeff0a00",
+        ));
+        let expected = hex(concat!(
+            "f195d8bec10ed1dbd57b5fa240bda1b8",
+            "85eee733f6a13e5df33ce4b33c45dee4",
+            "4bceeb8f646f4c55001706275e85e800",
+        ));
+
+        let ciphertext = acpkm(Algorithm::Kuznyechik, &plaintext, &key, Some(&iv), 32).unwrap();
+        assert_eq!(ciphertext, expected);
+        assert_eq!(
+            acpkm(Algorithm::Kuznyechik, &ciphertext, &key, Some(&iv), 32).unwrap(),
+            plaintext
+        );
+    }
+
+    #[test]
+    fn ciphertext_len_reports_overflow() {
+        let len = |plaintext_len, mode, padding| {
+            ciphertext_len(Algorithm::Kuznyechik, padding, plaintext_len, mode)
+        };
+        let (cbc, ctr) = (
+            EncryptMode::Block(modes::Mode::Cbc),
+            EncryptMode::Block(modes::Mode::Ctr),
+        );
+        for mode in [EncryptMode::Mgm, EncryptMode::Siv, cbc] {
+            assert_eq!(len(usize::MAX, mode, modes::Padding::default()), None);
+        }
+        assert_eq!(len(usize::MAX, cbc, modes::Padding::Zero), None);
+        assert_eq!(
+            len(usize::MAX, ctr, modes::Padding::default()),
+            Some(usize::MAX)
+        );
+        assert_eq!(len(17, cbc, modes::Padding::default()), Some(32));
     }
 }
@@ -0,0 +1,62 @@
+//! Конвертное шифрование: ключи данных под мастер-ключом.
+//!
+//! Обёрнутый ключ: `GDK + шифротекст ключа (ECB, 48) + имитовставка (32)`.
+//! Из мастер-ключа выводятся независимые ключи шифрования и HMAC-Стрибог,
+//! схема encrypt-then-MAC.
+
+use block_encryption::traits::CipherError;
+use zeroize::Zeroizing;
+
+use crate::aead::Error;
+use crate::{engine, mac, modes};
+
+/// Маркер обёрнутого ключа.
+pub const MAGIC: &[u8] = b"GDK";
+
+/// Размер обёрнутого ключа в байтах.
+pub const WRAPPED_SIZE: usize = 3 + engine::KEY_SIZE + engine::BLOCK_SIZE + TAG_SIZE;
+
+const TAG_SIZE: usize = 32;
+
+/// Обёртывание ключа данных мастер-ключом.
+///
+/// - master_key — Мастер-ключ (произвольной длины)
+/// - data_key — Ключ данных
+pub fn wrap(master_key: &[u8], data_key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let (enc_key, mac_key) = split_key(master_key);
+    let mut wrapped = MAGIC.to_vec();
+    wrapped.extend(engine::encrypting(
+        data_key,
+        &enc_key,
+        modes::Mode::Ecb,
+        None,
+    )?);
+    let tag = mac::mac_data(&wrapped, &mac_key);
+    wrapped.extend(tag);
+
+    Ok(wrapped)
+}
+
+/// Проверка и развёртывание ключа данных.
+pub fn unwrap(master_key: &[u8], wrapped: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    if wrapped.len() != WRAPPED_SIZE || !wrapped.starts_with(MAGIC) {
+        return Err(Error::Cipher(CipherError::InvalidKeyFormat));
+    }
+    let (enc_key, mac_key) = split_key(master_key);
+    let (body, tag) = wrapped.split_at(wrapped.len() - TAG_SIZE);
+    if !mac::verify_data(body, &mac_key, tag) {
+        return Err(Error::Authentication);
+    }
+
+    engine::decrypting(&body[MAGIC.len()..], &enc_key, modes::Mode::Ecb, None)
+        .map(Zeroizing::new)
+        .map_err(Error::Cipher)
+}
+
+/// Независимые ключи шифрования и имитовставки из мастер-ключа.
+fn split_key(master_key: &[u8]) -> (Zeroizing<Vec<u8>>, Zeroizing<Vec<u8>>) {
+    (
+        Zeroizing::new(mac::mac_data(b"grass-wrap-enc", master_key)),
+        Zeroizing::new(mac::mac_data(b"grass-wrap-mac", master_key)),
+    )
+}
@@ -0,0 +1,289 @@
+//! Шифрование с имитовставкой для режимов без аутентификации.
+//!
+//! Результат: `iv + шифротекст + имитовставка`, в ECB вектора нет.
+//! Из ключа выводятся независимые ключи шифрования и имитовставки.
+//! Имитовставка — HMAC-Стрибог-256 (32 байта) или CMAC тем же шифром
+//! (ГОСТ Р 34.13-2015, в размер блока). Она рассчитывается над
+//! `длина aad (8) + aad + iv + шифротекст` и проверяется до дешифровки
+//! (encrypt-then-MAC).
+//!
+//! Порядок MAC-then-encrypt (`Order::MacThenEncrypt`) — только для
+//! совместимости с протоколами, которые его требуют. Результат:
+//! `iv + шифротекст (открытый текст + имитовставка)`, имитовставка
+//! рассчитывается над `длина aad (8) + aad + открытый текст`. Ошибки
+//! дешифровки неотличимы от неверной имитовставки.
+
+use block_encryption::traits::CipherError;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::aead::Error;
+use crate::{engine, mac, modes};
+
+/// Размер имитовставки HMAC-Стрибог в байтах.
+pub const HMAC_TAG_SIZE: usize = 32;
+
+/// Порядок шифрования и расчёта имитовставки.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    #[default]
+    EncryptThenMac,
+    /// Устаревший порядок для совместимости.
+    MacThenEncrypt,
+}
+
+impl Order {
+    /// Порядок по имени (`encrypt-then-mac`, `mac-then-encrypt`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "encrypt-then-mac" => Some(Order::EncryptThenMac),
+            "mac-then-encrypt" => Some(Order::MacThenEncrypt),
+            _ => None,
+        }
+    }
+}
+
+/// Алгоритм имитовставки.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mac {
+    #[default]
+    Hmac,
+    Cmac,
+}
+
+impl Mac {
+    /// Алгоритм по имени (`hmac`, `cmac`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hmac" => Some(Mac::Hmac),
+            "cmac" => Some(Mac::Cmac),
+            _ => None,
+        }
+    }
+
+    /// Размер имитовставки в байтах.
+    pub fn tag_size(self, algorithm: engine::Algorithm) -> usize {
+        match self {
+            Mac::Hmac => HMAC_TAG_SIZE,
+            Mac::Cmac => algorithm.block_size(),
+        }
+    }
+}
+
+/// Порядок и алгоритм имитовставки.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    pub order: Order,
+    pub mac: Mac,
+}
+
+/// Шифрование с имитовставкой.
+///
+/// - key — Ключ (произвольной длины)
+/// - mode — Блочный режим без аутентификации
+/// - iv — Вектор инициализации размером в блок шифра, обычно случайный;
+///   в ECB пустой
+/// - aad — Дополнительные данные, защищаемые имитовставкой
+pub fn seal(
+    algorithm: engine::Algorithm,
+    key: &[u8],
+    mode: modes::Mode,
+    iv: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+    options: Options,
+) -> Result<Vec<u8>, Error> {
+    let iv = check_iv(algorithm, mode, iv.len())
+        .map_err(Error::Cipher)?
+        .then_some(iv);
+    let (enc_key, mac_key) = split_key(key);
+    let tagger = Tagger {
+        algorithm,
+        mac: options.mac,
+        key: &mac_key,
+    };
+    let mut sealed = iv.unwrap_or_default().to_vec();
+
+    match options.order {
+        Order::EncryptThenMac => {
+            sealed
+                .extend(encrypt(algorithm, &enc_key, mode, iv, plaintext).map_err(Error::Cipher)?);
+            let tag = tagger.tag(aad, &sealed)?;
+            sealed.extend(tag);
+        }
+        Order::MacThenEncrypt => {
+            let mut data = Zeroizing::new(plaintext.to_vec());
+            data.extend(tagger.tag(aad, plaintext)?);
+            sealed.extend(encrypt(algorithm, &enc_key, mode, iv, &data).map_err(Error::Cipher)?);
+        }
+    }
+
+    Ok(sealed)
+}
+
+/// Проверка и дешифровка результата `seal`.
+pub fn open(
+    algorithm: engine::Algorithm,
+    key: &[u8],
+    mode: modes::Mode,
+    sealed: &[u8],
+    aad: &[u8],
+    options: Options,
+) -> Result<Vec<u8>, Error> {
+    let iv_len = if mode.uses_iv() {
+        algorithm.block_size()
+    } else {
+        0
+    };
+    let (enc_key, mac_key) = split_key(key);
+    let tagger = Tagger {
+        algorithm,
+        mac: options.mac,
+        key: &mac_key,
+    };
+    let tag_size = options.mac.tag_size(algorithm);
+
+    match options.order {
+        Order::EncryptThenMac => {
+            if sealed.len() < iv_len + tag_size {
+                return Err(Error::Authentication);
+            }
+            let (body, tag) = sealed.split_at(sealed.len() - tag_size);
+            if !tagger.verify(aad, body, tag)? {
+                return Err(Error::Authentication);
+            }
+            let (iv, ciphertext) = body.split_at(iv_len);
+            decrypt(algorithm, &enc_key, mode, iv, ciphertext).map_err(Error::Cipher)
+        }
+        Order::MacThenEncrypt => {
+            if sealed.len() < iv_len {
+                return Err(Error::Authentication);
+            }
+            let (iv, ciphertext) = sealed.split_at(iv_len);
+            let data = decrypt(algorithm, &enc_key, mode, iv, ciphertext)
+                .map(Zeroizing::new)
+                .map_err(|_| Error::Authentication)?;
+            if data.len() < tag_size {
+                return Err(Error::Authentication);
+            }
+            let (plaintext, tag) = data.split_at(data.len() - tag_size);
+            if !tagger.verify(aad, plaintext, tag)? {
+                return Err(Error::Authentication);
+            }
+            Ok(plaintext.to_vec())
+        }
+    }
+}
+
+/// Признак режима с вектором; проверка длины вектора.
+fn check_iv(
+    algorithm: engine::Algorithm,
+    mode: modes::Mode,
+    iv_len: usize,
+) -> Result<bool, CipherError> {
+    let uses_iv = mode.uses_iv();
+    if uses_iv && iv_len != algorithm.block_size() {
+        return Err(CipherError::InvalidIVLenght);
+    }
+
+    Ok(uses_iv)
+}
+
+fn encrypt(
+    algorithm: engine::Algorithm,
+    key: &[u8],
+    mode: modes::Mode,
+    iv: Option<&[u8]>,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    engine::encrypting_with(algorithm, plaintext, key, mode, iv)
+}
+
+fn decrypt(
+    algorithm: engine::Algorithm,
+    key: &[u8],
+    mode: modes::Mode,
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    let iv = (!iv.is_empty()).then_some(iv);
+    engine::decrypting_with(algorithm, ciphertext, key, mode, iv)
+}
+
+/// Расчёт имитовставки `длина aad (8) + aad + data`.
+struct Tagger<'a> {
+    algorithm: engine::Algorithm,
+    mac: Mac,
+    key: &'a [u8],
+}
+
+impl Tagger<'_> {
+    fn tag(&self, aad: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+        let parts = [&(aad.len() as u64).to_be_bytes(), aad, data];
+        match self.mac {
+            Mac::Hmac => Ok(mac::mac_parts(&parts, self.key)),
+            Mac::Cmac => {
+                let tag_size = self.mac.tag_size(self.algorithm);
+                engine::cmac(self.algorithm, self.key, &parts, tag_size).map_err(Error::Cipher)
+            }
+        }
+    }
+
+    /// Проверка имитовставки `tag` за постоянное время.
+    fn verify(&self, aad: &[u8], data: &[u8], tag: &[u8]) -> Result<bool, Error> {
+        match self.mac {
+            Mac::Hmac => {
+                let parts = [&(aad.len() as u64).to_be_bytes(), aad, data];
+                Ok(mac::verify_parts(&parts, self.key, tag))
+            }
+            Mac::Cmac => Ok(self.tag(aad, data)?.ct_eq(tag).into()),
+        }
+    }
+}
+
+/// Независимые ключи шифрования и имитовставки.
+fn split_key(key: &[u8]) -> (Zeroizing<Vec<u8>>, Zeroizing<Vec<u8>>) {
+    (
+        Zeroizing::new(mac::mac_data(b"grass-etm-enc", key)),
+        Zeroizing::new(mac::mac_data(b"grass-etm-mac", key)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [0x42; 32];
+    const AAD: &[u8] = b"record 17";
+
+    /// Любой изменённый бит и любое усечение результата `seal` вызывают
+    /// ошибку имитовставки во всех режимах, порядках и имитовставках.
+    #[test]
+    fn tampered_seal_fails_authentication() {
+        let plaintext = [0x5a; 40];
+        for algorithm in engine::Algorithm::ALL {
+            let iv = vec![0x11; algorithm.block_size()];
+            for mode in modes::Mode::ALL {
+                let iv = if mode.uses_iv() { &iv[..] } else { &[] };
+                for order in [Order::EncryptThenMac, Order::MacThenEncrypt] {
+                    for mac in [Mac::Hmac, Mac::Cmac] {
+                        let options = Options { order, mac };
+                        let sealed =
+                            seal(algorithm, &KEY, mode, iv, &plaintext, AAD, options).unwrap();
+                        let opened = |data: &[u8]| open(algorithm, &KEY, mode, data, AAD, options);
+                        assert_eq!(opened(&sealed).unwrap(), plaintext);
+
+                        for i in 0..sealed.len() {
+                            let mut data = sealed.clone();
+                            data[i] ^= 0x01;
+                            assert!(matches!(opened(&data), Err(Error::Authentication)));
+                        }
+                        for len in 0..sealed.len() {
+                            assert!(matches!(opened(&sealed[..len]), Err(Error::Authentication)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
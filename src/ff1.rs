@@ -0,0 +1,272 @@
+//! Шифрование с сохранением формата FF1 (NIST SP 800-38G) для строк цифр
+//! в системе счисления `radix` на блочном шифре с блоком 16 байт.
+//!
+//! Строка из `n` цифр делится на половины `u = n / 2` и `v = n - u` цифр,
+//! которые проходят 10 раундов Фейстеля по модулю `radix^u` и `radix^v`.
+//! Раундовая функция — CBC-MAC от параметров, tweak, номера раунда и
+//! половины строки. Шифротекст — строка цифр той же длины.
+//!
+//! Половины ограничены `radix^v < 2^120`, чтобы арифметика шла в `u128`
+//! (для десятичных цифр — до 72 цифр), а наименьшая длина по стандарту —
+//! `radix^n >= 1 000 000`.
+
+use std::ops::RangeInclusive;
+
+use aes::cipher::consts::U16;
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, BlockSizeUser};
+use block_encryption::traits::CipherError;
+
+use crate::engine::BLOCK_SIZE;
+
+type Block = [u8; BLOCK_SIZE];
+
+/// Наибольшее основание: цифры `0-9a-z`.
+pub const MAX_RADIX: u32 = 36;
+
+const ROUNDS: u8 = 10;
+
+/// Наименьшее число сообщений `radix^n` (SP 800-38G Rev. 1).
+const MIN_DOMAIN: u128 = 1_000_000;
+
+/// Граница числа половины строки: `radix^v * 256` помещается в `u128`.
+const MAX_HALF: u128 = 1 << 120;
+
+/// Допустимая длина строки для основания `radix` (от 2 до `MAX_RADIX`).
+pub fn length_range(radix: u32) -> RangeInclusive<usize> {
+    let radix = u128::from(radix);
+    let (mut min, mut domain) = (1, radix);
+    while min < 2 || domain < MIN_DOMAIN {
+        min += 1;
+        domain *= radix;
+    }
+    let (mut half, mut power) = (0, 1u128);
+    while power * radix < MAX_HALF {
+        half += 1;
+        power *= radix;
+    }
+
+    min..=2 * half
+}
+
+/// Шифрование цифр `digits` (значения от 0 до `radix - 1`).
+///
+/// - cipher — Блочный шифр с блоком 16 байт
+/// - tweak — Открытые данные, меняющие перестановку (например, префикс
+///   номера); может быть пустым
+pub fn encrypt<C>(
+    cipher: &C,
+    radix: u32,
+    tweak: &[u8],
+    digits: &[u8],
+) -> Result<Vec<u8>, CipherError>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    let ff1 = Ff1::new(cipher, radix, tweak, digits.len())?;
+    let (a, b) = digits.split_at(ff1.u);
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    for i in 0..ROUNDS {
+        let m = ff1.half(i);
+        let modulus = pow(ff1.radix, m);
+        let c = (num(ff1.radix, &a) + ff1.round(i, &b, modulus)) % modulus;
+        a = std::mem::replace(&mut b, digits_of(ff1.radix, c, m));
+    }
+
+    Ok([a, b].concat())
+}
+
+/// Дешифровка результата `encrypt` с тем же ключом, основанием и tweak.
+pub fn decrypt<C>(
+    cipher: &C,
+    radix: u32,
+    tweak: &[u8],
+    digits: &[u8],
+) -> Result<Vec<u8>, CipherError>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    let ff1 = Ff1::new(cipher, radix, tweak, digits.len())?;
+    let (a, b) = digits.split_at(ff1.u);
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    for i in (0..ROUNDS).rev() {
+        let m = ff1.half(i);
+        let modulus = pow(ff1.radix, m);
+        let c = (num(ff1.radix, &b) + modulus - ff1.round(i, &a, modulus)) % modulus;
+        b = std::mem::replace(&mut a, digits_of(ff1.radix, c, m));
+    }
+
+    Ok([a, b].concat())
+}
+
+/// Параметры раундов для строки длиной `n`.
+struct Ff1<'a, C> {
+    cipher: &'a C,
+    radix: u128,
+    tweak: &'a [u8],
+    u: usize,
+    v: usize,
+    /// Длина числа половины в байтах (`b`).
+    b: usize,
+    /// Длина выработанных данных раунда в байтах (`d`).
+    d: usize,
+    /// Первый блок CBC-MAC: `P`, обработанный шифром.
+    p: Block,
+}
+
+impl<'a, C> Ff1<'a, C>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    fn new(cipher: &'a C, radix: u32, tweak: &'a [u8], n: usize) -> Result<Self, CipherError> {
+        if !(2..=MAX_RADIX).contains(&radix) {
+            return Err(CipherError::InvalidMode);
+        }
+        let range = length_range(radix);
+        if n < *range.start() {
+            return Err(CipherError::DataTooShort);
+        }
+        if n > *range.end() {
+            return Err(CipherError::InvalidBlockSize);
+        }
+
+        let (u, v) = (n / 2, n - n / 2);
+        let bits = 128 - (pow(u128::from(radix), v) - 1).leading_zeros() as usize;
+        let b = bits.div_ceil(8);
+        let mut p = [0u8; BLOCK_SIZE];
+        p[..3].copy_from_slice(&[1, 2, 1]);
+        p[3..6].copy_from_slice(&radix.to_be_bytes()[1..]);
+        p[6] = ROUNDS;
+        p[7] = u as u8;
+        p[8..12].copy_from_slice(&(n as u32).to_be_bytes());
+        p[12..].copy_from_slice(&(tweak.len() as u32).to_be_bytes());
+        encrypt_block(cipher, &mut p);
+
+        Ok(Ff1 {
+            cipher,
+            radix: u128::from(radix),
+            tweak,
+            u,
+            v,
+            b,
+            d: 4 * b.div_ceil(4) + 4,
+            p,
+        })
+    }
+
+    /// Длина половины, получаемой в раунде `i`.
+    fn half(&self, i: u8) -> usize {
+        if i.is_multiple_of(2) {
+            self.u
+        } else {
+            self.v
+        }
+    }
+
+    /// Раундовая функция `y mod modulus` от половины `half`.
+    fn round(&self, i: u8, half: &[u8], modulus: u128) -> u128 {
+        // Q = T || 0^((-t-b-1) mod 16) || [i] || [NUM(half)]^b
+        let pad = (BLOCK_SIZE - (self.tweak.len() + self.b + 1) % BLOCK_SIZE) % BLOCK_SIZE;
+        let mut q = self.tweak.to_vec();
+        q.resize(q.len() + pad, 0);
+        q.push(i);
+        q.extend_from_slice(&num(self.radix, half).to_be_bytes()[BLOCK_SIZE - self.b..]);
+
+        let mut r = self.p;
+        for block in q.chunks_exact(BLOCK_SIZE) {
+            r.iter_mut().zip(block).for_each(|(r, x)| *r ^= x);
+            encrypt_block(self.cipher, &mut r);
+        }
+
+        // S = R || E(R ^ [1]) || E(R ^ [2]) || ..., первые d байт.
+        let mut s = r.to_vec();
+        for j in 1..self.d.div_ceil(BLOCK_SIZE) as u128 {
+            let mut block = r;
+            block
+                .iter_mut()
+                .zip(j.to_be_bytes())
+                .for_each(|(r, x)| *r ^= x);
+            encrypt_block(self.cipher, &mut block);
+            s.extend_from_slice(&block);
+        }
+
+        s[..self.d]
+            .iter()
+            .fold(0, |y, &byte| (y * 256 + u128::from(byte)) % modulus)
+    }
+}
+
+fn encrypt_block<C: BlockEncrypt + BlockSizeUser<BlockSize = U16>>(cipher: &C, block: &mut Block) {
+    cipher.encrypt_block(GenericArray::from_mut_slice(block));
+}
+
+fn pow(radix: u128, m: usize) -> u128 {
+    (0..m).fold(1, |power, _| power * radix)
+}
+
+/// Число по цифрам (старшая первой).
+fn num(radix: u128, digits: &[u8]) -> u128 {
+    digits
+        .iter()
+        .fold(0, |x, &digit| x * radix + u128::from(digit))
+}
+
+/// `m` цифр числа `x` (старшая первой).
+fn digits_of(radix: u128, mut x: u128, m: usize) -> Vec<u8> {
+    let mut digits = vec![0u8; m];
+    for digit in digits.iter_mut().rev() {
+        *digit = (x % radix) as u8;
+        x /= radix;
+    }
+
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::KeyInit;
+    use aes::Aes256;
+
+    use crate::encoding::Encoding;
+
+    fn hex(text: &str) -> Vec<u8> {
+        Encoding::Hex.decode(text.as_bytes()).unwrap()
+    }
+
+    /// Цифры строки `0-9a-z`.
+    fn digits(text: &str) -> Vec<u8> {
+        text.chars()
+            .map(|c| c.to_digit(36).unwrap() as u8)
+            .collect()
+    }
+
+    /// NIST SP 800-38G, примеры FF1-AES256 (Sample 7-9).
+    #[test]
+    fn nist_samples() {
+        let cipher = Aes256::new_from_slice(&hex(
+            "2b7e151628aed2a6abf7158809cf4f3cef4359d8d580aa4f7f036d6f04fc6a94",
+        ))
+        .unwrap();
+        let samples = [
+            (10, "", "0123456789", "6657667009"),
+            (10, "39383736353433323130", "0123456789", "1001623463"),
+            (
+                36,
+                "3737373770717273373737",
+                "0123456789abcdefghi",
+                "xs8a0azh2avyalyzuwd",
+            ),
+        ];
+
+        for (radix, tweak, plaintext, ciphertext) in samples {
+            let (tweak, plaintext) = (hex(tweak), digits(plaintext));
+            let encrypted = encrypt(&cipher, radix, &tweak, &plaintext).unwrap();
+            assert_eq!(encrypted, digits(ciphertext));
+            assert_eq!(
+                decrypt(&cipher, radix, &tweak, &encrypted).unwrap(),
+                plaintext
+            );
+        }
+    }
+}
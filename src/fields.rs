@@ -0,0 +1,203 @@
+//! Шифрование отдельных полей записи (`dict`) для интеграции с ORM.
+//!
+//! Значения полей — строки в UTF-8, шифротексты — строки Base64 (см.
+//! `encoding`). Все поля записи шифруются за один вызов, как в
+//! `do_encrypt_batch`. В MGM и SIV имя поля входит в имитовставку как
+//! дополнительные данные, поэтому шифротекст, перенесённый в другое поле,
+//! не дешифруется. SIV детерминирован: равные значения поля дают равные
+//! шифротексты для поиска по индексу. В остальных режимах вектор для
+//! каждого поля случайный и записывается в заголовок шифротекста.
+
+use block_encryption::traits::CipherError;
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString};
+use zeroize::Zeroizing;
+
+use crate::encoding::Encoding;
+use crate::engine::{self, Algorithm, EncryptMode};
+use crate::{
+    aead_to_py_err, allow_threads_for, batch, decrypt_item, decrypt_mgm, encrypt_item, encrypt_mgm,
+    extract_buffer_key, mgm, rust_to_py_err,
+};
+
+/// Шифрование полей `fields` записи.
+///
+/// Возвращает новую запись, в которой значения этих полей заменены
+/// шифротекстами Base64; остальные поля копируются без изменений. `None`
+/// сохраняется как есть, отсутствующее поле вызывает `KeyError`.
+///
+/// - record — Запись
+/// - key — Ключ для шифрования
+/// - fields — Имена шифруемых полей; значения — строки
+/// - encrypt_mode — Режим: `MGM` (по умолчанию), `SIV` (для поиска по
+///   значению) или режим с вектором инициализации (CBC, CFB, OFB, CTR)
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+///   (кроме MGM)
+#[pyfunction]
+#[pyo3(name = "encrypt_fields")]
+#[pyo3(signature = (record, key, fields, *, encrypt_mode=EncryptMode::Mgm, cipher=Algorithm::Kuznyechik))]
+pub fn encrypt_fields<'py>(
+    record: &Bound<'py, PyDict>,
+    key: Bound<'py, PyAny>,
+    fields: Vec<String>,
+    encrypt_mode: EncryptMode,
+    cipher: Algorithm,
+) -> PyResult<Bound<'py, PyDict>> {
+    let py = record.py();
+    let algorithm = cipher;
+    check_mode(encrypt_mode)?;
+    let k = extract_buffer_key(&key)?;
+    let mut items = Vec::with_capacity(fields.len());
+    for name in fields {
+        if let Some(value) = field(record, &name)? {
+            let value = value
+                .downcast::<PyString>()
+                .map_err(|_| PyTypeError::new_err(format!("field '{name}' must be str or None")))?;
+            let value = Zeroizing::new(value.to_str()?.as_bytes().to_vec());
+            items.push((name, value));
+        }
+    }
+
+    let size = items.iter().map(|(_, value)| value.len()).sum();
+    let results = allow_threads_for(py, size, || {
+        batch::parallel_map_in(
+            &items,
+            engine::parallel_threads(size, None),
+            |(name, pt)| seal(algorithm, &k, encrypt_mode, name, pt),
+        )
+    });
+
+    let out = record.copy()?;
+    for ((name, _), ct) in items.iter().zip(results) {
+        out.set_item(name, Encoding::Base64.encode(&ct?))?;
+    }
+
+    Ok(out)
+}
+
+/// Дешифровка полей записи из `encrypt_fields` с теми же аргументами.
+///
+/// - record — Запись с шифротекстами Base64
+/// - key — Ключ для дешифровки
+/// - fields — Имена зашифрованных полей
+/// - encrypt_mode — Режим шифрования (см. `encrypt_fields`)
+/// - cipher — Шифр (см. `encrypt_fields`)
+#[pyfunction]
+#[pyo3(name = "decrypt_fields")]
+#[pyo3(signature = (record, key, fields, *, encrypt_mode=EncryptMode::Mgm, cipher=Algorithm::Kuznyechik))]
+pub fn decrypt_fields<'py>(
+    record: &Bound<'py, PyDict>,
+    key: Bound<'py, PyAny>,
+    fields: Vec<String>,
+    encrypt_mode: EncryptMode,
+    cipher: Algorithm,
+) -> PyResult<Bound<'py, PyDict>> {
+    let py = record.py();
+    let algorithm = cipher;
+    check_mode(encrypt_mode)?;
+    let k = extract_buffer_key(&key)?;
+    let mut items = Vec::with_capacity(fields.len());
+    for name in fields {
+        if let Some(value) = field(record, &name)? {
+            let ct = value
+                .downcast::<PyString>()
+                .ok()
+                .and_then(|value| Encoding::Base64.decode(value.to_str().ok()?.as_bytes()))
+                .ok_or_else(|| {
+                    PyValueError::new_err(format!("field '{name}' is not a base64 ciphertext"))
+                })?;
+            items.push((name, ct));
+        }
+    }
+
+    let size = items.iter().map(|(_, ct)| ct.len()).sum();
+    let results = allow_threads_for(py, size, || {
+        batch::parallel_map_in(
+            &items,
+            engine::parallel_threads(size, None),
+            |(name, ct)| open(algorithm, &k, encrypt_mode, name, ct),
+        )
+    });
+
+    let out = record.copy()?;
+    for ((name, _), pt) in items.iter().zip(results) {
+        let pt = pt?;
+        let value = std::str::from_utf8(&pt)
+            .map_err(|_| PyValueError::new_err(format!("field '{name}' is not valid UTF-8")))?;
+        out.set_item(name, value)?;
+    }
+
+    Ok(out)
+}
+
+/// Режим полей: MGM, SIV или режим со случайным вектором.
+fn check_mode(encrypt_mode: EncryptMode) -> PyResult<()> {
+    match encrypt_mode {
+        EncryptMode::Mgm | EncryptMode::Siv => Ok(()),
+        EncryptMode::Block(mode) if mode.uses_iv() => Ok(()),
+        EncryptMode::Block(_) => Err(PyValueError::new_err(
+            "fields require MGM, SIV or a mode with an IV",
+        )),
+        EncryptMode::CtrAcpkm => rust_to_py_err(Err(CipherError::InvalidMode)),
+    }
+}
+
+/// Значение поля; `None` — значение `None`.
+fn field<'py>(record: &Bound<'py, PyDict>, name: &str) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let value = record
+        .get_item(name)?
+        .ok_or_else(|| PyKeyError::new_err(name.to_owned()))?;
+
+    Ok((!value.is_none()).then_some(value))
+}
+
+fn seal(
+    algorithm: Algorithm,
+    key: &[u8],
+    encrypt_mode: EncryptMode,
+    name: &str,
+    pt: &[u8],
+) -> PyResult<Vec<u8>> {
+    match encrypt_mode {
+        EncryptMode::Mgm => encrypt_mgm(algorithm, pt, key, None, name.as_bytes(), mgm::TAG_SIZE),
+        EncryptMode::Siv => {
+            rust_to_py_err(engine::encrypting_siv(algorithm, pt, key, name.as_bytes()))
+        }
+        _ => encrypt_item(
+            algorithm,
+            key,
+            encrypt_mode,
+            None,
+            true,
+            engine::ACPKM_SECTION_SIZE,
+            pt,
+        ),
+    }
+}
+
+fn open(
+    algorithm: Algorithm,
+    key: &[u8],
+    encrypt_mode: EncryptMode,
+    name: &str,
+    ct: &[u8],
+) -> PyResult<Zeroizing<Vec<u8>>> {
+    match encrypt_mode {
+        EncryptMode::Mgm => decrypt_mgm(algorithm, ct, key, None, name.as_bytes(), mgm::TAG_SIZE)
+            .map(Zeroizing::new),
+        EncryptMode::Siv => {
+            aead_to_py_err(engine::decrypting_siv(algorithm, ct, key, name.as_bytes()))
+                .map(Zeroizing::new)
+        }
+        _ => decrypt_item(
+            algorithm,
+            key,
+            encrypt_mode,
+            None,
+            true,
+            engine::ACPKM_SECTION_SIZE,
+            ct,
+        ),
+    }
+}
@@ -0,0 +1,248 @@
+//! Шифрование и дешифровка файлов на стороне Rust.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::thread;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::PyResult;
+
+use crate::mac::{CHECK_INTERVAL, READ_CHUNK};
+use crate::{decrypt_to_py_err, engine};
+
+/// Пошаговое преобразование данных файла.
+pub trait Transform {
+    /// Обработка очередной части; возвращает готовую часть результата.
+    fn update(&mut self, data: &[u8]) -> Vec<u8>;
+
+    /// Завершение: оставшаяся часть результата.
+    fn finalize(self) -> PyResult<Vec<u8>>;
+}
+
+impl Transform for engine::Streaming {
+    fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        engine::Streaming::update(self, data)
+    }
+
+    fn finalize(self) -> PyResult<Vec<u8>> {
+        // Завершение шифрования ошибок не возвращает.
+        decrypt_to_py_err(engine::Streaming::finalize(self))
+    }
+}
+
+/// Потоковая обработка файла `src` в файл `dst`.
+///
+/// Файл читается частями по `READ_CHUNK` байт и проходит через `stream`,
+/// поэтому в памяти находится несколько частей независимо от размера
+/// файла. Чтение и запись идут параллельно с шифрованием. Каждые
+/// `CHECK_INTERVAL` частей вызывается `check` с числом прочитанных байт.
+/// При любой ошибке (в том числе прерывании) неполный файл `dst`
+/// удаляется.
+///
+/// - src — Исходный файл
+/// - dst — Файл результата
+/// - stream — Подготовленный шифровальщик
+/// - overwrite — Перезаписать существующий `dst`
+/// - check — Проверка прерывания операции
+///
+/// Возвращает размер записанного файла.
+pub fn transform_file<T, F>(
+    src: &Path,
+    dst: &Path,
+    stream: T,
+    overwrite: bool,
+    check: F,
+) -> PyResult<u64>
+where
+    T: Transform,
+    F: FnMut(u64) -> PyResult<()>,
+{
+    let mut input = File::open(src)?;
+    if dst.exists() && fs::canonicalize(src)? == fs::canonicalize(dst)? {
+        return Err(PyValueError::new_err(
+            "src_path and dst_path are the same file",
+        ));
+    }
+    let mut output = OpenOptions::new()
+        .write(true)
+        .create(overwrite)
+        .create_new(!overwrite)
+        .truncate(overwrite)
+        .open(dst)?;
+
+    let result = copy(&mut input, &mut output, stream, check);
+    if result.is_err() {
+        drop(output);
+        let _ = fs::remove_file(dst);
+    }
+
+    result
+}
+
+/// Число буферов чтения в обработке одновременно: пока шифруется одна
+/// часть, следующая уже читается.
+const BUFFERS: usize = 2;
+
+/// Конвейер чтения, шифрования и записи.
+///
+/// Чтение и запись выполняются в отдельных потоках с двумя буферами, что
+/// скрывает задержки сетевых файловых систем. `stream` и `check` работают
+/// в текущем потоке.
+fn copy<T, F>(input: &mut File, output: &mut File, stream: T, check: F) -> PyResult<u64>
+where
+    T: Transform,
+    F: FnMut(u64) -> PyResult<()>,
+{
+    thread::scope(|scope| {
+        let (full_tx, full_rx) = mpsc::sync_channel::<io::Result<(Vec<u8>, usize)>>(1);
+        let (free_tx, free_rx) = mpsc::channel::<Vec<u8>>();
+        for _ in 0..BUFFERS {
+            let _ = free_tx.send(vec![0u8; READ_CHUNK]);
+        }
+        scope.spawn(move || {
+            // Канал закрывается, если обработка остановлена: поток
+            // завершается, не дочитывая файл.
+            while let Ok(mut buf) = free_rx.recv() {
+                let read = read_chunk(input, &mut buf);
+                let done = !matches!(read, Ok(n) if n > 0);
+                if full_tx.send(read.map(|n| (buf, n))).is_err() || done {
+                    break;
+                }
+            }
+        });
+
+        let (out_tx, out_rx) = mpsc::sync_channel::<Vec<u8>>(1);
+        let writer = scope.spawn(move || -> io::Result<()> {
+            for out in out_rx {
+                output.write_all(&out)?;
+            }
+            output.flush()
+        });
+
+        let result = pump(&full_rx, &free_tx, &out_tx, stream, check);
+        drop((full_rx, free_tx, out_tx));
+        // Ошибка записи важнее: из-за неё обработка и остановилась.
+        writer.join().expect("writer thread panicked")?;
+
+        result
+    })
+}
+
+/// Шифрование прочитанных частей с передачей результата на запись.
+///
+/// Если поток записи завершился с ошибкой, обработка останавливается, а
+/// ошибку возвращает `copy`.
+fn pump<T, F>(
+    full: &Receiver<io::Result<(Vec<u8>, usize)>>,
+    free: &Sender<Vec<u8>>,
+    out: &SyncSender<Vec<u8>>,
+    mut stream: T,
+    mut check: F,
+) -> PyResult<u64>
+where
+    T: Transform,
+    F: FnMut(u64) -> PyResult<()>,
+{
+    let mut chunks = 0usize;
+    let (mut read, mut written) = (0u64, 0u64);
+
+    loop {
+        let (buf, n) = full.recv().expect("reader thread stopped")?;
+        if n == 0 {
+            break;
+        }
+        let data = stream.update(&buf[..n]);
+        let _ = free.send(buf);
+        read += n as u64;
+        written += data.len() as u64;
+        if out.send(data).is_err() {
+            return Ok(written);
+        }
+        chunks += 1;
+        if chunks.is_multiple_of(CHECK_INTERVAL) {
+            check(read)?;
+        }
+    }
+
+    let data = stream.finalize()?;
+    written += data.len() as u64;
+    let _ = out.send(data);
+
+    Ok(written)
+}
+
+/// Чтение очередной части с повтором при `Interrupted`; `0` — конец файла.
+fn read_chunk(input: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match input.read(buf) {
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Инверсия байтов с ошибкой на завершении по требованию.
+    struct Invert {
+        fail: bool,
+    }
+
+    impl Transform for Invert {
+        fn update(&mut self, data: &[u8]) -> Vec<u8> {
+            data.iter().map(|b| !b).collect()
+        }
+
+        fn finalize(self) -> PyResult<Vec<u8>> {
+            match self.fail {
+                true => Err(PyValueError::new_err("finalize failed")),
+                false => Ok(b"end".to_vec()),
+            }
+        }
+    }
+
+    fn paths(name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("cryptor-files-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        (dir.join("src"), dir.join("dst"))
+    }
+
+    #[test]
+    fn pipeline_preserves_order_across_chunks() {
+        let (src, dst) = paths("order");
+        let data: Vec<u8> = (0..READ_CHUNK * 5 + 123).map(|i| (i % 251) as u8).collect();
+        fs::write(&src, &data).unwrap();
+
+        let written = transform_file(&src, &dst, Invert { fail: false }, true, |_| Ok(())).unwrap();
+
+        let mut expected: Vec<u8> = data.iter().map(|b| !b).collect();
+        expected.extend_from_slice(b"end");
+        assert_eq!(written, expected.len() as u64);
+        assert_eq!(fs::read(&dst).unwrap(), expected);
+        fs::remove_dir_all(src.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn failure_stops_pipeline_and_removes_output() {
+        let (src, dst) = paths("fail");
+        fs::write(&src, vec![7u8; READ_CHUNK * CHECK_INTERVAL * 3]).unwrap();
+
+        let result = transform_file(&src, &dst, Invert { fail: true }, true, |_| Ok(()));
+        assert!(result.is_err());
+        assert!(!dst.exists());
+
+        let mut checks = 0;
+        let interrupted = transform_file(&src, &dst, Invert { fail: false }, true, |_| {
+            checks += 1;
+            Err(PyValueError::new_err("interrupted"))
+        });
+        assert!(interrupted.is_err());
+        assert_eq!(checks, 1);
+        assert!(!dst.exists());
+        fs::remove_dir_all(src.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+//! Хеш-функция "Стрибог" (ГОСТ Р 34.11-2012) с интерфейсом hashlib и
+//! HMAC-Стрибог с интерфейсом hmac.
+
+use hmac::{Hmac, Mac};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use streebog::{Digest, Streebog256, Streebog512};
+
+use crate::extract_key;
+
+/// Размер блока "Стрибога" в байтах.
+const BLOCK_SIZE: usize = 64;
+
+/// Хеш "Стрибог" с длиной 256 бит.
+///
+/// - data — Данные
+#[pyfunction]
+#[pyo3(name = "streebog256")]
+pub fn streebog256(data: &[u8]) -> Vec<u8> {
+    Streebog256::digest(data).to_vec()
+}
+
+/// Хеш "Стрибог" с длиной 512 бит.
+///
+/// - data — Данные
+#[pyfunction]
+#[pyo3(name = "streebog512")]
+pub fn streebog512(data: &[u8]) -> Vec<u8> {
+    Streebog512::digest(data).to_vec()
+}
+
+#[derive(Clone)]
+enum State {
+    S256(Streebog256),
+    S512(Streebog512),
+}
+
+/// Инкрементальный хеш "Стрибог", совместимый с объектами hashlib.
+///
+/// ```python
+/// h = Streebog(digest_size=32)
+/// h.update(b'part 1')
+/// h.update(b'part 2')
+/// h.hexdigest()
+/// ```
+#[pyclass(name = "Streebog")]
+#[derive(Clone)]
+pub struct Hasher {
+    state: State,
+}
+
+#[pymethods]
+impl Hasher {
+    /// - data — Начальные данные
+    /// - digest_size — Размер хеша в байтах: 32 или 64
+    #[new]
+    #[pyo3(signature = (data=None, *, digest_size=64))]
+    fn new(data: Option<&[u8]>, digest_size: usize) -> PyResult<Self> {
+        let state = match digest_size {
+            32 => State::S256(Streebog256::new()),
+            64 => State::S512(Streebog512::new()),
+            _ => return Err(PyValueError::new_err("digest_size must be 32 or 64")),
+        };
+
+        let mut hasher = Hasher { state };
+        if let Some(data) = data {
+            hasher.update(data);
+        }
+
+        Ok(hasher)
+    }
+
+    /// Имя алгоритма в стиле hashlib.
+    #[getter]
+    fn name(&self) -> &'static str {
+        match self.state {
+            State::S256(_) => "streebog256",
+            State::S512(_) => "streebog512",
+        }
+    }
+
+    #[getter]
+    fn digest_size(&self) -> usize {
+        match self.state {
+            State::S256(_) => 32,
+            State::S512(_) => 64,
+        }
+    }
+
+    #[getter]
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    /// Добавление данных.
+    fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            State::S256(h) => h.update(data),
+            State::S512(h) => h.update(data),
+        }
+    }
+
+    /// Хеш данных, добавленных к этому моменту.
+    fn digest(&self) -> Vec<u8> {
+        match &self.state {
+            State::S256(h) => h.clone().finalize().to_vec(),
+            State::S512(h) => h.clone().finalize().to_vec(),
+        }
+    }
+
+    /// Хеш в шестнадцатеричном виде.
+    fn hexdigest(&self) -> String {
+        self.digest().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Копия текущего состояния.
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[derive(Clone)]
+enum MacState {
+    S256(Hmac<Streebog256>),
+    S512(Hmac<Streebog512>),
+}
+
+impl MacState {
+    /// HMAC по размеру имитовставки в битах (256 или 512).
+    fn new(key: &[u8], digest_size: usize) -> PyResult<Self> {
+        Ok(match digest_size {
+            256 => MacState::S256(Hmac::new_from_slice(key).expect("HMAC accepts any key")),
+            512 => MacState::S512(Hmac::new_from_slice(key).expect("HMAC accepts any key")),
+            _ => return Err(PyValueError::new_err("digest_size must be 256 or 512")),
+        })
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            MacState::S256(mac) => mac.update(data),
+            MacState::S512(mac) => mac.update(data),
+        }
+    }
+
+    fn tag(&self) -> Vec<u8> {
+        match self {
+            MacState::S256(mac) => mac.clone().finalize().into_bytes().to_vec(),
+            MacState::S512(mac) => mac.clone().finalize().into_bytes().to_vec(),
+        }
+    }
+
+    /// Сравнение с имитовставкой за постоянное время.
+    fn verify(&self, tag: &[u8]) -> bool {
+        match self {
+            MacState::S256(mac) => mac.clone().verify_slice(tag).is_ok(),
+            MacState::S512(mac) => mac.clone().verify_slice(tag).is_ok(),
+        }
+    }
+}
+
+/// Имитовставка HMAC-Стрибог для данных.
+///
+/// - key — Ключ имитовставки
+/// - data — Данные
+/// - digest_size — Длина имитовставки в битах: 256 или 512
+#[pyfunction]
+#[pyo3(name = "hmac_streebog")]
+#[pyo3(signature = (key, data, digest_size=256))]
+pub fn hmac_streebog(
+    key: Bound<'_, PyBytes>,
+    data: &[u8],
+    digest_size: usize,
+) -> PyResult<Vec<u8>> {
+    let mut mac = MacState::new(&extract_key(&key)?, digest_size)?;
+    mac.update(data);
+
+    Ok(mac.tag())
+}
+
+/// Проверка имитовставки HMAC-Стрибог за постоянное время.
+///
+/// - key — Ключ имитовставки
+/// - data — Данные
+/// - tag — Проверяемая имитовставка
+/// - digest_size — Длина имитовставки в битах: 256 или 512
+#[pyfunction]
+#[pyo3(name = "hmac_streebog_verify")]
+#[pyo3(signature = (key, data, tag, digest_size=256))]
+pub fn hmac_streebog_verify(
+    key: Bound<'_, PyBytes>,
+    data: &[u8],
+    tag: &[u8],
+    digest_size: usize,
+) -> PyResult<bool> {
+    let mut mac = MacState::new(&extract_key(&key)?, digest_size)?;
+    mac.update(data);
+
+    Ok(mac.verify(tag))
+}
+
+/// Инкрементальная имитовставка HMAC-Стрибог, совместимая с объектами hmac.
+///
+/// ```python
+/// m = Hmac(key)
+/// m.update(b'part 1')
+/// m.update(b'part 2')
+/// m.verify(tag)
+/// ```
+#[pyclass(name = "Hmac")]
+#[derive(Clone)]
+pub struct MacHasher {
+    state: MacState,
+}
+
+#[pymethods]
+impl MacHasher {
+    /// - key — Ключ имитовставки
+    /// - data — Начальные данные
+    /// - digest_size — Длина имитовставки в битах: 256 или 512
+    #[new]
+    #[pyo3(signature = (key, data=None, *, digest_size=256))]
+    fn new(key: Bound<'_, PyBytes>, data: Option<&[u8]>, digest_size: usize) -> PyResult<Self> {
+        let mut state = MacState::new(&extract_key(&key)?, digest_size)?;
+        if let Some(data) = data {
+            state.update(data);
+        }
+
+        Ok(MacHasher { state })
+    }
+
+    /// Имя алгоритма в стиле hmac.
+    #[getter]
+    fn name(&self) -> &'static str {
+        match self.state {
+            MacState::S256(_) => "hmac-streebog256",
+            MacState::S512(_) => "hmac-streebog512",
+        }
+    }
+
+    /// Длина имитовставки в байтах (как в hmac).
+    #[getter]
+    fn digest_size(&self) -> usize {
+        match self.state {
+            MacState::S256(_) => 32,
+            MacState::S512(_) => 64,
+        }
+    }
+
+    #[getter]
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    /// Добавление данных.
+    fn update(&mut self, data: &[u8]) {
+        self.state.update(data);
+    }
+
+    /// Имитовставка данных, добавленных к этому моменту.
+    fn digest(&self) -> Vec<u8> {
+        self.state.tag()
+    }
+
+    /// Имитовставка в шестнадцатеричном виде.
+    fn hexdigest(&self) -> String {
+        self.digest().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Проверка имитовставки за постоянное время.
+    fn verify(&self, tag: &[u8]) -> bool {
+        self.state.verify(tag)
+    }
+
+    /// Копия текущего состояния.
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+}
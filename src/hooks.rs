@@ -0,0 +1,64 @@
+//! Хуки для внешнего мониторинга (APM) вызовов шифратора.
+//!
+//! Хук вызывается после каждой операции с аргументами
+//! `(operation, bytes, nanoseconds)`. Пока хук не зарегистрирован, время
+//! не замеряется: проверяется только атомарный флаг.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+
+static HOOK_SET: AtomicBool = AtomicBool::new(false);
+static TIMING_HOOK: Mutex<Option<Py<PyAny>>> = Mutex::new(None);
+
+/// Регистрация (или сброс при `None`) хука замера времени.
+///
+/// - hook — Вызываемый объект `hook(operation: str, bytes: int, ns: int)`
+#[pyfunction]
+#[pyo3(name = "set_timing_hook")]
+#[pyo3(signature = (hook))]
+pub fn set_timing_hook(py: Python<'_>, hook: Option<PyObject>) -> PyResult<()> {
+    if let Some(h) = &hook {
+        if !h.bind(py).is_callable() {
+            return Err(PyTypeError::new_err("'hook' must be callable or None"));
+        }
+    }
+
+    let mut slot = TIMING_HOOK.lock().unwrap();
+    HOOK_SET.store(hook.is_some(), Ordering::Release);
+    *slot = hook;
+
+    Ok(())
+}
+
+/// Начало замера: возвращает отметку времени, только если хук задан.
+pub fn start() -> Option<Instant> {
+    if HOOK_SET.load(Ordering::Acquire) {
+        Some(Instant::now())
+    } else {
+        None
+    }
+}
+
+/// Окончание замера и передача результата в хук.
+///
+/// Исключения хука не прерывают операцию шифрования, а передаются
+/// в `sys.unraisablehook`.
+pub fn finish(py: Python<'_>, operation: &str, bytes: usize, started: Option<Instant>) {
+    let Some(started) = started else {
+        return;
+    };
+    let nanos = started.elapsed().as_nanos() as u64;
+
+    let hook = match TIMING_HOOK.lock().unwrap().as_ref() {
+        Some(h) => h.clone_ref(py),
+        None => return,
+    };
+    let hook = hook.bind(py);
+    if let Err(e) = hook.call1((operation, bytes, nanos)) {
+        e.write_unraisable(py, Some(hook));
+    }
+}
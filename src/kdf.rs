@@ -0,0 +1,67 @@
+//! Выработка ключей из паролей и других ключей.
+//!
+//! - PBKDF2 с HMAC-Стрибог-512 (Р 50.1.111-2016) — для паролей;
+//! - KDF_GOSTR3411_2012_256 (Р 50.1.113-2016) — для ключей с высокой
+//!   энтропией, без перебора.
+//!
+//! Результат — ключ длиной `engine::KEY_SIZE`.
+
+use hmac::{Hmac, Mac};
+use streebog::Streebog512;
+use zeroize::Zeroizing;
+
+use crate::{engine, mac};
+
+type HmacStreebog512 = Hmac<Streebog512>;
+
+/// Функция выработки ключа.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kdf {
+    Pbkdf2,
+    GostR3411,
+}
+
+impl Kdf {
+    /// Функция по имени (`pbkdf2`, `kdf_gostr3411`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pbkdf2" => Some(Kdf::Pbkdf2),
+            "kdf_gostr3411" => Some(Kdf::GostR3411),
+            _ => None,
+        }
+    }
+}
+
+/// PBKDF2-HMAC-Стрибог-512: первые `KEY_SIZE` байт блока `T_1`.
+///
+/// - password — Пароль
+/// - salt — Соль
+/// - iterations — Число итераций (от 1)
+pub fn pbkdf2(password: &[u8], salt: &[u8], iterations: u32) -> Zeroizing<Vec<u8>> {
+    let prf = HmacStreebog512::new_from_slice(password).expect("HMAC accepts keys of any length");
+
+    let mut u = {
+        let mut mac = prf.clone();
+        mac.update(salt);
+        mac.update(&1u32.to_be_bytes());
+        Zeroizing::new(mac.finalize().into_bytes().to_vec())
+    };
+    let mut t = u.clone();
+    for _ in 1..iterations {
+        let mut mac = prf.clone();
+        mac.update(&u);
+        u = Zeroizing::new(mac.finalize().into_bytes().to_vec());
+        t.iter_mut().zip(u.iter()).for_each(|(t, u)| *t ^= u);
+    }
+    t.truncate(engine::KEY_SIZE);
+
+    t
+}
+
+/// KDF_GOSTR3411_2012_256:
+/// `HMAC-256(key, 0x01 || label || 0x00 || seed || 0x01 || 0x00)`.
+pub fn kdf_gostr3411(key: &[u8], label: &[u8], seed: &[u8]) -> Zeroizing<Vec<u8>> {
+    let data = [&[0x01][..], label, &[0x00], seed, &[0x01, 0x00]].concat();
+
+    Zeroizing::new(mac::mac_data(&data, key))
+}
@@ -0,0 +1,80 @@
+//! Экспорт и импорт ключей KExp15/KImp15 (Р 1323565.1.017-2018) на
+//! "Кузнечике".
+//!
+//! `KExp15(K, IV) = CTR(K_Exp_ENC, IV, K || CMAC(K_Exp_MAC, IV || K))`:
+//! вектор — половина блока (8 байт), счётчик CTR начинается с
+//! `IV || 0^64`, имитовставка — в размер блока. Импорт проверяет
+//! имитовставку за постоянное время.
+
+use block_encryption::traits::CipherError;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::aead::Error;
+use crate::engine::{self, Algorithm, BLOCK_SIZE};
+use crate::modes;
+
+/// Размер вектора инициализации в байтах.
+pub const IV_SIZE: usize = BLOCK_SIZE / 2;
+
+/// Размер имитовставки в байтах.
+pub const TAG_SIZE: usize = BLOCK_SIZE;
+
+/// Экспорт ключа `key`: `key.len() + TAG_SIZE` байт.
+///
+/// - mac_key — Ключ имитовставки `K_Exp_MAC` (32 байта)
+/// - enc_key — Ключ шифрования `K_Exp_ENC` (32 байта)
+/// - iv — Вектор инициализации, уникальный для пары ключей
+pub fn kexp15(
+    key: &[u8],
+    mac_key: &[u8],
+    enc_key: &[u8],
+    iv: &[u8; IV_SIZE],
+) -> Result<Vec<u8>, CipherError> {
+    let tag = engine::cmac(Algorithm::Kuznyechik, mac_key, &[iv, key], TAG_SIZE)?;
+    let data = Zeroizing::new([key, &tag].concat());
+
+    engine::encrypting_with(
+        Algorithm::Kuznyechik,
+        &data,
+        enc_key,
+        modes::Mode::Ctr,
+        Some(&counter_block(iv)),
+    )
+}
+
+/// Импорт ключа, экспортированного `kexp15` с теми же ключами и вектором.
+pub fn kimp15(
+    wrapped: &[u8],
+    mac_key: &[u8],
+    enc_key: &[u8],
+    iv: &[u8; IV_SIZE],
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+    if wrapped.len() <= TAG_SIZE {
+        return Err(Error::Cipher(CipherError::DataTooShort));
+    }
+    let data = engine::decrypting_with(
+        Algorithm::Kuznyechik,
+        wrapped,
+        enc_key,
+        modes::Mode::Ctr,
+        Some(&counter_block(iv)),
+    )
+    .map(Zeroizing::new)
+    .map_err(Error::Cipher)?;
+    let (key, tag) = data.split_at(data.len() - TAG_SIZE);
+    let expected = engine::cmac(Algorithm::Kuznyechik, mac_key, &[iv, key], TAG_SIZE)
+        .map_err(Error::Cipher)?;
+    if !bool::from(expected.ct_eq(tag)) {
+        return Err(Error::Authentication);
+    }
+
+    Ok(Zeroizing::new(key.to_vec()))
+}
+
+/// Начальный блок счётчика: `IV || 0^64`.
+fn counter_block(iv: &[u8; IV_SIZE]) -> [u8; BLOCK_SIZE] {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..IV_SIZE].copy_from_slice(iv);
+    block
+}
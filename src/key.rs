@@ -0,0 +1,251 @@
+//! Подготовленный ключ для шифрования множества коротких сообщений.
+//!
+//! ```python
+//! from cryptor import CipherSession, GrassKey
+//!
+//! key = GrassKey(raw_key)
+//! ct = key.encrypt(data, 'CBC', iv=iv)
+//!
+//! with CipherSession(raw_key, 'CBC', iv=iv) as session:
+//!     ct = session.encrypt(data)
+//! ```
+//!
+//! Ключ "Кузнечика" развёртывается один раз при создании, а вызовы
+//! `encrypt`/`decrypt` не обращаются к кешу шифровальщиков. Результат
+//! совпадает с `do_encrypt`/`do_decrypt` для того же ключа, режима и
+//! вектора инициализации.
+//!
+//! Развёрнутый ключ стирается (`zeroize`) при удалении объекта, а
+//! `CipherSession` — ещё и при выходе из блока `with` (`close`).
+
+use std::sync::Mutex;
+
+use block_encryption::traits::CipherError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyTuple};
+use zeroize::Zeroizing;
+
+use crate::{
+    allow_threads_for, decrypt_to_py_err, engine, extract_input, extract_mode_and_iv, extract_text,
+    hooks, modes, params, rust_to_py_err, AlreadyFinalized,
+};
+
+/// Ключ с развёрнутым расписанием раундовых ключей.
+#[pyclass(name = "GrassKey", frozen)]
+pub struct GrassKey {
+    schedule: engine::KeySchedule,
+}
+
+#[pymethods]
+impl GrassKey {
+    /// - key — Ключ (32 байта): `bytes` или другой объект с протоколом буфера
+    #[new]
+    fn new(key: Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(GrassKey {
+            schedule: extract_schedule(&key)?,
+        })
+    }
+
+    /// Шифрование сообщения.
+    ///
+    /// - data — Открытый текст: `bytes` или другой объект с протоколом буфера
+    /// - encrypt_mode — Режим шифрования: строка или объект параметров
+    ///   (`params`), кроме MGM
+    /// - iv — Вектор инициализации (16 байт); по умолчанию встроенный
+    #[pyo3(signature = (data, encrypt_mode, iv=None))]
+    fn encrypt<'py>(
+        &self,
+        data: Bound<'py, PyAny>,
+        encrypt_mode: Bound<'py, PyAny>,
+        iv: Option<Bound<'py, PyBytes>>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let (mode, iv) = extract_mode_and_iv(&encrypt_mode, iv)?;
+        let mode = rust_to_py_err(mode.block())?;
+        let counter = params::counter(&encrypt_mode);
+        let pt = extract_text(&data)?;
+        let encryption = self
+            .schedule
+            .encryption(mode, iv.as_deref(), counter, pt.len());
+
+        write_encrypted(data.py(), rust_to_py_err(encryption)?, &pt)
+    }
+
+    /// Дешифровка сообщения.
+    ///
+    /// - data — Шифротекст: `bytes` или другой объект с протоколом буфера
+    /// - encrypt_mode — Режим шифрования (см. `encrypt`)
+    /// - iv — Вектор инициализации, использованный при шифровании; по
+    ///   умолчанию встроенный (заголовок `random_iv` не распознаётся)
+    #[pyo3(signature = (data, encrypt_mode, iv=None))]
+    fn decrypt<'py>(
+        &self,
+        data: Bound<'py, PyAny>,
+        encrypt_mode: Bound<'py, PyAny>,
+        iv: Option<Bound<'py, PyBytes>>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let (mode, iv) = extract_mode_and_iv(&encrypt_mode, iv)?;
+        let mode = rust_to_py_err(mode.block())?;
+        let counter = params::counter(&encrypt_mode);
+        let ct = extract_text(&data)?;
+        let decryption = self.schedule.decryption(mode, iv.as_deref(), counter, &ct);
+
+        write_decrypted(data.py(), decrypt_to_py_err(decryption)?, ct.len())
+    }
+}
+
+/// Сеанс шифрования одним ключом и режимом с гарантированным стиранием
+/// ключа: `with CipherSession(key, mode) as session: ...`.
+///
+/// При выходе из блока `with` или вызове `close` развёрнутый ключ
+/// стирается, после чего `encrypt`/`decrypt` вызывают `AlreadyFinalized`.
+/// Объект можно передавать между потоками: состояние защищено мьютексом.
+#[pyclass(name = "CipherSession", frozen)]
+pub struct CipherSession {
+    schedule: Mutex<Option<engine::KeySchedule>>,
+    mode: modes::Mode,
+    iv: Option<Vec<u8>>,
+    counter: modes::Counter,
+}
+
+#[pymethods]
+impl CipherSession {
+    /// - key — Ключ (32 байта): `bytes` или другой объект с протоколом буфера
+    /// - encrypt_mode — Режим шифрования: строка или объект параметров
+    ///   (`params`), кроме MGM
+    /// - iv — Вектор инициализации (16 байт); по умолчанию встроенный
+    #[new]
+    #[pyo3(signature = (key, encrypt_mode, *, iv=None))]
+    fn new(
+        key: Bound<'_, PyAny>,
+        encrypt_mode: Bound<'_, PyAny>,
+        iv: Option<Bound<'_, PyBytes>>,
+    ) -> PyResult<Self> {
+        let (mode, iv) = extract_mode_and_iv(&encrypt_mode, iv)?;
+        let mode = rust_to_py_err(mode.block())?;
+        let counter = params::counter(&encrypt_mode);
+        let schedule = extract_schedule(&key)?;
+        // Проверка режима и вектора до первого сообщения.
+        rust_to_py_err(schedule.encryption(mode, iv.as_deref(), counter, 0))?;
+
+        Ok(CipherSession {
+            schedule: Mutex::new(Some(schedule)),
+            mode,
+            iv,
+            counter,
+        })
+    }
+
+    #[getter]
+    fn mode(&self) -> &'static str {
+        self.mode.name()
+    }
+
+    /// Сеанс завершён, ключ стёрт.
+    #[getter]
+    fn closed(&self, py: Python<'_>) -> bool {
+        py.allow_threads(|| self.schedule.lock().unwrap().is_none())
+    }
+
+    /// Шифрование сообщения.
+    ///
+    /// - data — Открытый текст: `bytes` или другой объект с протоколом буфера
+    fn encrypt<'py>(&self, data: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+        let py = data.py();
+        let pt = extract_text(&data)?;
+        let encryption = self.with_schedule(py, false, |schedule| {
+            schedule.encryption(self.mode, self.iv.as_deref(), self.counter, pt.len())
+        })?;
+
+        write_encrypted(py, encryption, &pt)
+    }
+
+    /// Дешифровка сообщения.
+    ///
+    /// - data — Шифротекст: `bytes` или другой объект с протоколом буфера
+    fn decrypt<'py>(&self, data: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+        let py = data.py();
+        let ct = extract_text(&data)?;
+        let decryption = self.with_schedule(py, true, |schedule| {
+            schedule.decryption(self.mode, self.iv.as_deref(), self.counter, &ct)
+        })?;
+
+        write_decrypted(py, decryption, ct.len())
+    }
+
+    /// Завершение сеанса со стиранием ключа; повторный вызов ничего не
+    /// делает.
+    fn close(&self, py: Python<'_>) {
+        py.allow_threads(|| *self.schedule.lock().unwrap() = None);
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) {
+        self.close(py);
+    }
+}
+
+impl CipherSession {
+    /// Подготовка шифровальщика под мьютексом без GIL; `AlreadyFinalized`
+    /// после `close`.
+    ///
+    /// - decrypting — Ошибки данных преобразуются как при дешифровке
+    ///   (`decrypt_to_py_err`)
+    fn with_schedule<T, F>(&self, py: Python<'_>, decrypting: bool, f: F) -> PyResult<T>
+    where
+        F: Send + FnOnce(&engine::KeySchedule) -> Result<T, CipherError>,
+        T: Send,
+    {
+        let result = py.allow_threads(|| self.schedule.lock().unwrap().as_ref().map(f));
+        let result = result.ok_or_else(|| AlreadyFinalized::new_err(()))?;
+
+        match decrypting {
+            true => decrypt_to_py_err(result),
+            false => rust_to_py_err(result),
+        }
+    }
+}
+
+/// Развёрнутый ключ; временная копия ключа стирается.
+fn extract_schedule(key: &Bound<'_, PyAny>) -> PyResult<engine::KeySchedule> {
+    let key = Zeroizing::new(extract_input(key, 0, None)?.into_owned());
+
+    rust_to_py_err(engine::KeySchedule::new(&key))
+}
+
+/// Шифрование `pt` подготовленным шифровальщиком сразу в `bytes`.
+fn write_encrypted<'py>(
+    py: Python<'py>,
+    encryption: engine::Encryption,
+    pt: &[u8],
+) -> PyResult<Bound<'py, PyBytes>> {
+    let (started, size) = (hooks::start(), pt.len());
+    let encryption = encryption.threads(None);
+    let result = PyBytes::new_with(py, encryption.output_len(), |out| {
+        allow_threads_for(py, size, || encryption.finish(pt, out));
+        Ok(())
+    });
+    hooks::finish(py, "encrypt", size, started);
+
+    result
+}
+
+/// Дешифровка подготовленным шифровальщиком сразу в `bytes`.
+fn write_decrypted<'py>(
+    py: Python<'py>,
+    decryption: engine::Decryption<'_>,
+    size: usize,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let started = hooks::start();
+    let decryption = decryption.threads(None);
+    let result = PyBytes::new_with(py, decryption.output_len(), |out| {
+        allow_threads_for(py, size, || decryption.finish(out));
+        Ok(())
+    });
+    hooks::finish(py, "decrypt", size, started);
+
+    result
+}
@@ -0,0 +1,549 @@
+//! Профиль совместимости с системами ГОСТ 28147-89.
+//!
+//! ```python
+//! from cryptor import legacy_decrypt
+//!
+//! data = legacy_decrypt(archived, key, synchro, 'CFB', sbox='cryptopro-a')
+//! ```
+//!
+//! Старые реализации (КриптоПро, OpenSSL gost) записывают слова ключа и
+//! половины блока младшим байтом вперёд, а не старшим, как ГОСТ Р
+//! 34.12-2015, и используют узлы замены RFC 4357. Режимы повторяют
+//! регистры сдвига ГОСТ 28147-89 и ГОСТ Р 34.13-2015:
+//!
+//! - `CNT` — гаммирование: синхропосылка зашифровывается, затем к половинам
+//!   накопителя прибавляются C2 (по модулю 2^32) и C1 (по модулю 2^32 - 1);
+//! - `CFB` — гаммирование с обратной связью: регистр длиной в синхропосылку
+//!   сдвигается на `s` байт шифротекста, гамма — первые `s` байт выхода;
+//! - `OFB` — обратная связь по выходу: регистр сдвигается на блок выхода
+//!   шифра, гамма — первые `s` байт.
+//!
+//! Смена ключа КриптоПро (RFC 4357, 2.3.2) каждые 1024 байта гаммы
+//! поддерживается для `CNT` и `CFB` с 64-битными гаммой и регистром.
+
+use block_encryption::traits::CipherError;
+use magma::cipher::consts::U8;
+use magma::cipher::generic_array::GenericArray;
+use magma::cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+use magma::{
+    Gost89CryptoProA, Gost89CryptoProB, Gost89CryptoProC, Gost89CryptoProD, Gost89Test, Magma,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use zeroize::Zeroizing;
+
+use crate::{allow_threads_for, extract_key, rust_to_py_err};
+
+/// Размер блока ГОСТ 28147-89 в байтах.
+const BLOCK_SIZE: usize = 8;
+
+/// Гамма между сменами ключа КриптоПро в блоках (1024 байта).
+const MESHING_BLOCKS: usize = 1024 / BLOCK_SIZE;
+
+/// Константа смены ключа КриптоПро (RFC 4357, 2.3.2).
+const MESHING_KEY: [u8; 32] = [
+    0x69, 0x00, 0x72, 0x22, 0x64, 0xC9, 0x04, 0x23, 0x8D, 0x3A, 0xDB, 0x96, 0x46, 0xE9, 0x2A, 0xC4,
+    0x18, 0xFE, 0xAC, 0x94, 0x00, 0xED, 0x07, 0x12, 0xC0, 0x86, 0xDC, 0xC2, 0xEF, 0x4C, 0xA9, 0x2B,
+];
+
+/// Константа гаммирования для N3 (первая половина накопителя).
+const C2: u32 = 0x0101_0101;
+
+/// Константа гаммирования для N4 (вторая половина накопителя).
+const C1: u32 = 0x0101_0104;
+
+/// Узел замены.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sbox {
+    Tc26,
+    CryptoProA,
+    CryptoProB,
+    CryptoProC,
+    CryptoProD,
+    Test,
+}
+
+impl Sbox {
+    pub const ALL: [Sbox; 6] = [
+        Sbox::Tc26,
+        Sbox::CryptoProA,
+        Sbox::CryptoProB,
+        Sbox::CryptoProC,
+        Sbox::CryptoProD,
+        Sbox::Test,
+    ];
+
+    /// Узел замены по имени (`tc26`, `cryptopro-a` ... `cryptopro-d`, `test`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|sbox| sbox.name() == name)
+    }
+
+    /// Имя узла замены.
+    pub fn name(self) -> &'static str {
+        match self {
+            Sbox::Tc26 => "tc26",
+            Sbox::CryptoProA => "cryptopro-a",
+            Sbox::CryptoProB => "cryptopro-b",
+            Sbox::CryptoProC => "cryptopro-c",
+            Sbox::CryptoProD => "cryptopro-d",
+            Sbox::Test => "test",
+        }
+    }
+}
+
+/// Режим гаммирования.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Cnt,
+    Cfb,
+    Ofb,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Cnt, Mode::Cfb, Mode::Ofb];
+
+    /// Режим по имени (`CNT`, `CFB`, `OFB`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Имя режима.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Cnt => "CNT",
+            Mode::Cfb => "CFB",
+            Mode::Ofb => "OFB",
+        }
+    }
+}
+
+/// Параметры старой системы.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Profile {
+    pub sbox: Sbox,
+    pub mode: Mode,
+    /// Ширина гаммы в байтах: от 1 до `BLOCK_SIZE`.
+    pub segment: usize,
+    /// Смена ключа КриптоПро каждые 1024 байта гаммы.
+    pub key_meshing: bool,
+}
+
+impl Profile {
+    /// Проверка ширины гаммы и синхропосылки: 8 байт для `CNT` и смены
+    /// ключа, для `CFB` и `OFB` — регистр, кратный блоку.
+    fn check(&self, iv: &[u8]) -> Result<(), CipherError> {
+        if !(1..=BLOCK_SIZE).contains(&self.segment) {
+            return Err(CipherError::InvalidBlockSize);
+        }
+        let register_only = self.mode == Mode::Cnt || self.key_meshing;
+        if iv.is_empty()
+            || !iv.len().is_multiple_of(BLOCK_SIZE)
+            || register_only && iv.len() != BLOCK_SIZE
+        {
+            return Err(CipherError::InvalidIVLenght);
+        }
+
+        Ok(())
+    }
+}
+
+/// Шифрование (`CNT` и `OFB` симметричны, `CFB` — нет).
+pub fn encrypt(
+    profile: &Profile,
+    key: &[u8],
+    iv: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    with_sbox(profile, key, iv, data, false)
+}
+
+/// Дешифровка.
+pub fn decrypt(
+    profile: &Profile,
+    key: &[u8],
+    iv: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    with_sbox(profile, key, iv, data, true)
+}
+
+fn with_sbox(
+    profile: &Profile,
+    key: &[u8],
+    iv: &[u8],
+    data: &[u8],
+    decrypt: bool,
+) -> Result<Vec<u8>, CipherError> {
+    match profile.sbox {
+        Sbox::Tc26 => gamma::<Magma>(profile, key, iv, data, decrypt),
+        Sbox::CryptoProA => gamma::<Gost89CryptoProA>(profile, key, iv, data, decrypt),
+        Sbox::CryptoProB => gamma::<Gost89CryptoProB>(profile, key, iv, data, decrypt),
+        Sbox::CryptoProC => gamma::<Gost89CryptoProC>(profile, key, iv, data, decrypt),
+        Sbox::CryptoProD => gamma::<Gost89CryptoProD>(profile, key, iv, data, decrypt),
+        Sbox::Test => gamma::<Gost89Test>(profile, key, iv, data, decrypt),
+    }
+}
+
+/// Наложение гаммы по отрезкам ширины `profile.segment`.
+fn gamma<C>(
+    profile: &Profile,
+    key: &[u8],
+    iv: &[u8],
+    data: &[u8],
+    decrypt: bool,
+) -> Result<Vec<u8>, CipherError>
+where
+    C: KeyInit + BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U8>,
+{
+    profile.check(iv)?;
+    let mut cipher = Legacy::<C>::new(key)?;
+    let mut register = Zeroizing::new(iv.to_vec());
+    if profile.mode == Mode::Cnt {
+        cipher.encrypt(&mut register);
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    for (index, segment) in data.chunks(profile.segment).enumerate() {
+        if profile.key_meshing && index > 0 && index % MESHING_BLOCKS == 0 {
+            cipher.mesh(&mut register);
+        }
+        if profile.mode == Mode::Cnt {
+            step(&mut register);
+        }
+        let mut output = [0u8; BLOCK_SIZE];
+        output.copy_from_slice(&register[..BLOCK_SIZE]);
+        cipher.encrypt(&mut output);
+
+        let start = out.len();
+        out.extend(segment.iter().zip(output).map(|(byte, gamma)| byte ^ gamma));
+        match profile.mode {
+            Mode::Cnt => {}
+            Mode::Cfb => {
+                let feedback = if decrypt { segment } else { &out[start..] };
+                register.drain(..profile.segment);
+                register.extend_from_slice(feedback);
+            }
+            Mode::Ofb => {
+                register.drain(..BLOCK_SIZE);
+                register.extend_from_slice(&output);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Шаг накопителя `CNT`: N3 + C2 по модулю 2^32, N4 + C1 по модулю
+/// 2^32 - 1 (перенос прибавляется к младшему разряду).
+fn step(register: &mut [u8]) {
+    let n3 = u32::from_le_bytes(register[..4].try_into().expect("half block"));
+    let n4 = u32::from_le_bytes(register[4..].try_into().expect("half block"));
+    let (n4, carry) = n4.overflowing_add(C1);
+
+    register[..4].copy_from_slice(&n3.wrapping_add(C2).to_le_bytes());
+    register[4..].copy_from_slice(&(n4 + carry as u32).to_le_bytes());
+}
+
+/// Шифр с порядком байтов ГОСТ 28147-89.
+///
+/// RustCrypto читает слова ключа и половины блока старшим байтом вперёд
+/// (ГОСТ Р 34.12-2015), поэтому байты слов ключа и всего блока обращаются.
+struct Legacy<C>(C);
+
+impl<C> Legacy<C>
+where
+    C: KeyInit + BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U8>,
+{
+    fn new(key: &[u8]) -> Result<Self, CipherError> {
+        let mut key = Zeroizing::new(key.to_vec());
+        key.chunks_exact_mut(4).for_each(|word| word.reverse());
+
+        C::new_from_slice(&key)
+            .map(Legacy)
+            .map_err(|_| CipherError::InvalidKeyLenght)
+    }
+
+    fn encrypt(&self, block: &mut [u8]) {
+        block.reverse();
+        self.0.encrypt_block(GenericArray::from_mut_slice(block));
+        block.reverse();
+    }
+
+    fn decrypt(&self, block: &mut [u8]) {
+        block.reverse();
+        self.0.decrypt_block(GenericArray::from_mut_slice(block));
+        block.reverse();
+    }
+
+    /// Смена ключа КриптоПро: новый ключ — константа, расшифрованная
+    /// текущим, регистр зашифровывается новым ключом.
+    fn mesh(&mut self, register: &mut [u8]) {
+        let mut key = Zeroizing::new(MESHING_KEY);
+        key.chunks_exact_mut(BLOCK_SIZE)
+            .for_each(|block| self.decrypt(block));
+        *self = Self::new(&key[..]).expect("meshing key size");
+        self.encrypt(register);
+    }
+}
+
+/// Профиль из аргументов Python.
+fn extract_profile(
+    mode: &str,
+    sbox: &str,
+    segment_bits: usize,
+    key_meshing: bool,
+) -> PyResult<Profile> {
+    let mode = rust_to_py_err(Mode::from_name(mode).ok_or(CipherError::InvalidMode))?;
+    let sbox = Sbox::from_name(sbox)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown sbox '{sbox}'")))?;
+    if !segment_bits.is_multiple_of(8) || !(8..=64).contains(&segment_bits) {
+        return Err(PyValueError::new_err(
+            "segment_bits must be a multiple of 8 from 8 to 64",
+        ));
+    }
+    let segment = segment_bits / 8;
+    if mode == Mode::Cnt && segment != BLOCK_SIZE {
+        return Err(PyValueError::new_err("CNT uses 64-bit gamma"));
+    }
+    if key_meshing && (mode == Mode::Ofb || segment != BLOCK_SIZE) {
+        return Err(PyValueError::new_err(
+            "key_meshing needs CNT or CFB with 64-bit gamma",
+        ));
+    }
+
+    Ok(Profile {
+        sbox,
+        mode,
+        segment,
+        key_meshing,
+    })
+}
+
+/// Шифрование в профиле ГОСТ 28147-89.
+///
+/// - data — Открытый текст
+/// - key — Ключ (32 байта, слова младшим байтом вперёд)
+/// - iv — Синхропосылка: 8 байт для `CNT` и смены ключа, для `CFB` и `OFB`
+///   — регистр, кратный 8 байтам
+/// - mode — Режим: `CNT` (по умолчанию), `CFB` или `OFB`
+/// - sbox — Узел замены: `cryptopro-a` (по умолчанию), `cryptopro-b`,
+///   `cryptopro-c`, `cryptopro-d`, `test` или `tc26`
+/// - segment_bits — Ширина гаммы `CFB` и `OFB` в битах: 8, 16, ..., 64
+/// - key_meshing — Смена ключа КриптоПро каждые 1024 байта (`CNT` и `CFB`
+///   с 64-битной гаммой)
+#[pyfunction]
+#[pyo3(name = "legacy_encrypt")]
+#[pyo3(signature = (data, key, iv, mode="CNT", *, sbox="cryptopro-a", segment_bits=64, key_meshing=false))]
+pub fn legacy_encrypt<'py>(
+    data: Bound<'py, PyBytes>,
+    key: Bound<'py, PyBytes>,
+    iv: Bound<'py, PyBytes>,
+    mode: &str,
+    sbox: &str,
+    segment_bits: usize,
+    key_meshing: bool,
+) -> PyResult<Vec<u8>> {
+    let profile = extract_profile(mode, sbox, segment_bits, key_meshing)?;
+
+    crypt(&profile, &data, &key, &iv, false)
+}
+
+/// Дешифровка в профиле ГОСТ 28147-89.
+///
+/// Аргументы те же, что у `legacy_encrypt`; `data` — шифротекст.
+#[pyfunction]
+#[pyo3(name = "legacy_decrypt")]
+#[pyo3(signature = (data, key, iv, mode="CNT", *, sbox="cryptopro-a", segment_bits=64, key_meshing=false))]
+pub fn legacy_decrypt<'py>(
+    data: Bound<'py, PyBytes>,
+    key: Bound<'py, PyBytes>,
+    iv: Bound<'py, PyBytes>,
+    mode: &str,
+    sbox: &str,
+    segment_bits: usize,
+    key_meshing: bool,
+) -> PyResult<Vec<u8>> {
+    let profile = extract_profile(mode, sbox, segment_bits, key_meshing)?;
+
+    crypt(&profile, &data, &key, &iv, true)
+}
+
+fn crypt(
+    profile: &Profile,
+    data: &Bound<'_, PyBytes>,
+    key: &Bound<'_, PyBytes>,
+    iv: &Bound<'_, PyBytes>,
+    decrypting: bool,
+) -> PyResult<Vec<u8>> {
+    let key = extract_key(key)?;
+    let (bytes, iv) = (data.as_bytes(), iv.as_bytes());
+
+    rust_to_py_err(allow_threads_for(data.py(), bytes.len(), || {
+        if decrypting {
+            decrypt(profile, &key, iv, bytes)
+        } else {
+            encrypt(profile, &key, iv, bytes)
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Encoding;
+
+    const KEY: [u8; 32] = {
+        let mut key = [0u8; 32];
+        let mut i = 0;
+        while i < key.len() {
+            key[i] = i as u8;
+            i += 1;
+        }
+        key
+    };
+    const IV: [u8; 16] = [
+        0x12, 0x34, 0x56, 0x78, 0x90, 0xAB, 0xCD, 0xEF, 0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD,
+        0xEF,
+    ];
+
+    fn hex(text: &str) -> Vec<u8> {
+        Encoding::Hex
+            .decode(text.as_bytes())
+            .expect("hex test vector")
+    }
+
+    fn profile(mode: Mode, segment: usize, key_meshing: bool) -> Profile {
+        Profile {
+            sbox: Sbox::CryptoProA,
+            mode,
+            segment,
+            key_meshing,
+        }
+    }
+
+    #[test]
+    fn byte_order_matches_gost_34_12() {
+        // Блок ГОСТ Р 34.12-2015 (Магма) в порядке байтов ГОСТ 28147-89.
+        let mut key = hex("ffeeddccbbaa99887766554433221100f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+        key.chunks_exact_mut(4).for_each(|word| word.reverse());
+        let mut block = hex("fedcba9876543210");
+        block.reverse();
+
+        Legacy::<Magma>::new(&key).unwrap().encrypt(&mut block);
+        block.reverse();
+        assert_eq!(block, hex("4ee901e5c2d8ca3d"));
+    }
+
+    #[test]
+    fn gamma_modes_match_reference() {
+        let data: Vec<u8> = (0..37).collect();
+        let cases = [
+            (
+                profile(Mode::Cnt, 8, false),
+                &IV[..8],
+                "b1d01c30697749df0850d192d5c7bc146dafe091ec425049180dfe4689be7222e63856bb21",
+            ),
+            (
+                profile(Mode::Cfb, 8, false),
+                &IV[..8],
+                "d120f6563757ea8bd1a0d41aa77a02e6b1ec743628d1438ee32b6d5785f9af5a38c7fbb241",
+            ),
+            (
+                profile(Mode::Cfb, 1, false),
+                &IV[..],
+                "d1c657007e1f6442d49df7b80e56ded7b395c0690a75339f64dbd68929879ff6c29879594d",
+            ),
+            (
+                profile(Mode::Ofb, 4, false),
+                &IV[..],
+                "d120f656d802f8e4ad21a89ff6a61bc6030a2419590bea82aee115b53b32c19db626e2a47c",
+            ),
+        ];
+
+        for (profile, iv, expected) in cases {
+            let ciphertext = encrypt(&profile, &KEY, iv, &data).unwrap();
+            assert_eq!(ciphertext, hex(expected), "{profile:?}");
+            assert_eq!(decrypt(&profile, &KEY, iv, &ciphertext).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn key_meshing_matches_reference() {
+        let data: Vec<u8> = (0..2100).map(|i| (i * 7 % 256) as u8).collect();
+        let cases = [
+            (
+                Mode::Cnt,
+                "2a17de8697ecdb10242e6612847d3ff10aac17c9923572e8",
+                "df004d4be04871142564c9a2eb9fee2d",
+            ),
+            (
+                Mode::Cfb,
+                "36894e734a6b5e26de866b0781bcb68ee2f8910854c1ea59",
+                "a6216a899eab7d673be209df8dee7064",
+            ),
+        ];
+
+        for (mode, first, second) in cases {
+            let profile = profile(mode, 8, true);
+            let ciphertext = encrypt(&profile, &KEY, &IV[..8], &data).unwrap();
+            // До первой смены ключа гамма та же, что и без неё.
+            let plain = encrypt(
+                &Profile {
+                    key_meshing: false,
+                    ..profile
+                },
+                &KEY,
+                &IV[..8],
+                &data,
+            );
+            assert_eq!(ciphertext[..1024], plain.unwrap()[..1024]);
+            assert_eq!(ciphertext[1016..1040], hex(first), "{mode:?}");
+            assert_eq!(ciphertext[2040..2056], hex(second), "{mode:?}");
+            assert_eq!(
+                decrypt(&profile, &KEY, &IV[..8], &ciphertext).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn sboxes_give_different_gamma() {
+        let data = [0u8; 24];
+        let gammas: Vec<_> = Sbox::ALL
+            .into_iter()
+            .map(|sbox| {
+                let profile = Profile {
+                    sbox,
+                    ..profile(Mode::Cnt, 8, false)
+                };
+                encrypt(&profile, &KEY, &IV[..8], &data).unwrap()
+            })
+            .collect();
+
+        for (i, gamma) in gammas.iter().enumerate() {
+            assert!(gammas[i + 1..].iter().all(|other| other != gamma));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_iv_and_segment() {
+        let data = [0u8; 16];
+        assert!(matches!(
+            encrypt(&profile(Mode::Cnt, 8, false), &KEY, &IV, &data),
+            Err(CipherError::InvalidIVLenght)
+        ));
+        assert!(matches!(
+            encrypt(&profile(Mode::Cfb, 8, false), &KEY, &IV[..5], &data),
+            Err(CipherError::InvalidIVLenght)
+        ));
+        assert!(matches!(
+            encrypt(&profile(Mode::Ofb, 0, false), &KEY, &IV, &data),
+            Err(CipherError::InvalidBlockSize)
+        ));
+        assert!(matches!(
+            encrypt(&profile(Mode::Cfb, 8, false), &KEY[..16], &IV, &data),
+            Err(CipherError::InvalidKeyLenght)
+        ));
+    }
+}
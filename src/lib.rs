@@ -12,61 +12,2089 @@
 //!
 //! Подробности в README.md.
 
+mod aead;
+mod asn1;
+mod batch;
+#[cfg(feature = "benchmarks")]
+pub mod bench;
+mod buffer;
+mod cache;
+mod cmac;
+mod cms;
+mod compat;
+mod container;
+mod deadline;
+mod dual;
+mod encoding;
 mod engine;
+mod envelope;
+mod etm;
+mod ff1;
+mod fields;
+mod files;
+mod hash;
+mod hooks;
+mod kdf;
+mod kexp;
+mod key;
+mod legacy;
+mod mac;
+mod mgm;
+mod migration;
+mod modes;
+mod params;
+mod selftest;
+mod stream;
+#[cfg(feature = "testing")]
+mod tamper;
+mod transcode;
+mod vectors;
 
 use block_encryption::traits::CipherError;
 use block_encryption::traits::CipherError::*;
-use pyo3::exceptions::PyValueError;
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::import_exception;
 use pyo3::marker::*;
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyString};
+use pyo3::types::{PyBytes, PyDict, PyIterator, PyString};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::engine::EncryptMode;
 
 import_exception!(grass_crypt.exceptions, KeyLengthError);
 import_exception!(grass_crypt.exceptions, IVLengthError);
 import_exception!(grass_crypt.exceptions, BlockSizeError);
+import_exception!(grass_crypt.exceptions, DecryptionError);
+import_exception!(grass_crypt.exceptions, SelfTestError);
 import_exception!(grass_crypt.exceptions, DataTooShortError);
 import_exception!(grass_crypt.exceptions, DataNotAlignedError);
 import_exception!(grass_crypt.exceptions, InvalidPaddingError);
 import_exception!(grass_crypt.exceptions, InvalidKeyFormatError);
 import_exception!(grass_crypt.exceptions, InvalidModeError);
+import_exception!(grass_crypt.exceptions, AuthenticationError);
+import_exception!(grass_crypt.exceptions, AlreadyFinalized);
+import_exception!(grass_crypt.exceptions, OperationTimeoutError);
+
+/// Размер данных, начиная с которого шифрование и дешифровка идут без GIL.
+///
+/// Для коротких сообщений освобождение и захват GIL дороже самой операции,
+/// а длинные не заставляют другие потоки Python ждать.
+const RELEASE_GIL_SIZE: usize = 64 * 1024;
+
+/// Выполнение `f` без GIL, если данных не меньше `RELEASE_GIL_SIZE`.
+fn allow_threads_for<T, F>(py: Python<'_>, size: usize, f: F) -> T
+where
+    F: Ungil + FnOnce() -> T,
+    T: Ungil,
+{
+    if size >= RELEASE_GIL_SIZE {
+        py.allow_threads(f)
+    } else {
+        f()
+    }
+}
 
 /// Шифратор.
 ///
+/// Текст и ключ — `bytes` или другие объекты с протоколом буфера
+/// (`bytearray`, `memoryview`, массивы numpy `uint8`), как и в остальных
+/// функциях шифрования и дешифровки.
+///
 /// - plaintext — Текст для шифрования
 /// - key — Ключ для шифрования
-/// - encrypt_mode — Режим шифрования
+/// - encrypt_mode — Режим шифрования: строка или объект параметров (`params`)
+/// - offset, length — Необязательный диапазон `plaintext` для шифрования
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - iv — Вектор инициализации (16 байт, для `magma` — 8); по умолчанию
+///   встроенный. В MGM — nonce, по умолчанию случайный в начале результата
+/// - random_iv — Случайный вектор в заголовке результата (кроме ECB);
+///   для дешифровки нужен `do_decrypt(..., random_iv=True)`
+/// - aad — Дополнительные данные, защищаемые имитовставкой без шифрования
+///   (только MGM и SIV)
+/// - timeout — Ограничение времени в секундах: по истечении обработка
+///   прерывается между частями данных (`OperationTimeoutError`); кроме MGM,
+///   SIV и CTR-ACPKM
+/// - padding — Выравнивание ECB и CBC: `PKCS7` (по умолчанию), `X923`,
+///   `ISO7816` или `ZERO`
+/// - nonce, counter_start — Nonce и начальный счётчик CTR вместо `iv`
+///   (как `CTRParams(nonce, counter_start)`)
+/// - threads — Число потоков для ECB и CTR без `timeout`; по умолчанию —
+///   `GRASS_CRYPT_THREADS` или размер пула rayon. Сообщения короче
+///   `GRASS_CRYPT_PARALLEL_SIZE` (по умолчанию 1 МиБ) обрабатываются в
+///   одном потоке
+/// - output — Кодировка результата: `raw` (`bytes`, по умолчанию), `hex`,
+///   `base64` или `base64url` (`str`, без выравнивания `=`)
+/// - container — Самоописываемый контейнер (`container`): шифр, режим,
+///   параметры KDF, соль, вектор (по умолчанию случайный) и имитовставка в
+///   заголовке; `do_decrypt` распознаёт его сам. Режим — строкой, без
+///   `random_iv`, `aad`, `timeout` и `padding`
+/// - kdf — Выработка ключа из пароля `key` в контейнере: `pbkdf2` или
+///   `kdf_gostr3411`; по умолчанию `key` — сам ключ
+/// - iterations — Число итераций `pbkdf2` в контейнере (не больше
+///   10 000 000)
 #[pyfunction]
 #[pyo3(name = "do_encrypt")]
-#[pyo3(signature = (plaintext, key, encrypt_mode))]
+#[pyo3(signature = (plaintext, key, encrypt_mode, *, offset=0, length=None, cipher=engine::Algorithm::Kuznyechik, iv=None, random_iv=false, aad=None, timeout=None, padding="PKCS7", nonce=None, counter_start=0, threads=None, output="raw", container=false, kdf=None, iterations=container::ITERATIONS))]
+#[allow(clippy::too_many_arguments)]
 fn do_encrypt<'py>(
-    plaintext: Bound<'py, PyBytes>,
-    key: Bound<'py, PyBytes>,
-    encrypt_mode: Bound<'py, PyString>,
+    plaintext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: Bound<'py, PyAny>,
+    offset: usize,
+    length: Option<usize>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    random_iv: bool,
+    aad: Option<Bound<'py, PyBytes>>,
+    timeout: Option<f64>,
+    padding: &str,
+    nonce: Option<Bound<'py, PyBytes>>,
+    counter_start: u64,
+    threads: Option<usize>,
+    output: &str,
+    container: bool,
+    kdf: Option<&str>,
+    iterations: u32,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = plaintext.py();
+    let stepwise = Stepwise {
+        deadline: extract_deadline(timeout)?,
+        padding: extract_padding(padding)?,
+    };
+    let threads = extract_threads(threads)?;
+    let output = extract_encoding(output)?;
+    let algorithm = cipher;
+    let encrypt_mode = extract_ctr_mode(encrypt_mode, nonce, counter_start)?;
+    let (pt, k) = extract_text_and_key(&plaintext, &key, offset, length)?;
+    let aad = aad.as_ref().map(|a| a.as_bytes());
+
+    if container {
+        if random_iv || aad.is_some() || timeout.is_some() || padding != "PKCS7" {
+            return Err(PyValueError::new_err(
+                "container cannot be used with random_iv, aad, timeout or padding",
+            ));
+        }
+        let kdf = kdf.map(extract_kdf).transpose()?;
+        let ct = encrypt_container(algorithm, &encrypt_mode, iv, kdf, iterations, &pt, &k)?;
+        return Ok(encoded(py, output, ct));
+    }
+    if kdf.is_some() {
+        return Err(PyValueError::new_err("kdf requires container"));
+    }
+
+    // Без кодировки результат пишется сразу в `bytes`.
+    let Some(encoding) = output else {
+        let ct: Bound<'py, PyBytes> = encrypt_data(
+            algorithm,
+            &encrypt_mode,
+            iv,
+            random_iv,
+            aad,
+            stepwise,
+            threads,
+            &pt,
+            &k,
+        )?;
+        return Ok(ct.into_any());
+    };
+
+    let ct: Vec<u8> = encrypt_data(
+        algorithm,
+        &encrypt_mode,
+        iv,
+        random_iv,
+        aad,
+        stepwise,
+        threads,
+        &pt,
+        &k,
+    )?;
+
+    Ok(PyString::new(py, &encoding.encode(&ct)).into_any())
+}
+
+/// Результат в кодировке `output`: `bytes` или `str`.
+fn encoded(py: Python<'_>, output: Option<encoding::Encoding>, data: Vec<u8>) -> Bound<'_, PyAny> {
+    match output {
+        Some(encoding) => PyString::new(py, &encoding.encode(&data)).into_any(),
+        None => PyBytes::new(py, &data).into_any(),
+    }
+}
+
+/// Шифрование в самоописываемый контейнер (`container::seal`).
+///
+/// Вектор по умолчанию случаен (в MGM — nonce), соль случайна при `kdf`.
+fn encrypt_container(
+    algorithm: engine::Algorithm,
+    encrypt_mode: &Bound<'_, PyAny>,
+    iv: Option<Bound<'_, PyBytes>>,
+    kdf: Option<kdf::Kdf>,
+    iterations: u32,
+    pt: &[u8],
+    secret: &[u8],
 ) -> PyResult<Vec<u8>> {
-    let (pt, k) = extract_text_and_key(&plaintext, &key)?;
-    let encrypt_result = engine::encrypting(pt, k, to_string(&encrypt_mode));
+    if encrypt_mode.extract::<EncryptMode>().is_err() {
+        return Err(PyValueError::new_err(
+            "container requires the mode given as a string or Mode",
+        ));
+    }
+    if kdf == Some(kdf::Kdf::Pbkdf2) && !(1..=container::MAX_ITERATIONS).contains(&iterations) {
+        return Err(PyValueError::new_err(format!(
+            "iterations must be from 1 to {}",
+            container::MAX_ITERATIONS
+        )));
+    }
+    let (mode, iv) = extract_mode_and_iv(encrypt_mode, iv)?;
+    let iv = match (mode, iv) {
+        (EncryptMode::Block(block), _) if !block.uses_iv() => Vec::new(),
+        (_, Some(iv)) => iv,
+        (EncryptMode::Mgm, None) => random_mgm_nonce()?.to_vec(),
+        (_, None) => random_bytes(algorithm.block_size())?,
+    };
+    let salt = match kdf {
+        Some(_) => random_bytes(container::SALT_SIZE)?,
+        None => Vec::new(),
+    };
+    let header = rust_to_py_err(container::Header::new(
+        algorithm, mode, kdf, iterations, salt, iv,
+    ))?;
+    let sealed = allow_threads_for(encrypt_mode.py(), pt.len(), || {
+        container::seal(&header, secret, pt)
+    });
+
+    aead_to_py_err(sealed)
+}
+
+/// Случайные байты от ОС.
+fn random_bytes(len: usize) -> PyResult<Vec<u8>> {
+    let mut data = vec![0u8; len];
+    getrandom::getrandom(&mut data).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    Ok(data)
+}
+
+/// Число потоков: `None` — по умолчанию.
+fn extract_threads(threads: Option<usize>) -> PyResult<Option<usize>> {
+    match threads {
+        Some(0) => Err(PyValueError::new_err("threads must be positive")),
+        _ => Ok(threads),
+    }
+}
+
+/// Кодировка `output`/`input`: `None` — `raw`, без кодировки.
+fn extract_encoding(name: &str) -> PyResult<Option<encoding::Encoding>> {
+    match name {
+        "raw" => Ok(None),
+        _ => encoding::Encoding::from_name(name)
+            .map(Some)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown encoding '{name}'"))),
+    }
+}
+
+/// Параметры пошаговой обработки (`deadline`) в `do_encrypt`/`do_decrypt`.
+#[derive(Clone, Copy, Default)]
+struct Stepwise {
+    padding: modes::Padding,
+    deadline: Option<deadline::Deadline>,
+}
+
+impl Stepwise {
+    /// Параметры режимов (счётчик CTR — из `encrypt_mode`) и срок, если
+    /// нужна пошаговая обработка: шифровальщики кеша (`engine::Encryptor`)
+    /// поддерживают только схему по умолчанию и обрабатывают данные за один
+    /// шаг.
+    fn resolve(
+        self,
+        encrypt_mode: &Bound<'_, PyAny>,
+    ) -> Option<(modes::Options, Option<deadline::Deadline>)> {
+        let options = modes::Options {
+            padding: self.padding,
+            counter: params::counter(encrypt_mode),
+        };
+        (options != modes::Options::default() || self.deadline.is_some())
+            .then_some((options, self.deadline))
+    }
+}
+
+/// Результат `encrypt_data`/`decrypt_data`.
+///
+/// `bytes` заполняется на месте, без промежуточного `Vec` и копии (для
+/// больших сообщений это вдвое меньше памяти), а `Vec` нужен для
+/// дальнейшей обработки данных в Rust.
+trait Output<'py>: Sized {
+    /// Результат длиной `len`, заполняемый `fill`.
+    fn with_len(py: Python<'py>, len: usize, fill: impl FnOnce(&mut [u8])) -> PyResult<Self>;
+
+    fn from_vec(py: Python<'py>, data: Vec<u8>) -> Self;
+}
+
+impl<'py> Output<'py> for Bound<'py, PyBytes> {
+    fn with_len(py: Python<'py>, len: usize, fill: impl FnOnce(&mut [u8])) -> PyResult<Self> {
+        PyBytes::new_with(py, len, |out| {
+            fill(out);
+            Ok(())
+        })
+    }
+
+    /// Промежуточный `Vec` (обычно открытый текст) стирается.
+    fn from_vec(py: Python<'py>, data: Vec<u8>) -> Self {
+        PyBytes::new(py, &Zeroizing::new(data))
+    }
+}
+
+impl<'py> Output<'py> for Vec<u8> {
+    fn with_len(_py: Python<'py>, len: usize, fill: impl FnOnce(&mut [u8])) -> PyResult<Self> {
+        let mut out = vec![0u8; len];
+        fill(&mut out);
+        Ok(out)
+    }
+
+    fn from_vec(_py: Python<'py>, data: Vec<u8>) -> Self {
+        data
+    }
+}
+
+/// Шифрование подготовленных данных ключом (общая часть `do_encrypt` и
+/// `dual`).
+#[allow(clippy::too_many_arguments)]
+fn encrypt_data<'py, O: Output<'py>>(
+    algorithm: engine::Algorithm,
+    encrypt_mode: &Bound<'py, PyAny>,
+    iv: Option<Bound<'_, PyBytes>>,
+    random_iv: bool,
+    aad: Option<&[u8]>,
+    stepwise: Stepwise,
+    threads: Option<usize>,
+    pt: &[u8],
+    k: &[u8],
+) -> PyResult<O> {
+    let py = encrypt_mode.py();
+    let (mode, iv) = extract_mode_and_iv(encrypt_mode, iv)?;
+    check_random_iv(mode, iv.as_deref(), random_iv)?;
+    check_siv(mode, iv.as_deref(), random_iv)?;
+    let aad = check_aad(mode, aad)?;
+    check_deadline(mode, stepwise.deadline)?;
+    let stepwise = stepwise.resolve(encrypt_mode);
+    let tag_len = params::tag_len(encrypt_mode);
+    let (started, size) = (hooks::start(), pt.len());
+    let random = || {
+        let mut iv = vec![0u8; algorithm.block_size()];
+        getrandom::getrandom(&mut iv).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok::<_, PyErr>(iv)
+    };
+    // Без GIL (для больших данных): другие потоки Python работают, пока
+    // идёт шифрование.
+    let encrypt_result = match (mode, stepwise) {
+        (EncryptMode::Mgm, _) => allow_threads_for(py, size, || {
+            encrypt_mgm(algorithm, pt, k, iv.as_deref(), aad, tag_len)
+        })
+        .map(|data| O::from_vec(py, data)),
+        (EncryptMode::Siv, _) => allow_threads_for(py, size, || {
+            rust_to_py_err(engine::encrypting_siv(algorithm, pt, k, aad))
+        })
+        .map(|data| O::from_vec(py, data)),
+        (EncryptMode::CtrAcpkm, _) => {
+            let section_size = params::section_size(encrypt_mode);
+            allow_threads_for(py, size, || {
+                rust_to_py_err(engine::acpkm(algorithm, pt, k, iv.as_deref(), section_size))
+            })
+            .map(|data| O::from_vec(py, data))
+        }
+        (EncryptMode::Block(mode), Some((options, deadline))) => {
+            allow_threads_for(py, size, || {
+                let result = if random_iv {
+                    deadline::encrypting_framed(
+                        algorithm,
+                        pt,
+                        k,
+                        mode,
+                        &random()?,
+                        options,
+                        deadline,
+                    )
+                } else {
+                    deadline::encrypting(algorithm, pt, k, mode, iv.as_deref(), options, deadline)
+                };
+                deadline_to_py_err(result, false)
+            })
+            .map(|data| O::from_vec(py, data))
+        }
+        (EncryptMode::Block(mode), None) => allow_threads_for(py, size, || {
+            rust_to_py_err(if random_iv {
+                engine::Encryption::framed(algorithm, k, mode, &random()?, pt.len())
+            } else {
+                engine::Encryption::new(algorithm, k, mode, iv.as_deref(), pt.len())
+            })
+            .map(|encryption| encryption.threads(threads))
+        })
+        .and_then(|encryption| {
+            O::with_len(py, encryption.output_len(), |out| {
+                allow_threads_for(py, size, || encryption.finish(pt, out))
+            })
+        }),
+    };
+    hooks::finish(py, "encrypt", size, started);
 
-    Ok(rust_to_py_err(encrypt_result)?)
+    encrypt_result
 }
 
 /// Дешифратор.
 ///
+/// Контейнер `do_encrypt(..., container=True)` распознаётся без `iv`: шифр,
+/// режим, вектор и параметры KDF берутся из его заголовка, а `key` —
+/// пароль или ключ, переданный при шифровании. Неверный пароль или
+/// повреждённый контейнер вызывают `DecryptionError`.
+///
 /// - ciphertext — Зашифрованный текст для дешифровки
 /// - key — Ключ для дешифровки
-/// - encrypt_mode — Режим шифрования
+/// - encrypt_mode — Режим шифрования: строка или объект параметров
+///   (`params`); для контейнера не нужен
+/// - offset, length — Необязательный диапазон `ciphertext` для дешифровки
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - iv — Вектор инициализации, использованный при шифровании, или `None`
+///   для встроенного. В MGM неверная имитовставка вызывает
+///   `DecryptionError`
+/// - random_iv — Вектор из заголовка шифротекста `do_encrypt(...,
+///   random_iv=True)`; без заголовка вызывает `DecryptionError`
+/// - aad — Дополнительные данные, переданные при шифровании (только MGM и
+///   SIV)
+/// - timeout — Ограничение времени в секундах (см. `do_encrypt`)
+/// - padding — Выравнивание, использованное при шифровании; несовпадение
+///   вызывает `DecryptionError`
+/// - nonce, counter_start — Nonce и начальный счётчик CTR (см.
+///   `do_encrypt`). Для дешифровки с блока `i` шифротекста — счётчик
+///   шифрования плюс `i`
+/// - threads — Число потоков для ECB и CTR (см. `do_encrypt`)
+/// - input — Кодировка `ciphertext`: `raw` (по умолчанию), `hex`, `base64`
+///   или `base64url`; закодированный текст — `str` или буфер ASCII.
+///   `offset` и `length` относятся к декодированным данным
 #[pyfunction]
 #[pyo3(name = "do_decrypt")]
-#[pyo3(signature = (ciphertext, key, encrypt_mode))]
+#[pyo3(signature = (ciphertext, key, encrypt_mode=None, *, offset=0, length=None, cipher=engine::Algorithm::Kuznyechik, iv=None, random_iv=false, aad=None, timeout=None, padding="PKCS7", nonce=None, counter_start=0, threads=None, input="raw"))]
+#[allow(clippy::too_many_arguments)]
 fn do_decrypt<'py>(
-    ciphertext: Bound<'py, PyBytes>,
+    ciphertext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: Option<Bound<'py, PyAny>>,
+    offset: usize,
+    length: Option<usize>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    random_iv: bool,
+    aad: Option<Bound<'py, PyBytes>>,
+    timeout: Option<f64>,
+    padding: &str,
+    nonce: Option<Bound<'py, PyBytes>>,
+    counter_start: u64,
+    threads: Option<usize>,
+    input: &str,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let py = ciphertext.py();
+    let stepwise = Stepwise {
+        deadline: extract_deadline(timeout)?,
+        padding: extract_padding(padding)?,
+    };
+    let threads = extract_threads(threads)?;
+    let algorithm = cipher;
+    let ciphertext = match extract_encoding(input)? {
+        Some(encoding) => encoding::decode_text(&ciphertext, encoding)?.into_any(),
+        None => ciphertext,
+    };
+    let (ct, k) = extract_text_and_key(&ciphertext, &key, offset, length)?;
+
+    if iv.is_none() && !random_iv && container::is_container(&ct) {
+        let pt = allow_threads_for(py, ct.len(), || container::open(&k, &ct));
+        return Ok(Output::from_vec(py, aead_to_py_err(pt)?));
+    }
+    let encrypt_mode = encrypt_mode.ok_or_else(|| {
+        PyValueError::new_err("encrypt_mode is required for data without a container")
+    })?;
+    let encrypt_mode = extract_ctr_mode(encrypt_mode, nonce, counter_start)?;
+    let aad = aad.as_ref().map(|a| a.as_bytes());
+
+    decrypt_data(
+        algorithm,
+        &encrypt_mode,
+        iv,
+        random_iv,
+        aad,
+        stepwise,
+        threads,
+        &ct,
+        &k,
+    )
+}
+
+/// Дешифровка подготовленных данных ключом (общая часть `do_decrypt` и
+/// `dual`).
+#[allow(clippy::too_many_arguments)]
+fn decrypt_data<'py, O: Output<'py>>(
+    algorithm: engine::Algorithm,
+    encrypt_mode: &Bound<'py, PyAny>,
+    iv: Option<Bound<'_, PyBytes>>,
+    random_iv: bool,
+    aad: Option<&[u8]>,
+    stepwise: Stepwise,
+    threads: Option<usize>,
+    ct: &[u8],
+    k: &[u8],
+) -> PyResult<O> {
+    let py = encrypt_mode.py();
+    let (mode, iv) = extract_mode_and_iv(encrypt_mode, iv)?;
+    check_random_iv(mode, iv.as_deref(), random_iv)?;
+    check_siv(mode, iv.as_deref(), random_iv)?;
+    let aad = check_aad(mode, aad)?;
+    check_deadline(mode, stepwise.deadline)?;
+    let stepwise = stepwise.resolve(encrypt_mode);
+    let tag_len = params::tag_len(encrypt_mode);
+    let (started, size) = (hooks::start(), ct.len());
+    // Без GIL (для больших данных): фоновая дешифровка
+    // (`streams.iter_decrypt`) идёт параллельно с обработкой данных в Python.
+    let decrypt_result = match (mode, stepwise) {
+        (EncryptMode::Mgm, _) => allow_threads_for(py, size, || {
+            decrypt_mgm(algorithm, ct, k, iv.as_deref(), aad, tag_len)
+        })
+        .map(|data| O::from_vec(py, data)),
+        (EncryptMode::Siv, _) => allow_threads_for(py, size, || {
+            aead_to_py_err(engine::decrypting_siv(algorithm, ct, k, aad))
+        })
+        .map(|data| O::from_vec(py, data)),
+        (EncryptMode::CtrAcpkm, _) => {
+            let section_size = params::section_size(encrypt_mode);
+            allow_threads_for(py, size, || {
+                decrypt_to_py_err(engine::acpkm(algorithm, ct, k, iv.as_deref(), section_size))
+            })
+            .map(|data| O::from_vec(py, data))
+        }
+        (EncryptMode::Block(mode), Some((options, deadline))) => {
+            allow_threads_for(py, size, || {
+                let result = if random_iv {
+                    deadline::decrypting_framed(algorithm, ct, k, mode, options, deadline)
+                } else {
+                    deadline::decrypting(algorithm, ct, k, mode, iv.as_deref(), options, deadline)
+                };
+                deadline_to_py_err(result, true)
+            })
+            .map(|data| O::from_vec(py, data))
+        }
+        (EncryptMode::Block(mode), None) => allow_threads_for(py, size, || {
+            decrypt_to_py_err(if random_iv {
+                engine::Decryption::framed(algorithm, k, mode, ct)
+            } else {
+                engine::Decryption::new(algorithm, k, mode, iv.as_deref(), ct)
+            })
+            .map(|decryption| decryption.threads(threads))
+        })
+        .and_then(|decryption| {
+            O::with_len(py, decryption.output_len(), |out| {
+                allow_threads_for(py, size, || decryption.finish(out))
+            })
+        }),
+    };
+    hooks::finish(py, "decrypt", size, started);
+
+    decrypt_result
+}
+
+/// Дешифровка части шифротекста CTR без дешифровки предыдущих блоков.
+///
+/// Гамма начинается с блока `block_offset`: для байтов шифротекста с
+/// `block_offset × размер блока` передаётся только эта часть. Шифротекст
+/// должен быть получен с `nonce` (см. `do_encrypt`) или с
+/// `CTRParams(nonce, counter)` со счётчиком `half`.
+///
+/// - ciphertext — Часть шифротекста, начинающаяся на границе блока
+/// - key — Ключ для дешифровки
+/// - nonce — Nonce, использованный при шифровании
+/// - block_offset — Номер первого блока части в шифротексте
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - counter_start — Начальный счётчик при шифровании
+#[pyfunction]
+#[pyo3(name = "decrypt_range")]
+#[pyo3(signature = (ciphertext, key, nonce, block_offset, *, cipher=engine::Algorithm::Kuznyechik, counter_start=0))]
+fn decrypt_range<'py>(
+    ciphertext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    nonce: Bound<'py, PyBytes>,
+    block_offset: u64,
+    cipher: engine::Algorithm,
+    counter_start: u64,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let algorithm = cipher;
+    let counter = counter_start
+        .checked_add(block_offset)
+        .ok_or_else(|| PyValueError::new_err("block_offset is too large"))?;
+    let params = params::CtrParams::from_nonce(nonce.as_bytes(), counter)?;
+    let encrypt_mode = Bound::new(ciphertext.py(), params)?.into_any();
+    let (ct, k) = extract_text_and_key(&ciphertext, &key, 0, None)?;
+
+    decrypt_data(
+        algorithm,
+        &encrypt_mode,
+        None,
+        false,
+        None,
+        Stepwise::default(),
+        None,
+        &ct,
+        &k,
+    )
+}
+
+/// Шифрование строки цифр с сохранением формата (FF1, NIST SP 800-38G).
+///
+/// Результат — строка цифр той же длины в той же системе счисления
+/// (буквы — строчные), например номер карты остаётся 16 цифрами. Одинаковые
+/// строки с одним tweak дают одинаковые шифровки; имитовставки нет.
+///
+/// - digits — Строка цифр: для `radix=10` — от 6 до 72 символов
+/// - key — Ключ (32 байта)
+/// - tweak — Открытые данные, меняющие шифровку (например, первые цифры
+///   номера, оставленные открытыми); может быть пустым
+/// - radix — Основание системы счисления от 2 до 36 (цифры `0-9a-z`)
+/// - cipher — Шифр: `kuznyechik` (по умолчанию) или `aes256`
+#[pyfunction]
+#[pyo3(name = "encrypt_fpe")]
+#[pyo3(signature = (digits, key, tweak, *, radix=10, cipher=engine::Algorithm::Kuznyechik))]
+fn encrypt_fpe(
+    digits: &str,
+    key: Bound<'_, PyAny>,
+    tweak: Bound<'_, PyBytes>,
+    radix: u32,
+    cipher: engine::Algorithm,
+) -> PyResult<String> {
+    fpe(digits, &key, &tweak, radix, cipher, true)
+}
+
+/// Дешифровка строки `encrypt_fpe` с теми же ключом, tweak и основанием.
+///
+/// - digits — Зашифрованная строка цифр
+/// - key — Ключ (32 байта)
+/// - tweak — Tweak, использованный при шифровании
+/// - radix — Основание системы счисления (см. `encrypt_fpe`)
+/// - cipher — Шифр (см. `encrypt_fpe`)
+#[pyfunction]
+#[pyo3(name = "decrypt_fpe")]
+#[pyo3(signature = (digits, key, tweak, *, radix=10, cipher=engine::Algorithm::Kuznyechik))]
+fn decrypt_fpe(
+    digits: &str,
+    key: Bound<'_, PyAny>,
+    tweak: Bound<'_, PyBytes>,
+    radix: u32,
+    cipher: engine::Algorithm,
+) -> PyResult<String> {
+    fpe(digits, &key, &tweak, radix, cipher, false)
+}
+
+/// Общая часть `encrypt_fpe` и `decrypt_fpe`: проверка строки и перевод
+/// цифр в значения и обратно.
+fn fpe(
+    digits: &str,
+    key: &Bound<'_, PyAny>,
+    tweak: &Bound<'_, PyBytes>,
+    radix: u32,
+    cipher: engine::Algorithm,
+    encrypt: bool,
+) -> PyResult<String> {
+    let algorithm = cipher;
+    if !(2..=ff1::MAX_RADIX).contains(&radix) {
+        return Err(PyValueError::new_err(format!(
+            "radix must be from 2 to {}",
+            ff1::MAX_RADIX
+        )));
+    }
+    let range = ff1::length_range(radix);
+    if !range.contains(&digits.len()) {
+        return Err(PyValueError::new_err(format!(
+            "digits must be from {} to {} characters for radix {radix}",
+            range.start(),
+            range.end()
+        )));
+    }
+    let values = digits
+        .chars()
+        .map(|c| c.to_digit(radix).map(|d| d as u8))
+        .collect::<Option<Vec<u8>>>()
+        .map(Zeroizing::new)
+        .ok_or_else(|| PyValueError::new_err(format!("digits must be radix-{radix} digits")))?;
+    let k = extract_buffer_key(key)?;
+    let result = Zeroizing::new(rust_to_py_err(engine::ff1(
+        algorithm,
+        &k,
+        radix,
+        tweak.as_bytes(),
+        &values,
+        encrypt,
+    ))?);
+
+    Ok(result
+        .iter()
+        .map(|&d| char::from_digit(u32::from(d), radix).expect("digit below radix"))
+        .collect())
+}
+
+/// Дополнительные данные: только для режимов с аутентификацией (MGM, SIV).
+fn check_aad(mode: EncryptMode, aad: Option<&[u8]>) -> PyResult<&[u8]> {
+    match aad {
+        Some(_) if !matches!(mode, EncryptMode::Mgm | EncryptMode::Siv) => Err(
+            PyValueError::new_err("aad requires an authenticated mode (MGM or SIV)"),
+        ),
+        aad => Ok(aad.unwrap_or_default()),
+    }
+}
+
+/// Срок операции из аргумента `timeout` (секунды).
+fn extract_deadline(timeout: Option<f64>) -> PyResult<Option<deadline::Deadline>> {
+    timeout
+        .map(|timeout| {
+            let timeout = Duration::try_from_secs_f64(timeout)
+                .map_err(|_| PyValueError::new_err("timeout must be a non-negative number"))?;
+            deadline::Deadline::after(timeout)
+                .ok_or_else(|| PyValueError::new_err("timeout is too large"))
+        })
+        .transpose()
+}
+
+/// Режим из `encrypt_mode` и аргументов `nonce`/`counter_start`: строка
+/// `CTR` с nonce заменяется на `CTRParams`.
+fn extract_ctr_mode<'py>(
+    encrypt_mode: Bound<'py, PyAny>,
+    nonce: Option<Bound<'py, PyBytes>>,
+    counter_start: u64,
+) -> PyResult<Bound<'py, PyAny>> {
+    let Some(nonce) = nonce else {
+        if counter_start != 0 {
+            return Err(PyValueError::new_err("counter_start requires a nonce"));
+        }
+        return Ok(encrypt_mode);
+    };
+    let is_ctr = encrypt_mode
+        .extract::<EncryptMode>()
+        .is_ok_and(|mode| mode == EncryptMode::Block(modes::Mode::Ctr));
+    if !is_ctr {
+        return Err(PyValueError::new_err(
+            "nonce requires the CTR mode given as a string or Mode",
+        ));
+    }
+    let params = params::CtrParams::from_nonce(nonce.as_bytes(), counter_start)?;
+
+    Ok(Bound::new(encrypt_mode.py(), params)?.into_any())
+}
+
+/// Схема выравнивания по имени из аргумента `padding`.
+fn extract_padding(name: &str) -> PyResult<modes::Padding> {
+    modes::Padding::from_name(name)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown padding '{name}'")))
+}
+
+/// Ограничение времени: MGM, SIV и CTR-ACPKM обрабатывают данные целиком
+/// и его не поддерживают.
+fn check_deadline(mode: EncryptMode, deadline: Option<deadline::Deadline>) -> PyResult<()> {
+    if deadline.is_some() && !matches!(mode, EncryptMode::Block(_)) {
+        return Err(PyValueError::new_err(format!(
+            "timeout is not supported in {}",
+            mode.name()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Случайный вектор в заголовке вместо `iv`; в CTR-ACPKM не
+/// поддерживается.
+fn check_random_iv(mode: EncryptMode, iv: Option<&[u8]>, random_iv: bool) -> PyResult<()> {
+    if random_iv && iv.is_some() {
+        return Err(PyValueError::new_err("random_iv cannot be used with iv"));
+    }
+    if random_iv && mode == EncryptMode::CtrAcpkm {
+        return Err(PyValueError::new_err(
+            "random_iv is not supported in CTR-ACPKM",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Вектор SIV вырабатывается из текста: `iv` и `random_iv` не
+/// применяются.
+fn check_siv(mode: EncryptMode, iv: Option<&[u8]>, random_iv: bool) -> PyResult<()> {
+    if mode == EncryptMode::Siv && (iv.is_some() || random_iv) {
+        return Err(PyValueError::new_err(
+            "SIV derives its IV from the plaintext; iv and random_iv cannot be used",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Шифрование MGM.
+///
+/// Без nonce генерируется случайный и записывается перед шифротекстом:
+/// `nonce (16) + шифротекст + имитовставка`.
+fn encrypt_mgm(
+    algorithm: engine::Algorithm,
+    plaintext: &[u8],
+    key: &[u8],
+    nonce: Option<&[u8]>,
+    aad: &[u8],
+    tag_len: usize,
+) -> PyResult<Vec<u8>> {
+    if let Some(nonce) = nonce {
+        let nonce = params::mgm_nonce(nonce)?;
+        return rust_to_py_err(engine::encrypting_mgm(
+            algorithm, plaintext, key, &nonce, aad, tag_len,
+        ));
+    }
+
+    let nonce = random_mgm_nonce()?;
+    let sealed = rust_to_py_err(engine::encrypting_mgm(
+        algorithm, plaintext, key, &nonce, aad, tag_len,
+    ))?;
+
+    Ok([&nonce[..], &sealed].concat())
+}
+
+/// Случайный nonce MGM (старший бит равен нулю).
+fn random_mgm_nonce() -> PyResult<[u8; mgm::NONCE_SIZE]> {
+    let mut nonce = [0u8; mgm::NONCE_SIZE];
+    getrandom::getrandom(&mut nonce).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    nonce[0] &= 0x7F;
+
+    Ok(nonce)
+}
+
+/// Проверка имитовставки и дешифровка MGM (см. `encrypt_mgm`).
+fn decrypt_mgm(
+    algorithm: engine::Algorithm,
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: Option<&[u8]>,
+    aad: &[u8],
+    tag_len: usize,
+) -> PyResult<Vec<u8>> {
+    let (nonce, sealed) = match nonce {
+        Some(nonce) => (params::mgm_nonce(nonce)?, ciphertext),
+        None if ciphertext.len() < mgm::NONCE_SIZE => {
+            return decrypt_to_py_err(Err(DataTooShort));
+        }
+        None => {
+            let (nonce, sealed) = ciphertext.split_at(mgm::NONCE_SIZE);
+            (nonce.try_into().expect("nonce prefix"), sealed)
+        }
+    };
+
+    aead_to_py_err(engine::decrypting_mgm(
+        algorithm, sealed, key, &nonce, aad, tag_len,
+    ))
+}
+
+/// Шифратор с аутентификацией (AEAD).
+///
+/// Результат: `nonce (12) + шифротекст + имитовставка (16)`, одноразовый
+/// номер по умолчанию генерируется случайно.
+///
+/// - plaintext — Текст для шифрования
+/// - key — Ключ для шифрования
+/// - cipher — Алгоритм: `chacha20poly1305`
+/// - aad — Дополнительные данные, защищаемые имитовставкой
+/// - nonce — Одноразовый номер (12 байт) для воспроизводимого результата;
+///   не должен повторяться для одного ключа
+#[pyfunction]
+#[pyo3(name = "do_seal")]
+#[pyo3(signature = (plaintext, key, *, cipher="chacha20poly1305", aad=None, nonce=None))]
+fn do_seal<'py>(
+    plaintext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    cipher: &str,
+    aad: Option<Bound<'py, PyBytes>>,
+    nonce: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Vec<u8>> {
+    let algorithm = extract_aead(cipher)?;
+    let (pt, k) = extract_text_and_key(&plaintext, &key, 0, None)?;
+    let nonce = match nonce {
+        Some(nonce) => <[u8; aead::NONCE_SIZE]>::try_from(nonce.as_bytes())
+            .map_err(|_| IVLengthError::new_err("The nonce must be 12 bytes"))?,
+        None => {
+            let mut nonce = [0u8; aead::NONCE_SIZE];
+            getrandom::getrandom(&mut nonce).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+            nonce
+        }
+    };
+    let aad = aad.as_ref().map_or(&[][..], |a| a.as_bytes());
+
+    let (started, size) = (hooks::start(), pt.len());
+    let seal_result = allow_threads_for(plaintext.py(), size, || {
+        aead::seal(algorithm, &k, &nonce, &pt, aad)
+    });
+    hooks::finish(plaintext.py(), "encrypt", size, started);
+
+    aead_to_py_err(seal_result)
+}
+
+/// Проверка и дешифровка результата `do_seal`.
+///
+/// - ciphertext — Результат `do_seal`
+/// - key — Ключ для дешифровки
+/// - cipher — Алгоритм: `chacha20poly1305`
+/// - aad — Дополнительные данные, переданные при шифровании
+#[pyfunction]
+#[pyo3(name = "do_open")]
+#[pyo3(signature = (ciphertext, key, *, cipher="chacha20poly1305", aad=None))]
+fn do_open<'py>(
+    ciphertext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    cipher: &str,
+    aad: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let algorithm = extract_aead(cipher)?;
+    let (ct, k) = extract_text_and_key(&ciphertext, &key, 0, None)?;
+    let aad = aad.as_ref().map_or(&[][..], |a| a.as_bytes());
+
+    let (started, size) = (hooks::start(), ct.len());
+    let open_result = allow_threads_for(ciphertext.py(), size, || {
+        aead::open(algorithm, &k, &ct, aad)
+    });
+    hooks::finish(ciphertext.py(), "decrypt", size, started);
+
+    aead_to_py_err(open_result).map(|data| Output::from_vec(ciphertext.py(), data))
+}
+
+/// Шифрование с имитовставкой HMAC-Стрибог (encrypt-then-MAC).
+///
+/// Из ключа выводятся независимые ключи шифрования и имитовставки.
+/// Результат: `iv (случайный, кроме ECB) + шифротекст + имитовставка (32)`.
+///
+/// - plaintext — Текст для шифрования
+/// - key — Ключ (произвольной длины)
+/// - encrypt_mode — Режим без аутентификации: `ECB`, `CBC`, `CFB`, `OFB`,
+///   `CTR`
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - aad — Дополнительные данные, защищаемые имитовставкой
+/// - order — `encrypt-then-mac` (по умолчанию) или устаревший
+///   `mac-then-encrypt` для совместимости с протоколами, которые его
+///   требуют
+/// - mac — Имитовставка: `hmac` (HMAC-Стрибог-256, по умолчанию) или
+///   `cmac` (ГОСТ Р 34.13-2015 тем же шифром, в размер блока)
+#[pyfunction]
+#[pyo3(name = "seal")]
+#[pyo3(signature = (plaintext, key, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, aad=None, order="encrypt-then-mac", mac="hmac"))]
+fn seal<'py>(
+    plaintext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: EncryptMode,
+    cipher: engine::Algorithm,
+    aad: Option<Bound<'py, PyBytes>>,
+    order: &str,
+    mac: &str,
+) -> PyResult<Vec<u8>> {
+    let mode = rust_to_py_err(encrypt_mode.block())?;
+    let algorithm = cipher;
+    let options = extract_etm_options(order, mac)?;
+    let (pt, k) = extract_text_and_key(&plaintext, &key, 0, None)?;
+    let aad = aad.as_ref().map_or(&[][..], |a| a.as_bytes());
+    let mut iv = vec![0u8; algorithm.block_size()];
+    if mode.uses_iv() {
+        getrandom::getrandom(&mut iv).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    } else {
+        iv.clear();
+    }
+
+    let (started, size) = (hooks::start(), pt.len());
+    let seal_result = allow_threads_for(plaintext.py(), size, || {
+        etm::seal(algorithm, &k, mode, &iv, &pt, aad, options)
+    });
+    hooks::finish(plaintext.py(), "encrypt", size, started);
+
+    aead_to_py_err(seal_result)
+}
+
+/// Проверка имитовставки и дешифровка результата `seal`.
+///
+/// Неверная имитовставка вызывает `DecryptionError`, дешифровка при
+/// этом не выполняется.
+///
+/// - ciphertext — Результат `seal`
+/// - key — Ключ
+/// - encrypt_mode — Режим, использованный при шифровании
+/// - cipher — Шифр, использованный при шифровании
+/// - aad — Дополнительные данные, переданные при шифровании
+/// - order, mac — Порядок и имитовставка, использованные при шифровании
+#[pyfunction]
+#[pyo3(name = "open")]
+#[pyo3(signature = (ciphertext, key, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, aad=None, order="encrypt-then-mac", mac="hmac"))]
+fn open<'py>(
+    ciphertext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: EncryptMode,
+    cipher: engine::Algorithm,
+    aad: Option<Bound<'py, PyBytes>>,
+    order: &str,
+    mac: &str,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mode = rust_to_py_err(encrypt_mode.block())?;
+    let algorithm = cipher;
+    let options = extract_etm_options(order, mac)?;
+    let (ct, k) = extract_text_and_key(&ciphertext, &key, 0, None)?;
+    let aad = aad.as_ref().map_or(&[][..], |a| a.as_bytes());
+
+    let (started, size) = (hooks::start(), ct.len());
+    let open_result = allow_threads_for(ciphertext.py(), size, || {
+        etm::open(algorithm, &k, mode, &ct, aad, options)
+    });
+    hooks::finish(ciphertext.py(), "decrypt", size, started);
+
+    aead_to_py_err(open_result).map(|data| Output::from_vec(ciphertext.py(), data))
+}
+
+/// Новый ключ данных для конвертного шифрования.
+///
+/// Возвращает кортеж: ключ данных (32 байта) для шифрования и он же,
+/// обёрнутый мастер-ключом, для хранения рядом с шифровкой.
+///
+/// - master_key — Мастер-ключ
+#[pyfunction]
+#[pyo3(name = "generate_data_key")]
+#[pyo3(signature = (master_key))]
+fn generate_data_key<'py>(
+    py: Python<'py>,
+    master_key: Bound<'py, PyBytes>,
+) -> PyResult<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+    let master_key = extract_key(&master_key)?;
+    let mut data_key = Zeroizing::new(vec![0u8; engine::KEY_SIZE]);
+    getrandom::getrandom(&mut data_key).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    let wrapped = rust_to_py_err(envelope::wrap(&master_key, &data_key))?;
+
+    Ok((PyBytes::new(py, &data_key), PyBytes::new(py, &wrapped)))
+}
+
+/// Проверка и развёртывание ключа данных, обёрнутого `generate_data_key`.
+///
+/// - master_key — Мастер-ключ
+/// - wrapped_key — Обёрнутый ключ данных
+#[pyfunction]
+#[pyo3(name = "unwrap_data_key")]
+#[pyo3(signature = (master_key, wrapped_key))]
+fn unwrap_data_key<'py>(
+    py: Python<'py>,
+    master_key: Bound<'py, PyBytes>,
+    wrapped_key: Bound<'py, PyBytes>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let master_key = extract_key(&master_key)?;
+    let data_key = aead_to_py_err(envelope::unwrap(&master_key, wrapped_key.as_bytes()))?;
+
+    Ok(PyBytes::new(py, &data_key))
+}
+
+/// Экспорт ключа KExp15 (Р 1323565.1.017-2018) на "Кузнечике".
+///
+/// Возвращает `iv (8) + экспортированный ключ + имитовставка (16)`.
+///
+/// - kek — Ключ экспорта (32 байта): `K_Exp_ENC` при `mac_key`, иначе из
+///   него выводятся оба ключа KDF_GOSTR3411_2012_256
+/// - key_to_wrap — Экспортируемый ключ
+/// - mac_key — Ключ имитовставки `K_Exp_MAC` (32 байта)
+/// - iv — Вектор инициализации (8 байт); по умолчанию случайный
+#[pyfunction]
+#[pyo3(name = "wrap_key")]
+#[pyo3(signature = (kek, key_to_wrap, *, mac_key=None, iv=None))]
+fn wrap_key<'py>(
+    py: Python<'py>,
+    kek: Bound<'py, PyBytes>,
+    key_to_wrap: Bound<'py, PyBytes>,
+    mac_key: Option<Bound<'py, PyBytes>>,
+    iv: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let keys = extract_kexp_keys(&kek, mac_key.as_ref())?;
+    let key = extract_key(&key_to_wrap)?;
+    let iv: [u8; kexp::IV_SIZE] = match iv {
+        Some(iv) => extract_kexp_iv(iv.as_bytes())?,
+        None => extract_kexp_iv(&random_bytes(kexp::IV_SIZE)?)?,
+    };
+    let wrapped = rust_to_py_err(kexp::kexp15(&key, &keys.mac, &keys.enc, &iv))?;
+
+    Ok(PyBytes::new(py, &[&iv[..], &wrapped].concat()))
+}
+
+/// Импорт ключа KImp15, экспортированного `wrap_key`.
+///
+/// Неверная имитовставка вызывает `DecryptionError`.
+///
+/// - kek — Ключ экспорта (см. `wrap_key`)
+/// - wrapped — Результат `wrap_key`
+/// - mac_key — Ключ имитовставки, переданный `wrap_key`
+#[pyfunction]
+#[pyo3(name = "unwrap_key")]
+#[pyo3(signature = (kek, wrapped, *, mac_key=None))]
+fn unwrap_key<'py>(
+    py: Python<'py>,
+    kek: Bound<'py, PyBytes>,
+    wrapped: Bound<'py, PyBytes>,
+    mac_key: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let keys = extract_kexp_keys(&kek, mac_key.as_ref())?;
+    let wrapped = wrapped.as_bytes();
+    if wrapped.len() < kexp::IV_SIZE {
+        return decrypt_to_py_err(Err(DataTooShort));
+    }
+    let (iv, wrapped) = wrapped.split_at(kexp::IV_SIZE);
+    let iv = extract_kexp_iv(iv)?;
+    let key = aead_to_py_err(kexp::kimp15(wrapped, &keys.mac, &keys.enc, &iv))?;
+
+    Ok(PyBytes::new(py, &key))
+}
+
+/// Ключи имитовставки и шифрования KExp15.
+struct KexpKeys {
+    mac: Zeroizing<Vec<u8>>,
+    enc: Zeroizing<Vec<u8>>,
+}
+
+/// Ключи KExp15 из `kek` и `mac_key`.
+fn extract_kexp_keys(
+    kek: &Bound<'_, PyBytes>,
+    mac_key: Option<&Bound<'_, PyBytes>>,
+) -> PyResult<KexpKeys> {
+    let kek = extract_key(kek)?;
+    match mac_key {
+        Some(mac_key) => Ok(KexpKeys {
+            mac: extract_key(mac_key)?,
+            enc: kek,
+        }),
+        None => Ok(KexpKeys {
+            mac: kdf::kdf_gostr3411(&kek, b"kexp15 mac", b""),
+            enc: kdf::kdf_gostr3411(&kek, b"kexp15 enc", b""),
+        }),
+    }
+}
+
+/// Вектор KExp15 длиной `kexp::IV_SIZE`.
+fn extract_kexp_iv(iv: &[u8]) -> PyResult<[u8; kexp::IV_SIZE]> {
+    iv.try_into()
+        .map_err(|_| IVLengthError::new_err("The IV must be 8 bytes"))
+}
+
+/// Две доли нового ключа для двойного контроля (`dual`).
+///
+/// Ключ — XOR долей; сам ключ не создаётся ни в Python, ни в Rust.
+#[pyfunction]
+#[pyo3(name = "generate_key_shares")]
+fn generate_key_shares(py: Python<'_>) -> PyResult<(Bound<'_, PyBytes>, Bound<'_, PyBytes>)> {
+    let (share_a, share_b) =
+        dual::generate().map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    Ok((PyBytes::new(py, &share_a), PyBytes::new(py, &share_b)))
+}
+
+/// Разделение существующего ключа на две доли для двойного контроля.
+///
+/// - key — Ключ (32 байта)
+#[pyfunction]
+#[pyo3(name = "split_key")]
+#[pyo3(signature = (key))]
+fn split_key<'py>(
+    py: Python<'py>,
+    key: Bound<'py, PyBytes>,
+) -> PyResult<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+    let key = extract_share(&key)?;
+    let (share_a, share_b) =
+        dual::split(&key).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    Ok((PyBytes::new(py, &share_a), PyBytes::new(py, &share_b)))
+}
+
+/// Шифратор с ключом из двух долей (см. `do_encrypt`).
+///
+/// - share_a, share_b — Доли ключа, хранимые разными людьми
+#[pyfunction]
+#[pyo3(name = "do_encrypt_dual")]
+#[pyo3(signature = (plaintext, share_a, share_b, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, iv=None, random_iv=false, aad=None))]
+#[allow(clippy::too_many_arguments)]
+fn do_encrypt_dual<'py>(
+    plaintext: Bound<'py, PyAny>,
+    share_a: Bound<'py, PyBytes>,
+    share_b: Bound<'py, PyBytes>,
+    encrypt_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    random_iv: bool,
+    aad: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let algorithm = cipher;
+    let key = combine_shares(&share_a, &share_b)?;
+    let pt = extract_text(&plaintext)?;
+
+    encrypt_data(
+        algorithm,
+        &encrypt_mode,
+        iv,
+        random_iv,
+        aad.as_ref().map(|a| a.as_bytes()),
+        Stepwise::default(),
+        None,
+        &pt,
+        &key,
+    )
+}
+
+/// Дешифратор с ключом из двух долей (см. `do_decrypt`).
+///
+/// Без обеих долей дешифровка невозможна: так API обеспечивает двойной
+/// контроль для ценных данных.
+///
+/// - share_a, share_b — Доли ключа, хранимые разными людьми
+#[pyfunction]
+#[pyo3(name = "do_decrypt_dual")]
+#[pyo3(signature = (ciphertext, share_a, share_b, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, iv=None, random_iv=false, aad=None))]
+#[allow(clippy::too_many_arguments)]
+fn do_decrypt_dual<'py>(
+    ciphertext: Bound<'py, PyAny>,
+    share_a: Bound<'py, PyBytes>,
+    share_b: Bound<'py, PyBytes>,
+    encrypt_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    random_iv: bool,
+    aad: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let algorithm = cipher;
+    let key = combine_shares(&share_a, &share_b)?;
+    let ct = extract_text(&ciphertext)?;
+
+    decrypt_data(
+        algorithm,
+        &encrypt_mode,
+        iv,
+        random_iv,
+        aad.as_ref().map(|a| a.as_bytes()),
+        Stepwise::default(),
+        None,
+        &ct,
+        &key,
+    )
+}
+
+/// Ключ из долей: обе по 32 байта и не совпадают.
+///
+/// Одинаковые доли дают нулевой ключ и означают, что обе хранит один
+/// человек.
+fn combine_shares(
+    share_a: &Bound<'_, PyBytes>,
+    share_b: &Bound<'_, PyBytes>,
+) -> PyResult<Zeroizing<Vec<u8>>> {
+    let (share_a, share_b) = (extract_share(share_a)?, extract_share(share_b)?);
+    if bool::from(share_a.as_slice().ct_eq(share_b.as_slice())) {
+        return Err(PyValueError::new_err("key shares must be different"));
+    }
+
+    Ok(dual::combine(&share_a, &share_b))
+}
+
+/// Доля ключа длиной `KEY_SIZE`.
+fn extract_share(share: &Bound<'_, PyBytes>) -> PyResult<Zeroizing<Vec<u8>>> {
+    if share.as_bytes().len() != engine::KEY_SIZE {
+        return Err(KeyLengthError::new_err(
+            "The key length is invalid (must be 32 bytes)",
+        ));
+    }
+
+    Ok(Zeroizing::new(share.as_bytes().to_vec()))
+}
+
+/// Непустые данные для шифрования или дешифровки.
+fn extract_text<'a>(text: &'a Bound<'_, PyAny>) -> PyResult<Input<'a>> {
+    let text = extract_input(text, 0, None)?;
+    if text.is_empty() {
+        return Err(PyValueError::new_err("'text' cannot be empty"));
+    }
+
+    Ok(text)
+}
+
+/// Шифратор для сообщения из нескольких частей (scatter/gather).
+///
+/// Части шифруются как одно сообщение: результат тот же, что у
+/// `do_encrypt(b"".join(parts), ...)`, но без промежуточной Python-копии.
+/// В ECB, CBC, CFB, OFB и CTR части копируются сразу в результат; в MGM,
+/// SIV, CTR-ACPKM и с параметрами счётчика CTR сообщение собирается в
+/// одну стираемую копию.
+///
+/// - parts — Список частей сообщения (`bytes` или другие буферы)
+/// - key — Ключ для шифрования
+/// - encrypt_mode — Режим шифрования: строка или объект параметров
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - iv — Вектор инициализации (см. `do_encrypt`)
+/// - random_iv — Случайный вектор в заголовке результата (см.
+///   `do_encrypt`)
+#[pyfunction]
+#[pyo3(name = "do_encrypt_vectored")]
+#[pyo3(signature = (parts, key, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, iv=None, random_iv=false))]
+fn do_encrypt_vectored<'py>(
+    parts: Vec<Bound<'py, PyAny>>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    random_iv: bool,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let py = key.py();
+    let algorithm = cipher;
+    let parts = parts
+        .iter()
+        .map(|part| extract_input(part, 0, None))
+        .collect::<PyResult<Vec<_>>>()?;
+    let parts: Vec<&[u8]> = parts.iter().map(|part| &**part).collect();
+    let size = parts.iter().map(|part| part.len()).sum();
+    if size == 0 {
+        return Err(PyValueError::new_err("'parts' cannot be empty"));
+    }
+    let k = extract_buffer_key(&key)?;
+
+    let (mode, mode_iv) = extract_mode_and_iv(&encrypt_mode, iv.clone())?;
+    let block = match mode {
+        EncryptMode::Block(block) if Stepwise::default().resolve(&encrypt_mode).is_none() => block,
+        _ => {
+            let pt = Zeroizing::new(parts.concat());
+            return encrypt_data(
+                algorithm,
+                &encrypt_mode,
+                iv,
+                random_iv,
+                None,
+                Stepwise::default(),
+                None,
+                &pt,
+                &k,
+            );
+        }
+    };
+    check_random_iv(mode, mode_iv.as_deref(), random_iv)?;
+
+    let started = hooks::start();
+    let random_iv = random_iv
+        .then(|| random_bytes(algorithm.block_size()))
+        .transpose()?;
+    let encryption = allow_threads_for(py, size, || {
+        rust_to_py_err(match &random_iv {
+            Some(iv) => engine::Encryption::framed(algorithm, &k, block, iv, size),
+            None => engine::Encryption::new(algorithm, &k, block, mode_iv.as_deref(), size),
+        })
+    })?;
+    let encrypt_result = PyBytes::new_with(py, encryption.output_len(), |out| {
+        allow_threads_for(py, size, || encryption.finish_parts(&parts, out));
+        Ok(())
+    });
+    hooks::finish(py, "encrypt", size, started);
+
+    encrypt_result
+}
+
+/// Шифрование набора сообщений одним ключом за один вызов.
+///
+/// Сообщения шифруются на стороне Rust без GIL, наборы от
+/// `GRASS_CRYPT_PARALLEL_SIZE` байт — параллельно (см. `do_encrypt`).
+/// Каждый шифротекст дешифруется `do_decrypt` с теми же аргументами или
+/// весь набор — `do_decrypt_batch`.
+///
+/// - messages — Список сообщений (`bytes` или другие буферы)
+/// - key — Ключ для шифрования
+/// - encrypt_mode — Режим шифрования: строка или объект параметров. MGM —
+///   только строкой: nonce случайный для каждого сообщения
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - iv — Вектор инициализации, общий для всех сообщений; одинаковые
+///   сообщения дают одинаковые шифровки, а в CFB, OFB и CTR общий вектор
+///   повторяет гамму. Для разных векторов — `random_iv`
+/// - random_iv — Случайный вектор в заголовке каждого шифротекста
+/// - threads — Число потоков; `1` отключает параллельную обработку
+#[pyfunction]
+#[pyo3(name = "do_encrypt_batch")]
+#[pyo3(signature = (messages, key, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, iv=None, random_iv=false, threads=None))]
+#[allow(clippy::too_many_arguments)]
+fn do_encrypt_batch<'py>(
+    messages: Vec<Bound<'py, PyAny>>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    random_iv: bool,
+    threads: Option<usize>,
+) -> PyResult<Vec<Bound<'py, PyBytes>>> {
+    let py = key.py();
+    let algorithm = cipher;
+    let threads = extract_threads(threads)?;
+    let (mode, iv) = extract_batch_mode(&encrypt_mode, iv)?;
+    check_random_iv(mode, iv.as_deref(), random_iv)?;
+    check_siv(mode, iv.as_deref(), random_iv)?;
+    let section_size = params::section_size(&encrypt_mode);
+    let k = extract_buffer_key(&key)?;
+    let texts = messages
+        .iter()
+        .map(extract_text)
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let (started, size) = (hooks::start(), texts.iter().map(|t| t.len()).sum());
+    let results = allow_threads_for(py, size, || {
+        let threads = engine::parallel_threads(size, threads);
+        batch::parallel_map_in(&texts, threads, |pt| {
+            encrypt_item(
+                algorithm,
+                &k,
+                mode,
+                iv.as_deref(),
+                random_iv,
+                section_size,
+                pt,
+            )
+        })
+    });
+    hooks::finish(py, "encrypt", size, started);
+
+    results
+        .into_iter()
+        .map(|ct| ct.map(|ct| PyBytes::new(py, &ct)))
+        .collect()
+}
+
+/// Дешифровка набора шифротекстов одним ключом за один вызов (см.
+/// `do_encrypt_batch`).
+///
+/// Результат — список открытых текстов в исходном порядке; ошибка в любом
+/// шифротексте прерывает обработку всего набора.
+///
+/// - ciphertexts — Список шифротекстов (`bytes` или другие буферы)
+/// - key — Ключ для дешифровки
+/// - encrypt_mode — Режим шифрования (см. `do_encrypt_batch`)
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - iv — Общий вектор инициализации или `None` для встроенного
+/// - random_iv — Вектор каждого шифротекста в его заголовке (см.
+///   `do_decrypt`)
+/// - threads — Число потоков; `1` отключает параллельную обработку
+#[pyfunction]
+#[pyo3(name = "do_decrypt_batch")]
+#[pyo3(signature = (ciphertexts, key, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, iv=None, random_iv=false, threads=None))]
+fn do_decrypt_batch<'py>(
+    ciphertexts: Vec<Bound<'py, PyAny>>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    random_iv: bool,
+    threads: Option<usize>,
+) -> PyResult<Vec<Bound<'py, PyBytes>>> {
+    let py = key.py();
+    let algorithm = cipher;
+    let threads = extract_threads(threads)?;
+    let (mode, iv) = extract_batch_mode(&encrypt_mode, iv)?;
+    check_random_iv(mode, iv.as_deref(), random_iv)?;
+    check_siv(mode, iv.as_deref(), random_iv)?;
+    let section_size = params::section_size(&encrypt_mode);
+    let k = extract_buffer_key(&key)?;
+    let texts = ciphertexts
+        .iter()
+        .map(extract_text)
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let (started, size) = (hooks::start(), texts.iter().map(|t| t.len()).sum());
+    let results = allow_threads_for(py, size, || {
+        let threads = engine::parallel_threads(size, threads);
+        batch::parallel_map_in(&texts, threads, |ct| {
+            decrypt_item(
+                algorithm,
+                &k,
+                mode,
+                iv.as_deref(),
+                random_iv,
+                section_size,
+                ct,
+            )
+        })
+    });
+    hooks::finish(py, "decrypt", size, started);
+
+    results
+        .into_iter()
+        .map(|pt| pt.map(|pt| PyBytes::new(py, &pt)))
+        .collect()
+}
+
+/// Режим и вектор набора: общий nonce MGM повторялся бы в каждом
+/// сообщении.
+fn extract_batch_mode(
+    encrypt_mode: &Bound<'_, PyAny>,
+    iv: Option<Bound<'_, PyBytes>>,
+) -> PyResult<(EncryptMode, Option<Vec<u8>>)> {
+    let (mode, iv) = extract_mode_and_iv(encrypt_mode, iv)?;
+    if mode == EncryptMode::Mgm && iv.is_some() {
+        return Err(PyValueError::new_err(
+            "a batch cannot share an MGM nonce; pass the mode as the string 'MGM'",
+        ));
+    }
+
+    Ok((mode, iv))
+}
+
+/// Непустой ключ из объекта с протоколом буфера.
+fn extract_buffer_key(key: &Bound<'_, PyAny>) -> PyResult<Zeroizing<Vec<u8>>> {
+    let key = extract_input(key, 0, None)?.into_owned();
+    if key.is_empty() {
+        return Err(PyValueError::new_err("'key' cannot be empty"));
+    }
+
+    Ok(key)
+}
+
+/// Шифрование одного сообщения набора.
+fn encrypt_item(
+    algorithm: engine::Algorithm,
+    key: &[u8],
+    mode: EncryptMode,
+    iv: Option<&[u8]>,
+    random_iv: bool,
+    section_size: usize,
+    pt: &[u8],
+) -> PyResult<Vec<u8>> {
+    let mode = match mode {
+        EncryptMode::Block(mode) => mode,
+        EncryptMode::Mgm => return encrypt_mgm(algorithm, pt, key, None, &[], mgm::TAG_SIZE),
+        EncryptMode::Siv => return rust_to_py_err(engine::encrypting_siv(algorithm, pt, key, &[])),
+        EncryptMode::CtrAcpkm => {
+            return rust_to_py_err(engine::acpkm(algorithm, pt, key, iv, section_size))
+        }
+    };
+    let encryption = rust_to_py_err(if random_iv {
+        let iv = random_bytes(algorithm.block_size())?;
+        engine::Encryption::framed(algorithm, key, mode, &iv, pt.len())
+    } else {
+        engine::Encryption::new(algorithm, key, mode, iv, pt.len())
+    })?;
+    let mut ct = vec![0u8; encryption.output_len()];
+    encryption.finish(pt, &mut ct);
+
+    Ok(ct)
+}
+
+/// Дешифровка одного шифротекста набора; текст стирается после копирования
+/// в Python.
+fn decrypt_item(
+    algorithm: engine::Algorithm,
+    key: &[u8],
+    mode: EncryptMode,
+    iv: Option<&[u8]>,
+    random_iv: bool,
+    section_size: usize,
+    ct: &[u8],
+) -> PyResult<Zeroizing<Vec<u8>>> {
+    let mode = match mode {
+        EncryptMode::Block(mode) => mode,
+        EncryptMode::Mgm => {
+            return decrypt_mgm(algorithm, ct, key, None, &[], mgm::TAG_SIZE).map(Zeroizing::new)
+        }
+        EncryptMode::Siv => {
+            return aead_to_py_err(engine::decrypting_siv(algorithm, ct, key, &[]))
+                .map(Zeroizing::new)
+        }
+        EncryptMode::CtrAcpkm => {
+            return decrypt_to_py_err(engine::acpkm(algorithm, ct, key, iv, section_size))
+                .map(Zeroizing::new)
+        }
+    };
+    let decryption = decrypt_to_py_err(if random_iv {
+        engine::Decryption::framed(algorithm, key, mode, ct)
+    } else {
+        engine::Decryption::new(algorithm, key, mode, iv, ct)
+    })?;
+    let mut pt = Zeroizing::new(vec![0u8; decryption.output_len()]);
+    decryption.finish(&mut pt);
+
+    Ok(pt)
+}
+
+/// Перешифрование набора сообщений новым ключом (смена ключей).
+///
+/// Возвращает итератор с перешифрованными сообщениями в исходном порядке.
+/// Ошибка сообщения без `on_error` поднимается на его месте, следующий
+/// вызов `next` продолжает со следующего сообщения.
+///
+/// - ciphertexts — Итерируемый набор шифротекстов (bytes)
+/// - old_key — Действующий ключ
+/// - new_key — Новый ключ
+/// - encrypt_mode — Режим шифрования
+/// - progress — Необязательный `progress(done: int)` после каждой партии
+/// - batch_size — Размер партии, обрабатываемой параллельно
+/// - on_error — Необязательный `on_error(index: int, error)` для сообщений,
+///   которые не удалось перешифровать; они пропускаются
+#[pyfunction]
+#[pyo3(name = "migrate")]
+#[pyo3(signature = (ciphertexts, old_key, new_key, encrypt_mode, progress=None, batch_size=1024, *, on_error=None))]
+fn migrate<'py>(
+    ciphertexts: &Bound<'py, PyAny>,
+    old_key: Bound<'py, PyBytes>,
+    new_key: Bound<'py, PyBytes>,
+    encrypt_mode: EncryptMode,
+    progress: Option<PyObject>,
+    batch_size: usize,
+    on_error: Option<PyObject>,
+) -> PyResult<migration::Migration> {
+    let mode = rust_to_py_err(encrypt_mode.block())?;
+    let source = PyIterator::from_object(ciphertexts)?.unbind();
+
+    Ok(migration::Migration::new(
+        source,
+        extract_key(&old_key)?,
+        extract_key(&new_key)?,
+        mode,
+        progress,
+        on_error,
+        batch_size,
+    ))
+}
+
+/// Перешифрование сообщения без передачи открытого текста в Python.
+///
+/// Сообщение дешифруется и сразу шифруется заново на стороне Rust (как
+/// в `migrate`), открытый текст не возвращается в Python.
+///
+/// - ciphertext — Шифротекст `do_encrypt`
+/// - key — Ключ для дешифровки
+/// - encrypt_mode — Режим шифротекста (см. `do_decrypt`)
+/// - new_key — Ключ результата
+/// - new_mode — Режим результата (см. `do_encrypt`)
+/// - cipher — Шифр сообщения и результата (см. `do_encrypt`)
+/// - random_iv — Вектор шифротекста в заголовке (см. `do_decrypt`)
+/// - aad — Дополнительные данные шифротекста (только MGM и SIV)
+/// - new_aad — Дополнительные данные результата (только MGM и SIV)
+#[pyfunction]
+#[pyo3(name = "do_reencrypt")]
+#[pyo3(signature = (ciphertext, key, encrypt_mode, new_key, new_mode, *, cipher=engine::Algorithm::Kuznyechik, random_iv=false, aad=None, new_aad=None))]
+#[allow(clippy::too_many_arguments)]
+fn do_reencrypt<'py>(
+    ciphertext: Bound<'py, PyAny>,
+    key: Bound<'py, PyAny>,
+    encrypt_mode: Bound<'py, PyAny>,
+    new_key: Bound<'py, PyBytes>,
+    new_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    random_iv: bool,
+    aad: Option<Bound<'py, PyBytes>>,
+    new_aad: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let algorithm = cipher;
+    let (ct, k) = extract_text_and_key(&ciphertext, &key, 0, None)?;
+    let new_k = extract_key(&new_key)?;
+    let aad = aad.as_ref().map(|a| a.as_bytes());
+    let new_aad = new_aad.as_ref().map(|a| a.as_bytes());
+
+    let plaintext = Zeroizing::new(decrypt_data::<Vec<u8>>(
+        algorithm,
+        &encrypt_mode,
+        None,
+        random_iv,
+        aad,
+        Stepwise::default(),
+        None,
+        &ct,
+        &k,
+    )?);
+    encrypt_data(
+        algorithm,
+        &new_mode,
+        None,
+        false,
+        new_aad,
+        Stepwise::default(),
+        None,
+        &plaintext,
+        &new_k,
+    )
+}
+
+/// Выработка ключа шифрования из пароля.
+///
+/// - password — Пароль (для `kdf_gostr3411` — исходный ключ)
+/// - salt — Соль (для `kdf_gostr3411` — `seed`), случайная для каждого
+///   пароля
+/// - iterations — Число итераций PBKDF2 (от 1); для `kdf_gostr3411`
+///   не используется
+/// - kdf — Функция: `pbkdf2` (PBKDF2-HMAC-Стрибог-512, по умолчанию) или
+///   `kdf_gostr3411` (KDF_GOSTR3411_2012_256)
+/// - label — Метка назначения ключа (только `kdf_gostr3411`)
+///
+/// Возвращает ключ длиной 32 байта для `do_encrypt`.
+#[pyfunction]
+#[pyo3(name = "derive_key")]
+#[pyo3(signature = (password, salt, iterations, kdf="pbkdf2", *, label=None))]
+fn derive_key<'py>(
+    py: Python<'py>,
+    password: Bound<'py, PyBytes>,
+    salt: Bound<'py, PyBytes>,
+    iterations: u32,
+    kdf: &str,
+    label: Option<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let kdf = extract_kdf(kdf)?;
+    let password = extract_key(&password)?;
+    let salt = salt.as_bytes().to_vec();
+    if iterations == 0 {
+        return Err(PyValueError::new_err("iterations must be positive"));
+    }
+    if label.is_some() && kdf != kdf::Kdf::GostR3411 {
+        return Err(PyValueError::new_err("label requires kdf_gostr3411"));
+    }
+    let label = label.as_ref().map_or(&[][..], |l| l.as_bytes());
+
+    let key = match kdf {
+        kdf::Kdf::Pbkdf2 => py.allow_threads(|| kdf::pbkdf2(&password, &salt, iterations)),
+        kdf::Kdf::GostR3411 => kdf::kdf_gostr3411(&password, label, &salt),
+    };
+
+    Ok(PyBytes::new(py, &key))
+}
+
+/// Функция выработки ключа по имени из аргумента `kdf`.
+fn extract_kdf(name: &str) -> PyResult<kdf::Kdf> {
+    kdf::Kdf::from_name(name).ok_or_else(|| PyValueError::new_err(format!("unknown kdf '{name}'")))
+}
+
+/// Имитовставка HMAC-Стрибог-256 для данных.
+///
+/// - data — Данные
+/// - key — Ключ имитовставки
+#[pyfunction]
+#[pyo3(name = "do_mac")]
+#[pyo3(signature = (data, key))]
+fn do_mac<'py>(data: Bound<'py, PyBytes>, key: Bound<'py, PyBytes>) -> PyResult<Vec<u8>> {
+    let key = extract_key(&key)?;
+    let bytes = data.as_bytes();
+
+    Ok(allow_threads_for(data.py(), bytes.len(), || {
+        mac::mac_data(bytes, &key)
+    }))
+}
+
+/// Имитовставка CMAC (ГОСТ Р 34.13-2015) для данных.
+///
+/// - key — Ключ шифра (32 байта)
+/// - data — Данные
+/// - tag_len — Длина имитовставки: от 1 до размера блока (16 байт, для
+///   `magma` — 8)
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+#[pyfunction]
+#[pyo3(name = "cmac")]
+#[pyo3(signature = (key, data, tag_len=16, *, cipher=engine::Algorithm::Kuznyechik))]
+fn do_cmac<'py>(
     key: Bound<'py, PyBytes>,
-    encrypt_mode: Bound<'py, PyString>,
+    data: Bound<'py, PyBytes>,
+    tag_len: usize,
+    cipher: engine::Algorithm,
 ) -> PyResult<Vec<u8>> {
-    let (ct, k) = extract_text_and_key(&ciphertext, &key)?;
-    let decrypt_result = engine::decrypting(ct, k, to_string(&encrypt_mode));
+    let algorithm = cipher;
+    if !(1..=algorithm.block_size()).contains(&tag_len) {
+        return Err(PyValueError::new_err(format!(
+            "tag_len must be from 1 to {}",
+            algorithm.block_size()
+        )));
+    }
+    let key = extract_key(&key)?;
+
+    rust_to_py_err(engine::cmac(algorithm, &key, &[data.as_bytes()], tag_len))
+}
+
+/// Имитовставка HMAC-Стрибог-256 для файла.
+///
+/// Файл читается потоково на стороне Rust без удержания GIL. Между частями
+/// файла проверяются сигналы Python, поэтому Ctrl-C прерывает расчёт.
+///
+/// - path — Путь к файлу
+/// - key — Ключ имитовставки
+#[pyfunction]
+#[pyo3(name = "mac_file")]
+#[pyo3(signature = (path, key))]
+fn mac_file(py: Python<'_>, path: PathBuf, key: Bound<'_, PyBytes>) -> PyResult<Vec<u8>> {
+    let key = extract_key(&key)?;
+
+    py.allow_threads(|| mac::mac_file(&path, &key, check_signals))
+}
+
+/// Шифрование файла в файл.
+///
+/// Файл читается и записывается частями на стороне Rust без удержания GIL,
+/// между частями проверяются сигналы Python. Результат совпадает с
+/// `do_encrypt` для содержимого файла (без метастроки).
+///
+/// - src_path — Исходный файл
+/// - dst_path — Файл шифровки
+/// - key — Ключ для шифрования
+/// - encrypt_mode — Режим шифрования: строка или объект параметров (`params`)
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - iv — Вектор инициализации (16 байт, для `magma` — 8); по умолчанию
+///   встроенный
+/// - overwrite — Перезаписать существующий `dst_path`
+/// - timeout — Ограничение времени в секундах, проверяется вместе с
+///   сигналами (`OperationTimeoutError`)
+/// - padding — Выравнивание ECB и CBC (см. `do_encrypt`)
+///
+/// Возвращает размер записанного файла.
+#[pyfunction]
+#[pyo3(name = "encrypt_file")]
+#[pyo3(signature = (src_path, dst_path, key, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, iv=None, overwrite=false, timeout=None, padding="PKCS7"))]
+#[allow(clippy::too_many_arguments)]
+fn encrypt_file<'py>(
+    py: Python<'py>,
+    src_path: PathBuf,
+    dst_path: PathBuf,
+    key: Bound<'py, PyBytes>,
+    encrypt_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    overwrite: bool,
+    timeout: Option<f64>,
+    padding: &str,
+) -> PyResult<u64> {
+    let deadline = extract_deadline(timeout)?;
+    let stream = file_stream(&key, &encrypt_mode, cipher, iv, padding, true)?;
+
+    py.allow_threads(|| {
+        files::transform_file(&src_path, &dst_path, stream, overwrite, |_| {
+            check_interrupt(deadline)
+        })
+    })
+}
+
+/// Дешифровка файла в файл (см. `encrypt_file`).
+///
+/// При ошибке, в том числе неверном ключе, неполный `dst_path` удаляется.
+#[pyfunction]
+#[pyo3(name = "decrypt_file")]
+#[pyo3(signature = (src_path, dst_path, key, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, iv=None, overwrite=false, timeout=None, padding="PKCS7"))]
+#[allow(clippy::too_many_arguments)]
+fn decrypt_file<'py>(
+    py: Python<'py>,
+    src_path: PathBuf,
+    dst_path: PathBuf,
+    key: Bound<'py, PyBytes>,
+    encrypt_mode: Bound<'py, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'py, PyBytes>>,
+    overwrite: bool,
+    timeout: Option<f64>,
+    padding: &str,
+) -> PyResult<u64> {
+    let deadline = extract_deadline(timeout)?;
+    let stream = file_stream(&key, &encrypt_mode, cipher, iv, padding, false)?;
+
+    py.allow_threads(|| {
+        files::transform_file(&src_path, &dst_path, stream, overwrite, |_| {
+            check_interrupt(deadline)
+        })
+    })
+}
+
+/// Пошаговый шифровальщик из аргументов `encrypt_file`/`decrypt_file`.
+fn file_stream(
+    key: &Bound<'_, PyBytes>,
+    encrypt_mode: &Bound<'_, PyAny>,
+    cipher: engine::Algorithm,
+    iv: Option<Bound<'_, PyBytes>>,
+    padding: &str,
+    encrypting: bool,
+) -> PyResult<engine::Streaming> {
+    let algorithm = cipher;
+    let options = modes::Options {
+        padding: extract_padding(padding)?,
+        counter: params::counter(encrypt_mode),
+    };
+    let (mode, iv) = extract_mode_and_iv(encrypt_mode, iv)?;
+    let mode = rust_to_py_err(mode.block())?;
+    let key = extract_key(key)?;
+
+    rust_to_py_err(engine::Streaming::new(
+        algorithm,
+        &key,
+        mode,
+        iv.as_deref(),
+        options,
+        encrypting,
+    ))
+}
+
+/// Режим и вектор инициализации из `encrypt_mode` и аргумента `iv`.
+///
+/// Длина вектора проверяется движком (`IVLengthError`).
+fn extract_mode_and_iv(
+    encrypt_mode: &Bound<'_, PyAny>,
+    iv: Option<Bound<'_, PyBytes>>,
+) -> PyResult<(engine::EncryptMode, Option<Vec<u8>>)> {
+    let (mode, mode_iv) = params::extract(encrypt_mode)?;
+    match (mode_iv, iv) {
+        (Some(_), Some(_)) => Err(PyValueError::new_err(
+            "iv is given both in encrypt_mode and as an argument",
+        )),
+        (Some(mode_iv), None) => Ok((mode, Some(mode_iv))),
+        (None, iv) => Ok((mode, iv.map(|iv| iv.as_bytes().to_vec()))),
+    }
+}
+
+/// Алгоритм AEAD по имени из аргумента `cipher`.
+fn extract_aead(name: &str) -> PyResult<aead::Algorithm> {
+    aead::Algorithm::from_name(name)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown AEAD cipher '{name}'")))
+}
+
+/// Порядок и имитовставка `seal`/`open` из аргументов `order` и `mac`.
+fn extract_etm_options(order: &str, mac: &str) -> PyResult<etm::Options> {
+    Ok(etm::Options {
+        order: etm::Order::from_name(order)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown order '{order}'")))?,
+        mac: etm::Mac::from_name(mac)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown mac '{mac}'")))?,
+    })
+}
+
+/// Стирание кешей подготовленных шифровальщиков во всех потоках.
+///
+/// Кеши `do_encrypt`/`do_decrypt` хранят развёрнутые ключи. Записи, не
+/// использованные дольше времени хранения (`set_engine_cache_ttl`, по
+/// умолчанию минута), удаляются при следующем обращении к кешу из любого
+/// потока, а эта функция стирает все записи немедленно (например, после
+/// смены ключей). Кеш, занятый операцией, стирается по её завершении.
+#[pyfunction]
+#[pyo3(name = "clear_engine_cache")]
+fn clear_engine_cache(py: Python<'_>) {
+    py.allow_threads(cache::clear);
+}
+
+/// Время хранения неиспользуемых записей кеша шифровальщиков.
+///
+/// Записи старше нового времени стираются сразу во всех потоках, `0`
+/// отключает кеш.
+///
+/// - seconds — Время хранения в секундах
+#[pyfunction]
+#[pyo3(name = "set_engine_cache_ttl")]
+#[pyo3(signature = (seconds))]
+fn set_engine_cache_ttl(py: Python<'_>, seconds: f64) -> PyResult<()> {
+    let ttl = Duration::try_from_secs_f64(seconds)
+        .map_err(|_| PyValueError::new_err("seconds must be a non-negative number"))?;
+
+    py.allow_threads(|| cache::set_idle_ttl(ttl));
+    Ok(())
+}
+
+/// Проверка сигналов Python из потока без GIL (для длительных операций).
+fn check_signals() -> PyResult<()> {
+    Python::with_gil(|py| py.check_signals())
+}
+
+/// Проверка сигналов и срока операции (`timeout`).
+fn check_interrupt(deadline: Option<deadline::Deadline>) -> PyResult<()> {
+    check_signals()?;
+    deadline.map_or(Ok(()), |deadline| {
+        deadline_to_py_err(deadline.check(), false)
+    })
+}
+
+/// Размер шифротекста для открытого текста заданной длины.
+///
+/// Если размер не помещается в `usize`, возникает `ValueError`.
+///
+/// - plaintext_len — Длина открытого текста в байтах
+/// - encrypt_mode — Режим шифрования
+/// - cipher — Шифр (размер блока определяет выравнивание)
+/// - padding — Выравнивание ECB и CBC
+#[pyfunction]
+#[pyo3(name = "ciphertext_len")]
+#[pyo3(signature = (plaintext_len, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, padding="PKCS7"))]
+fn ciphertext_len(
+    plaintext_len: usize,
+    encrypt_mode: EncryptMode,
+    cipher: engine::Algorithm,
+    padding: &str,
+) -> PyResult<usize> {
+    let padding = extract_padding(padding)?;
+    engine::ciphertext_len(cipher, padding, plaintext_len, encrypt_mode)
+        .ok_or_else(|| PyValueError::new_err("plaintext_len is too large"))
+}
+
+/// Размер открытого текста для шифротекста заданной длины.
+///
+/// Для режимов с выравниванием (ECB, CBC) — максимально возможный.
+///
+/// - ciphertext_len — Длина шифротекста в байтах
+/// - encrypt_mode — Режим шифрования
+/// - cipher — Шифр
+/// - padding — Выравнивание ECB и CBC
+#[pyfunction]
+#[pyo3(name = "plaintext_len")]
+#[pyo3(signature = (ciphertext_len, encrypt_mode, *, cipher=engine::Algorithm::Kuznyechik, padding="PKCS7"))]
+fn plaintext_len(
+    ciphertext_len: usize,
+    encrypt_mode: EncryptMode,
+    cipher: engine::Algorithm,
+    padding: &str,
+) -> PyResult<usize> {
+    let padding = extract_padding(padding)?;
+    rust_to_py_err(engine::plaintext_len(
+        cipher,
+        padding,
+        ciphertext_len,
+        encrypt_mode,
+    ))
+}
+
+/// Режимы шифрования для списков выбора и проверки настроек без жёстко
+/// заданных строк: ECB, CBC, CFB, OFB, CTR, MGM (кроме `magma`), SIV и
+/// CTR-ACPKM.
+///
+/// Возвращает список словарей с ключами `name` (значение `encrypt_mode`),
+/// `iv_length` (длина `iv`, в MGM — nonce; 0 — вектор не передаётся),
+/// `block_size`, `requires_padding` (ECB и CBC), `authenticated`,
+/// `tag_length` и `min_tag_length` (длина имитовставки по умолчанию и
+/// наименьшая, `MGMParams(tag_len=...)`; 0 — без имитовставки).
+///
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+#[pyfunction]
+#[pyo3(name = "supported_modes")]
+#[pyo3(signature = (*, cipher=engine::Algorithm::Kuznyechik))]
+fn supported_modes(py: Python<'_>, cipher: engine::Algorithm) -> PyResult<Vec<Py<PyDict>>> {
+    engine::supported_modes(cipher)
+        .into_iter()
+        .map(|mode| {
+            let info = PyDict::new(py);
+            info.set_item("name", mode.name)?;
+            info.set_item("iv_length", mode.iv_length)?;
+            info.set_item("block_size", cipher.block_size())?;
+            info.set_item("requires_padding", mode.requires_padding)?;
+            info.set_item("authenticated", mode.tag_length > 0)?;
+            info.set_item("tag_length", mode.tag_length)?;
+            info.set_item("min_tag_length", mode.min_tag_length)?;
+            Ok(info.unbind())
+        })
+        .collect()
+}
 
-    Ok(rust_to_py_err(decrypt_result)?)
+/// Поддерживаемые шифры.
+///
+/// Возвращает список словарей с ключами `name` (значение `cipher`),
+/// `block_size`, `key_size` и `iv_length` (вектор — в размер блока).
+#[pyfunction]
+#[pyo3(name = "supported_ciphers")]
+fn supported_ciphers(py: Python<'_>) -> PyResult<Vec<Py<PyDict>>> {
+    engine::Algorithm::ALL
+        .into_iter()
+        .map(|algorithm| {
+            let info = PyDict::new(py);
+            info.set_item("name", algorithm.name())?;
+            info.set_item("block_size", algorithm.block_size())?;
+            info.set_item("key_size", engine::KEY_SIZE)?;
+            info.set_item("iv_length", algorithm.block_size())?;
+            Ok(info.unbind())
+        })
+        .collect()
 }
 
 /// Модуль, который может быть импортирован в Python.
@@ -74,11 +2102,119 @@ fn do_decrypt<'py>(
 fn cryptor(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(do_encrypt, m)?)?;
     m.add_function(wrap_pyfunction!(do_decrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_range, m)?)?;
+    m.add_function(wrap_pyfunction!(encrypt_fpe, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_fpe, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_engine_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(set_engine_cache_ttl, m)?)?;
+    m.add_function(wrap_pyfunction!(do_seal, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_data_key, m)?)?;
+    m.add_function(wrap_pyfunction!(unwrap_data_key, m)?)?;
+    m.add_function(wrap_pyfunction!(wrap_key, m)?)?;
+    m.add_function(wrap_pyfunction!(unwrap_key, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_key_shares, m)?)?;
+    m.add_function(wrap_pyfunction!(split_key, m)?)?;
+    m.add_function(wrap_pyfunction!(do_encrypt_dual, m)?)?;
+    m.add_function(wrap_pyfunction!(do_decrypt_dual, m)?)?;
+    m.add_function(wrap_pyfunction!(do_open, m)?)?;
+    m.add_function(wrap_pyfunction!(seal, m)?)?;
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_function(wrap_pyfunction!(do_encrypt_vectored, m)?)?;
+    m.add_function(wrap_pyfunction!(do_encrypt_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(do_decrypt_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(migrate, m)?)?;
+    m.add_class::<migration::Migration>()?;
+    m.add_function(wrap_pyfunction!(do_reencrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(transcode::transcode, m)?)?;
+    m.add_function(wrap_pyfunction!(do_mac, m)?)?;
+    m.add_function(wrap_pyfunction!(do_cmac, m)?)?;
+    m.add_function(wrap_pyfunction!(legacy::legacy_encrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(legacy::legacy_decrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(derive_key, m)?)?;
+    m.add_function(wrap_pyfunction!(mac_file, m)?)?;
+    m.add_function(wrap_pyfunction!(encrypt_file, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_file, m)?)?;
+    m.add_function(wrap_pyfunction!(ciphertext_len, m)?)?;
+    m.add_function(wrap_pyfunction!(plaintext_len, m)?)?;
+    m.add_function(wrap_pyfunction!(supported_modes, m)?)?;
+    m.add_function(wrap_pyfunction!(supported_ciphers, m)?)?;
+    m.add_function(wrap_pyfunction!(hooks::set_timing_hook, m)?)?;
+    m.add_function(wrap_pyfunction!(set_debug_errors, m)?)?;
+    m.add_function(wrap_pyfunction!(selftest::run_self_test, m)?)?;
+    m.add_function(wrap_pyfunction!(hash::streebog256, m)?)?;
+    m.add_function(wrap_pyfunction!(hash::streebog512, m)?)?;
+    m.add_function(wrap_pyfunction!(hash::hmac_streebog, m)?)?;
+    m.add_function(wrap_pyfunction!(hash::hmac_streebog_verify, m)?)?;
+    m.add_function(wrap_pyfunction!(cms::encrypt_cms, m)?)?;
+    m.add_function(wrap_pyfunction!(cms::decrypt_cms, m)?)?;
+    m.add_function(wrap_pyfunction!(fields::encrypt_fields, m)?)?;
+    m.add_function(wrap_pyfunction!(fields::decrypt_fields, m)?)?;
+    m.add_class::<hash::Hasher>()?;
+    m.add_class::<hash::MacHasher>()?;
+    m.add_class::<buffer::EncryptedBuffer>()?;
+    m.add_class::<compat::CompatCipher>()?;
+    m.add_class::<stream::GrassCipher>()?;
+    m.add_class::<key::GrassKey>()?;
+    m.add_class::<key::CipherSession>()?;
+    m.add("MODE_ECB", compat::MODE_ECB)?;
+    m.add("MODE_CBC", compat::MODE_CBC)?;
+    m.add("MODE_CFB", compat::MODE_CFB)?;
+    m.add("MODE_OFB", compat::MODE_OFB)?;
+    m.add("MODE_CTR", compat::MODE_CTR)?;
+    m.add("IV_HEADER_LEN", engine::FRAME_HEADER_SIZE)?;
+    #[cfg(feature = "testing")]
+    {
+        m.add_function(wrap_pyfunction!(tamper::tamper_byte, m)?)?;
+        m.add_function(wrap_pyfunction!(tamper::tamper_range, m)?)?;
+    }
+    #[cfg(feature = "benchmarks")]
+    m.add_function(wrap_pyfunction!(bench::run_benchmarks, m)?)?;
+    m.add_class::<params::Mode>()?;
+    m.add_class::<params::Cipher>()?;
+    m.add_class::<params::EcbParams>()?;
+    m.add_class::<params::CbcParams>()?;
+    m.add_class::<params::CfbParams>()?;
+    m.add_class::<params::OfbParams>()?;
+    m.add_class::<params::CtrParams>()?;
+    m.add_class::<params::MgmParams>()?;
+    m.add_class::<params::AcpkmParams>()?;
     Ok(())
 }
 
 /// Конвертер ошибок Rust-библиотеки в Python-исключения.
-fn rust_to_py_err(result: Result<Vec<u8>, CipherError>) -> Result<Vec<u8>, PyErr> {
+///
+/// Ошибки данных возникают только при проверке и дешифровке, поэтому
+/// преобразуются как в `decrypt_to_py_err`.
+fn aead_to_py_err<T>(result: Result<T, aead::Error>) -> Result<T, PyErr> {
+    match result {
+        Ok(r) => Ok(r),
+        Err(aead::Error::Cipher(e)) => decrypt_to_py_err(Err(e)),
+        Err(aead::Error::Authentication) => Err(decryption_error(AuthenticationError::new_err(
+            "The authentication tag is invalid",
+        ))),
+    }
+}
+
+/// Конвертер ошибок операций с ограничением времени.
+///
+/// - decrypting — Путь дешифровки (см. `decrypt_to_py_err`)
+fn deadline_to_py_err<T>(result: Result<T, deadline::Error>, decrypting: bool) -> Result<T, PyErr> {
+    match result {
+        Ok(r) => Ok(r),
+        Err(deadline::Error::Cipher(e)) if decrypting => decrypt_to_py_err(Err(e)),
+        Err(deadline::Error::Cipher(e)) => rust_to_py_err(Err(e)),
+        Err(deadline::Error::Expired) => Err(OperationTimeoutError::new_err(
+            "The operation deadline has expired",
+        )),
+    }
+}
+
+/// Конвертер ошибок Rust-библиотеки в Python-исключения.
+///
+/// Ошибки длины и выравнивания — подробные (`DataTooShortError`,
+/// `DataNotAlignedError`): так их получают расчёт размеров и шифрование.
+/// На путях дешифровки используется `decrypt_to_py_err`.
+fn rust_to_py_err<T>(result: Result<T, CipherError>) -> Result<T, PyErr> {
     match result {
         Ok(r) => Ok(r),
         Err(e) => match e {
@@ -94,27 +2230,68 @@ fn rust_to_py_err(result: Result<Vec<u8>, CipherError>) -> Result<Vec<u8>, PyErr
                 "The data is not aligned to block size",
             )),
             // InvalidPadding возникает при несовпадении ключа.
-            InvalidPadding => Err(InvalidPaddingError::new_err("The padding is invalid")),
+            InvalidPadding => Err(decryption_error(InvalidPaddingError::new_err(
+                "The padding is invalid",
+            ))),
             InvalidKeyFormat => Err(InvalidKeyFormatError::new_err("The key format is invalid")),
             InvalidMode => Err(InvalidModeError::new_err("The mode is invalid")),
         },
     }
 }
 
-/// Преобразователь Python-строки в &str.
-fn to_string<'a>(data: &'a Bound<PyString>) -> &'a str {
-    data.to_str().unwrap()
+/// Конвертер ошибок дешифровки: ошибки длины, выравнивания и
+/// выравнивания блока неотличимы (`decryption_error`).
+fn decrypt_to_py_err<T>(result: Result<T, CipherError>) -> Result<T, PyErr> {
+    match result {
+        Err(e @ (DataTooShort | DataNotAligned)) => {
+            rust_to_py_err(Err(e)).map_err(decryption_error)
+        }
+        result => rust_to_py_err(result),
+    }
+}
+
+/// Подробные ошибки дешифровки (`set_debug_errors`).
+static DEBUG_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Подробные ошибки дешифровки для отладки.
+///
+/// По умолчанию выравнивание, длина и имитовставка при дешифровке
+/// вызывают одну и ту же `DecryptionError`: разные исключения позволили бы
+/// подбирать открытый текст CBC по ответам (padding oracle). С
+/// `enabled=True` возникают её подклассы (`InvalidPaddingError`,
+/// `AuthenticationError` и др.) — только для отладки, не в сервисах,
+/// доступных извне.
+#[pyfunction]
+#[pyo3(name = "set_debug_errors")]
+#[pyo3(signature = (enabled))]
+fn set_debug_errors(enabled: bool) {
+    DEBUG_ERRORS.store(enabled, Ordering::Relaxed);
+}
+
+/// Ошибка дешифровки: общая `DecryptionError` или, при
+/// `set_debug_errors(True)`, подробная `detail`.
+fn decryption_error(detail: PyErr) -> PyErr {
+    if DEBUG_ERRORS.load(Ordering::Relaxed) {
+        detail
+    } else {
+        DecryptionError::new_err("The data cannot be decrypted")
+    }
 }
 
-/// Преобразователь PyBytes для текста и ключа в Vec<u8>.
+/// Текст и ключ из объектов с протоколом буфера (см. `extract_input`).
 ///
-/// Одновременно проводятся базовые проверки.
-pub fn extract_text_and_key<'py>(
-    text: &Bound<'py, PyBytes>,
-    key: &Bound<'py, PyBytes>,
-) -> Result<(Vec<u8>, Vec<u8>), PyErr> {
-    let text: Vec<u8> = text.extract()?;
-    let key: Vec<u8> = key.extract()?;
+/// Используется только диапазон текста `offset..offset + length` (по
+/// умолчанию — до конца), `bytes` не копируется. Одновременно проводятся
+/// базовые проверки. Копии текста и ключа стираются при удалении, в том
+/// числе при ошибке.
+pub fn extract_text_and_key<'a>(
+    text: &'a Bound<'_, PyAny>,
+    key: &Bound<'_, PyAny>,
+    offset: usize,
+    length: Option<usize>,
+) -> Result<(Input<'a>, Zeroizing<Vec<u8>>), PyErr> {
+    let text = extract_input(text, offset, length)?;
+    let key = extract_input(key, 0, None)?.into_owned();
 
     if text.is_empty() || key.is_empty() {
         return Err(PyValueError::new_err(
@@ -124,3 +2301,91 @@ pub fn extract_text_and_key<'py>(
 
     Ok((text, key))
 }
+
+/// Входные данные: `bytes` без копии или стираемая при удалении копия
+/// другого буфера.
+pub enum Input<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Zeroizing<Vec<u8>>),
+}
+
+impl Input<'_> {
+    /// Собственная стираемая копия данных.
+    fn into_owned(self) -> Zeroizing<Vec<u8>> {
+        match self {
+            Input::Borrowed(data) => Zeroizing::new(data.to_vec()),
+            Input::Owned(data) => data,
+        }
+    }
+}
+
+impl std::ops::Deref for Input<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Borrowed(data) => data,
+            Input::Owned(data) => data,
+        }
+    }
+}
+
+/// Входные данные из объекта с протоколом буфера.
+///
+/// `bytes` используется без копии. Из прочих буферов (`bytearray`,
+/// `memoryview`, `mmap`, массивы numpy `uint8`) копируется только диапазон
+/// `offset..offset + length`, пока удерживается GIL: иначе другой поток
+/// Python мог бы изменить их во время обработки без GIL. Буфер с
+/// разрывами (срез `memoryview` с шагом) копируется целиком.
+fn extract_input<'a>(
+    data: &'a Bound<'_, PyAny>,
+    offset: usize,
+    length: Option<usize>,
+) -> Result<Input<'a>, PyErr> {
+    if let Ok(bytes) = data.downcast::<PyBytes>() {
+        return slice_input(bytes.as_bytes(), offset, length).map(Input::Borrowed);
+    }
+
+    let py = data.py();
+    let buffer = PyBuffer::<u8>::get(data)?;
+    if let Some(cells) = buffer.as_slice(py) {
+        let range = input_range(cells.len(), offset, length)?;
+        let copy = cells[range].iter().map(|cell| cell.get()).collect();
+        return Ok(Input::Owned(Zeroizing::new(copy)));
+    }
+
+    let copy = Zeroizing::new(buffer.to_vec(py)?);
+    let range = input_range(copy.len(), offset, length)?;
+    Ok(Input::Owned(Zeroizing::new(copy[range].to_vec())))
+}
+
+/// Диапазон входных данных без промежуточной копии.
+fn slice_input(data: &[u8], offset: usize, length: Option<usize>) -> Result<&[u8], PyErr> {
+    Ok(&data[input_range(data.len(), offset, length)?])
+}
+
+/// Проверка диапазона `offset`, `length` в данных длиной `len`.
+fn input_range(len: usize, offset: usize, length: Option<usize>) -> Result<Range<usize>, PyErr> {
+    let end = match length {
+        Some(length) => offset.checked_add(length),
+        None => Some(len),
+    };
+
+    match end {
+        Some(end) if offset <= end && end <= len => Ok(offset..end),
+        _ => Err(PyValueError::new_err(
+            "'offset' and 'length' are out of the data range",
+        )),
+    }
+}
+
+/// Стираемая копия ключа из PyBytes с проверкой на пустое значение.
+fn extract_key(key: &Bound<'_, PyBytes>) -> Result<Zeroizing<Vec<u8>>, PyErr> {
+    let key = key.as_bytes();
+
+    if key.is_empty() {
+        return Err(PyValueError::new_err("'key' cannot be empty"));
+    }
+
+    Ok(Zeroizing::new(key.to_vec()))
+}
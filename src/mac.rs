@@ -0,0 +1,90 @@
+//! Имитовставки на основе HMAC-Стрибог (ГОСТ Р 34.11-2012, 256 бит).
+
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use streebog::Streebog256;
+
+type HmacStreebog256 = Hmac<Streebog256>;
+
+/// Размер буфера при потоковом чтении файлов.
+pub const READ_CHUNK: usize = 64 * 1024;
+
+/// Через сколько прочитанных частей вызывается проверка прерывания.
+pub const CHECK_INTERVAL: usize = 16;
+
+/// Потоковый расчёт HMAC-Стрибог для файла.
+///
+/// Файл читается частями по `READ_CHUNK` байт и целиком в память не
+/// загружается. Каждые `CHECK_INTERVAL` частей вызывается `check`: ошибка
+/// из него (например, `KeyboardInterrupt`) прерывает расчёт.
+///
+/// - path — Путь к файлу
+/// - key — Ключ имитовставки (произвольной длины)
+/// - check — Проверка прерывания операции
+pub fn mac_file<E, F>(path: &Path, key: &[u8], mut check: F) -> Result<Vec<u8>, E>
+where
+    E: From<io::Error>,
+    F: FnMut() -> Result<(), E>,
+{
+    let mut mac = new_mac(key);
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; READ_CHUNK];
+    let mut chunks = 0usize;
+
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                mac.update(&buf[..n]);
+                chunks += 1;
+                if chunks.is_multiple_of(CHECK_INTERVAL) {
+                    check()?;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Расчёт HMAC-Стрибог для данных в памяти.
+pub fn mac_data(data: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut mac = new_mac(key);
+    mac.update(data);
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Проверка имитовставки HMAC-Стрибог за постоянное время.
+pub fn verify_data(data: &[u8], key: &[u8], tag: &[u8]) -> bool {
+    let mut mac = new_mac(key);
+    mac.update(data);
+
+    mac.verify_slice(tag).is_ok()
+}
+
+/// Расчёт HMAC-Стрибог для данных из нескольких частей.
+pub fn mac_parts(parts: &[&[u8]], key: &[u8]) -> Vec<u8> {
+    let mut mac = new_mac(key);
+    parts.iter().for_each(|part| mac.update(part));
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Проверка имитовставки данных из нескольких частей за постоянное время.
+pub fn verify_parts(parts: &[&[u8]], key: &[u8], tag: &[u8]) -> bool {
+    let mut mac = new_mac(key);
+    parts.iter().for_each(|part| mac.update(part));
+
+    mac.verify_slice(tag).is_ok()
+}
+
+/// Подготовка HMAC: ключ HMAC может быть любой длины, поэтому ошибки нет.
+fn new_mac(key: &[u8]) -> HmacStreebog256 {
+    HmacStreebog256::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
@@ -0,0 +1,346 @@
+//! Режим MGM (ГОСТ Р 34.13-2015, Р 1323565.1.026-2019): шифрование с
+//! аутентификацией для блочного шифра с блоком 16 байт.
+//!
+//! Результат: `шифротекст + имитовставка`. Шифротекст той же длины, что и
+//! открытый текст; дополнительные данные защищаются имитовставкой без
+//! шифрования.
+
+use aes::cipher::consts::U16;
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, BlockSizeUser};
+use block_encryption::traits::CipherError;
+use subtle::ConstantTimeEq;
+
+use crate::aead::Error;
+use crate::engine::BLOCK_SIZE;
+
+type Block = [u8; BLOCK_SIZE];
+
+/// Размер nonce в байтах (старший бит равен нулю).
+pub const NONCE_SIZE: usize = BLOCK_SIZE;
+
+/// Размер имитовставки по умолчанию в байтах.
+pub const TAG_SIZE: usize = BLOCK_SIZE;
+
+/// Наименьший допустимый размер имитовставки в байтах.
+pub const MIN_TAG_SIZE: usize = 4;
+
+/// Шифрование с выработкой имитовставки.
+///
+/// - cipher — Блочный шифр с блоком 16 байт
+/// - nonce — Nonce, не повторяется для одного ключа
+/// - plaintext — Открытый текст
+/// - aad — Дополнительные данные
+/// - tag_len — Длина имитовставки (от 4 до 16 байт)
+pub fn seal<C>(cipher: &C, nonce: &Block, plaintext: &[u8], aad: &[u8], tag_len: usize) -> Vec<u8>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    let mut out = plaintext.to_vec();
+    apply_gamma(cipher, nonce, &mut out);
+    let tag = tag(cipher, nonce, aad, &out);
+    out.extend_from_slice(&tag[..tag_len]);
+
+    out
+}
+
+/// Проверка имитовставки и дешифровка результата `seal`.
+pub fn open<C>(
+    cipher: &C,
+    nonce: &Block,
+    data: &[u8],
+    aad: &[u8],
+    tag_len: usize,
+) -> Result<Vec<u8>, Error>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    // Старший бит не участвует в выработке Y и Z: nonce с ним — подделка.
+    if nonce[0] & 0x80 != 0 {
+        return Err(Error::Authentication);
+    }
+    let Some(split) = data.len().checked_sub(tag_len) else {
+        return Err(Error::Cipher(CipherError::DataTooShort));
+    };
+    let (ciphertext, expected) = data.split_at(split);
+    let tag = tag(cipher, nonce, aad, ciphertext);
+    if !bool::from(tag[..tag_len].ct_eq(expected)) {
+        return Err(Error::Authentication);
+    }
+
+    let mut out = ciphertext.to_vec();
+    apply_gamma(cipher, nonce, &mut out);
+
+    Ok(out)
+}
+
+/// Пошаговое шифрование или дешифровка MGM.
+///
+/// Результат совпадает с `seal`/`open` для тех же данных, но данные
+/// передаются частями и в памяти хранится не больше блока (и имитовставки
+/// при дешифровке). При дешифровке открытый текст выдаётся до проверки
+/// имитовставки: вызывающий отбрасывает его, если `finalize` вернул ошибку.
+pub struct Stream<C> {
+    cipher: C,
+    y: Block,
+    z: Block,
+    sum: u128,
+    aad_len: usize,
+    len: usize,
+    buffer: Vec<u8>,
+    tag_len: usize,
+    encrypting: bool,
+}
+
+impl<C> Stream<C>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    /// - cipher — Блочный шифр с блоком 16 байт
+    /// - nonce — Nonce (при дешифровке старший бит равен нулю)
+    /// - aad — Дополнительные данные
+    /// - tag_len — Длина имитовставки (от 4 до 16 байт)
+    /// - encrypting — Шифрование (`true`) или дешифровка
+    pub fn new(
+        cipher: C,
+        nonce: &Block,
+        aad: &[u8],
+        tag_len: usize,
+        encrypting: bool,
+    ) -> Result<Self, Error> {
+        if !encrypting && nonce[0] & 0x80 != 0 {
+            return Err(Error::Authentication);
+        }
+        let (mut y, mut z) = (*nonce, *nonce);
+        y[0] &= 0x7F;
+        z[0] |= 0x80;
+        encrypt_block(&cipher, &mut y);
+        encrypt_block(&cipher, &mut z);
+
+        let mut stream = Stream {
+            cipher,
+            y,
+            z,
+            sum: 0,
+            aad_len: aad.len(),
+            len: 0,
+            buffer: Vec::with_capacity(BLOCK_SIZE + tag_len),
+            tag_len,
+            encrypting,
+        };
+        for chunk in aad.chunks(BLOCK_SIZE) {
+            stream.absorb(chunk);
+        }
+
+        Ok(stream)
+    }
+
+    /// Обработка очередной части данных; возвращает готовые полные блоки.
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(data);
+        let keep = if self.encrypting { 0 } else { self.tag_len };
+        let ready = self.buffer.len().saturating_sub(keep) / BLOCK_SIZE * BLOCK_SIZE;
+        let mut out: Vec<u8> = self.buffer.drain(..ready).collect();
+        for block in out.chunks_mut(BLOCK_SIZE) {
+            self.process(block);
+        }
+
+        out
+    }
+
+    /// Завершение: последний неполный блок и имитовставка (при
+    /// шифровании — в конце результата, при дешифровке — проверка).
+    pub fn finalize(mut self) -> Result<Vec<u8>, Error> {
+        let mut out = std::mem::take(&mut self.buffer);
+        let expected = if self.encrypting {
+            Vec::new()
+        } else {
+            let Some(split) = out.len().checked_sub(self.tag_len) else {
+                return Err(Error::Cipher(CipherError::DataTooShort));
+            };
+            out.split_off(split)
+        };
+        if !out.is_empty() {
+            self.process(&mut out);
+        }
+
+        let lengths = ((self.aad_len as u128 * 8) << 64) | (self.len as u128 * 8);
+        let h = self.next_h();
+        self.sum ^= gf_mul(h, lengths);
+        let mut tag = self.sum.to_be_bytes();
+        encrypt_block(&self.cipher, &mut tag);
+
+        if self.encrypting {
+            out.extend_from_slice(&tag[..self.tag_len]);
+        } else if !bool::from(tag[..self.tag_len].ct_eq(&expected)) {
+            return Err(Error::Authentication);
+        }
+
+        Ok(out)
+    }
+
+    /// Гамма и имитовставка для блока (последний может быть неполным).
+    fn process(&mut self, block: &mut [u8]) {
+        if !self.encrypting {
+            self.absorb(block);
+        }
+        let mut gamma = self.y;
+        encrypt_block(&self.cipher, &mut gamma);
+        block.iter_mut().zip(&gamma).for_each(|(d, g)| *d ^= g);
+        self.y = increment(self.y, false);
+        if self.encrypting {
+            self.absorb(block);
+        }
+        self.len += block.len();
+    }
+
+    /// Добавление блока шифротекста или дополнительных данных к сумме.
+    fn absorb(&mut self, chunk: &[u8]) {
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let h = self.next_h();
+        self.sum ^= gf_mul(h, u128::from_be_bytes(block));
+    }
+
+    fn next_h(&mut self) -> u128 {
+        let mut h = self.z;
+        encrypt_block(&self.cipher, &mut h);
+        self.z = increment(self.z, true);
+        u128::from_be_bytes(h)
+    }
+}
+
+/// Гамма: `E(Y_i)`, где `Y_1 = E(0 || nonce)`, правая половина `Y`
+/// увеличивается на единицу для каждого блока.
+fn apply_gamma<C>(cipher: &C, nonce: &Block, data: &mut [u8])
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    let mut y = *nonce;
+    y[0] &= 0x7F;
+    encrypt_block(cipher, &mut y);
+
+    for chunk in data.chunks_mut(BLOCK_SIZE) {
+        let mut gamma = y;
+        encrypt_block(cipher, &mut gamma);
+        chunk.iter_mut().zip(&gamma).for_each(|(d, g)| *d ^= g);
+        y = increment(y, false);
+    }
+}
+
+/// Имитовставка: `E(Σ H_i ⊗ A_i ⊕ Σ H_j ⊗ C_j ⊕ H ⊗ (len(A) || len(C)))`.
+///
+/// `H_i = E(Z_i)`, `Z_1 = E(1 || nonce)`, левая половина `Z`
+/// увеличивается на единицу для каждого блока. Неполные блоки дополняются
+/// нулями, длины — в битах.
+fn tag<C>(cipher: &C, nonce: &Block, aad: &[u8], ciphertext: &[u8]) -> Block
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    let mut z = *nonce;
+    z[0] |= 0x80;
+    encrypt_block(cipher, &mut z);
+
+    let mut sum = 0u128;
+    let mut next_h = || {
+        let mut h = z;
+        encrypt_block(cipher, &mut h);
+        z = increment(z, true);
+        u128::from_be_bytes(h)
+    };
+    for chunk in aad.chunks(BLOCK_SIZE).chain(ciphertext.chunks(BLOCK_SIZE)) {
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..chunk.len()].copy_from_slice(chunk);
+        sum ^= gf_mul(next_h(), u128::from_be_bytes(block));
+    }
+    let lengths = ((aad.len() as u128 * 8) << 64) | (ciphertext.len() as u128 * 8);
+    sum ^= gf_mul(next_h(), lengths);
+
+    let mut tag = sum.to_be_bytes();
+    encrypt_block(cipher, &mut tag);
+
+    tag
+}
+
+/// Увеличение левой (`left`) или правой половины блока по модулю 2^64.
+fn increment(block: Block, left: bool) -> Block {
+    let value = u128::from_be_bytes(block);
+    let value = if left {
+        value.wrapping_add(1 << 64)
+    } else {
+        (value & !u128::from(u64::MAX)) | u128::from((value as u64).wrapping_add(1))
+    };
+
+    value.to_be_bytes()
+}
+
+/// Умножение в GF(2^128) по модулю x^128 + x^7 + x^2 + x + 1.
+///
+/// Без ветвлений по значениям: время не зависит от секретного `H`.
+fn gf_mul(mut a: u128, b: u128) -> u128 {
+    let mut product = 0u128;
+    for bit in 0..128 {
+        product ^= a & 0u128.wrapping_sub((b >> bit) & 1);
+        let carry = a >> 127;
+        a = (a << 1) ^ (0x87 & 0u128.wrapping_sub(carry));
+    }
+
+    product
+}
+
+fn encrypt_block<C>(cipher: &C, block: &mut [u8])
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16>,
+{
+    cipher.encrypt_block(GenericArray::from_mut_slice(block));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::KeyInit;
+    use kuznyechik::Kuznyechik;
+
+    use crate::encoding::Encoding;
+
+    fn hex(text: &str) -> Vec<u8> {
+        Encoding::Hex.decode(text.as_bytes()).unwrap()
+    }
+
+    /// Р 1323565.1.026-2019, приложение А.1 ("Кузнечик").
+    #[test]
+    fn kuznyechik_reference_example() {
+        let cipher = Kuznyechik::new_from_slice(&hex(
+            "8899aabbccddeeff0011223344556677fedcba98765432100123456789abcdef",
+        ))
+        .unwrap();
+        let nonce: Block = hex("1122334455667700ffeeddccbbaa9988").try_into().unwrap();
+        let aad = hex(concat!(
+            "02020202020202020101010101010101",
+            "04040404040404040303030303030303",
+            "ea0505050505050505",
+        ));
+        let plaintext = hex(concat!(
+            "1122334455667700ffeeddccbbaa9988",
+            "00112233445566778899aabbcceeff0a",
+            "112233445566778899aabbcceeff0a00",
+            "2233445566778899aabbcceeff0a0011",
+            "aabbcc",
+        ));
+        let expected = hex(concat!(
+            "a9757b8147956e9055b8a33de89f42fc",
+            "8075d2212bf9fd5bd3f7069aadc16b39",
+            "497ab15915a6ba85936b5d0ea9f6851c",
+            "c60c14d4d3f883d0ab94420695c76deb",
+            "2c7552",
+            "cf5d656f40c34f5c46e8bb0e29fcdb4c",
+        ));
+
+        let sealed = seal(&cipher, &nonce, &plaintext, &aad, TAG_SIZE);
+        assert_eq!(sealed, expected);
+        assert_eq!(
+            open(&cipher, &nonce, &sealed, &aad, TAG_SIZE).unwrap(),
+            plaintext
+        );
+    }
+}
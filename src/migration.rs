@@ -0,0 +1,126 @@
+//! Массовое перешифрование сообщений новым ключом (смена ключей).
+
+use std::collections::VecDeque;
+
+use pyo3::prelude::*;
+use pyo3::types::PyIterator;
+use zeroize::Zeroizing;
+
+use crate::{batch, decrypt_to_py_err, engine, modes};
+
+/// Итератор перешифрования: `decrypt(old_key)` → `encrypt(new_key)`.
+///
+/// Сообщения забираются из исходного итератора партиями по `batch_size`,
+/// каждая партия обрабатывается параллельно без удержания GIL. После каждой
+/// партии вызывается `progress(done)` с общим числом обработанных сообщений.
+///
+/// Результат выдаётся для каждого сообщения по порядку. Ошибка сообщения
+/// (неверный ключ, повреждение, не `bytes`) передаётся в `on_error(index,
+/// error)`, а без него поднимается на месте сообщения; уже перешифрованные
+/// сообщения партии не теряются и выдаются следующими вызовами `next`.
+#[pyclass]
+pub struct Migration {
+    source: Py<PyIterator>,
+    old_key: Zeroizing<Vec<u8>>,
+    new_key: Zeroizing<Vec<u8>>,
+    mode: modes::Mode,
+    progress: Option<PyObject>,
+    on_error: Option<PyObject>,
+    batch_size: usize,
+    done: usize,
+    /// Номер и результат каждого сообщения обработанной партии.
+    pending: VecDeque<(usize, PyResult<Vec<u8>>)>,
+}
+
+impl Migration {
+    pub fn new(
+        source: Py<PyIterator>,
+        old_key: Zeroizing<Vec<u8>>,
+        new_key: Zeroizing<Vec<u8>>,
+        mode: modes::Mode,
+        progress: Option<PyObject>,
+        on_error: Option<PyObject>,
+        batch_size: usize,
+    ) -> Self {
+        Migration {
+            source,
+            old_key,
+            new_key,
+            mode,
+            progress,
+            on_error,
+            batch_size: batch_size.max(1),
+            done: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Обработка очередной партии. Возвращает `false`, если источник исчерпан.
+    fn fill(&mut self, py: Python<'_>) -> PyResult<bool> {
+        let mut source = self.source.bind(py).clone();
+        let mut items: Vec<Vec<u8>> = Vec::with_capacity(self.batch_size);
+        // Ошибка чтения источника завершает партию, но прочитанное до неё
+        // перешифровывается.
+        let mut failure = None;
+        for item in source.by_ref().take(self.batch_size) {
+            match item.and_then(|item| item.extract()) {
+                Ok(item) => items.push(item),
+                Err(err) => {
+                    failure = Some(err);
+                    break;
+                }
+            }
+        }
+        if items.is_empty() && failure.is_none() {
+            return Ok(false);
+        }
+
+        let (old_key, new_key, mode) = (&self.old_key, &self.new_key, self.mode);
+        let results = py.allow_threads(|| {
+            batch::parallel_map(&items, |ct| {
+                engine::reencrypting(ct, old_key, new_key, mode)
+            })
+        });
+
+        for result in results {
+            self.pending
+                .push_back((self.done, decrypt_to_py_err(result)));
+            self.done += 1;
+        }
+        if let Some(err) = failure {
+            self.pending.push_back((self.done, Err(err)));
+            self.done += 1;
+        }
+
+        if let Some(progress) = &self.progress {
+            progress.call1(py, (self.done,))?;
+        }
+
+        Ok(true)
+    }
+}
+
+#[pymethods]
+impl Migration {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Vec<u8>>> {
+        loop {
+            if self.pending.is_empty() && !self.fill(py)? {
+                return Ok(None);
+            }
+            let Some((index, result)) = self.pending.pop_front() else {
+                continue;
+            };
+            match (result, &self.on_error) {
+                (Ok(ct), _) => return Ok(Some(ct)),
+                (Err(err), Some(on_error)) => {
+                    on_error.call1(py, (index, err))?;
+                }
+                (Err(err), None) => return Err(err),
+            }
+        }
+    }
+}
@@ -0,0 +1,600 @@
+//! Режимы шифрования для блочных шифров RustCrypto.
+//!
+//! Повторяют схему `block-encryption`: единый вектор инициализации,
+//! выравнивание PKCS7 для ECB и CBC, потоковые CFB, OFB и CTR. Благодаря
+//! этому альтернативные шифры используют тот же формат данных. Для обмена
+//! с другими библиотеками поддерживаются и другие схемы выравнивания
+//! (`Padding`) и счётчика CTR (`Counter`).
+//!
+//! Все режимы реализованы пошагово (`Stream`), а `encrypt`/`decrypt` —
+//! обработка данных за один шаг. Размер блока берётся из шифра: 16 байт
+//! для "Кузнечика" и AES, 8 для "Магмы".
+//!
+//! Блоки ECB и CTR не зависят друг от друга, поэтому в `encrypt_into` и
+//! `decrypt_into` они обрабатываются частями в пуле потоков rayon
+//! (`Stream::set_threads`).
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::typenum::Unsigned;
+use aes::cipher::{BlockDecrypt, BlockEncrypt};
+use block_encryption::traits::CipherError;
+use rayon::prelude::*;
+use subtle::{ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Шифрование данных в режиме `mode`.
+///
+/// - cipher — Блочный шифр
+/// - data — Открытый текст
+/// - mode — Режим шифрования
+/// - iv — Вектор инициализации размером в блок (не используется в ECB)
+pub fn encrypt<C>(cipher: C, data: &[u8], mode: Mode, iv: &[u8]) -> Result<Vec<u8>, CipherError>
+where
+    C: BlockEncrypt + BlockDecrypt,
+{
+    process(cipher, data, mode, iv, true)
+}
+
+/// Дешифровка данных в режиме `mode`.
+pub fn decrypt<C>(cipher: C, data: &[u8], mode: Mode, iv: &[u8]) -> Result<Vec<u8>, CipherError>
+where
+    C: BlockEncrypt + BlockDecrypt,
+{
+    process(cipher, data, mode, iv, false)
+}
+
+/// Обработка данных за один шаг.
+pub fn process<C>(
+    cipher: C,
+    data: &[u8],
+    mode: Mode,
+    iv: &[u8],
+    encrypting: bool,
+) -> Result<Vec<u8>, CipherError>
+where
+    C: BlockEncrypt + BlockDecrypt,
+{
+    let mut stream = Stream::new(cipher, mode, iv, Options::default(), encrypting)?;
+    let mut out = stream.update(data);
+    out.extend_from_slice(&Zeroizing::new(stream.finalize()?));
+
+    Ok(out)
+}
+
+/// Режим шифрования.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Ecb,
+    Cbc,
+    Cfb,
+    Ofb,
+    Ctr,
+}
+
+impl Mode {
+    /// Все режимы: таблица фабрики шифровальщиков (`engine::get_encryptor`)
+    /// и `cryptor.supported_modes`.
+    pub const ALL: [Mode; 5] = [Mode::Ecb, Mode::Cbc, Mode::Cfb, Mode::Ofb, Mode::Ctr];
+
+    /// Режим по имени (`ECB`, `CBC`, `CFB`, `OFB`, `CTR`).
+    pub fn from_name(encrypt_mode: &str) -> Result<Self, CipherError> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name() == encrypt_mode)
+            .ok_or(CipherError::InvalidMode)
+    }
+
+    /// Имя режима.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Ecb => "ECB",
+            Mode::Cbc => "CBC",
+            Mode::Cfb => "CFB",
+            Mode::Ofb => "OFB",
+            Mode::Ctr => "CTR",
+        }
+    }
+
+    /// Режим с выравниванием (ECB, CBC).
+    pub fn is_block(self) -> bool {
+        matches!(self, Mode::Ecb | Mode::Cbc)
+    }
+
+    /// Режим с вектором инициализации (все, кроме ECB).
+    pub fn uses_iv(self) -> bool {
+        self != Mode::Ecb
+    }
+
+    /// Режим с независимыми блоками (ECB, CTR).
+    pub fn is_parallel(self) -> bool {
+        matches!(self, Mode::Ecb | Mode::Ctr)
+    }
+}
+
+/// Схема выравнивания ECB и CBC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Padding {
+    /// PKCS7: `n` байт со значением `n` (по умолчанию).
+    #[default]
+    Pkcs7,
+    /// ANSI X9.23: нули и последний байт `n`.
+    AnsiX923,
+    /// ISO/IEC 7816-4: байт `0x80` и нули.
+    Iso7816,
+    /// Нули до границы блока; выровненные данные не дополняются, а нули в
+    /// конце открытого текста при дешифровке теряются.
+    Zero,
+}
+
+impl Padding {
+    /// Схема по имени (`PKCS7`, `X923`, `ISO7816`, `ZERO`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "PKCS7" => Some(Padding::Pkcs7),
+            "X923" => Some(Padding::AnsiX923),
+            "ISO7816" => Some(Padding::Iso7816),
+            "ZERO" => Some(Padding::Zero),
+            _ => None,
+        }
+    }
+}
+
+/// Счётчик режима CTR: увеличиваемая часть блока и порядок байт.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Counter {
+    /// Весь блок, big-endian (по умолчанию).
+    #[default]
+    Full,
+    /// Правая половина блока, big-endian; левая (nonce) не меняется, а
+    /// счётчик переполняется в ноль.
+    Half,
+    /// Правая половина блока, little-endian.
+    HalfLe,
+}
+
+impl Counter {
+    /// Схема по имени (`full`, `half`, `half-le`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(Counter::Full),
+            "half" => Some(Counter::Half),
+            "half-le" => Some(Counter::HalfLe),
+            _ => None,
+        }
+    }
+}
+
+/// Параметры режимов сверх схемы `block-encryption`; по умолчанию
+/// совпадают с ней.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Выравнивание ECB и CBC.
+    pub padding: Padding,
+    /// Счётчик CTR.
+    pub counter: Counter,
+}
+
+/// Пошаговое шифрование или дешифровка.
+///
+/// Результаты всех вызовов `update` и `finalize`, склеенные вместе,
+/// совпадают с результатом `encrypt`/`decrypt` для склеенных данных.
+/// Неполные блоки накапливаются между вызовами.
+pub struct Stream<C> {
+    cipher: C,
+    mode: Mode,
+    options: Options,
+    encrypting: bool,
+    /// Предыдущий блок шифротекста (CBC) или регистр обратной связи
+    /// (CFB, OFB).
+    register: Vec<u8>,
+    /// Блок счётчика CTR, начиная с вектора инициализации; увеличивается
+    /// на единицу для каждого блока по схеме `Options::counter`.
+    counter: Vec<u8>,
+    /// Гамма текущего блока потоковых режимов.
+    gamma: Vec<u8>,
+    /// Количество использованных байт `gamma`.
+    used: usize,
+    /// Неполный блок ECB и CBC; при дешифровке — и последний полный блок,
+    /// выравнивание которого снимает `finalize`.
+    pending: Vec<u8>,
+    /// Число частей для параллельной обработки ECB и CTR.
+    threads: usize,
+}
+
+impl<C> Stream<C>
+where
+    C: BlockEncrypt + BlockDecrypt,
+{
+    /// Размер блока шифра в байтах.
+    const BLOCK_SIZE: usize = C::BlockSize::USIZE;
+
+    /// - cipher — Блочный шифр
+    /// - mode — Режим шифрования
+    /// - iv — Вектор инициализации размером в блок (не используется в ECB)
+    /// - options — Выравнивание и счётчик CTR
+    /// - encrypting — Шифрование (`true`) или дешифровка
+    pub fn new(
+        cipher: C,
+        mode: Mode,
+        iv: &[u8],
+        options: Options,
+        encrypting: bool,
+    ) -> Result<Self, CipherError> {
+        if iv.len() != Self::BLOCK_SIZE {
+            return Err(CipherError::InvalidIVLenght);
+        }
+
+        Ok(Stream {
+            cipher,
+            mode,
+            options,
+            encrypting,
+            register: iv.to_vec(),
+            counter: iv.to_vec(),
+            gamma: vec![0u8; Self::BLOCK_SIZE],
+            used: Self::BLOCK_SIZE,
+            pending: Vec::with_capacity(Self::BLOCK_SIZE),
+            threads: 1,
+        })
+    }
+
+    /// Обработка ECB и CTR в `encrypt_into`/`decrypt_into` не более чем
+    /// `threads` частями параллельно; 1 (по умолчанию) — в текущем потоке.
+    /// Результат не зависит от числа частей.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Обработка очередной части данных.
+    ///
+    /// Потоковые режимы возвращают результат той же длины, ECB и CBC —
+    /// только полные блоки.
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        if !self.mode.is_block() {
+            let mut out = data.to_vec();
+            self.apply_keystream(&mut out);
+            return out;
+        }
+
+        self.pending.extend_from_slice(data);
+        let held = usize::from(!self.encrypting);
+        let block_size = Self::BLOCK_SIZE;
+        let ready = self.pending.len().saturating_sub(held) / block_size * block_size;
+        let rest = self.pending.split_off(ready);
+        let mut out = std::mem::replace(&mut self.pending, rest);
+        self.process_blocks(&mut out);
+
+        out
+    }
+
+    /// Завершение: последний блок с выравниванием (ECB, CBC).
+    pub fn finalize(mut self) -> Result<Vec<u8>, CipherError> {
+        if !self.mode.is_block() {
+            return Ok(Vec::new());
+        }
+
+        let mut out = std::mem::take(&mut self.pending);
+        if self.encrypting {
+            out = pad(&out, Self::BLOCK_SIZE, self.options.padding);
+            self.process_blocks(&mut out);
+            return Ok(out);
+        }
+        match out.len() {
+            0 => Err(CipherError::DataTooShort),
+            n if n == Self::BLOCK_SIZE => {
+                self.process_blocks(&mut out);
+                unpad(out, Self::BLOCK_SIZE, self.options.padding)
+            }
+            _ => Err(CipherError::DataNotAligned),
+        }
+    }
+
+    /// Шифрование сообщения целиком сразу в `out`.
+    ///
+    /// Сообщение передаётся частями `parts` (одна часть или несколько для
+    /// `do_encrypt_vectored`), которые копируются сразу в `out`. Размер
+    /// `out` — длина шифротекста (`engine::ciphertext_len`).
+    pub fn encrypt_into(mut self, parts: &[&[u8]], out: &mut [u8])
+    where
+        C: Sync,
+    {
+        let mut len = 0;
+        for part in parts {
+            out[len..len + part.len()].copy_from_slice(part);
+            len += part.len();
+        }
+        if !self.mode.is_block() {
+            self.keystream_into(out);
+            return;
+        }
+
+        let aligned = len - len % Self::BLOCK_SIZE;
+        let tail = pad(&out[aligned..len], Self::BLOCK_SIZE, self.options.padding);
+        out[aligned..].copy_from_slice(&tail);
+        self.blocks_into(out);
+    }
+
+    /// Конец открытого текста для `decrypt_into`: последний блок ECB и CBC
+    /// без выравнивания, в потоковых режимах пустой.
+    ///
+    /// По нему длина результата известна до дешифровки остальных блоков.
+    pub fn final_block(&self, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+        if !self.mode.is_block() {
+            return Ok(Vec::new());
+        }
+        if data.is_empty() {
+            return Err(CipherError::DataTooShort);
+        }
+        if !data.len().is_multiple_of(Self::BLOCK_SIZE) {
+            return Err(CipherError::DataNotAligned);
+        }
+
+        let start = data.len() - Self::BLOCK_SIZE;
+        let mut block = data[start..].to_vec();
+        self.cipher
+            .decrypt_block(GenericArray::from_mut_slice(&mut block));
+        if self.mode == Mode::Cbc {
+            let previous = match start {
+                0 => &self.register[..],
+                _ => &data[start - Self::BLOCK_SIZE..start],
+            };
+            xor(&mut block, previous);
+        }
+
+        unpad(block, Self::BLOCK_SIZE, self.options.padding)
+    }
+
+    /// Длина открытого текста по длине шифротекста и `final_block`.
+    pub fn decrypted_len(&self, data_len: usize, final_block: &[u8]) -> usize {
+        if self.mode.is_block() {
+            data_len - Self::BLOCK_SIZE + final_block.len()
+        } else {
+            data_len
+        }
+    }
+
+    /// Дешифровка сообщения целиком сразу в `out` (`decrypted_len` байт).
+    pub fn decrypt_into(mut self, data: &[u8], final_block: &[u8], out: &mut [u8])
+    where
+        C: Sync,
+    {
+        if !self.mode.is_block() {
+            out.copy_from_slice(data);
+            self.keystream_into(out);
+            return;
+        }
+
+        let body_len = data.len() - Self::BLOCK_SIZE;
+        let (body, tail) = out.split_at_mut(body_len);
+        body.copy_from_slice(&data[..body_len]);
+        self.blocks_into(body);
+        tail.copy_from_slice(final_block);
+    }
+
+    /// Размер части параллельной обработки: поровну на `threads` частей с
+    /// округлением до границы блока.
+    fn part_size(&self, len: usize) -> Option<usize> {
+        (self.mode.is_parallel() && self.threads > 1).then(|| {
+            len.div_ceil(self.threads)
+                .next_multiple_of(Self::BLOCK_SIZE)
+                .max(Self::BLOCK_SIZE)
+        })
+    }
+
+    /// Полные блоки сообщения целиком, ECB — параллельно.
+    fn blocks_into(&mut self, data: &mut [u8])
+    where
+        C: Sync,
+    {
+        let Some(part) = self.part_size(data.len()) else {
+            return self.process_blocks(data);
+        };
+
+        data.par_chunks_mut(part).for_each(|part| {
+            for block in part.chunks_exact_mut(Self::BLOCK_SIZE) {
+                let block = GenericArray::from_mut_slice(block);
+                if self.encrypting {
+                    self.cipher.encrypt_block(block);
+                } else {
+                    self.cipher.decrypt_block(block);
+                }
+            }
+        });
+    }
+
+    /// Гамма для сообщения целиком, CTR — параллельно: счётчик каждой
+    /// части сдвигается на число блоков перед ней.
+    fn keystream_into(&mut self, data: &mut [u8])
+    where
+        C: Sync,
+    {
+        let Some(part_size) = self.part_size(data.len()) else {
+            return self.apply_keystream(data);
+        };
+
+        let blocks_per_part = (part_size / Self::BLOCK_SIZE) as u64;
+        data.par_chunks_mut(part_size)
+            .enumerate()
+            .for_each(|(index, part)| {
+                let mut counter = self.counter.clone();
+                advance(
+                    &mut counter,
+                    self.options.counter,
+                    index as u64 * blocks_per_part,
+                );
+                let mut gamma = Zeroizing::new(vec![0u8; Self::BLOCK_SIZE]);
+                for block in part.chunks_mut(Self::BLOCK_SIZE) {
+                    gamma.copy_from_slice(&counter);
+                    advance(&mut counter, self.options.counter, 1);
+                    encrypt_block(&self.cipher, &mut gamma);
+                    xor(block, &gamma);
+                }
+            });
+    }
+
+    /// Режимы ECB и CBC над полными блоками.
+    fn process_blocks(&mut self, data: &mut [u8]) {
+        let chained = self.mode == Mode::Cbc;
+        for chunk in data.chunks_exact_mut(Self::BLOCK_SIZE) {
+            if self.encrypting {
+                if chained {
+                    xor(chunk, &self.register);
+                }
+                encrypt_block(&self.cipher, chunk);
+                self.register.copy_from_slice(chunk);
+            } else {
+                let saved = chunk.to_vec();
+                self.cipher
+                    .decrypt_block(GenericArray::from_mut_slice(chunk));
+                if chained {
+                    xor(chunk, &self.register);
+                }
+                self.register = saved;
+            }
+        }
+    }
+
+    /// Режимы CFB, OFB и CTR: наложение гаммы с места остановки.
+    ///
+    /// В CFB гамма следующего блока вырабатывается из шифротекста
+    /// предыдущего, в OFB и CTR не зависит от данных.
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.used == Self::BLOCK_SIZE {
+                if self.mode == Mode::Ctr {
+                    self.gamma.copy_from_slice(&self.counter);
+                    advance(&mut self.counter, self.options.counter, 1);
+                } else {
+                    self.gamma.copy_from_slice(&self.register);
+                }
+                encrypt_block(&self.cipher, &mut self.gamma);
+                if self.mode == Mode::Ofb {
+                    self.register.copy_from_slice(&self.gamma);
+                }
+                self.used = 0;
+            }
+
+            let input = *byte;
+            *byte ^= self.gamma[self.used];
+            if self.mode == Mode::Cfb {
+                self.register[self.used] = if self.encrypting { *byte } else { input };
+            }
+            self.used += 1;
+        }
+    }
+}
+
+impl<C> Drop for Stream<C> {
+    /// Стирание гаммы, регистра и накопленных данных.
+    fn drop(&mut self) {
+        self.gamma.zeroize();
+        self.register.zeroize();
+        self.pending.zeroize();
+    }
+}
+
+fn encrypt_block<C: BlockEncrypt>(cipher: &C, block: &mut [u8]) {
+    cipher.encrypt_block(GenericArray::from_mut_slice(block));
+}
+
+/// Увеличение счётчика на `n` с переносом (с переполнением в ноль).
+pub fn advance(block: &mut [u8], counter: Counter, n: u64) {
+    let half = block.len() / 2;
+    match counter {
+        Counter::Full => carry(block.iter_mut().rev(), n),
+        Counter::Half => carry(block[half..].iter_mut().rev(), n),
+        Counter::HalfLe => carry(block[half..].iter_mut(), n),
+    }
+}
+
+/// Прибавление `n` к байтам от младшего к старшему.
+fn carry<'a>(bytes: impl Iterator<Item = &'a mut u8>, n: u64) {
+    let mut rest = u128::from(n);
+    for byte in bytes {
+        if rest == 0 {
+            break;
+        }
+        rest += u128::from(*byte);
+        *byte = rest as u8;
+        rest >>= 8;
+    }
+}
+
+fn xor(data: &mut [u8], gamma: &[u8]) {
+    data.iter_mut().zip(gamma).for_each(|(d, g)| *d ^= g);
+}
+
+/// Выравнивание до границы блока: от 1 до `block_size` байт (для `Zero` —
+/// от 0).
+fn pad(data: &[u8], block_size: usize, padding: Padding) -> Vec<u8> {
+    let pad_len = match data.len() % block_size {
+        0 if padding == Padding::Zero => 0,
+        rest => block_size - rest,
+    };
+    let len = data.len() + pad_len;
+    let mut out = Vec::with_capacity(len);
+    out.extend_from_slice(data);
+    match padding {
+        Padding::Pkcs7 => out.resize(len, pad_len as u8),
+        Padding::AnsiX923 => {
+            out.resize(len - 1, 0);
+            out.push(pad_len as u8);
+        }
+        Padding::Iso7816 => {
+            out.push(0x80);
+            out.resize(len, 0);
+        }
+        Padding::Zero => out.resize(len, 0),
+    }
+
+    out
+}
+
+/// Снятие выравнивания с последнего блока; `InvalidPadding`, если оно не
+/// соответствует схеме (обычно из-за неверного ключа).
+///
+/// Проверка идёт за постоянное время по всему блоку: время не зависит от
+/// того, где выравнивание нарушено, и не служит оракулом (padding oracle).
+fn unpad(mut data: Vec<u8>, block_size: usize, padding: Padding) -> Result<Vec<u8>, CipherError> {
+    if data.len() < block_size {
+        return Err(CipherError::DataTooShort);
+    }
+    let block = &data[data.len() - block_size..];
+    let (pad_len, valid) = match padding {
+        Padding::Pkcs7 | Padding::AnsiX923 => {
+            let pad_len = block[block_size - 1];
+            let filler = if padding == Padding::Pkcs7 {
+                pad_len
+            } else {
+                0
+            };
+            let mut valid = !pad_len.ct_eq(&0) & !pad_len.ct_gt(&(block_size as u8));
+            for (i, byte) in block[..block_size - 1].iter().enumerate() {
+                let in_padding = !((block_size - i) as u8).ct_gt(&pad_len);
+                valid &= !in_padding | byte.ct_eq(&filler);
+            }
+            (pad_len, valid)
+        }
+        Padding::Iso7816 => {
+            // Последний ненулевой байт — маркер 0x80.
+            let (mut end, mut marker) = (0u8, 0u8);
+            for (i, byte) in block.iter().enumerate() {
+                let nonzero = !byte.ct_eq(&0);
+                end.conditional_assign(&(i as u8), nonzero);
+                marker.conditional_assign(byte, nonzero);
+            }
+            (block_size as u8 - end, marker.ct_eq(&0x80))
+        }
+        Padding::Zero => {
+            let end = data.iter().rposition(|&b| b != 0).map_or(0, |end| end + 1);
+            data.truncate(end);
+            return Ok(data);
+        }
+    };
+    if !bool::from(valid) {
+        return Err(CipherError::InvalidPadding);
+    }
+    data.truncate(data.len() - usize::from(pad_len));
+
+    Ok(data)
+}
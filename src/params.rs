@@ -0,0 +1,456 @@
+//! Типизированные параметры режимов шифрования.
+//!
+//! `do_encrypt`/`do_decrypt` принимают вместо строки режима объект
+//! параметров, который проверяется уже при создании:
+//!
+//! ```python
+//! from cryptor import do_encrypt, CTRParams
+//!
+//! ct = do_encrypt(p, k, CTRParams())
+//! ```
+//!
+//! Без вектора инициализации (nonce) используется встроенный вектор движка,
+//! а в режиме MGM — случайный nonce, записываемый перед шифротекстом.
+//!
+//! Перечисления `Mode` и `Algorithm` заменяют строки режима и шифра там, где
+//! параметры не нужны: `do_encrypt(p, k, Mode.CBC, cipher=Algorithm.MAGMA)`.
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+
+use crate::engine::{Algorithm, EncryptMode, ACPKM_SECTION_SIZE, BLOCK_SIZE, MAGMA_BLOCK_SIZE};
+use crate::mgm;
+use crate::modes::{self, Counter};
+use crate::{rust_to_py_err, IVLengthError};
+
+/// Размер nonce режима CTR: половина блока (ГОСТ Р 34.13-2015).
+pub const CTR_NONCE_SIZE: usize = BLOCK_SIZE / 2;
+
+/// Размер nonce режима CTR для шифра Магма.
+pub const MAGMA_CTR_NONCE_SIZE: usize = MAGMA_BLOCK_SIZE / 2;
+
+/// Допустимая длина имитовставки MGM в байтах.
+pub const MGM_TAG_LEN: std::ops::RangeInclusive<usize> = mgm::MIN_TAG_SIZE..=mgm::TAG_SIZE;
+
+/// Параметры режима ECB.
+#[pyclass(name = "ECBParams", frozen)]
+pub struct EcbParams;
+
+#[pymethods]
+impl EcbParams {
+    #[new]
+    fn new() -> Self {
+        EcbParams
+    }
+}
+
+/// Объявление параметров режима с необязательным вектором инициализации.
+///
+/// Длина вектора сверяется с блоком выбранного шифра при шифровании
+/// (`engine::check_iv`), здесь — только с блоками "Кузнечика" и "Магмы".
+macro_rules! iv_params {
+    ($ty:ident, $name:literal) => {
+        #[doc = concat!("Параметры режима ", $name, ".")]
+        #[pyclass(name = $name, frozen)]
+        pub struct $ty {
+            iv: Option<Vec<u8>>,
+        }
+
+        #[pymethods]
+        impl $ty {
+            /// - iv — Вектор инициализации в блок шифра (16 байт, для `magma`
+            ///   — 8) или `None` для встроенного
+            #[new]
+            #[pyo3(signature = (iv=None))]
+            fn new(iv: Option<Bound<'_, PyBytes>>) -> PyResult<Self> {
+                Ok($ty {
+                    iv: iv.map(|iv| extract_iv(&iv)).transpose()?,
+                })
+            }
+
+            #[getter]
+            fn iv(&self) -> Option<Vec<u8>> {
+                self.iv.clone()
+            }
+        }
+    };
+}
+
+iv_params!(CbcParams, "CBCParams");
+iv_params!(CfbParams, "CFBParams");
+iv_params!(OfbParams, "OFBParams");
+
+/// Параметры режима CTR.
+///
+/// Блок счётчика — `nonce || counter`. По ГОСТ Р 34.13-2015 (`half`)
+/// увеличивается только правая половина блока, поэтому счётчик блока с
+/// номером `i` равен `counter + i`: дешифровать можно с любого блока.
+#[pyclass(name = "CTRParams", frozen)]
+pub struct CtrParams {
+    nonce: Option<Vec<u8>>,
+    counter: u64,
+    layout: Counter,
+}
+
+#[pymethods]
+impl CtrParams {
+    /// - nonce — Nonce в половину блока (8 байт, для `magma` — 4) или
+    ///   `None` для встроенного вектора
+    /// - counter — Начальное значение счётчика (только вместе с `nonce`)
+    /// - layout — Счётчик: `half` (правая половина блока, big-endian, по
+    ///   умолчанию), `half-le` (то же, little-endian) или `full` (весь блок
+    ///   big-endian, как у встроенного вектора)
+    #[new]
+    #[pyo3(signature = (nonce=None, counter=0, layout="half"))]
+    fn new(nonce: Option<Bound<'_, PyBytes>>, counter: u64, layout: &str) -> PyResult<Self> {
+        let layout = Counter::from_name(layout)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown counter layout '{layout}'")))?;
+        let nonce = match nonce {
+            Some(nonce) => Some(ctr_nonce(nonce.as_bytes(), counter)?),
+            None if counter != 0 => {
+                return Err(PyValueError::new_err("counter requires a nonce"));
+            }
+            None => None,
+        };
+
+        Ok(CtrParams {
+            nonce,
+            counter,
+            layout,
+        })
+    }
+
+    #[getter]
+    fn nonce(&self) -> Option<Vec<u8>> {
+        self.nonce.clone()
+    }
+
+    #[getter]
+    fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    #[getter]
+    fn layout(&self) -> &'static str {
+        match self.layout {
+            Counter::Full => "full",
+            Counter::Half => "half",
+            Counter::HalfLe => "half-le",
+        }
+    }
+}
+
+impl CtrParams {
+    /// Параметры из аргументов `nonce`/`counter_start` функций
+    /// `do_encrypt`/`do_decrypt` (счётчик по ГОСТ).
+    pub fn from_nonce(nonce: &[u8], counter: u64) -> PyResult<Self> {
+        Ok(CtrParams {
+            nonce: Some(ctr_nonce(nonce, counter)?),
+            counter,
+            layout: Counter::Half,
+        })
+    }
+
+    /// Начальный блок счётчика: `nonce || counter` в порядке байт `layout`.
+    fn initial_block(&self) -> Option<Vec<u8>> {
+        self.nonce.as_ref().map(|nonce| {
+            let size = nonce.len();
+            let counter = match self.layout {
+                Counter::HalfLe => self.counter.to_le_bytes()[..size].to_vec(),
+                _ => self.counter.to_be_bytes()[8 - size..].to_vec(),
+            };
+            [nonce.as_slice(), &counter].concat()
+        })
+    }
+}
+
+/// Проверка nonce CTR: половина блока, и в другую половину помещается
+/// начальный счётчик.
+fn ctr_nonce(nonce: &[u8], counter: u64) -> PyResult<Vec<u8>> {
+    if nonce.len() != CTR_NONCE_SIZE && nonce.len() != MAGMA_CTR_NONCE_SIZE {
+        return Err(IVLengthError::new_err(
+            "The nonce must be 8 bytes (4 for magma)",
+        ));
+    }
+    if nonce.len() < 8 && counter >> (nonce.len() * 8) != 0 {
+        return Err(PyValueError::new_err(
+            "counter does not fit in the counter half-block",
+        ));
+    }
+
+    Ok(nonce.to_vec())
+}
+
+/// Параметры режима CTR-ACPKM (Р 1323565.1.017-2018).
+///
+/// Блок счётчика — `nonce || 0`, ключ меняется в начале каждой секции
+/// данных длиной `section_size`.
+#[pyclass(name = "ACPKMParams", frozen)]
+pub struct AcpkmParams {
+    nonce: Option<Vec<u8>>,
+    section_size: usize,
+}
+
+#[pymethods]
+impl AcpkmParams {
+    /// - nonce — Nonce в половину блока (8 байт, для `magma` — 4) или
+    ///   `None` для встроенного вектора
+    /// - section_size — Размер секции в байтах, кратный 16
+    #[new]
+    #[pyo3(signature = (nonce=None, section_size=ACPKM_SECTION_SIZE))]
+    fn new(nonce: Option<Bound<'_, PyBytes>>, section_size: usize) -> PyResult<Self> {
+        if section_size == 0 || !section_size.is_multiple_of(BLOCK_SIZE) {
+            return Err(PyValueError::new_err(
+                "section_size must be a positive multiple of 16",
+            ));
+        }
+
+        Ok(AcpkmParams {
+            nonce: nonce.map(|n| ctr_nonce(n.as_bytes(), 0)).transpose()?,
+            section_size,
+        })
+    }
+
+    #[getter]
+    fn nonce(&self) -> Option<Vec<u8>> {
+        self.nonce.clone()
+    }
+
+    #[getter]
+    fn section_size(&self) -> usize {
+        self.section_size
+    }
+}
+
+impl AcpkmParams {
+    fn initial_block(&self) -> Option<Vec<u8>> {
+        self.nonce
+            .as_ref()
+            .map(|nonce| [nonce.as_slice(), &vec![0u8; nonce.len()]].concat())
+    }
+}
+
+/// Параметры режима MGM (ГОСТ Р 34.13-2015, аутентифицированное шифрование).
+#[pyclass(name = "MGMParams", frozen)]
+pub struct MgmParams {
+    nonce: [u8; BLOCK_SIZE],
+    tag_len: usize,
+}
+
+#[pymethods]
+impl MgmParams {
+    /// - nonce — Nonce (16 байт, старший бит равен нулю)
+    /// - tag_len — Длина имитовставки в байтах (от 4 до 16)
+    #[new]
+    #[pyo3(signature = (nonce, tag_len=BLOCK_SIZE))]
+    fn new(nonce: Bound<'_, PyBytes>, tag_len: usize) -> PyResult<Self> {
+        let nonce = mgm_nonce(nonce.as_bytes())?;
+        if !MGM_TAG_LEN.contains(&tag_len) {
+            return Err(PyValueError::new_err("tag_len must be from 4 to 16"));
+        }
+
+        Ok(MgmParams { nonce, tag_len })
+    }
+
+    #[getter]
+    fn nonce(&self) -> Vec<u8> {
+        self.nonce.to_vec()
+    }
+
+    #[getter]
+    fn tag_len(&self) -> usize {
+        self.tag_len
+    }
+}
+
+/// Режим шифрования вместо строки: `encrypt_mode=Mode.CBC`.
+///
+/// Переводится в режим движка сопоставлением, без строки, поэтому
+/// опечатка, которая со строкой дала бы `InvalidModeError`, здесь невозможна.
+#[pyclass(name = "Mode", eq, eq_int, frozen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    #[pyo3(name = "ECB")]
+    Ecb,
+    #[pyo3(name = "CBC")]
+    Cbc,
+    #[pyo3(name = "CFB")]
+    Cfb,
+    #[pyo3(name = "OFB")]
+    Ofb,
+    #[pyo3(name = "CTR")]
+    Ctr,
+    #[pyo3(name = "MGM")]
+    Mgm,
+    #[pyo3(name = "SIV")]
+    Siv,
+    #[pyo3(name = "CTR_ACPKM")]
+    CtrAcpkm,
+}
+
+#[pymethods]
+impl Mode {
+    /// Строковое имя режима, например `CTR-ACPKM`.
+    #[getter]
+    fn value(&self) -> &'static str {
+        self.encrypt_mode().name()
+    }
+}
+
+impl Mode {
+    /// Режим движка.
+    pub fn encrypt_mode(self) -> EncryptMode {
+        match self {
+            Mode::Ecb => EncryptMode::Block(modes::Mode::Ecb),
+            Mode::Cbc => EncryptMode::Block(modes::Mode::Cbc),
+            Mode::Cfb => EncryptMode::Block(modes::Mode::Cfb),
+            Mode::Ofb => EncryptMode::Block(modes::Mode::Ofb),
+            Mode::Ctr => EncryptMode::Block(modes::Mode::Ctr),
+            Mode::Mgm => EncryptMode::Mgm,
+            Mode::Siv => EncryptMode::Siv,
+            Mode::CtrAcpkm => EncryptMode::CtrAcpkm,
+        }
+    }
+}
+
+/// Аргумент режима без объекта параметров: строка (неизвестная —
+/// `InvalidModeError`) или `Mode`.
+impl<'py> FromPyObject<'py> for EncryptMode {
+    fn extract_bound(mode: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(mode) = mode.downcast::<Mode>() {
+            return Ok(mode.get().encrypt_mode());
+        }
+        let name = mode
+            .downcast::<PyString>()
+            .map_err(|_| PyTypeError::new_err("encrypt_mode must be str or Mode"))?;
+
+        rust_to_py_err(EncryptMode::from_name(name.to_str()?))
+    }
+}
+
+/// Шифр вместо строки: `cipher=Algorithm.MAGMA`.
+///
+/// Имя `Cipher` занято интерфейсом PyCryptodome (`compat`).
+#[pyclass(name = "Algorithm", eq, eq_int, frozen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cipher {
+    #[pyo3(name = "KUZNYECHIK")]
+    Kuznyechik,
+    #[pyo3(name = "AES256")]
+    Aes256,
+    #[pyo3(name = "MAGMA")]
+    Magma,
+}
+
+#[pymethods]
+impl Cipher {
+    /// Строковое имя шифра, например `kuznyechik`.
+    #[getter]
+    fn value(&self) -> &'static str {
+        self.algorithm().name()
+    }
+}
+
+impl Cipher {
+    pub fn algorithm(self) -> Algorithm {
+        match self {
+            Cipher::Kuznyechik => Algorithm::Kuznyechik,
+            Cipher::Aes256 => Algorithm::Aes256,
+            Cipher::Magma => Algorithm::Magma,
+        }
+    }
+}
+
+/// Аргумент `cipher`: строка (`kuznyechik`, `aes256`, `magma`) или
+/// `Algorithm`.
+impl<'py> FromPyObject<'py> for Algorithm {
+    fn extract_bound(cipher: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(cipher) = cipher.downcast::<Cipher>() {
+            return Ok(cipher.get().algorithm());
+        }
+        let name = cipher
+            .downcast::<PyString>()
+            .map_err(|_| PyTypeError::new_err("cipher must be str or Algorithm"))?
+            .to_str()?;
+
+        Algorithm::from_name(name)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown cipher '{name}'")))
+    }
+}
+
+/// Режим и вектор инициализации из строки режима или объекта параметров.
+///
+/// Вектор `None` означает встроенный вектор движка.
+pub fn extract(mode: &Bound<'_, PyAny>) -> PyResult<(EncryptMode, Option<Vec<u8>>)> {
+    if let Ok(params) = mode.downcast::<CbcParams>() {
+        return Ok((EncryptMode::Block(modes::Mode::Cbc), params.get().iv()));
+    }
+    if let Ok(params) = mode.downcast::<CfbParams>() {
+        return Ok((EncryptMode::Block(modes::Mode::Cfb), params.get().iv()));
+    }
+    if let Ok(params) = mode.downcast::<OfbParams>() {
+        return Ok((EncryptMode::Block(modes::Mode::Ofb), params.get().iv()));
+    }
+    if let Ok(params) = mode.downcast::<CtrParams>() {
+        return Ok((
+            EncryptMode::Block(modes::Mode::Ctr),
+            params.get().initial_block(),
+        ));
+    }
+    if let Ok(params) = mode.downcast::<MgmParams>() {
+        return Ok((EncryptMode::Mgm, Some(params.get().nonce())));
+    }
+    if let Ok(params) = mode.downcast::<AcpkmParams>() {
+        return Ok((EncryptMode::CtrAcpkm, params.get().initial_block()));
+    }
+    if mode.downcast::<EcbParams>().is_ok() {
+        return Ok((EncryptMode::Block(modes::Mode::Ecb), None));
+    }
+    if mode.downcast::<PyString>().is_ok() || mode.downcast::<Mode>().is_ok() {
+        return Ok((mode.extract()?, None));
+    }
+
+    Err(PyTypeError::new_err(
+        "encrypt_mode must be str, Mode or a mode parameters object",
+    ))
+}
+
+/// Длина имитовставки MGM: из `MGMParams` или полный блок.
+pub fn tag_len(mode: &Bound<'_, PyAny>) -> usize {
+    mode.downcast::<MgmParams>()
+        .map_or(BLOCK_SIZE, |params| params.get().tag_len)
+}
+
+/// Размер секции CTR-ACPKM: из `ACPKMParams` или по умолчанию.
+pub fn section_size(mode: &Bound<'_, PyAny>) -> usize {
+    mode.downcast::<AcpkmParams>()
+        .map_or(ACPKM_SECTION_SIZE, |params| params.get().section_size)
+}
+
+/// Счётчик CTR: из `CTRParams` или весь блок (как у `block-encryption`).
+pub fn counter(mode: &Bound<'_, PyAny>) -> Counter {
+    mode.downcast::<CtrParams>()
+        .map_or(Counter::Full, |params| params.get().layout)
+}
+
+/// Проверка nonce MGM: 16 байт, старший бит равен нулю.
+pub fn mgm_nonce(nonce: &[u8]) -> PyResult<[u8; BLOCK_SIZE]> {
+    let nonce = <[u8; BLOCK_SIZE]>::try_from(nonce)
+        .map_err(|_| IVLengthError::new_err("The IV length is invalid"))?;
+    if nonce[0] & 0x80 != 0 {
+        return Err(PyValueError::new_err("The MGM nonce high bit must be zero"));
+    }
+
+    Ok(nonce)
+}
+
+/// Вектор инициализации в блок одного из шифров.
+fn extract_iv(data: &Bound<'_, PyBytes>) -> PyResult<Vec<u8>> {
+    let iv = data.as_bytes();
+    if iv.len() != BLOCK_SIZE && iv.len() != MAGMA_BLOCK_SIZE {
+        return Err(IVLengthError::new_err("The IV length is invalid"));
+    }
+
+    Ok(iv.to_vec())
+}
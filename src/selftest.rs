@@ -0,0 +1,238 @@
+//! Самопроверка движка по контрольным примерам ГОСТ (`vectors`) при
+//! запуске, как в проверках работоспособности FIPS 140.
+//!
+//! Примеры проходят через те же пути, что и `do_encrypt`/`do_decrypt`
+//! (`engine`, кеш шифровальщиков), и проверяются в обе стороны. В ECB и
+//! CBC сравниваются блоки до выравнивания. Режимы с регистром из двух
+//! блоков (OFB, CBC, CFB по ГОСТ Р 34.13-2015) — это две независимые
+//! цепочки с вектором в один блок: по одной для нечётных и для чётных
+//! блоков, так они и проверяются.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::encoding::Encoding;
+use crate::engine::{self, Algorithm};
+use crate::modes::Mode;
+use crate::vectors::{self, Block, Modes};
+use crate::SelfTestError;
+
+const GOST_34_12: &str = "ГОСТ Р 34.12-2015";
+const GOST_34_13: &str = "ГОСТ Р 34.13-2015";
+
+/// Результат одного примера.
+pub struct Outcome {
+    /// `шифр/проверка`, например `kuznyechik/CBC`.
+    pub name: String,
+    pub standard: &'static str,
+    pub passed: bool,
+}
+
+/// Все контрольные примеры по порядку.
+pub fn run() -> Vec<Outcome> {
+    let ciphers = [
+        (
+            "kuznyechik",
+            Algorithm::Kuznyechik,
+            &vectors::KUZNYECHIK_BLOCK,
+            &vectors::KUZNYECHIK,
+        ),
+        (
+            "magma",
+            Algorithm::Magma,
+            &vectors::MAGMA_BLOCK,
+            &vectors::MAGMA,
+        ),
+    ];
+    let mut outcomes = Vec::new();
+    let mut push = |name: String, standard, passed| {
+        outcomes.push(Outcome {
+            name,
+            standard,
+            passed,
+        })
+    };
+
+    for (name, algorithm, block, modes) in ciphers {
+        push(
+            format!("{name}/block"),
+            GOST_34_12,
+            check_block(algorithm, block),
+        );
+        for (mode, passed) in check_modes(algorithm, modes) {
+            push(format!("{name}/{mode}"), GOST_34_13, passed);
+        }
+    }
+    for (mode, expected) in [
+        (Mode::Ofb, vectors::KUZNYECHIK_OFB),
+        (Mode::Cbc, vectors::KUZNYECHIK_CBC),
+        (Mode::Cfb, vectors::KUZNYECHIK_CFB),
+    ] {
+        push(
+            format!("kuznyechik/{}", mode.name()),
+            GOST_34_13,
+            check_register(mode, expected),
+        );
+    }
+
+    outcomes
+}
+
+fn check_block(algorithm: Algorithm, block: &Block) -> bool {
+    check(
+        algorithm,
+        &hex(block.key),
+        Mode::Ecb,
+        None,
+        &hex(block.plaintext),
+        &hex(block.ciphertext),
+    )
+}
+
+/// ECB, CTR и имитовставка.
+fn check_modes(algorithm: Algorithm, modes: &Modes) -> [(&'static str, bool); 3] {
+    let key = hex(modes.key);
+    let plaintext = hex(modes.plaintext);
+    // Счётчик — правая половина блока: `nonce || 0`.
+    let mut counter = hex(modes.ctr_nonce);
+    counter.resize(algorithm.block_size(), 0);
+    let mac = hex(modes.mac);
+
+    [
+        (
+            "ECB",
+            check(
+                algorithm,
+                &key,
+                Mode::Ecb,
+                None,
+                &plaintext,
+                &hex(modes.ecb),
+            ),
+        ),
+        (
+            "CTR",
+            check(
+                algorithm,
+                &key,
+                Mode::Ctr,
+                Some(&counter),
+                &plaintext,
+                &hex(modes.ctr),
+            ),
+        ),
+        (
+            "MAC",
+            engine::cmac(algorithm, &key, &[&plaintext], mac.len()).is_ok_and(|tag| tag == mac),
+        ),
+    ]
+}
+
+/// Режим "Кузнечика" с регистром из двух блоков.
+fn check_register(mode: Mode, expected: &str) -> bool {
+    let key = hex(vectors::KUZNYECHIK.key);
+    let (plaintext, expected) = (hex(vectors::KUZNYECHIK.plaintext), hex(expected));
+    let iv = hex(vectors::KUZNYECHIK_IV);
+
+    iv.chunks(engine::BLOCK_SIZE)
+        .enumerate()
+        .all(|(chain, iv)| {
+            let plaintext = interleaved(&plaintext, chain);
+            let expected = interleaved(&expected, chain);
+            check(
+                Algorithm::Kuznyechik,
+                &key,
+                mode,
+                Some(iv),
+                &plaintext,
+                &expected,
+            )
+        })
+}
+
+/// Блоки `chain`, `chain + 2`, ... из `data`.
+fn interleaved(data: &[u8], chain: usize) -> Vec<u8> {
+    data.chunks(engine::BLOCK_SIZE)
+        .skip(chain)
+        .step_by(2)
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Шифротекст начинается с `expected` (дальше — только выравнивание), а
+/// дешифровка возвращает `plaintext`.
+fn check(
+    algorithm: Algorithm,
+    key: &[u8],
+    mode: Mode,
+    iv: Option<&[u8]>,
+    plaintext: &[u8],
+    expected: &[u8],
+) -> bool {
+    let Ok(ciphertext) = engine::encrypting_with(algorithm, plaintext, key, mode, iv) else {
+        return false;
+    };
+    ciphertext.get(..expected.len()) == Some(expected)
+        && ciphertext.len() - expected.len() <= algorithm.block_size()
+        && engine::decrypting_with(algorithm, &ciphertext, key, mode, iv)
+            .is_ok_and(|decrypted| decrypted == plaintext)
+}
+
+fn hex(text: &str) -> Vec<u8> {
+    Encoding::Hex
+        .decode(text.as_bytes())
+        .expect("hex test vector")
+}
+
+/// Самопроверка по контрольным примерам ГОСТ Р 34.12-2015 и ГОСТ Р
+/// 34.13-2015: блочные шифры "Кузнечик" и "Магма", режимы ECB, CTR, OFB,
+/// CBC, CFB и имитовставка.
+///
+/// Возвращает отчёт — список словарей с ключами `name` (например,
+/// `kuznyechik/CBC`), `standard` и `passed`. Если хотя бы один пример не
+/// прошёл, вызывает `SelfTestError` со списком непрошедших.
+#[pyfunction]
+#[pyo3(name = "run_self_test")]
+pub fn run_self_test(py: Python<'_>) -> PyResult<Vec<Py<PyDict>>> {
+    let outcomes = py.allow_threads(run);
+    let failed: Vec<&str> = outcomes
+        .iter()
+        .filter(|outcome| !outcome.passed)
+        .map(|outcome| outcome.name.as_str())
+        .collect();
+    if !failed.is_empty() {
+        return Err(SelfTestError::new_err(format!(
+            "Known-answer tests failed: {}",
+            failed.join(", ")
+        )));
+    }
+
+    let mut report = Vec::with_capacity(outcomes.len());
+    for outcome in outcomes {
+        let result = PyDict::new(py);
+        result.set_item("name", outcome.name)?;
+        result.set_item("standard", outcome.standard)?;
+        result.set_item("passed", outcome.passed)?;
+        report.push(result.unbind());
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_examples_pass() {
+        let outcomes = run();
+        assert!(!outcomes.is_empty());
+        let failed: Vec<&str> = outcomes
+            .iter()
+            .filter(|outcome| !outcome.passed)
+            .map(|outcome| outcome.name.as_str())
+            .collect();
+        assert!(failed.is_empty(), "failed: {failed:?}");
+    }
+}
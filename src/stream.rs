@@ -0,0 +1,128 @@
+//! Пошаговое шифрование больших данных без загрузки в память целиком.
+//!
+//! ```python
+//! from cryptor import GrassCipher
+//!
+//! c = GrassCipher(key, 'CBC', iv=iv)
+//! ct = b''.join(c.update(part) for part in parts) + c.finalize()
+//! ```
+//!
+//! Результат совпадает с `do_encrypt`/`do_decrypt` для тех же данных,
+//! ключа, режима и вектора инициализации.
+
+use std::sync::Mutex;
+
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use zeroize::Zeroizing;
+
+use crate::{
+    decrypt_to_py_err, engine, extract_mode_and_iv, extract_padding, modes, params, rust_to_py_err,
+    AlreadyFinalized,
+};
+
+/// Пошаговый шифровальщик: `update` для очередной части, `finalize` в конце.
+///
+/// Объект можно создать один раз и передавать между потоками: состояние
+/// защищено мьютексом, который захватывается без GIL, и вызовы из разных
+/// потоков не повреждают его. Порядок частей при этом определяет
+/// вызывающий, поэтому одно сообщение шифруется из одного потока (или под
+/// внешней блокировкой).
+#[pyclass(name = "GrassCipher", frozen)]
+pub struct GrassCipher {
+    state: Mutex<Option<engine::Streaming>>,
+    mode: modes::Mode,
+    encrypting: bool,
+}
+
+#[pymethods]
+impl GrassCipher {
+    /// - key — Ключ (32 байта)
+    /// - encrypt_mode — Режим шифрования: строка или объект параметров (`params`)
+    /// - decrypt — Дешифровка вместо шифрования
+    /// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+    /// - iv — Вектор инициализации (16 байт, для `magma` — 8); по умолчанию
+    ///   встроенный
+    /// - padding — Выравнивание ECB и CBC: `PKCS7` (по умолчанию), `X923`,
+    ///   `ISO7816` или `ZERO`
+    #[new]
+    #[pyo3(signature = (key, encrypt_mode, *, decrypt=false, cipher=engine::Algorithm::Kuznyechik, iv=None, padding="PKCS7"))]
+    fn new(
+        key: Bound<'_, PyBytes>,
+        encrypt_mode: Bound<'_, PyAny>,
+        decrypt: bool,
+        cipher: engine::Algorithm,
+        iv: Option<Bound<'_, PyBytes>>,
+        padding: &str,
+    ) -> PyResult<Self> {
+        let algorithm = cipher;
+        let options = modes::Options {
+            padding: extract_padding(padding)?,
+            counter: params::counter(&encrypt_mode),
+        };
+        let (mode, iv) = extract_mode_and_iv(&encrypt_mode, iv)?;
+        let mode = rust_to_py_err(mode.block())?;
+        let key = Zeroizing::new(key.as_bytes().to_vec());
+        let state = rust_to_py_err(engine::Streaming::new(
+            algorithm,
+            &key,
+            mode,
+            iv.as_deref(),
+            options,
+            !decrypt,
+        ))?;
+
+        Ok(GrassCipher {
+            state: Mutex::new(Some(state)),
+            mode,
+            encrypting: !decrypt,
+        })
+    }
+
+    #[getter]
+    fn mode(&self) -> &'static str {
+        self.mode.name()
+    }
+
+    #[getter]
+    fn encrypting(&self) -> bool {
+        self.encrypting
+    }
+
+    /// Обработка очередной части данных.
+    ///
+    /// Возвращает готовую часть результата: в ECB и CBC неполный блок (а
+    /// при дешифровке и последний блок) остаётся до следующего вызова.
+    fn update(&self, py: Python<'_>, data: &[u8]) -> PyResult<Vec<u8>> {
+        py.allow_threads(|| {
+            let mut state = self.state.lock().unwrap();
+            state.as_mut().map(|stream| stream.update(data))
+        })
+        .ok_or_else(|| AlreadyFinalized::new_err(()))
+    }
+
+    /// Завершение: последний блок с выравниванием (ECB, CBC).
+    ///
+    /// После вызова объект не используется (`AlreadyFinalized`).
+    fn finalize(&self, py: Python<'_>) -> PyResult<Vec<u8>> {
+        let result = py.allow_threads(|| {
+            let stream = self.state.lock().unwrap().take();
+            stream.map(engine::Streaming::finalize)
+        });
+
+        // Завершение шифрования ошибок не возвращает.
+        decrypt_to_py_err(result.ok_or_else(|| AlreadyFinalized::new_err(()))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Объект доступен из разных потоков Python без внешней блокировки.
+    #[test]
+    fn grass_cipher_is_shareable() {
+        fn shareable<T: Send + Sync>() {}
+        shareable::<GrassCipher>();
+    }
+}
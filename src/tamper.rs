@@ -0,0 +1,61 @@
+//! Внесение повреждений в шифровки для проверки обработки ошибок.
+//!
+//! Собирается только с функцией `testing` (`maturin develop --features
+//! testing`) и не входит в обычную сборку. Python-обёртки с разметкой
+//! контейнеров — в `grass_crypt.testing`.
+
+use pyo3::exceptions::PyIndexError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+/// Копия данных с изменёнными битами байта.
+///
+/// - data — Данные
+/// - offset — Смещение байта; отрицательное отсчитывается от конца
+/// - mask — Маска XOR (не ноль)
+#[pyfunction]
+#[pyo3(name = "tamper_byte")]
+#[pyo3(signature = (data, offset, mask=0x01))]
+pub fn tamper_byte(data: Bound<'_, PyBytes>, offset: isize, mask: u8) -> PyResult<Vec<u8>> {
+    let mut data = data.as_bytes().to_vec();
+    let index = resolve(data.len(), offset)?;
+    data[index] ^= mask.max(1);
+
+    Ok(data)
+}
+
+/// Копия данных с изменённым диапазоном байт.
+///
+/// - data — Данные
+/// - start — Начало диапазона; отрицательное отсчитывается от конца
+/// - length — Длина диапазона
+/// - mask — Маска XOR (не ноль)
+#[pyfunction]
+#[pyo3(name = "tamper_range")]
+#[pyo3(signature = (data, start, length, mask=0xFF))]
+pub fn tamper_range(
+    data: Bound<'_, PyBytes>,
+    start: isize,
+    length: usize,
+    mask: u8,
+) -> PyResult<Vec<u8>> {
+    let mut data = data.as_bytes().to_vec();
+    let start = resolve(data.len(), start)?;
+    let Some(end) = start.checked_add(length).filter(|&end| end <= data.len()) else {
+        return Err(PyIndexError::new_err("tamper range out of data"));
+    };
+    data[start..end].iter_mut().for_each(|b| *b ^= mask.max(1));
+
+    Ok(data)
+}
+
+/// Индекс байта по смещению, в том числе отрицательному.
+fn resolve(len: usize, offset: isize) -> PyResult<usize> {
+    let index = if offset < 0 {
+        len.checked_sub(offset.unsigned_abs())
+    } else {
+        Some(offset.unsigned_abs()).filter(|&i| i < len)
+    };
+
+    index.ok_or_else(|| PyIndexError::new_err("tamper offset out of data"))
+}
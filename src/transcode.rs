@@ -0,0 +1,213 @@
+//! Потоковое перешифрование файла из одного режима в другой.
+//!
+//! ```python
+//! from cryptor import transcode
+//!
+//! transcode('old.bin', 'new.bin', key, 'CBC', 'MGM')
+//! ```
+//!
+//! Файл дешифруется и сразу шифруется заново частями на стороне Rust:
+//! память не зависит от размера файла, открытый текст в Python не
+//! попадает. Результат совпадает с `do_encrypt` для содержимого файла (со
+//! строкой `MGM` — случайный nonce в начале файла).
+
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use block_encryption::traits::CipherError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use zeroize::Zeroizing;
+
+use crate::engine::EncryptMode;
+use crate::files::{self, Transform};
+use crate::{
+    aead_to_py_err, check_signals, decrypt_to_py_err, engine, extract_key, extract_mode_and_iv,
+    mgm, modes, params, random_mgm_nonce, rust_to_py_err,
+};
+
+/// Перешифрование файла из режима `from_mode` в режим `to_mode`.
+///
+/// Файл обрабатывается частями без удержания GIL, между частями
+/// проверяются сигналы Python. При ошибке, в том числе неверной
+/// имитовставке MGM исходного файла, неполный `output_path` удаляется.
+///
+/// - input_path — Исходный файл (результат `encrypt_file` или `do_encrypt`)
+/// - output_path — Файл результата
+/// - key — Ключ исходного файла
+/// - from_mode — Режим исходного файла: строка или объект параметров
+///   (`params`); со строкой `MGM` nonce читается из начала файла
+/// - to_mode — Режим результата; со строкой `MGM` случайный nonce
+///   записывается в начало файла
+/// - new_key — Ключ результата; по умолчанию `key`
+/// - cipher — Шифр: `kuznyechik` (по умолчанию), `aes256` или `magma`
+/// - overwrite — Перезаписать существующий `output_path`
+/// - progress — Необязательный `progress(done: int)` с числом прочитанных
+///   байт исходного файла
+///
+/// Возвращает размер записанного файла.
+#[pyfunction]
+#[pyo3(name = "transcode")]
+#[pyo3(signature = (input_path, output_path, key, from_mode, to_mode, *, new_key=None, cipher=engine::Algorithm::Kuznyechik, overwrite=false, progress=None))]
+#[allow(clippy::too_many_arguments)]
+pub fn transcode<'py>(
+    py: Python<'py>,
+    input_path: PathBuf,
+    output_path: PathBuf,
+    key: Bound<'py, PyBytes>,
+    from_mode: Bound<'py, PyAny>,
+    to_mode: Bound<'py, PyAny>,
+    new_key: Option<Bound<'py, PyBytes>>,
+    cipher: engine::Algorithm,
+    overwrite: bool,
+    progress: Option<PyObject>,
+) -> PyResult<u64> {
+    let algorithm = cipher;
+    let key = Zeroizing::new(extract_key(&key)?);
+    let new_key = match &new_key {
+        Some(new_key) => Zeroizing::new(extract_key(new_key)?),
+        None => key.clone(),
+    };
+
+    let mut skip = 0;
+    let source = stage(algorithm, &key, &from_mode, false, || {
+        let nonce = read_nonce(&input_path)?;
+        skip = nonce.len();
+        Ok(nonce)
+    })?;
+    let mut header = Vec::new();
+    let target = stage(algorithm, &new_key, &to_mode, true, || {
+        let nonce = random_mgm_nonce()?;
+        header = nonce.to_vec();
+        Ok(nonce)
+    })?;
+    let transcoder = Transcoder {
+        source,
+        target,
+        header,
+        skip,
+    };
+
+    py.allow_threads(|| {
+        files::transform_file(&input_path, &output_path, transcoder, overwrite, |done| {
+            check_signals()?;
+            match &progress {
+                Some(progress) => Python::with_gil(|py| progress.call1(py, (done,)).map(drop)),
+                None => Ok(()),
+            }
+        })
+    })
+}
+
+/// Дешифровка или шифрование в одном режиме.
+enum Stage {
+    Block(engine::Streaming),
+    Mgm(engine::MgmStreaming),
+}
+
+impl Stage {
+    fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Stage::Block(stream) => stream.update(data),
+            Stage::Mgm(stream) => stream.update(data),
+        }
+    }
+
+    fn finalize(self) -> PyResult<Vec<u8>> {
+        match self {
+            Stage::Block(stream) => decrypt_to_py_err(stream.finalize()),
+            Stage::Mgm(stream) => aead_to_py_err(stream.finalize()),
+        }
+    }
+}
+
+/// Дешифровка `source` и шифрование её результата `target`.
+struct Transcoder {
+    source: Stage,
+    target: Stage,
+    /// Начало результата (nonce MGM), ещё не записанное.
+    header: Vec<u8>,
+    /// Уже прочитанное начало файла (nonce MGM), которое пропускается.
+    skip: usize,
+}
+
+impl Transform for Transcoder {
+    fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        let skip = self.skip.min(data.len());
+        self.skip -= skip;
+        let plaintext = Zeroizing::new(self.source.update(&data[skip..]));
+        let mut out = std::mem::take(&mut self.header);
+        out.extend(self.target.update(&plaintext));
+
+        out
+    }
+
+    fn finalize(mut self) -> PyResult<Vec<u8>> {
+        let plaintext = Zeroizing::new(self.source.finalize()?);
+        let mut out = std::mem::take(&mut self.header);
+        out.extend(self.target.update(&plaintext));
+        out.extend(self.target.finalize()?);
+
+        Ok(out)
+    }
+}
+
+/// Стадия по режиму из строки или объекта параметров.
+///
+/// - nonce — Источник nonce для строки `MGM`
+fn stage<F>(
+    algorithm: engine::Algorithm,
+    key: &[u8],
+    encrypt_mode: &Bound<'_, PyAny>,
+    encrypting: bool,
+    nonce: F,
+) -> PyResult<Stage>
+where
+    F: FnOnce() -> PyResult<[u8; mgm::NONCE_SIZE]>,
+{
+    let (mode, iv) = extract_mode_and_iv(encrypt_mode, None)?;
+    if mode == EncryptMode::Mgm {
+        let nonce = match iv {
+            Some(iv) => params::mgm_nonce(&iv)?,
+            None => nonce()?,
+        };
+        let tag_len = params::tag_len(encrypt_mode);
+        return aead_to_py_err(engine::MgmStreaming::new(
+            algorithm,
+            key,
+            &nonce,
+            &[],
+            tag_len,
+            encrypting,
+        ))
+        .map(Stage::Mgm);
+    }
+
+    let mode = rust_to_py_err(mode.block())?;
+    let options = modes::Options {
+        counter: params::counter(encrypt_mode),
+        ..Default::default()
+    };
+    rust_to_py_err(engine::Streaming::new(
+        algorithm,
+        key,
+        mode,
+        iv.as_deref(),
+        options,
+        encrypting,
+    ))
+    .map(Stage::Block)
+}
+
+/// Nonce MGM из начала файла.
+fn read_nonce(path: &Path) -> PyResult<[u8; mgm::NONCE_SIZE]> {
+    let mut nonce = [0u8; mgm::NONCE_SIZE];
+    match File::open(path)?.read_exact(&mut nonce) {
+        Ok(()) => Ok(nonce),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            decrypt_to_py_err(Err(CipherError::DataTooShort))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
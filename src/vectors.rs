@@ -0,0 +1,86 @@
+//! Контрольные примеры ГОСТ Р 34.12-2015 (приложения А) и ГОСТ Р
+//! 34.13-2015 (приложения А.1 и А.2) в шестнадцатеричной записи.
+//!
+//! В OFB, CBC и CFB стандарт использует регистр из нескольких блоков,
+//! поэтому для этих режимов здесь только "Кузнечик" с регистром из двух
+//! блоков (`KUZNYECHIK_IV`).
+
+/// Пример блочного шифра: ключ, открытый и зашифрованный блок.
+pub struct Block {
+    pub key: &'static str,
+    pub plaintext: &'static str,
+    pub ciphertext: &'static str,
+}
+
+/// Примеры режимов одного шифра; открытый текст — четыре блока.
+pub struct Modes {
+    pub key: &'static str,
+    pub plaintext: &'static str,
+    pub ecb: &'static str,
+    /// Nonce CTR в половину блока; счётчик начинается с нуля.
+    pub ctr_nonce: &'static str,
+    pub ctr: &'static str,
+    /// Имитовставка CMAC в половину блока.
+    pub mac: &'static str,
+}
+
+pub const KUZNYECHIK_BLOCK: Block = Block {
+    key: "8899aabbccddeeff0011223344556677fedcba98765432100123456789abcdef",
+    plaintext: "1122334455667700ffeeddccbbaa9988",
+    ciphertext: "7f679d90bebc24305a468d42b9d4edcd",
+};
+
+pub const MAGMA_BLOCK: Block = Block {
+    key: "ffeeddccbbaa99887766554433221100f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+    plaintext: "fedcba9876543210",
+    ciphertext: "4ee901e5c2d8ca3d",
+};
+
+pub const KUZNYECHIK: Modes = Modes {
+    key: KUZNYECHIK_BLOCK.key,
+    plaintext: "1122334455667700ffeeddccbbaa9988\
+                00112233445566778899aabbcceeff0a\
+                112233445566778899aabbcceeff0a00\
+                2233445566778899aabbcceeff0a0011",
+    ecb: "7f679d90bebc24305a468d42b9d4edcd\
+          b429912c6e0032f9285452d76718d08b\
+          f0ca33549d247ceef3f5a5313bd4b157\
+          d0b09ccde830b9eb3a02c4c5aa8ada98",
+    ctr_nonce: "1234567890abcef0",
+    ctr: "f195d8bec10ed1dbd57b5fa240bda1b8\
+          85eee733f6a13e5df33ce4b33c45dee4\
+          a5eae88be6356ed3d5e877f13564a3a5\
+          cb91fab1f20cbab6d1c6d15820bdba73",
+    mac: "336f4d296059fbe3",
+};
+
+pub const MAGMA: Modes = Modes {
+    key: MAGMA_BLOCK.key,
+    plaintext: "92def06b3c130a59db54c704f8189d20\
+                4a98fb2e67a8024c8912409b17b57e41",
+    ecb: "2b073f0494f372a0de70e715d3556e48\
+          11d8d9e9eacfbc1e7c68260996c67efb",
+    ctr_nonce: "12345678",
+    ctr: "4e98110c97b7b93c3e250d93d6e85d69\
+          136d868807b2dbef568eb680ab52a12d",
+    mac: "154e7210",
+};
+
+/// Вектор OFB, CBC и CFB "Кузнечика" (два блока).
+pub const KUZNYECHIK_IV: &str = "1234567890abcef0a1b2c3d4e5f00112\
+                                 23344556677889901213141516171819";
+
+pub const KUZNYECHIK_OFB: &str = "81800a59b1842b24ff1f795e897abd95\
+                                  ed5b47a7048cfab48fb521369d9326bf\
+                                  66a257ac3ca0b8b1c80fe7fc10288a13\
+                                  203ebbc066138660a0292243f6903150";
+
+pub const KUZNYECHIK_CBC: &str = "689972d4a085fa4d90e52e3d6d7dcc27\
+                                  2826e661b478eca6af1e8e448d5ea5ac\
+                                  fe7babf1e91999e85640e8b0f49d90d0\
+                                  167688065a895c631a2d9a1560b63970";
+
+pub const KUZNYECHIK_CFB: &str = "81800a59b1842b24ff1f795e897abd95\
+                                  ed5b47a7048cfab48fb521369d9326bf\
+                                  79f2a8eb5cc68d38842d264e97a238b5\
+                                  4ffebecd4e922de6c75bd9dd44fbf4d1";
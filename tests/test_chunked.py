@@ -0,0 +1,92 @@
+"""Параллельная проверка и расшифровка контейнера ``chunked``."""
+import pytest
+
+from grass_crypt import chunked
+from grass_crypt.chunked import (EncryptedMmap, decrypt_chunked_file,
+                                 encrypt_chunked_file)
+from grass_crypt.exceptions import AuthenticationError
+from grass_crypt.interfaces import peek_header
+from grass_crypt.tools import EncryptMode
+
+CODE = 'chunked test code'
+CHUNK = 1024
+DATA = bytes(range(256)) * 41
+
+
+@pytest.fixture
+def container(tmp_path):
+    source = tmp_path / 'data'
+    source.write_bytes(DATA)
+    return encrypt_chunked_file(source, tmp_path / 'data.gck', code=CODE,
+                                chunk_size=CHUNK)
+
+
+@pytest.mark.parametrize('workers', [None, 1, 4])
+def test_decrypt_chunked_file_round_trip(container, tmp_path, workers):
+    target = decrypt_chunked_file(container, tmp_path / 'plain', code=CODE,
+                                  workers=workers)
+    assert target.read_bytes() == DATA
+
+
+def test_corrupted_chunk_rejected_before_decryption(container, tmp_path,
+                                                    monkeypatch):
+    blob = bytearray(container.read_bytes())
+    blob[-chunked._TAG_LEN - 1] ^= 0x01
+    container.write_bytes(blob)
+
+    def no_decryption(*args, **kwargs):
+        raise AssertionError('decrypted before verification')
+
+    monkeypatch.setattr(chunked, 'decrypting_rust', no_decryption)
+    last = -(-len(DATA) // CHUNK) - 1
+    with pytest.raises(AuthenticationError, match=f'chunk {last}'):
+        decrypt_chunked_file(container, tmp_path / 'plain', code=CODE,
+                             workers=4)
+    assert not (tmp_path / 'plain').exists()
+
+    with EncryptedMmap(container, code=CODE) as view:
+        with pytest.raises(AuthenticationError, match=f'chunk {last}'):
+            view.verify(workers=2)
+
+
+def test_verify_checks_workers(container):
+    with EncryptedMmap(container, code=CODE) as view:
+        view.verify()
+        with pytest.raises(ValueError):
+            view.verify(workers=0)
+
+
+def test_chunk_changed_after_verification_rejected(container, tmp_path,
+                                                   monkeypatch):
+    verify = EncryptedMmap.verify
+
+    def verify_then_tamper(view, **kwargs):
+        verify(view, **kwargs)
+        with container.open('r+b') as file:
+            file.seek(-chunked._TAG_LEN - 1, 2)
+            byte = file.read(1)[0]
+            file.seek(-chunked._TAG_LEN - 1, 2)
+            file.write(bytes([byte ^ 0x01]))
+
+    monkeypatch.setattr(EncryptedMmap, 'verify', verify_then_tamper)
+    with pytest.raises(AuthenticationError):
+        decrypt_chunked_file(container, tmp_path / 'plain', code=CODE,
+                             workers=2)
+    assert not (tmp_path / 'plain').exists()
+
+
+def test_peek_header_reports_chunks_and_kdf(container, tmp_path):
+    header = peek_header(container)
+    assert header['mode'] is EncryptMode.CTR
+    assert header['chunks'] == {'size': CHUNK,
+                                'count': -(-len(DATA) // CHUNK),
+                                'length': len(DATA)}
+    assert header['kdf']['name'] == 'blake2b'
+    assert peek_header(container.read_bytes()) == header
+
+    scrypt = encrypt_chunked_file(container, tmp_path / 'scrypt.gck',
+                                  code=CODE, chunk_size=CHUNK, kdf='scrypt',
+                                  kdf_params={'n': 2 ** 10})
+    kdf = peek_header(scrypt)['kdf']
+    assert kdf['name'] == 'scrypt'
+    assert kdf['params'] == {'n': 2 ** 10, 'r': 8, 'p': 1}
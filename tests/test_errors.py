@@ -0,0 +1,22 @@
+"""Подробные ошибки размеров вне дешифровки и общая — при дешифровке."""
+import pytest
+
+from cryptor import do_decrypt, plaintext_len
+from grass_crypt.exceptions import (DataNotAlignedError, DataTooShortError,
+                                    DecryptionError)
+
+KEY = bytes(range(32))
+
+
+@pytest.mark.parametrize('length, error', [(17, DataNotAlignedError),
+                                           (0, DataTooShortError)])
+def test_size_helpers_raise_specific_errors(length, error):
+    with pytest.raises(error):
+        plaintext_len(length, 'CBC')
+
+
+@pytest.mark.parametrize('data', [b'x' * 15, b'x' * 17])
+def test_decryption_errors_stay_uniform(data):
+    with pytest.raises(DecryptionError) as info:
+        do_decrypt(data, KEY, 'CBC')
+    assert type(info.value) is DecryptionError
@@ -0,0 +1,47 @@
+"""Идентификатор ключа и имитовставка метаданных."""
+import base64
+
+import pytest
+
+from grass_crypt.exceptions import MetaStringError
+from grass_crypt.interfaces import (decrypt, encrypt, peek_header,
+                                    upgrade_container)
+from grass_crypt.keyring import KeyRing
+from grass_crypt.tools import EncryptMode, KEY_ID_LEN
+
+CODE = 'key id test code'
+
+
+def test_default_key_id_differs_per_encryption():
+    first = peek_header(encrypt(b'data', code=CODE))['key_id']
+    second = peek_header(encrypt(b'data', code=CODE))['key_id']
+    assert len(first) == KEY_ID_LEN
+    assert first != second
+
+
+@pytest.mark.parametrize('mode', [EncryptMode.CBC, EncryptMode.CTR,
+                                  EncryptMode.MGM])
+def test_tampered_key_id_rejected(mode):
+    blob = encrypt(b'data', code=CODE, mode=mode, key_id=b'2025-01')
+    assert peek_header(blob)['key_id'] == b'2025-01'
+
+    raw = bytearray(base64.b64decode(blob))
+    raw[raw.index(b'2025-01')] ^= 0x01
+    with pytest.raises(MetaStringError, match='metadata'):
+        decrypt(base64.b64encode(raw), code=CODE)
+
+
+def test_upgrade_keeps_explicit_key_id():
+    blob = encrypt(b'data', code=CODE, mode=EncryptMode.CBC, key_id='kid')
+    upgraded = upgrade_container(blob, code=CODE)
+    assert peek_header(upgraded)['mode'] is EncryptMode.MGM
+    assert peek_header(upgraded)['key_id'] == b'kid'
+    assert decrypt(upgraded, code=CODE) == b'data'
+
+
+def test_keyring_matches_default_key_id():
+    ring = KeyRing()
+    ring.add('other code')
+    ring.add(CODE, default=False)
+    assert ring.decrypt(encrypt(b'data', code=CODE, mode=EncryptMode.CFB)) \
+        == b'data'
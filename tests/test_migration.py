@@ -0,0 +1,35 @@
+"""Смена ключей ``cryptor.migrate`` с повреждёнными сообщениями."""
+import pytest
+
+from cryptor import do_decrypt, do_encrypt, migrate
+from grass_crypt.exceptions import DecryptionError
+
+OLD_KEY = bytes(range(32))
+NEW_KEY = bytes(range(32, 64))
+MESSAGES = [b'record %d' % i for i in range(4)]
+
+
+def batch():
+    ciphertexts = [do_encrypt(m, OLD_KEY, 'CBC') for m in MESSAGES]
+    ciphertexts[1] = ciphertexts[1][:-1] + bytes([ciphertexts[1][-1] ^ 1])
+    return ciphertexts
+
+
+def test_on_error_reports_failed_records_and_keeps_the_rest():
+    failed = []
+    migrated = list(migrate(batch(), OLD_KEY, NEW_KEY, 'CBC', batch_size=4,
+                            on_error=lambda i, e: failed.append((i, e))))
+
+    assert [i for i, _ in failed] == [1]
+    assert isinstance(failed[0][1], DecryptionError)
+    assert [do_decrypt(ct, NEW_KEY, 'CBC') for ct in migrated] == [
+        MESSAGES[0], MESSAGES[2], MESSAGES[3]]
+
+
+def test_error_raised_in_place_and_iteration_continues():
+    migration = migrate(batch(), OLD_KEY, NEW_KEY, 'CBC', batch_size=4)
+
+    assert do_decrypt(next(migration), NEW_KEY, 'CBC') == MESSAGES[0]
+    with pytest.raises(DecryptionError):
+        next(migration)
+    assert [do_decrypt(ct, NEW_KEY, 'CBC') for ct in migration] == MESSAGES[2:]
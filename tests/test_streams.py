@@ -0,0 +1,143 @@
+"""Ограниченная память потокового шифрования (``grass_crypt.streams``)."""
+import io
+import os
+import threading
+import time
+import tracemalloc
+
+import pytest
+
+from grass_crypt.exceptions import MetaStringError
+from grass_crypt.streams import decrypt_stream, encrypt_stream, iter_decrypt
+from grass_crypt.tools import MAX_RECORD, RECORD_HEADER
+
+CODE = 'stream test code'
+CHUNK = 4096
+SIZE = 4 * 1024 * 1024
+
+
+class Trickle(io.RawIOBase):
+    """Канал, который после ``slow_from`` отдаёт данные по одному байту."""
+
+    def __init__(self, data: bytes, slow_from: int = 0):
+        self._data, self._pos, self._slow_from = data, 0, slow_from
+        self.largest_read = 0
+
+    def read(self, size: int = -1) -> bytes:
+        self.largest_read = max(self.largest_read, size)
+        end = len(self._data) if size < 0 else self._pos + size
+        if self._pos >= self._slow_from:
+            end = min(end, self._pos + 1)
+        data = self._data[self._pos:end]
+        self._pos += len(data)
+        return data
+
+
+class Counter(io.RawIOBase):
+    """Приёмник, который только считает байты."""
+
+    def __init__(self):
+        self.total = 0
+
+    def write(self, data: bytes) -> int:
+        self.total += len(data)
+        return len(data)
+
+
+def encrypted(data: bytes) -> bytes:
+    sink = io.BytesIO()
+    encrypt_stream(io.BytesIO(data), sink, code=CODE, chunk_size=CHUNK)
+    return sink.getvalue()
+
+
+def peak_memory(run) -> int:
+    tracemalloc.start()
+    try:
+        run()
+        return tracemalloc.get_traced_memory()[1]
+    finally:
+        tracemalloc.stop()
+
+
+def test_decrypt_stream_memory_does_not_grow_with_input():
+    """Пик памяти ограничен несколькими записями, хотя поток в сотни раз
+    больше, а последняя запись приходит по одному байту."""
+    data = os.urandom(SIZE + CHUNK // 2)
+    stream = encrypted(data)
+    last = len(stream) - len(encrypted(data[-(CHUNK // 2):]))
+    source, sink = Trickle(stream, slow_from=last), Counter()
+
+    peak = peak_memory(lambda: decrypt_stream(source, sink, code=CODE))
+
+    assert sink.total == len(data)
+    assert source.largest_read <= 2 * CHUNK + RECORD_HEADER.size
+    assert peak < 64 * CHUNK
+
+
+def test_encrypt_stream_memory_does_not_grow_with_input():
+    """Шифрование канала по одному байту не накапливает буферы."""
+    source, sink = Trickle(bytes(SIZE // 16)), Counter()
+
+    peak = peak_memory(
+        lambda: encrypt_stream(source, sink, code=CODE, chunk_size=CHUNK))
+
+    assert source.largest_read <= CHUNK
+    assert peak < 64 * CHUNK
+
+
+def test_record_length_checked_before_body():
+    """Длина записи больше ``MAX_RECORD`` отклоняется без чтения тела."""
+    source = Trickle(RECORD_HEADER.pack(MAX_RECORD + 1) + bytes(64))
+
+    with pytest.raises(MetaStringError):
+        decrypt_stream(source, Counter(), code=CODE)
+    assert source.largest_read == RECORD_HEADER.size
+
+
+class Blocking(io.RawIOBase):
+    """Источник, чтение которого после ``block_at`` ждёт ``release``."""
+
+    def __init__(self, data: bytes, block_at: int):
+        self._data, self._pos, self._block_at = data, 0, block_at
+        self.release = threading.Event()
+        self.reads_after_release = 0
+
+    def read(self, size: int = -1) -> bytes:
+        if self._pos >= self._block_at:
+            if self.release.is_set():
+                self.reads_after_release += 1
+            self.release.wait()
+        data = self._data[self._pos:self._pos + size]
+        self._pos += len(data)
+        return data
+
+
+def test_early_stop_does_not_wait_for_blocked_read():
+    """Остановка потребителя не ждёт чтения, на котором висит фоновый
+    поток, и после него поток источник больше не читает."""
+    first = encrypted(bytes(CHUNK))
+    source = Blocking(first + encrypted(bytes(CHUNK)) * 3, len(first))
+
+    chunks = iter_decrypt(source, code=CODE)
+    assert next(chunks) == bytes(CHUNK)
+    started = time.monotonic()
+    chunks.close()
+    assert time.monotonic() - started < 1
+
+    source.release.set()
+    time.sleep(0.3)
+    assert source.reads_after_release <= 1
+
+
+def test_corrupted_record_body_raises_meta_error():
+    """Запись с верной длиной и повреждённым base64 — ``MetaStringError``,
+    а не ``binascii.Error``."""
+    body = b'AAAAA'
+    stream = encrypted(bytes(CHUNK)) + RECORD_HEADER.pack(len(body)) + body
+
+    with pytest.raises(MetaStringError, match='encoding'):
+        decrypt_stream(io.BytesIO(stream), Counter(), code=CODE)
+    chunks = iter_decrypt(io.BytesIO(stream), code=CODE)
+    assert next(chunks) == bytes(CHUNK)
+    with pytest.raises(MetaStringError, match='encoding'):
+        next(chunks)
@@ -0,0 +1,82 @@
+"""Повреждённые шифровки не расшифровываются (``grass_crypt.testing``).
+
+Нужна сборка с функцией ``testing``: ``maturin develop --features testing``.
+Ошибки движка намеренно неразличимы (``DecryptionError``), поэтому
+``AuthenticationError`` проверяется только у ``chunked``.
+"""
+import pytest
+
+testing = pytest.importorskip('grass_crypt.testing')
+
+from cryptor import do_decrypt, do_encrypt, open as open_sealed, seal  # noqa: E402
+from grass_crypt.chunked import EncryptedMmap, encrypt_chunked_file  # noqa: E402
+from grass_crypt.exceptions import (AuthenticationError,  # noqa: E402
+                                    DecryptionError, GrassCryptException)
+
+KEY = bytes(range(32))
+DATA = b'tamper harness message ' * 4
+# Контейнер без KDF: маркер и версия (4), шифр, режим, KDF, итерации (4),
+# длина соли и длина вектора.
+CONTAINER_HEAD = 13
+
+
+def every_byte(blob: bytes):
+    """Копии шифровки с изменённым битом в каждом байте."""
+    return (testing.corrupt(blob, offset=i, mask=0x01) for i in range(len(blob)))
+
+
+@pytest.mark.parametrize('mode', ['MGM', 'SIV'])
+def test_tampered_aead_fails(mode):
+    blob = do_encrypt(DATA, KEY, mode)
+    for bad in every_byte(blob):
+        testing.expect_failure(lambda b: do_decrypt(b, KEY, mode), bad,
+                               DecryptionError)
+    for length in range(1, len(blob)):
+        testing.expect_failure(lambda b: do_decrypt(b, KEY, mode),
+                               blob[:length], DecryptionError)
+
+
+@pytest.mark.parametrize('mode', ['CBC', 'CTR'])
+def test_tampered_seal_fails(mode):
+    blob = seal(DATA, KEY, mode)
+    for bad in every_byte(blob):
+        testing.expect_failure(lambda b: open_sealed(b, KEY, mode), bad,
+                               DecryptionError)
+    for length in range(1, len(blob)):
+        testing.expect_failure(lambda b: open_sealed(b, KEY, mode),
+                               blob[:length], DecryptionError)
+
+
+@pytest.mark.parametrize('mode', ['CBC', 'MGM'])
+def test_tampered_container_fails(mode):
+    """Повреждённый маркер или версия — уже не контейнер (нужен режим),
+    параметры заголовка — ошибка разбора или дешифровки, вектор и
+    шифротекст — ошибка дешифровки."""
+    # Одна итерация: изменённый идентификатор KDF включает PBKDF2.
+    blob = do_encrypt(DATA, KEY, mode, container=True, iterations=1)
+    assert do_decrypt(blob, KEY) == DATA
+    for i, bad in enumerate(every_byte(blob)):
+        if i < 4:
+            expected = ValueError
+        elif i < CONTAINER_HEAD:
+            expected = GrassCryptException
+        else:
+            expected = DecryptionError
+        testing.expect_failure(lambda b: do_decrypt(b, KEY), bad, expected)
+    for length in range(CONTAINER_HEAD, len(blob)):
+        testing.expect_failure(lambda b: do_decrypt(b, KEY), blob[:length],
+                               DecryptionError)
+
+
+def test_tampered_chunk_fails_authentication(tmp_path):
+    source, target = tmp_path / 'data', tmp_path / 'data.gck'
+    source.write_bytes(DATA * 100)
+    encrypt_chunked_file(source, target, code='chunk code', chunk_size=1024)
+    blob = target.read_bytes()
+
+    for tag in (False, True):
+        target.write_bytes(testing.corrupt_chunk(blob, 1, byte=5, tag=tag))
+        with EncryptedMmap(target, code='chunk code') as mapped:
+            assert mapped.chunk(0) == (DATA * 100)[:1024]
+            testing.expect_failure(lambda _: mapped.chunk(1), b'',
+                                   AuthenticationError, match='chunk 1')
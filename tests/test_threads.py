@@ -0,0 +1,44 @@
+"""Общие объекты движка в нескольких потоках."""
+import os
+from concurrent.futures import ThreadPoolExecutor
+
+from cryptor import GrassCipher, do_decrypt, do_encrypt
+
+BLOCK = 16
+
+
+def test_grass_cipher_shared_between_threads():
+    """Части из разных потоков получают разные блоки гаммы CTR: состояние
+    не теряет и не повторяет счётчик."""
+    key = os.urandom(32)
+    shared = GrassCipher(key, 'CTR')
+
+    def work(_):
+        return [shared.update(bytes(BLOCK)) for _ in range(200)]
+
+    with ThreadPoolExecutor(8) as pool:
+        parts = [part for parts in pool.map(work, range(8)) for part in parts]
+
+    gamma = GrassCipher(key, 'CTR').update(bytes(BLOCK * len(parts)))
+    expected = [gamma[i:i + BLOCK] for i in range(0, len(gamma), BLOCK)]
+    assert sorted(parts) == sorted(expected)
+
+
+def test_encryptor_cache_between_threads():
+    """Кеш шифровальщиков даёт в каждом потоке тот же результат, что и
+    последовательные вызовы."""
+    keys = [os.urandom(32) for _ in range(40)]
+    data = os.urandom(100)
+    modes = ['ECB', 'CBC', 'CFB', 'OFB', 'CTR']
+    cases = [(key, mode) for key in keys for mode in modes]
+    expected = [do_encrypt(data, key, mode) for key, mode in cases]
+
+    def work(shift):
+        for i in range(len(cases)):
+            i = (i * (shift + 1)) % len(cases)
+            key, mode = cases[i]
+            assert do_encrypt(data, key, mode) == expected[i]
+            assert do_decrypt(expected[i], key, mode) == data
+
+    with ThreadPoolExecutor(8) as pool:
+        list(pool.map(work, range(8)))